@@ -16,6 +16,7 @@ fn main() {
             "import_nested.proto",
             "import_nonunique.proto",
             "import_pkg_nested.proto",
+            "import_public.proto",
             "import_root.proto",
             "oneof_default_value.proto",
             "required.proto",
@@ -30,6 +31,8 @@ fn main() {
         &[
             "data_types.proto",
             "boxed_field.proto",
+            "comments.proto",
+            "enum_alias.proto",
             "enums.proto",
             "keyword_enum_variant.proto",
             "optional_empty.proto",
@@ -42,10 +45,10 @@ fn main() {
     // services
     compile("tests/services", &["health.proto", "example.proto"]);
 
-    // custom map
+    // custom map, every map of the package but the ones named `hashmap`
     prust_build::Config::default()
-        .btree_map(&["different_map_type.Data.btreemap"])
-        .btree_map(&["different_map_type.Data.Inner.btreemap"])
+        .btree_map(&[".different_map_type"])
+        .hashmap(&["hashmap"])
         .output("tests/proto3/prust")
         .compile(&[], &["tests/proto3/different_map_type.proto"])
         .unwrap();
@@ -59,6 +62,14 @@ fn main() {
         .compile_protos(&["tests/services/example.proto"], &["tests/services"])
         .unwrap();
 
+    // drop deprecated fields
+    prust_build::Config::default()
+        .skip_deprecated_fields(true)
+        .filename("deprecated_field_skipped")
+        .output("tests/proto2/prust")
+        .compile(&[], &["tests/proto2/deprecated_field.proto"])
+        .unwrap();
+
     // skip deserialize or serialize
     prust_build::Config::default()
         .skip_deserialize(&[
@@ -85,6 +96,102 @@ fn main() {
         .compile(&[], &["tests/proto3/field_attributes.proto"])
         .unwrap();
 
+    // custom rust types of fields, and the same message without them
+    prust_build::Config::default()
+        .field_type(".field_types.Data.address", "std::net::Ipv4Addr")
+        .field_type("Data.id", "super::Id")
+        .field_type("Data.price", "super::Price")
+        .field_type("Data.peers", "std::net::Ipv4Addr")
+        .field_type("Data.history", "super::Price")
+        .field_type("Data.color", "super::Shade")
+        .field_type("Data.tags", "super::Tag")
+        .field_type("Data.gateway", "std::net::Ipv4Addr")
+        .output("tests/proto3/prust")
+        .compile(&[], &["tests/proto3/field_types.proto"])
+        .unwrap();
+    prust_build::Config::default()
+        .output("tests/proto3/prust/plain")
+        .compile(&[], &["tests/proto3/field_types.proto"])
+        .unwrap();
+
+    // other string, bytes and repeated types, and the same messages without
+    // them
+    prust_build::Config::default()
+        .string_type(".containers", "std::sync::Arc<str>")
+        .string_type("Data.tags", "Box<str>")
+        .bytes_type(".containers", "bytes::Bytes")
+        .repeated_type(".containers", "smallvec::SmallVec<[_; 2]>")
+        .field_type("Data.peers", "std::net::Ipv4Addr")
+        .output("tests/proto2/prust")
+        .compile(&[], &["tests/proto2/containers.proto"])
+        .unwrap();
+    prust_build::Config::default()
+        .output("tests/proto2/prust/plain")
+        .compile(&[], &["tests/proto2/containers.proto"])
+        .unwrap();
+
+    // `Hash` needs the map to be a `BTreeMap`
+    prust_build::Config::default()
+        .btree_map(["Tree.labels"])
+        .output("tests/proto3/prust")
+        .compile(&[], &["tests/proto3/derives.proto"])
+        .unwrap();
+
+    // open enums
+    prust_build::Config::default()
+        .open_enums(true)
+        .output("tests/proto3/prust")
+        .compile(&[], &["tests/proto3/open_enums.proto"])
+        .unwrap();
+    prust_build::Config::default()
+        .open_enums(true)
+        .output("tests/proto2/prust")
+        .compile(&[], &["tests/proto2/closed_enums.proto"])
+        .unwrap();
+
+    // extensions
+    prust_build::Config::default()
+        .output("tests/proto2/prust")
+        .compile(&[], &["tests/proto2/extensions.proto"])
+        .unwrap();
+
+    // types of another module, `foo.baz` is not generated
+    prust_build::Config::default()
+        .extern_path(".foo.baz", "super::super::super::imported")
+        .output("tests/proto2/prust/extern_path")
+        .compile(&["tests/proto2"], &["tests/proto2/import_pkg_nested.proto"])
+        .unwrap();
+
+    // descriptor set of compiled files and their imports, and the module
+    // tree of their packages
+    prust_build::Config::default()
+        .file_descriptor_set_path("tests/proto2/prust/descriptor_set.bin")
+        .include_file("mod.rs")
+        .output("tests/proto2/prust/descriptor_set")
+        .compile(
+            &["tests/proto2"],
+            &[
+                "tests/proto2/import_public.proto",
+                "tests/proto2/extensions.proto",
+            ],
+        )
+        .unwrap();
+
+    // generate code from the descriptor set above
+    let set = std::fs::read("tests/proto2/prust/descriptor_set.bin").unwrap();
+    prust_build::Config::default()
+        .output("tests/proto2/prust/fds")
+        .compile_fds(&set)
+        .unwrap();
+
+    // descriptors embedded for server reflection
+    prust_build::Config::default()
+        .embed_descriptors(true)
+        .filename("reflection")
+        .output("tests/services/prust")
+        .compile(&["tests/services"], &["tests/services/example.proto"])
+        .unwrap();
+
     // third party
     // prust_build::Config::default()
     //     .output("tests/third_party/prust")
@@ -102,12 +209,12 @@ fn compile(root: &str, protos: &[&str]) {
     prust_build::Config::default()
         .message_attribute(
             "",
-            "#[derive(arbitrary::Arbitrary, serde::Serialize, PartialEq)]",
+            "#[derive(arbitrary::Arbitrary, serde::Serialize)]",
         )
         .enum_attribute("", "#[derive(arbitrary::Arbitrary, serde::Serialize)]")
         .oneof_attribute(
             "",
-            "#[derive(arbitrary::Arbitrary, serde::Serialize, PartialEq)]",
+            "#[derive(arbitrary::Arbitrary, serde::Serialize)]",
         )
         .output(root.join("prust"))
         .compile(&[root.clone()], protos.as_slice())
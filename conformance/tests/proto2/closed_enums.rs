@@ -0,0 +1,48 @@
+mod prust {
+    include!("prust/closed_enums.rs");
+}
+
+use ::prust::{Deserialize, Serialize};
+
+fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    let written = msg.encode(&mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    buf
+}
+
+#[test]
+fn unknown_values_are_kept() {
+    let data = [
+        0x08, 0x02, // single = OFF
+        0x08, 0x05, // single = 5, unknown
+        0x10, 0x07, // optional = 7, unknown
+        0x1a, 0x03, 0x01, 0x07, 0x02, // packed = [ON, 7, OFF]
+        0x20, 0x09, // non_packed = 9, unknown
+        0x2a, 0x05, 0x0a, 0x01, 0x61, 0x10, 0x08, // map = { "a": 8 }, unknown
+        0x30, 0x2a, // value = 42
+    ];
+
+    let msg = prust::Data::decode(&data).unwrap();
+    assert_eq!(msg.single, prust::State::Off);
+    assert_eq!(msg.optional, None);
+    assert_eq!(msg.packed, vec![prust::State::On, prust::State::Off]);
+    assert!(msg.non_packed.is_empty());
+    assert!(msg.map.is_empty());
+    assert_eq!(msg.value, Some(42));
+
+    // the records of unknown values, the packed one unpacked
+    let unknown = [
+        0x08, 0x05, // single
+        0x10, 0x07, // optional
+        0x18, 0x07, // packed
+        0x20, 0x09, // non_packed
+        0x2a, 0x05, 0x0a, 0x01, 0x61, 0x10, 0x08, // map
+    ];
+    assert_eq!(msg.unknown_fields.as_bytes(), unknown);
+
+    // written back after the known fields
+    let buf = encode(&msg);
+    assert!(buf.ends_with(&unknown));
+    assert_eq!(prust::Data::decode(&buf).unwrap(), msg);
+}
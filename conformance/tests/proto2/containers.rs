@@ -0,0 +1,79 @@
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+
+use ::prust::{Deserialize, Serialize};
+use bytes::Bytes;
+use smallvec::smallvec;
+
+mod prust {
+    include!("prust/containers.rs");
+}
+
+mod plain {
+    include!("prust/plain/containers.rs");
+}
+
+fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    let written = msg.encode(&mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    buf
+}
+
+#[test]
+fn defaults() {
+    let msg = prust::Data::default();
+    assert_eq!(msg.name(), "hello");
+    assert_eq!(msg.data, Bytes::from_static(b"abc"));
+
+    // required fields are written even if they are the default
+    assert_eq!(encode(&msg), encode(&plain::Data::default()));
+}
+
+#[test]
+fn same_wire_format() {
+    let custom = prust::Data {
+        name: Some(Arc::from("name")),
+        data: Bytes::from_static(b"data"),
+        tags: smallvec![Box::from("a"), Box::from("b"), Box::from("c")],
+        ids: smallvec![1, -2, 300],
+        items: smallvec![prust::Item {
+            name: Some(Arc::from("item")),
+        }],
+        chunks: smallvec![Bytes::from_static(b"x"), Bytes::new()],
+        peers: smallvec![Ipv4Addr::LOCALHOST],
+        value: Some(prust::data::Value::Blob(Bytes::from_static(b"blob"))),
+    };
+    let plain = plain::Data {
+        name: Some("name".to_string()),
+        data: b"data".to_vec(),
+        tags: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        ids: vec![1, -2, 300],
+        items: vec![plain::Item {
+            name: Some("item".to_string()),
+        }],
+        chunks: vec![b"x".to_vec(), vec![]],
+        peers: vec![0x7F00_0001],
+        value: Some(plain::data::Value::Blob(b"blob".to_vec())),
+    };
+
+    let buf = encode(&custom);
+    assert_eq!(buf, encode(&plain));
+
+    let got = prust::Data::decode(&buf).unwrap();
+    assert_eq!(got.name, custom.name);
+    assert_eq!(got.data, custom.data);
+    assert_eq!(got.tags, custom.tags);
+    assert_eq!(got.ids, custom.ids);
+    assert_eq!(got.items[0].name, custom.items[0].name);
+    assert_eq!(got.chunks, custom.chunks);
+    assert_eq!(got.peers, custom.peers);
+    assert!(matches!(got.value, Some(prust::data::Value::Blob(v)) if v == "blob"));
+
+    let custom = prust::Data {
+        value: Some(prust::data::Value::Text(Arc::from("text"))),
+        ..Default::default()
+    };
+    let got = prust::Data::decode(&encode(&custom)).unwrap();
+    assert!(matches!(got.value, Some(prust::data::Value::Text(v)) if &*v == "text"));
+}
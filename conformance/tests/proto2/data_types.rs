@@ -1,7 +1,16 @@
 mod prust {
     #![allow(unused_imports)]
 
-    include!("prust/data_types.rs");
+    pub mod data_types {
+        include!("prust/data_types.rs");
+    }
+    pub mod a {
+        pub mod b {
+            include!("prust/a_b.rs");
+        }
+    }
+
+    pub use data_types::*;
 }
 
 mod a {
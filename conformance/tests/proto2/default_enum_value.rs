@@ -14,9 +14,9 @@ fn default() {
 
     let msg = Test::default();
 
-    assert_eq!(msg.privacy_level_1, PrivacyLevel::One);
-    assert_eq!(msg.privacy_level_3, PrivacyLevel::PrivacyLevelThree);
-    assert_eq!(msg.privacy_level_4, PrivacyLevel::PrivacyLevelFour);
+    assert_eq!(msg.privacy_level_1(), PrivacyLevel::One);
+    assert_eq!(msg.privacy_level_3(), PrivacyLevel::PrivacyLevelThree);
+    assert_eq!(msg.privacy_level_4(), PrivacyLevel::PrivacyLevelFour);
 }
 
 #[test]
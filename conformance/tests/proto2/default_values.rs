@@ -8,6 +8,65 @@ mod prust {
     include!("prust/default_values.rs");
 }
 
+use ::prust::{Deserialize, Serialize};
+
+fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    let written = msg.encode(&mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    buf
+}
+
+#[test]
+fn optional() {
+    let mut msg = prust::TestDefaultValuesOptional::default();
+
+    assert!(!msg.has_int32_field());
+    assert_eq!(msg.double_field(), 1f64);
+    assert_eq!(msg.float_field(), 2f32);
+    assert_eq!(msg.int32_field(), 3i32);
+    assert_eq!(msg.uint64_field(), 6u64);
+    assert_eq!(msg.sfixed64_field(), 12i64);
+    assert_eq!(msg.bool_field(), true);
+    assert_eq!(msg.string_field(), "abc\n22");
+    assert_eq!(msg.bytes_field(), b"cde\n33");
+    assert_eq!(msg.enum_field(), prust::EnumForDefaultValue::Two);
+    assert_eq!(
+        msg.enum_field_without_default(),
+        prust::EnumForDefaultValue::One
+    );
+    // unset fields are not written
+    assert!(encode(&msg).is_empty());
+
+    // defaults set explicitly are written, and set after decoding
+    msg.set_int32_field(3);
+    msg.set_string_field("abc\n22".to_string());
+    msg.set_enum_field(prust::EnumForDefaultValue::Two);
+    let got = prust::TestDefaultValuesOptional::decode(&encode(&msg)).unwrap();
+    assert!(got.has_int32_field());
+    assert_eq!(got.string_field.as_deref(), Some("abc\n22"));
+    assert_eq!(got.enum_field, Some(prust::EnumForDefaultValue::Two));
+    assert!(!got.has_bool_field());
+
+    msg.set_int32_field(30);
+    assert_eq!(msg.int32_field(), 30);
+    msg.clear_int32_field();
+    assert!(!msg.has_int32_field());
+    assert_eq!(msg.int32_field(), 3);
+}
+
+#[test]
+fn extreme() {
+    let msg = prust::TestExtremeDefaultValues::default();
+
+    assert_eq!(msg.inf_double(), f64::INFINITY);
+    assert_eq!(msg.neg_inf_double(), f64::NEG_INFINITY);
+    assert!(msg.nan_double().is_nan());
+    assert_eq!(msg.inf_float(), f32::INFINITY);
+    assert_eq!(msg.neg_inf_float(), f32::NEG_INFINITY);
+    assert!(msg.nan_float().is_nan());
+}
+
 #[test]
 fn required() {
     let msg = prust::TestDefaultValuesRequired::default();
@@ -32,6 +91,13 @@ fn required() {
         msg.enum_field_without_default,
         prust::EnumForDefaultValue::One
     );
+
+    // required fields are written even if they are the default
+    let buf = encode(&msg);
+    assert!(!buf.is_empty());
+    let got = prust::TestDefaultValuesRequired::decode(&buf).unwrap();
+    assert_eq!(got.int32_field, 3i32);
+    assert_eq!(encode(&got), buf);
 }
 
 conformance::fuzz!(TestDefaultValuesRequired);
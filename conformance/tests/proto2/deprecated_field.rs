@@ -1,16 +1,46 @@
+#![allow(deprecated)]
+
 mod prust {
     include!("prust/deprecated_field.rs");
 }
 
+mod skipped {
+    include!("prust/deprecated_field_skipped.rs");
+}
+
 mod prost {
     include!("prost/deprecated_field.rs");
 }
 
+use ::prust::{Deserialize, Serialize};
+
 #[test]
-fn no_deprecated_field() {
-    let _t = prust::Test {
+fn keep_deprecated_field() {
+    let msg = prust::Test {
         not_outdated: "foo".to_string(),
+        outdated: "bar".to_string(),
     };
+
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf).unwrap();
+
+    let got = prust::Test::decode(&buf).unwrap();
+    assert_eq!(got.not_outdated, "foo");
+    assert_eq!(got.outdated, "bar");
+}
+
+#[test]
+fn skip_deprecated_field() {
+    let msg = prust::Test {
+        not_outdated: "foo".to_string(),
+        outdated: "bar".to_string(),
+    };
+
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf).unwrap();
+
+    let got = skipped::Test::decode(&buf).unwrap();
+    assert_eq!(got.not_outdated, "foo");
 }
 
 conformance::fuzz!(Test);
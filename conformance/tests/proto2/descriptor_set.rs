@@ -0,0 +1,156 @@
+use prust::{Deserialize, Serialize};
+use prust::descriptor::FileDescriptorSet;
+use prust::descriptor::field_descriptor_proto::{Label, Type};
+
+#[test]
+fn imports_first() {
+    let set = FileDescriptorSet::decode(include_bytes!("prust/descriptor_set.bin")).unwrap();
+
+    let names = set
+        .file
+        .iter()
+        .map(|file| file.name.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "import_public_inner.proto",
+            "import_public_middle.proto",
+            "import_public.proto",
+            "extensions.proto"
+        ]
+    );
+
+    let middle = &set.file[1];
+    assert_eq!(middle.dependency, ["import_public_inner.proto"]);
+    assert_eq!(middle.public_dependency, [0]);
+}
+
+#[test]
+fn resolved_types() {
+    let set = FileDescriptorSet::decode(include_bytes!("prust/descriptor_set.bin")).unwrap();
+
+    let file = &set.file[2];
+    assert_eq!(file.package.as_deref(), Some("import_public"));
+
+    let msg = &file.message_type[0];
+    assert_eq!(msg.name.as_deref(), Some("ContainsPublicImported"));
+
+    let fields = msg
+        .field
+        .iter()
+        .map(|field| {
+            (
+                field.name.as_deref().unwrap(),
+                field.label.unwrap(),
+                field.r#type.unwrap(),
+                field.type_name.as_deref().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        [
+            ("middle", Label::Optional, Type::Message, ".middle.Middle"),
+            ("inner", Label::Optional, Type::Message, ".inner.Inner"),
+            ("color", Label::Optional, Type::Enum, ".inner.Inner.Color"),
+        ]
+    );
+
+    // comments are kept in source info, in the form protoc writes them
+    let info = file.source_code_info.as_ref().unwrap();
+    let inner = info
+        .location
+        .iter()
+        .find(|location| location.path == [4, 0, 2, 1])
+        .unwrap();
+    assert_eq!(inner.span, [10, 4, 35]);
+    assert_eq!(
+        inner.leading_comments.as_deref(),
+        Some(
+            " defined in `import_public_inner.proto`, imported publicly by\n `import_public_middle.proto`\n"
+        )
+    );
+}
+
+#[test]
+fn extensions() {
+    let set = FileDescriptorSet::decode(include_bytes!("prust/descriptor_set.bin")).unwrap();
+
+    // extensions are described like regular fields, top level ones in the
+    // file and nested ones in their message
+    let file = &set.file[3];
+    let fields = file
+        .extension
+        .iter()
+        .map(|field| {
+            (
+                field.name.as_deref().unwrap(),
+                field.extendee.as_deref().unwrap(),
+                field.json_name.as_deref().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        [
+            ("score", ".extensions.Base", "score"),
+            ("label", ".extensions.Base", "label"),
+            ("ids", ".extensions.Base", "ids"),
+            ("notes", ".extensions.Base", "notes"),
+            ("color", ".extensions.Base", "color"),
+            ("big", ".extensions.Empty", "big"),
+        ]
+    );
+
+    let scope = file
+        .message_type
+        .iter()
+        .find(|msg| msg.name.as_deref() == Some("Scope"))
+        .unwrap();
+    assert_eq!(scope.extension[0].json_name.as_deref(), Some("flag"));
+}
+
+#[test]
+fn compile_fds() {
+    // the same code as compiling the sources with the same config
+    assert_eq!(
+        include_str!("prust/fds/import_public.rs"),
+        include_str!("prust/descriptor_set/import_public.rs")
+    );
+    assert_eq!(
+        include_str!("prust/fds/middle.rs"),
+        include_str!("prust/descriptor_set/middle.rs")
+    );
+    assert_eq!(
+        include_str!("prust/fds/inner.rs"),
+        include_str!("prust/descriptor_set/inner.rs")
+    );
+    assert_eq!(
+        include_str!("prust/fds/extensions.rs"),
+        include_str!("prust/descriptor_set/extensions.rs")
+    );
+}
+
+mod packages {
+    include!("prust/descriptor_set/mod.rs");
+}
+
+#[test]
+fn include_file() {
+    let msg = packages::import_public::ContainsPublicImported {
+        middle: Some(packages::middle::Middle {
+            inner: Some(packages::inner::Inner { num: Some(1) }),
+        }),
+        inner: None,
+        color: Some(packages::inner::inner::Color::Red),
+    };
+
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf).unwrap();
+    let got = packages::import_public::ContainsPublicImported::decode(&buf).unwrap();
+    assert_eq!(got.color, msg.color);
+
+    // and the package of extensions next to them
+    assert_eq!(packages::extensions::Scope.encoded_len(), 0);
+}
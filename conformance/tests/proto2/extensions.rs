@@ -0,0 +1,89 @@
+mod prust {
+    include!("prust/extensions.rs");
+}
+
+use ::prust::{DecodeError, Deserialize, Extendable, ExtensionRegistry, Serialize};
+
+fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    let written = msg.encode(&mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    buf
+}
+
+#[test]
+fn set_and_get() {
+    let mut base = prust::Base {
+        id: Some(1),
+        ..Default::default()
+    };
+
+    base.set_extension(&prust::SCORE, -5);
+    base.set_extension(&prust::LABEL, "hello".to_string());
+    base.set_extension(&prust::IDS, vec![1, 2, 300]);
+    base.set_extension(
+        &prust::NOTES,
+        vec![prust::Note {
+            text: Some("note".to_string()),
+        }],
+    );
+    base.set_extension(&prust::COLOR, prust::Color::Green);
+    base.set_extension(&prust::scope::FLAG, true);
+
+    let got = prust::Base::decode(&encode(&base)).unwrap();
+    assert_eq!(got.id, Some(1));
+    assert_eq!(got.get_extension(&prust::SCORE), Ok(Some(-5)));
+    assert_eq!(
+        got.get_extension(&prust::LABEL),
+        Ok(Some("hello".to_string()))
+    );
+    assert_eq!(got.get_extension(&prust::IDS), Ok(Some(vec![1, 2, 300])));
+    assert_eq!(
+        got.get_extension(&prust::NOTES).unwrap().unwrap()[0].text,
+        Some("note".to_string())
+    );
+    assert_eq!(
+        got.get_extension(&prust::COLOR),
+        Ok(Some(prust::Color::Green))
+    );
+    assert_eq!(got.get_extension(&prust::scope::FLAG), Ok(Some(true)));
+    assert_eq!(prust::scope::FLAG.name(), "extensions.Scope.flag");
+
+    // the message scoping the extension has no fields of its own
+    assert!(encode(&prust::Scope).is_empty());
+
+    let mut empty = prust::Empty::default();
+    assert!(!empty.has_extension(&prust::BIG));
+    empty.set_extension(&prust::BIG, u64::MAX);
+    let got = prust::Empty::decode(&encode(&empty)).unwrap();
+    assert_eq!(got.get_extension(&prust::BIG), Ok(Some(u64::MAX)));
+}
+
+#[test]
+fn registry() {
+    let mut registry = ExtensionRegistry::new();
+    prust::register_extensions(&mut registry);
+    assert_eq!(registry.len(), 7);
+    assert_eq!(
+        registry.find("extensions.Base", 101).map(|info| info.name),
+        Some("extensions.label")
+    );
+
+    // score = 1, field 150 is not registered
+    let data = [0xa0, 0x06, 0x02, 0xb0, 0x09, 0x01];
+    let base = prust::Base::decode_with_registry(&data, &registry).unwrap();
+    assert_eq!(base.get_extension(&prust::SCORE), Ok(Some(1)));
+    assert!(!base.extensions.contains(150));
+
+    // without registry, all fields in extension ranges are kept
+    let base = prust::Base::decode(&data).unwrap();
+    assert!(base.extensions.contains(150));
+    assert_eq!(encode(&base), data);
+
+    // label is not valid UTF-8
+    let data = [0xaa, 0x06, 0x01, 0xff];
+    assert_eq!(
+        prust::Base::decode_with_registry(&data, &registry).err(),
+        Some(DecodeError::Utf8)
+    );
+}
@@ -0,0 +1,23 @@
+mod imported {
+    #![allow(dead_code)]
+
+    include!("prust/foo_baz.rs");
+}
+
+mod prust {
+    pub mod foo {
+        pub mod bar {
+            include!("prust/extern_path/foo_bar.rs");
+        }
+    }
+}
+
+#[test]
+fn extern_types() {
+    assert!(!std::path::Path::new("tests/proto2/prust/extern_path/foo_baz.rs").exists());
+
+    let _ = prust::foo::bar::ContainsImportedNested {
+        m: Some(imported::container_for_nested::NestedMessage {}),
+        e: Some(imported::container_for_nested::NestedEnum::Red),
+    };
+}
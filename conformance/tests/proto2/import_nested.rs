@@ -1,5 +1,12 @@
 mod prust {
-    include!("prust/import_nested.rs");
+    pub mod import_nested {
+        include!("prust/import_nested.rs");
+    }
+    pub mod import_nested_imported {
+        include!("prust/import_nested_imported.rs");
+    }
+
+    pub use import_nested::*;
 }
 
 #[test]
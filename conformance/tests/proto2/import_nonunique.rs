@@ -1,5 +1,15 @@
 mod prust {
-    include!("prust/import_nonunique.rs");
+    pub mod import_nonunique {
+        include!("prust/import_nonunique.rs");
+    }
+    pub mod nonunique_1 {
+        include!("prust/nonunique_1.rs");
+    }
+    pub mod nonunique_2 {
+        include!("prust/nonunique_2.rs");
+    }
+
+    pub use import_nonunique::*;
 }
 
 #[test]
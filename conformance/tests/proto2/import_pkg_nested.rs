@@ -1,7 +1,16 @@
 mod prust {
     #![allow(dead_code)]
 
-    include!("prust/foo_bar.rs");
+    pub mod foo {
+        pub mod bar {
+            include!("prust/foo_bar.rs");
+        }
+        pub mod baz {
+            include!("prust/foo_baz.rs");
+        }
+    }
+
+    pub use foo::bar::*;
 }
 
 #[test]
@@ -0,0 +1,32 @@
+mod prust {
+    pub mod import_public {
+        include!("prust/import_public.rs");
+    }
+    pub mod middle {
+        include!("prust/middle.rs");
+    }
+    pub mod inner {
+        include!("prust/inner.rs");
+    }
+
+    pub use import_public::*;
+}
+
+use ::prust::{Deserialize, Serialize};
+
+#[test]
+fn msg() {
+    let msg = prust::ContainsPublicImported {
+        middle: Some(prust::middle::Middle {
+            inner: Some(prust::inner::Inner { num: Some(1) }),
+        }),
+        inner: Some(prust::inner::Inner { num: Some(2) }),
+        color: Some(prust::inner::inner::Color::Red),
+    };
+
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf).unwrap();
+
+    let got = prust::ContainsPublicImported::decode(&buf).unwrap();
+    assert_eq!(got, msg);
+}
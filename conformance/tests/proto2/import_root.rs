@@ -1,5 +1,12 @@
 mod prust {
-    include!("prust/import_root.rs");
+    pub mod import_root {
+        include!("prust/import_root.rs");
+    }
+    pub mod import_root_imported {
+        include!("prust/import_root_imported.rs");
+    }
+
+    pub use import_root::*;
 }
 
 #[test]
@@ -1,11 +1,17 @@
+mod closed_enums;
+mod containers;
 mod data_types;
 mod default_enum_value;
 mod default_string_escape;
 mod default_values;
 mod deprecated_field;
+mod descriptor_set;
+mod extensions;
+mod extern_path;
 mod import_nested;
 mod import_nonunique;
 mod import_pkg_nested;
+mod import_public;
 mod import_root;
 mod map;
 mod oneof_default_value;
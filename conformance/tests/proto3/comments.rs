@@ -0,0 +1,21 @@
+mod prust {
+    include!("prust/comments.rs");
+}
+
+use ::prust::{Deserialize, Serialize};
+
+#[test]
+fn roundtrip() {
+    let msg = prust::Data {
+        id: 1,
+        name: "name".to_string(),
+        kind: prust::Kind::Text,
+        body: Some(prust::data::Body::Text("text".to_string())),
+    };
+
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf).unwrap();
+
+    let got = prust::Data::decode(&buf).unwrap();
+    assert_eq!(got, msg);
+}
@@ -3,7 +3,16 @@ use ::prust::Deserialize;
 mod prust {
     #![allow(dead_code, unused_imports)]
 
-    include!("prust/data_types.rs");
+    pub mod data_types {
+        include!("prust/data_types.rs");
+    }
+    pub mod a {
+        pub mod b {
+            include!("prust/a_b.rs");
+        }
+    }
+
+    pub use data_types::*;
 }
 
 pub mod a {
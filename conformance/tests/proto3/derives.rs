@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+mod prust {
+    include!("prust/derives.rs");
+}
+
+fn eq_hash<T: Clone + Eq + Hash>(value: T) -> T {
+    value
+}
+
+#[test]
+fn eq_and_hash() {
+    let tree = prust::Tree {
+        name: "root".to_string(),
+        children: vec![prust::Tree::default()],
+        labels: [(
+            "a".to_string(),
+            prust::Leaf {
+                id: 1,
+                weight: Some(-1),
+            },
+        )]
+        .into(),
+        kind: prust::Kind::Root,
+        value: Some(prust::tree::Value::Raw(vec![1, 2])),
+    };
+
+    let set = HashSet::from([eq_hash(tree.clone()), prust::Tree::default()]);
+    assert!(set.contains(&tree));
+    assert!(!set.contains(&prust::Tree {
+        name: "other".to_string(),
+        ..Default::default()
+    }));
+
+    eq_hash(prust::tree::Value::Leaf(prust::Leaf::default()));
+    eq_hash(prust::Kind::Root);
+}
+
+#[test]
+fn clone_and_partial_eq() {
+    // floats rule out `Eq` and `Hash`, but not `Clone` and `PartialEq`
+    let shape = prust::Shape {
+        outline: Some(prust::Outline {
+            scales: vec![prust::Scale { factor: 0.5 }],
+        }),
+        area: Some(prust::shape::Area::Scale(prust::Scale { factor: 2.0 })),
+    };
+    assert_eq!(shape.clone(), shape);
+
+    let nan = prust::Scale { factor: f32::NAN };
+    assert_ne!(nan.clone(), nan);
+
+    // the oneof has no float of its own
+    let side = prust::shape::Area::Side(3);
+    assert_eq!(side.clone(), side);
+}
@@ -0,0 +1,14 @@
+mod prust {
+    include!("prust/different_map_type.rs");
+}
+
+#[test]
+fn map_types() {
+    let data = prust::Data::default();
+    let _: &std::collections::BTreeMap<String, i32> = &data.btreemap;
+    let _: &std::collections::HashMap<String, i32> = &data.hashmap;
+
+    let inner = prust::data::Inner::default();
+    let _: &std::collections::BTreeMap<String, i32> = &inner.btreemap;
+    let _: &std::collections::HashMap<String, i32> = &inner.hashmap;
+}
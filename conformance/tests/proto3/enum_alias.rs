@@ -0,0 +1,34 @@
+#![allow(deprecated)]
+
+mod prust {
+    include!("prust/enum_alias.rs");
+}
+
+use ::prust::{Deserialize, Serialize};
+
+#[test]
+fn aliases() {
+    assert_eq!(prust::State::Running, prust::State::Started);
+    assert_eq!(prust::State::Halted, prust::State::Stopped);
+    assert_eq!(i32::from(prust::State::Running), 1);
+    assert_eq!(prust::State::try_from(2), Ok(prust::State::Stopped));
+    assert_eq!(prust::State::try_from(-1), Ok(prust::State::Negative));
+}
+
+#[test]
+fn roundtrip() {
+    let msg = prust::Data {
+        state: prust::State::Running,
+        states: vec![prust::State::Halted, prust::State::Negative],
+    };
+
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf).unwrap();
+
+    let got = prust::Data::decode(&buf).unwrap();
+    assert_eq!(got.state, prust::State::Started);
+    assert_eq!(
+        got.states,
+        vec![prust::State::Stopped, prust::State::Negative]
+    );
+}
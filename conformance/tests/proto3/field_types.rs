@@ -0,0 +1,165 @@
+use std::net::Ipv4Addr;
+
+use ::prust::{DecodeError, Deserialize, ProtoMessage, ProtoScalar, Serialize};
+
+mod prust {
+    include!("prust/field_types.rs");
+}
+
+mod plain {
+    include!("prust/plain/field_types.rs");
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Id([u8; 4]);
+
+impl ProtoScalar<Vec<u8>> for Id {
+    fn to_proto(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn from_proto(value: Vec<u8>) -> Result<Self, DecodeError> {
+        value
+            .try_into()
+            .map(Id)
+            .map_err(|_| DecodeError::Conversion("id is 4 bytes".to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Price(i64);
+
+impl ProtoMessage<prust::Money> for Price {
+    fn to_message(&self) -> prust::Money {
+        prust::Money {
+            currency: "EUR".to_string(),
+            units: self.0,
+        }
+    }
+
+    fn from_message(msg: prust::Money) -> Result<Self, DecodeError> {
+        match msg.currency.as_str() {
+            "EUR" => Ok(Price(msg.units)),
+            other => Err(DecodeError::Conversion(format!("unknown currency {other}"))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Shade(bool);
+
+impl ProtoScalar<prust::Color> for Shade {
+    fn to_proto(&self) -> prust::Color {
+        if self.0 {
+            prust::Color::Green
+        } else {
+            prust::Color::Red
+        }
+    }
+
+    fn from_proto(value: prust::Color) -> Result<Self, DecodeError> {
+        Ok(Shade(value == prust::Color::Green))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tag(String);
+
+impl ProtoScalar<String> for Tag {
+    fn to_proto(&self) -> String {
+        self.0.clone()
+    }
+
+    fn from_proto(value: String) -> Result<Self, DecodeError> {
+        Ok(Tag(value))
+    }
+}
+
+fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    let written = msg.encode(&mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    buf
+}
+
+#[test]
+fn same_wire_format() {
+    let custom = prust::Data {
+        address: Some(Ipv4Addr::new(10, 0, 0, 1)),
+        id: Some(Id([1, 2, 3, 4])),
+        price: Some(Price(5)),
+        peers: vec![Ipv4Addr::LOCALHOST, Ipv4Addr::BROADCAST],
+        history: vec![Price(3), Price(4)],
+        color: Shade(true),
+        tags: vec![Tag("a".to_string()), Tag("b".to_string())],
+        plain: 7,
+        gateway: Ipv4Addr::new(10, 0, 0, 254),
+    };
+    let plain = plain::Data {
+        address: Some(0x0A00_0001),
+        id: Some(vec![1, 2, 3, 4]),
+        price: Some(plain::Money {
+            currency: "EUR".to_string(),
+            units: 5,
+        }),
+        peers: vec![0x7F00_0001, 0xFFFF_FFFF],
+        history: vec![
+            plain::Money {
+                currency: "EUR".to_string(),
+                units: 3,
+            },
+            plain::Money {
+                currency: "EUR".to_string(),
+                units: 4,
+            },
+        ],
+        color: plain::Color::Green,
+        tags: vec!["a".to_string(), "b".to_string()],
+        plain: 7,
+        gateway: 0x0A00_00FE,
+    };
+
+    let buf = encode(&custom);
+    assert_eq!(buf, encode(&plain));
+
+    let got = prust::Data::decode(&buf).unwrap();
+    assert_eq!(got.address, custom.address);
+    assert_eq!(got.id, custom.id);
+    assert_eq!(got.price, custom.price);
+    assert_eq!(got.peers, custom.peers);
+    assert_eq!(got.history, custom.history);
+    assert_eq!(got.color, custom.color);
+    assert_eq!(got.tags, custom.tags);
+    assert_eq!(got.gateway, custom.gateway);
+
+    // defaults of implicit fields are not written, the ones of custom types
+    // are converted from the zero value
+    let default = prust::Data::default();
+    assert_eq!(default.gateway, Ipv4Addr::UNSPECIFIED);
+    assert_eq!(default.color, Shade(false));
+    assert!(encode(&default).is_empty());
+}
+
+#[test]
+fn conversion_error() {
+    let plain = plain::Data {
+        id: Some(vec![1, 2, 3]),
+        ..Default::default()
+    };
+    assert_eq!(
+        prust::Data::decode(&encode(&plain)).unwrap_err(),
+        DecodeError::Conversion("id is 4 bytes".to_string())
+    );
+
+    let plain = plain::Data {
+        history: vec![plain::Money {
+            currency: "USD".to_string(),
+            units: 1,
+        }],
+        ..Default::default()
+    };
+    assert_eq!(
+        prust::Data::decode(&encode(&plain)).unwrap_err(),
+        DecodeError::Conversion("unknown currency USD".to_string())
+    );
+}
@@ -1,8 +1,14 @@
 mod boxed_field;
+mod comments;
 mod data_types;
+mod derives;
+mod different_map_type;
+mod enum_alias;
 mod enums;
 mod field_attributes;
+mod field_types;
 mod keyword_enum_variant;
+mod open_enums;
 mod optional;
 mod optional_empty;
 mod presence;
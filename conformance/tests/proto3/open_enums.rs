@@ -0,0 +1,47 @@
+mod prust {
+    include!("prust/open_enums.rs");
+}
+
+use ::prust::{Deserialize, Serialize};
+
+fn encode<T: Serialize>(msg: &T) -> Vec<u8> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    let written = msg.encode(&mut buf).unwrap();
+    assert_eq!(written, buf.len());
+    buf
+}
+
+#[test]
+fn unknown_values_are_kept() {
+    // single = 5, optional = -1, packed = [1, 7], non_packed = 9, state = 3
+    let data = [
+        0x08, 0x05, // single
+        0x10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // optional
+        0x1a, 0x02, 0x01, 0x07, // packed
+        0x20, 0x09, // non_packed
+        0x30, 0x03, // choice.state
+    ];
+
+    let msg = prust::Data::decode(&data).unwrap();
+    assert_eq!(msg.single, prust::State::Unrecognized(5));
+    assert_eq!(msg.optional, Some(prust::State::Unrecognized(-1)));
+    assert_eq!(
+        msg.packed,
+        vec![prust::State::On, prust::State::Unrecognized(7)]
+    );
+    assert_eq!(msg.non_packed, vec![prust::State::Unrecognized(9)]);
+    assert!(matches!(
+        msg.choice,
+        Some(prust::data::Choice::State(prust::State::Unrecognized(3)))
+    ));
+
+    assert_eq!(encode(&msg), data);
+}
+
+#[test]
+fn conversion() {
+    assert_eq!(i32::from(prust::State::Off), 2);
+    assert_eq!(i32::from(prust::State::Unrecognized(42)), 42);
+    assert_eq!(prust::State::try_from(1), Ok(prust::State::On));
+    assert_eq!(prust::State::try_from(42), Ok(prust::State::Unrecognized(42)));
+}
@@ -1,2 +1,3 @@
 mod example;
 mod health;
+mod reflection;
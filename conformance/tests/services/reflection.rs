@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use prust::Deserialize;
+use prust::descriptor::FileDescriptorProto;
+use prust_reflection::v1::server_reflection_client::ServerReflectionClient;
+use prust_reflection::v1::server_reflection_request::MessageRequest;
+use prust_reflection::v1::server_reflection_response::MessageResponse;
+use prust_reflection::v1::{ServerReflectionRequest, ServerReflectionResponse};
+use prust_reflection::{Builder, v1alpha};
+use tokio_stream::StreamExt;
+use tonic::transport::{Channel, Server};
+
+mod example {
+    include!("prust/reflection.rs");
+}
+
+async fn ask(
+    client: &mut ServerReflectionClient<Channel>,
+    req: MessageRequest,
+) -> ServerReflectionResponse {
+    let req = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(req),
+    };
+
+    let mut stream = client
+        .server_reflection_info(tokio_stream::iter([req]))
+        .await
+        .unwrap()
+        .into_inner();
+
+    stream.next().await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn reflection() {
+    let builder =
+        Builder::configure().register_encoded_file_descriptor_set(example::FILE_DESCRIPTOR_SET);
+    let v1 = builder.clone().build_v1().unwrap();
+    let v1alpha = builder.build_v1alpha().unwrap();
+
+    let addr = "127.0.0.1:50052".parse().unwrap();
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(v1)
+            .add_service(v1alpha)
+            .serve(addr)
+            .await
+    });
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let conn = tonic::transport::Endpoint::new("http://127.0.0.1:50052")
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+
+    // services
+    let mut client = ServerReflectionClient::new(conn.clone());
+    let resp = ask(&mut client, MessageRequest::ListServices(String::new())).await;
+    let Some(MessageResponse::ListServicesResponse(resp)) = resp.message_response else {
+        panic!("unexpected response {resp:?}");
+    };
+    let mut names = resp
+        .service
+        .into_iter()
+        .map(|svc| svc.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        ["example.Example", "grpc.reflection.v1.ServerReflection"]
+    );
+
+    // the file describing a method
+    let req = MessageRequest::FileContainingSymbol("example.Example.BidiHello".to_string());
+    let resp = ask(&mut client, req).await;
+    let Some(MessageResponse::FileDescriptorResponse(resp)) = resp.message_response else {
+        panic!("unexpected response {resp:?}");
+    };
+    let file = FileDescriptorProto::decode(&resp.file_descriptor_proto[0]).unwrap();
+    assert_eq!(file.name.as_deref(), Some("example.proto"));
+    let methods = file.service[0]
+        .method
+        .iter()
+        .map(|method| method.name.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        methods,
+        ["SayHello", "LotsOfReplies", "LotsOfGreetings", "BidiHello"]
+    );
+
+    // unknown symbols
+    let req = MessageRequest::FileContainingSymbol("example.Missing".to_string());
+    let resp = ask(&mut client, req).await;
+    let Some(MessageResponse::ErrorResponse(resp)) = resp.message_response else {
+        panic!("unexpected response {resp:?}");
+    };
+    assert_eq!(resp.error_code, tonic::Code::NotFound as i32);
+
+    // the older version is served as well
+    let mut client = v1alpha::server_reflection_client::ServerReflectionClient::new(conn);
+    let req = v1alpha::ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(
+            v1alpha::server_reflection_request::MessageRequest::FileByFilename(
+                "example.proto".to_string(),
+            ),
+        ),
+    };
+    let mut stream = client
+        .server_reflection_info(tokio_stream::iter([req]))
+        .await
+        .unwrap()
+        .into_inner();
+    let resp = stream.next().await.unwrap().unwrap();
+    let Some(v1alpha::server_reflection_response::MessageResponse::FileDescriptorResponse(resp)) =
+        resp.message_response
+    else {
+        panic!("unexpected response {resp:?}");
+    };
+    assert_eq!(resp.file_descriptor_proto.len(), 1);
+}
@@ -1,10 +1,11 @@
 #![allow(dead_code)]
 
+mod option;
 mod service;
 
-use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 
+pub use option::{NamePart, OptionName, OptionValue, Options, ProtoOption};
 pub use service::{Function, Method, Service};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
@@ -16,17 +17,73 @@ pub enum Syntax {
     Edition(u32),
 }
 
+/// Comments around a definition, comment markers are stripped already
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comments {
+    /// Comments right before the definition, without blank lines between
+    pub leading: Option<String>,
+    /// Comment after the definition and on the same line
+    pub trailing: Option<String>,
+    /// Comment blocks before the leading ones, separated by blank lines
+    pub detached: Vec<String>,
+    /// Comment blocks before the closing brace of a block, or the end of the
+    /// file
+    pub closing: Vec<String>,
+}
+
+impl Comments {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_none()
+            && self.trailing.is_none()
+            && self.detached.is_empty()
+            && self.closing.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportKind {
+    #[default]
+    Normal,
+    /// `import public`, definitions of the imported file are visible to files
+    /// importing this one
+    Public,
+    /// `import weak`, the imported file is allowed to be missing
+    Weak,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub path: String,
+    pub kind: ImportKind,
+    pub comments: Comments,
+}
+
+/// Where a definition is in the proto file, lines and columns are zero based
+/// like `SourceCodeInfo` of descriptors, and the end is exclusive
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Location {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
 #[derive(Debug, Default)]
 pub struct FileDescriptor {
     pub syntax: Syntax,
+    /// Comments of the `syntax` statement, and the ones at the end of file
+    pub comments: Comments,
     pub package: Option<String>,
-    pub options: HashMap<String, String>,
-    pub imports: Vec<String>,
+    pub package_comments: Comments,
+    pub options: Options,
+    pub imports: Vec<Import>,
 
     // All top-level definitions in this file
     pub messages: Vec<Message>,
     pub enums: Vec<Enum>,
     pub services: Vec<Service>,
+    pub extends: Vec<Extend>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -228,16 +285,18 @@ pub struct Field {
     pub name: String,
     pub number: u32,
 
-    pub options: HashMap<String, String>,
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
 }
 
 impl Display for Field {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.options.len() > 0 {
+        if !self.options.is_empty() {
             let options = self
                 .options
                 .iter()
-                .map(|(k, v)| format!("{k} = {v}"))
+                .map(|option| format!("{} = {}", option.name, option.value))
                 .collect::<Vec<_>>()
                 .join(", ");
             f.write_fmt(format_args!(
@@ -262,55 +321,106 @@ impl Display for Field {
 
 impl Field {
     pub fn packed(&self) -> bool {
-        self.options.get("packed").map(|s| s.as_str()) == Some("true")
+        self.options.get_bool("packed") == Some(true)
     }
 
     #[inline]
     pub fn deprecated(&self) -> bool {
-        self.options.get("deprecated").map(|v| v.as_str()) == Some("true")
+        self.options.get_bool("deprecated") == Some(true)
     }
 
-    #[inline]
-    pub fn default_value(&self) -> Option<&String> {
-        self.options.get("default")
+    /// Default value in the form used by code generators, strings are
+    /// escaped already
+    pub fn default_value(&self) -> Option<String> {
+        self.options.get("default").map(|value| match value {
+            OptionValue::String(s) => s
+                .iter()
+                .flat_map(|b| (*b as char).escape_default())
+                .collect(),
+            value => value.to_string(),
+        })
     }
 }
 
+// enum values are reserved too, and they could be negative
 #[derive(Debug)]
 pub enum Reserved {
-    Single(u32),
-    // (inclusive, exclusive)
-    Range(u32, u32),
+    Single(i64),
+    // (inclusive, inclusive)
+    Range(i64, i64),
     Field(String),
 }
 
 impl Reserved {
-    pub fn contains(&self, value: u32) -> bool {
+    pub fn contains(&self, value: i64) -> bool {
         match self {
             Reserved::Single(v) => *v == value,
-            Reserved::Range(start, end) => value >= *start && value < *end,
+            Reserved::Range(start, end) => value >= *start && value <= *end,
+            Reserved::Field(_) => false,
+        }
+    }
+
+    pub fn contains_name(&self, name: &str) -> bool {
+        match self {
+            Reserved::Field(reserved) => reserved == name,
             _ => false,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Message {
     pub name: String,
     pub fields: Vec<Field>,
     pub reserved: Vec<Reserved>,
-    pub options: HashMap<String, String>,
+    /// Comments of all `reserved` statements
+    pub reserved_comments: Comments,
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
+
+    /// Field numbers reserved for extensions, `extensions 100 to 199;`
+    pub extensions: Vec<Extension>,
 
     /// Message level definitions
     pub messages: Vec<Message>,
     pub enums: Vec<Enum>,
     pub oneofs: Vec<OneOf>,
+    pub extends: Vec<Extend>,
 }
 
 impl Message {
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.fields.is_empty() && self.oneofs.is_empty()
+        self.fields.is_empty() && self.oneofs.is_empty() && self.extensions.is_empty()
+    }
+
+    #[inline]
+    pub fn extendable(&self) -> bool {
+        !self.extensions.is_empty()
+    }
+
+    /// Generated impls touching fields need `#[allow(deprecated)]`
+    #[inline]
+    pub fn has_deprecated_fields(&self) -> bool {
+        self.fields.iter().any(Field::deprecated)
+    }
+}
+
+#[derive(Debug)]
+pub struct EnumVariant {
+    pub name: String,
+    pub value: i32,
+
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
+}
+
+impl EnumVariant {
+    #[inline]
+    pub fn deprecated(&self) -> bool {
+        self.options.get_bool("deprecated") == Some(true)
     }
 }
 
@@ -318,12 +428,40 @@ impl Message {
 pub struct Enum {
     pub name: String,
 
-    pub variants: Vec<(String, i32)>,
+    pub variants: Vec<EnumVariant>,
+    pub reserved: Vec<Reserved>,
+    /// Comments of all `reserved` statements
+    pub reserved_comments: Comments,
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
+
+    // enums defined in proto2 files are closed, values not listed in
+    // `variants` must not be stored in the enum field
+    pub closed: bool,
 }
 
 impl Enum {
     pub fn default_value(&self) -> &str {
-        &self.variants.first().unwrap().0
+        &self.variants.first().unwrap().name
+    }
+
+    #[inline]
+    pub fn allow_alias(&self) -> bool {
+        self.options.get_bool("allow_alias") == Some(true)
+    }
+
+    /// The first variant with the same value, aliases point to it
+    pub fn canonical<'a>(&'a self, variant: &'a EnumVariant) -> &'a EnumVariant {
+        self.variants
+            .iter()
+            .find(|v| v.value == variant.value)
+            .unwrap_or(variant)
+    }
+
+    #[inline]
+    pub fn is_alias(&self, variant: &EnumVariant) -> bool {
+        self.canonical(variant).name != variant.name
     }
 }
 
@@ -332,7 +470,9 @@ pub struct OneOfVarint {
     pub number: u32,
     pub name: String,
     pub typ: FieldType,
-    pub options: HashMap<String, String>,
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
 }
 
 impl OneOfVarint {
@@ -347,11 +487,34 @@ pub struct OneOf {
     pub name: String,
 
     pub variants: Vec<OneOfVarint>,
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
 }
 
+// (inclusive, inclusive)
+#[derive(Debug)]
 pub struct Extension {
     pub start: u32,
     pub end: u32,
 
-    pub properties: BTreeMap<String, String>,
+    pub properties: Options,
+    /// Comments of the `extensions` statement, kept by its first range
+    pub comments: Comments,
+}
+
+impl Extension {
+    #[inline]
+    pub fn contains(&self, number: u32) -> bool {
+        number >= self.start && number <= self.end
+    }
+}
+
+/// `extend Foo { ... }`, the fields are stored in the extension set of `Foo`
+#[derive(Debug)]
+pub struct Extend {
+    pub extendee: String,
+    pub fields: Vec<Field>,
+    pub comments: Comments,
+    pub location: Location,
 }
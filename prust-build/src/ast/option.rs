@@ -0,0 +1,225 @@
+use std::fmt::{Display, Formatter, Write};
+
+/// One segment of an option name, `(google.api.http)` is an extension, and
+/// `get` in `(google.api.http).get` is a plain field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamePart {
+    pub name: String,
+    pub extension: bool,
+}
+
+/// Option name, e.g. `deprecated`, `(gogoproto.nullable)` or
+/// `(google.api.http).additional_bindings`
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionName(pub Vec<NamePart>);
+
+impl OptionName {
+    pub fn simple(name: impl Into<String>) -> Self {
+        Self(vec![NamePart {
+            name: name.into(),
+            extension: false,
+        }])
+    }
+
+    /// The extension this option belongs to, without parentheses
+    pub fn extension(&self) -> Option<&str> {
+        self.0
+            .first()
+            .filter(|part| part.extension)
+            .map(|part| part.name.as_str())
+    }
+}
+
+impl Display for OptionName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, part) in self.0.iter().enumerate() {
+            if index != 0 {
+                f.write_char('.')?;
+            }
+
+            if part.extension {
+                write!(f, "({})", part.name)?;
+            } else {
+                f.write_str(&part.name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValue {
+    /// `true`, `false`, enum values, `inf` and `nan`
+    Ident(String),
+    Integer(i64),
+    Float(f64),
+    /// Unescaped string, it might be not valid UTF-8 for `bytes` fields
+    String(Vec<u8>),
+    /// Text format message, `{ get: "/v1/{name=messages/*}" body: "*" }`,
+    /// extension fields keep the brackets, e.g. `[foo.bar]`
+    Aggregate(Vec<(String, OptionValue)>),
+    /// `[1, 2, 3]` in aggregate values
+    List(Vec<OptionValue>),
+}
+
+impl OptionValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            OptionValue::Ident(ident) if ident == "true" => Some(true),
+            OptionValue::Ident(ident) if ident == "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OptionValue::Ident(ident) => Some(ident),
+            OptionValue::String(s) => std::str::from_utf8(s).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            OptionValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            OptionValue::Integer(value) => Some(*value as f64),
+            OptionValue::Float(value) => Some(*value),
+            OptionValue::Ident(ident) if ident == "inf" => Some(f64::INFINITY),
+            OptionValue::Ident(ident) if ident == "nan" => Some(f64::NAN),
+            _ => None,
+        }
+    }
+
+    /// Lookup a field of aggregate value, the last one wins
+    pub fn get(&self, field: &str) -> Option<&OptionValue> {
+        match self {
+            OptionValue::Aggregate(fields) => fields
+                .iter()
+                .rev()
+                .find_map(|(name, value)| (name == field).then_some(value)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for OptionValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionValue::Ident(ident) => f.write_str(ident),
+            OptionValue::Integer(value) => value.fmt(f),
+            OptionValue::Float(value) if value.is_nan() => f.write_str("nan"),
+            OptionValue::Float(value) => value.fmt(f),
+            OptionValue::String(s) => {
+                f.write_char('"')?;
+                for b in s {
+                    match b {
+                        b'"' => f.write_str("\\\"")?,
+                        b'\\' => f.write_str("\\\\")?,
+                        b'\n' => f.write_str("\\n")?,
+                        b'\r' => f.write_str("\\r")?,
+                        b'\t' => f.write_str("\\t")?,
+                        0x20..=0x7e => f.write_char(*b as char)?,
+                        _ => write!(f, "\\{b:03o}")?,
+                    }
+                }
+                f.write_char('"')
+            }
+            OptionValue::Aggregate(fields) => {
+                f.write_char('{')?;
+                for (name, value) in fields {
+                    match value {
+                        OptionValue::Aggregate(_) => write!(f, " {name} {value}")?,
+                        _ => write!(f, " {name}: {value}")?,
+                    }
+                }
+                f.write_str(" }")
+            }
+            OptionValue::List(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 {
+                        f.write_str(", ")?;
+                    }
+                    value.fmt(f)?;
+                }
+                f.write_char(']')
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtoOption {
+    pub name: OptionName,
+    pub value: OptionValue,
+}
+
+/// Options in declaration order. Names are compared in the written form,
+/// e.g. `packed` or `(google.api.http).get`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options(Vec<ProtoOption>);
+
+impl Options {
+    #[inline]
+    pub fn push(&mut self, option: ProtoOption) {
+        self.0.push(option);
+    }
+
+    #[inline]
+    pub fn insert(&mut self, name: impl Into<String>, value: OptionValue) {
+        self.push(ProtoOption {
+            name: OptionName::simple(name),
+            value,
+        })
+    }
+
+    /// The last one wins if an option is set more than once
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        self.0
+            .iter()
+            .rev()
+            .find(|option| option.name.to_string() == name)
+            .map(|option| &option.value)
+    }
+
+    #[inline]
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).and_then(OptionValue::as_bool)
+    }
+
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// All options set by the extension, sub paths included, e.g.
+    /// `extension("google.api.http")` returns both `(google.api.http)` and
+    /// `(google.api.http).get`
+    pub fn extension<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a ProtoOption> {
+        self.0
+            .iter()
+            .filter(move |option| option.name.extension() == Some(name))
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &ProtoOption> {
+        self.0.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -1,3 +1,5 @@
+use super::{Comments, Location, Options};
+
 #[derive(Debug, PartialEq)]
 pub enum Method {
     Unary,
@@ -13,6 +15,10 @@ pub struct Function {
 
     pub request: String,
     pub response: String,
+
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
 }
 
 #[derive(Debug, PartialEq)]
@@ -20,4 +26,7 @@ pub struct Service {
     pub name: String,
 
     pub functions: Vec<Function>,
+    pub options: Options,
+    pub comments: Comments,
+    pub location: Location,
 }
@@ -0,0 +1,801 @@
+//! Breaking changes between two versions of proto files. Definitions are
+//! matched by name, fields by number, and every change that breaks peers on
+//! the wire or code using the generated types is reported, e.g. a field
+//! removed without reserving its number, or a field number reused with
+//! another type.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::ast::{
+    Enum, Extend, Field, FieldType, FileDescriptor, Function, Label, Message, Method, Reserved,
+    Service, Syntax,
+};
+use crate::codegen::config::{load_imports, load_proto};
+use crate::codegen::context::Context;
+use crate::lint::{join, push_json_string};
+use crate::{Config, Error};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// A file exists in the old version only
+    FileRemoved,
+    /// The package of a file changed, which renames everything in it
+    PackageChanged,
+    MessageRemoved,
+    EnumRemoved,
+    ServiceRemoved,
+    /// A field is removed without reserving its number
+    FieldRemoved,
+    /// A field number is used by a field with another name
+    FieldRenamed,
+    /// A field number is used by a field with another type
+    FieldTypeChanged,
+    /// A field changed between repeated, map and singular, or between the
+    /// kinds of singular fields
+    FieldLabelChanged,
+    /// A field moved into, out of or between oneofs
+    FieldOneofChanged,
+    /// An enum value is removed without reserving its number
+    EnumValueRemoved,
+    /// An enum value has another number
+    EnumValueChanged,
+    MethodRemoved,
+    /// The request, the response or the streaming of a method changed
+    MethodSignatureChanged,
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::FileRemoved => "file-removed",
+            Rule::PackageChanged => "package-changed",
+            Rule::MessageRemoved => "message-removed",
+            Rule::EnumRemoved => "enum-removed",
+            Rule::ServiceRemoved => "service-removed",
+            Rule::FieldRemoved => "field-removed",
+            Rule::FieldRenamed => "field-renamed",
+            Rule::FieldTypeChanged => "field-type-changed",
+            Rule::FieldLabelChanged => "field-label-changed",
+            Rule::FieldOneofChanged => "field-oneof-changed",
+            Rule::EnumValueRemoved => "enum-value-removed",
+            Rule::EnumValueChanged => "enum-value-changed",
+            Rule::MethodRemoved => "method-removed",
+            Rule::MethodSignatureChanged => "method-signature-changed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    /// The file in the new version, or the old one if it's removed
+    pub file: PathBuf,
+    pub rule: Rule,
+    /// Fully qualified name of the definition in the old version, e.g.
+    /// `pkg.Message.field`, or the relative path of a removed file
+    pub path: String,
+    pub message: String,
+}
+
+impl Change {
+    /// One line JSON object, for tools consuming the output
+    pub fn to_json(&self) -> String {
+        let mut buf = String::new();
+
+        buf.push_str("{\"file\":");
+        push_json_string(&mut buf, &self.file.to_string_lossy());
+        buf.push_str(",\"rule\":");
+        push_json_string(&mut buf, self.rule.as_str());
+        buf.push_str(",\"path\":");
+        push_json_string(&mut buf, &self.path);
+        buf.push_str(",\"message\":");
+        push_json_string(&mut buf, &self.message);
+        buf.push('}');
+
+        buf
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: breaking[{}] {}: {}",
+            self.file.display(),
+            self.rule.as_str(),
+            self.path,
+            self.message
+        )
+    }
+}
+
+/// Find breaking changes from `old` to `new`, both are proto files or both
+/// are directories, whose `.proto` files are matched by relative path.
+///
+/// Imports are searched in the directory of the version first, then in
+/// `includes`. Parse errors and missing files are returned as `Err`.
+pub fn breaking<P: AsRef<Path>>(includes: &[P], old: P, new: P) -> Result<Vec<Change>, Error> {
+    let (old, new) = (old.as_ref(), new.as_ref());
+
+    let pairs = if old.is_dir() {
+        let mut files = vec![];
+        proto_files(old, Path::new(""), &mut files)?;
+        files.sort();
+        files
+            .into_iter()
+            .map(|file| (file.display().to_string(), old.join(&file), new.join(&file)))
+            .collect()
+    } else {
+        vec![(
+            old.display().to_string(),
+            old.to_path_buf(),
+            new.to_path_buf(),
+        )]
+    };
+
+    let includes_of = |path: &Path, root: &Path| {
+        let root = if root.is_dir() {
+            root
+        } else {
+            path.parent().unwrap_or(Path::new(""))
+        };
+        std::iter::once(root.to_path_buf())
+            .chain(
+                includes
+                    .iter()
+                    .map(|include| include.as_ref().to_path_buf()),
+            )
+            .collect::<Vec<_>>()
+    };
+
+    let mut changes = vec![];
+    for (name, old_path, new_path) in pairs {
+        if !new_path.exists() {
+            changes.push(Change {
+                file: old_path.clone(),
+                rule: Rule::FileRemoved,
+                path: name,
+                message: "file is removed".to_string(),
+            });
+            continue;
+        }
+
+        let (old_fd, old_imports) = load(&old_path, &includes_of(&old_path, old))?;
+        let (new_fd, new_imports) = load(&new_path, &includes_of(&new_path, new))?;
+        changes.extend(breaking_file(
+            &new_path,
+            (&old_fd, &old_imports),
+            (&new_fd, &new_imports),
+        ));
+    }
+
+    Ok(changes)
+}
+
+/// Find breaking changes between two parsed versions of a file, each comes
+/// with the files it imports, keyed by the import path
+pub fn breaking_file(
+    file: &Path,
+    old: (&FileDescriptor, &HashMap<String, FileDescriptor>),
+    new: (&FileDescriptor, &HashMap<String, FileDescriptor>),
+) -> Vec<Change> {
+    let config = Config::default();
+    let mut checker = Checker {
+        file,
+        old: Context {
+            fd: old.0,
+            config: &config,
+            imports: old.1,
+            messages: vec![],
+        },
+        new: Context {
+            fd: new.0,
+            config: &config,
+            imports: new.1,
+            messages: vec![],
+        },
+        changes: vec![],
+    };
+
+    checker.check_file(old.0, new.0);
+    checker.changes
+}
+
+fn load(
+    path: &Path,
+    includes: &[PathBuf],
+) -> Result<(FileDescriptor, HashMap<String, FileDescriptor>), Error> {
+    let fd = load_proto(path)?;
+    let mut imports = HashMap::new();
+    for import in &fd.imports {
+        load_imports(&import.path, includes, &mut imports)?;
+    }
+
+    Ok((fd, imports))
+}
+
+// `.proto` files under `dir`, relative to the root
+fn proto_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            proto_files(root, &path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "proto") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// a field, an extension or a field of oneof
+struct Member<'a> {
+    name: &'a str,
+    number: u32,
+    typ: &'a FieldType,
+    /// `None` for fields of oneof
+    label: Option<&'static str>,
+    oneof: Option<&'a str>,
+}
+
+impl<'a> Member<'a> {
+    fn field(syntax: Syntax, field: &'a Field) -> Self {
+        Member {
+            name: &field.name,
+            number: field.number,
+            typ: &field.typ,
+            label: Some(label(syntax, field)),
+            oneof: None,
+        }
+    }
+}
+
+fn members(syntax: Syntax, msg: &Message) -> Vec<Member<'_>> {
+    msg.fields
+        .iter()
+        .map(|field| Member::field(syntax, field))
+        .chain(msg.oneofs.iter().flat_map(|oneof| {
+            oneof.variants.iter().map(|variant| Member {
+                name: &variant.name,
+                number: variant.number,
+                typ: &variant.typ,
+                label: None,
+                oneof: Some(oneof.name.as_str()),
+            })
+        }))
+        .collect()
+}
+
+struct Checker<'a> {
+    file: &'a Path,
+    old: Context<'a>,
+    new: Context<'a>,
+    changes: Vec<Change>,
+}
+
+impl<'a> Checker<'a> {
+    fn report(&mut self, rule: Rule, path: impl Into<String>, message: impl Into<String>) {
+        self.changes.push(Change {
+            file: self.file.to_path_buf(),
+            rule,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn check_file(&mut self, old: &'a FileDescriptor, new: &'a FileDescriptor) {
+        let scope = old.package.clone().unwrap_or_default();
+
+        if old.package != new.package {
+            self.report(
+                Rule::PackageChanged,
+                scope.as_str(),
+                format!(
+                    "package changed from `{}` to `{}`",
+                    old.package.as_deref().unwrap_or_default(),
+                    new.package.as_deref().unwrap_or_default()
+                ),
+            );
+        }
+
+        self.check_definitions(
+            &scope,
+            (&old.messages, &new.messages),
+            (&old.enums, &new.enums),
+            (&old.extends, &new.extends),
+        );
+
+        for svc in &old.services {
+            match new.services.iter().find(|other| other.name == svc.name) {
+                Some(other) => self.check_service(&scope, svc, other),
+                None => self.report(
+                    Rule::ServiceRemoved,
+                    join(&scope, &svc.name),
+                    format!("service `{}` is removed", svc.name),
+                ),
+            }
+        }
+    }
+
+    // messages, enums and extensions of a file or a message
+    fn check_definitions(
+        &mut self,
+        scope: &str,
+        messages: (&'a [Message], &'a [Message]),
+        enums: (&[Enum], &[Enum]),
+        extends: (&[Extend], &[Extend]),
+    ) {
+        for msg in messages.0 {
+            match messages.1.iter().find(|other| other.name == msg.name) {
+                Some(other) => self.check_message(scope, msg, other),
+                None => self.report(
+                    Rule::MessageRemoved,
+                    join(scope, &msg.name),
+                    format!("message `{}` is removed", msg.name),
+                ),
+            }
+        }
+
+        for en in enums.0 {
+            match enums.1.iter().find(|other| other.name == en.name) {
+                Some(other) => self.check_enum(scope, en, other),
+                None => self.report(
+                    Rule::EnumRemoved,
+                    join(scope, &en.name),
+                    format!("enum `{}` is removed", en.name),
+                ),
+            }
+        }
+
+        self.check_extends(scope, extends.0, extends.1);
+    }
+
+    fn check_message(&mut self, scope: &str, old: &'a Message, new: &'a Message) {
+        let scope = join(scope, &old.name);
+        self.old.messages.push(old);
+        self.new.messages.push(new);
+
+        let news = members(self.new.fd.syntax, new);
+        for member in members(self.old.fd.syntax, old) {
+            let path = join(&scope, member.name);
+
+            let Some(other) = news.iter().find(|other| other.number == member.number) else {
+                if !reserved(&new.reserved, member.number as i64) {
+                    self.report(
+                        Rule::FieldRemoved,
+                        path,
+                        format!(
+                            "field `{}` = {} is removed without reserving its number",
+                            member.name, member.number
+                        ),
+                    );
+                }
+                continue;
+            };
+
+            self.check_field(&path, &member, other);
+
+            if member.oneof != other.oneof {
+                let describe = |oneof: Option<&str>| match oneof {
+                    Some(name) => format!("oneof `{name}`"),
+                    None => "no oneof".to_string(),
+                };
+                self.report(
+                    Rule::FieldOneofChanged,
+                    path,
+                    format!(
+                        "field `{}` moved from {} to {}",
+                        member.name,
+                        describe(member.oneof),
+                        describe(other.oneof)
+                    ),
+                );
+            }
+        }
+
+        self.check_definitions(
+            &scope,
+            (&old.messages, &new.messages),
+            (&old.enums, &new.enums),
+            (&old.extends, &new.extends),
+        );
+
+        self.old.messages.pop();
+        self.new.messages.pop();
+    }
+
+    // fields matched by number already
+    fn check_field(&mut self, path: &str, old: &Member, new: &Member) {
+        if old.name != new.name {
+            self.report(
+                Rule::FieldRenamed,
+                path,
+                format!(
+                    "field {} is renamed from `{}` to `{}`",
+                    old.number, old.name, new.name
+                ),
+            );
+        }
+
+        let (old_type, new_type) = (type_name(&self.old, old.typ), type_name(&self.new, new.typ));
+        if old_type != new_type {
+            self.report(
+                Rule::FieldTypeChanged,
+                path,
+                format!(
+                    "field {} changed type from `{old_type}` to `{new_type}`",
+                    old.number
+                ),
+            );
+        }
+
+        // moving into or out of oneofs is reported by the caller
+        if let (Some(old_label), Some(new_label)) = (old.label, new.label)
+            && old_label != new_label
+        {
+            self.report(
+                Rule::FieldLabelChanged,
+                path,
+                format!(
+                    "field {} changed from {old_label} to {new_label}",
+                    old.number
+                ),
+            );
+        }
+    }
+
+    // extensions are matched by the extendee and the number
+    fn check_extends(&mut self, scope: &str, old: &[Extend], new: &[Extend]) {
+        for extend in old {
+            let extendee = full_type_name(&self.old, &extend.extendee);
+            let others = new
+                .iter()
+                .filter(|other| full_type_name(&self.new, &other.extendee) == extendee)
+                .flat_map(|other| &other.fields)
+                .map(|field| Member::field(self.new.fd.syntax, field))
+                .collect::<Vec<_>>();
+
+            for field in &extend.fields {
+                let path = join(scope, &field.name);
+                match others.iter().find(|other| other.number == field.number) {
+                    Some(other) => {
+                        let field = Member::field(self.old.fd.syntax, field);
+                        self.check_field(&path, &field, other);
+                    }
+                    None => self.report(
+                        Rule::FieldRemoved,
+                        path,
+                        format!(
+                            "extension `{}` = {} of `{extendee}` is removed",
+                            field.name, field.number
+                        ),
+                    ),
+                }
+            }
+        }
+    }
+
+    fn check_enum(&mut self, scope: &str, old: &Enum, new: &Enum) {
+        let scope = join(scope, &old.name);
+
+        for variant in &old.variants {
+            // enum values are siblings of the enum, just like C++
+            let path = join(
+                scope.rsplit_once('.').map_or("", |(parent, _)| parent),
+                &variant.name,
+            );
+
+            match new.variants.iter().find(|other| other.name == variant.name) {
+                Some(other) if other.value != variant.value => self.report(
+                    Rule::EnumValueChanged,
+                    path,
+                    format!(
+                        "enum value `{}` changed from {} to {}",
+                        variant.name, variant.value, other.value
+                    ),
+                ),
+                Some(_) => {}
+                None if reserved(&new.reserved, variant.value as i64) => {}
+                None => self.report(
+                    Rule::EnumValueRemoved,
+                    path,
+                    format!(
+                        "enum value `{}` = {} is removed without reserving its number",
+                        variant.name, variant.value
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn check_service(&mut self, scope: &str, old: &Service, new: &Service) {
+        let scope = join(scope, &old.name);
+
+        for func in &old.functions {
+            let path = join(&scope, &func.name);
+            let Some(other) = new.functions.iter().find(|other| other.name == func.name) else {
+                self.report(
+                    Rule::MethodRemoved,
+                    path,
+                    format!("method `{}` is removed", func.name),
+                );
+                continue;
+            };
+
+            let signature = |cx: &Context, func: &Function| {
+                let stream = |streaming: bool| if streaming { "stream " } else { "" };
+                let (client, server) = match func.method {
+                    Method::Unary => (false, false),
+                    Method::ClientStreaming => (true, false),
+                    Method::ServerStreaming => (false, true),
+                    Method::BidiStreaming => (true, true),
+                };
+                format!(
+                    "({}{}) returns ({}{})",
+                    stream(client),
+                    full_type_name(cx, &func.request),
+                    stream(server),
+                    full_type_name(cx, &func.response)
+                )
+            };
+
+            let (old_signature, new_signature) =
+                (signature(&self.old, func), signature(&self.new, other));
+            if old_signature != new_signature {
+                self.report(
+                    Rule::MethodSignatureChanged,
+                    path,
+                    format!("method changed from `{old_signature}` to `{new_signature}`"),
+                );
+            }
+        }
+    }
+}
+
+fn reserved(reserved: &[Reserved], number: i64) -> bool {
+    reserved.iter().any(|reserved| reserved.contains(number))
+}
+
+// the fully qualified name, or the name as written if it can't be resolved.
+// Types of the package of the file are relative to it, a changed package is
+// reported once only.
+fn full_type_name(cx: &Context, typ: &str) -> String {
+    let full_name = match cx.resolve_symbol(typ) {
+        Ok(resolved) => resolved.full_name,
+        Err(_) => return typ.trim_start_matches('.').to_string(),
+    };
+
+    match &cx.fd.package {
+        Some(package) => match full_name.strip_prefix(&format!("{package}.")) {
+            Some(name) => name.to_string(),
+            None => full_name,
+        },
+        None => full_name,
+    }
+}
+
+fn type_name(cx: &Context, typ: &FieldType) -> String {
+    match typ {
+        FieldType::Message(name) => full_type_name(cx, name),
+        FieldType::Map(key, value) => {
+            format!("map<{}, {}>", type_name(cx, key), type_name(cx, value))
+        }
+        typ => typ.to_string(),
+    }
+}
+
+fn label(syntax: Syntax, field: &Field) -> &'static str {
+    if matches!(field.typ, FieldType::Map(..)) {
+        return "map";
+    }
+
+    match (&field.label, syntax) {
+        (Label::Repeated, _) => "repeated",
+        (Label::Optional, _) => "optional",
+        // singular fields without label
+        (Label::Required, Syntax::Proto3) => "implicit",
+        (Label::Required, _) => "required",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(old: &str, new: &str) -> Vec<(Rule, String)> {
+        let old = crate::parse(old.as_bytes()).unwrap();
+        let new = crate::parse(new.as_bytes()).unwrap();
+        let imports = HashMap::new();
+
+        breaking_file(Path::new("test.proto"), (&old, &imports), (&new, &imports))
+            .into_iter()
+            .map(|change| (change.rule, change.path))
+            .collect()
+    }
+
+    #[test]
+    fn message() {
+        let old = r#"
+        syntax = "proto3";
+        package pkg;
+
+        message Foo {
+            string name = 1;
+            int32 id = 2;
+            repeated string tags = 3;
+            string removed = 4;
+            string dropped = 5;
+            Bar bar = 6;
+            oneof body {
+                string text = 7;
+            }
+            string note = 8;
+            message Bar {}
+        }
+
+        message Gone {}
+        "#;
+        let new = r#"
+        syntax = "proto3";
+        package pkg;
+
+        message Foo {
+            reserved 5;
+
+            string title = 1;
+            int64 id = 2;
+            string tags = 3;
+            Foo.Bar bar = 6;
+            string text = 7;
+            oneof body {
+                string note = 8;
+            }
+            message Bar {}
+            string added = 9;
+        }
+        "#;
+
+        assert_eq!(
+            rules(old, new),
+            [
+                (Rule::FieldRenamed, "pkg.Foo.name".to_string()),
+                (Rule::FieldTypeChanged, "pkg.Foo.id".to_string()),
+                (Rule::FieldLabelChanged, "pkg.Foo.tags".to_string()),
+                (Rule::FieldRemoved, "pkg.Foo.removed".to_string()),
+                (Rule::FieldOneofChanged, "pkg.Foo.note".to_string()),
+                (Rule::FieldOneofChanged, "pkg.Foo.text".to_string()),
+                (Rule::MessageRemoved, "pkg.Gone".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged() {
+        let input = r#"
+        syntax = "proto3";
+        package pkg;
+
+        message Foo {
+            map<string, Foo> children = 1;
+            optional Kind kind = 2;
+        }
+        enum Kind {
+            KIND_UNSPECIFIED = 0;
+        }
+        service Svc {
+            rpc Get(Foo) returns (stream Foo);
+        }
+        "#;
+
+        assert_eq!(rules(input, input), []);
+    }
+
+    #[test]
+    fn enums_and_services() {
+        let old = r#"
+        syntax = "proto3";
+        package pkg;
+
+        enum Kind {
+            KIND_UNSPECIFIED = 0;
+            KIND_A = 1;
+            KIND_B = 2;
+            KIND_C = 3;
+        }
+        enum Gone {
+            GONE_UNSPECIFIED = 0;
+        }
+
+        message Req {}
+        message Resp {}
+
+        service Svc {
+            rpc Get(Req) returns (Resp);
+            rpc Watch(Req) returns (stream Resp);
+            rpc Put(Req) returns (Resp);
+            rpc Delete(Req) returns (Resp);
+        }
+        service Old {}
+        "#;
+        let new = r#"
+        syntax = "proto3";
+        package pkg;
+
+        enum Kind {
+            reserved 2;
+            KIND_UNSPECIFIED = 0;
+            KIND_A = 4;
+        }
+
+        message Req {}
+        message Resp {}
+
+        service Svc {
+            rpc Get(Req) returns (Resp);
+            rpc Watch(Req) returns (Resp);
+            rpc Put(Resp) returns (Resp);
+        }
+        "#;
+
+        assert_eq!(
+            rules(old, new),
+            [
+                (Rule::EnumValueChanged, "pkg.KIND_A".to_string()),
+                (Rule::EnumValueRemoved, "pkg.KIND_C".to_string()),
+                (Rule::EnumRemoved, "pkg.Gone".to_string()),
+                (Rule::MethodSignatureChanged, "pkg.Svc.Watch".to_string()),
+                (Rule::MethodSignatureChanged, "pkg.Svc.Put".to_string()),
+                (Rule::MethodRemoved, "pkg.Svc.Delete".to_string()),
+                (Rule::ServiceRemoved, "pkg.Old".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn proto2() {
+        let old = r#"
+        package pkg;
+
+        message Foo {
+            optional string name = 1;
+            required int32 id = 2;
+            extensions 100 to 200;
+        }
+        extend Foo {
+            optional int32 ext = 100;
+            optional int32 gone = 101;
+        }
+        "#;
+        let new = r#"
+        package pkg;
+
+        message Foo {
+            required string name = 1;
+            required int32 id = 2;
+            extensions 100 to 200;
+        }
+        extend Foo {
+            optional sint32 ext = 100;
+        }
+        "#;
+
+        assert_eq!(
+            rules(old, new),
+            [
+                (Rule::FieldLabelChanged, "pkg.Foo.name".to_string()),
+                (Rule::FieldTypeChanged, "pkg.ext".to_string()),
+                (Rule::FieldRemoved, "pkg.gone".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn package() {
+        let old = "syntax = \"proto3\"; package a; message Foo { Foo foo = 1; }";
+        let new = "syntax = \"proto3\"; package b; message Foo { Foo foo = 1; }";
+
+        assert_eq!(rules(old, new), [(Rule::PackageChanged, "a".to_string())]);
+    }
+}
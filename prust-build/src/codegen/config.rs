@@ -1,11 +1,16 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 
+use prust::descriptor::FileDescriptorSet;
+use prust::{Deserialize, Serialize};
+
 use super::Buffer;
-use super::context::Context;
-use super::generate::generate_proto;
-use super::sanitize::sanitize_filepath;
-use crate::ast::FileDescriptor;
+use super::context::{Context, ROOT, package_of};
+use super::generate::generate_package;
+use super::path::PathMap;
+use super::sanitize::{sanitize_filepath, snake};
+use crate::ast::{FileDescriptor, Message};
+use crate::descriptor;
 use crate::{Error, parse};
 
 #[derive(Debug, Default)]
@@ -18,18 +23,32 @@ pub enum MapType {
 pub struct Config {
     output: Option<PathBuf>,
     filename: Option<String>,
+    include_file: Option<String>,
+    file_descriptor_set_path: Option<PathBuf>,
 
     pub(crate) build_server: bool,
     pub(crate) build_client: bool,
     pub(crate) no_std: bool,
-    pub(crate) message_attributes: HashMap<String, Vec<String>>,
-    pub(crate) enum_attributes: HashMap<String, Vec<String>>,
-    pub(crate) oneof_attributes: HashMap<String, Vec<String>>,
-    pub(crate) field_attributes: HashMap<String, Vec<String>>,
-
-    pub(crate) skip_serialize: HashSet<String>,
-    pub(crate) skip_deserialize: HashSet<String>,
-    pub(crate) tree_map: HashMap<String, MapType>,
+    pub(crate) open_enums: bool,
+    pub(crate) skip_deprecated_fields: bool,
+    pub(crate) derive_clone: bool,
+    pub(crate) derive_partial_eq: bool,
+    pub(crate) derive_eq: bool,
+    pub(crate) derive_hash: bool,
+    pub(crate) embed_descriptors: bool,
+    pub(crate) message_attributes: PathMap<String>,
+    pub(crate) enum_attributes: PathMap<String>,
+    pub(crate) oneof_attributes: PathMap<String>,
+    pub(crate) field_attributes: PathMap<String>,
+
+    pub(crate) skip_serialize: PathMap<()>,
+    pub(crate) skip_deserialize: PathMap<()>,
+    pub(crate) tree_map: PathMap<MapType>,
+    pub(crate) field_types: PathMap<String>,
+    pub(crate) string_types: PathMap<String>,
+    pub(crate) bytes_types: PathMap<String>,
+    pub(crate) repeated_types: PathMap<String>,
+    pub(crate) extern_paths: HashMap<String, String>,
 }
 
 impl Default for Config {
@@ -37,7 +56,16 @@ impl Default for Config {
         Self {
             output: None,
             filename: None,
+            include_file: None,
+            file_descriptor_set_path: None,
             no_std: false,
+            open_enums: false,
+            skip_deprecated_fields: false,
+            derive_clone: true,
+            derive_partial_eq: true,
+            derive_eq: true,
+            derive_hash: true,
+            embed_descriptors: false,
             build_client: true,
             build_server: true,
             message_attributes: Default::default(),
@@ -47,6 +75,11 @@ impl Default for Config {
             skip_deserialize: Default::default(),
             skip_serialize: Default::default(),
             tree_map: Default::default(),
+            field_types: Default::default(),
+            string_types: Default::default(),
+            bytes_types: Default::default(),
+            repeated_types: Default::default(),
+            extern_paths: Default::default(),
         }
     }
 }
@@ -73,69 +106,188 @@ impl Config {
         self
     }
 
+    /// Generate open enums, so unknown enum values no longer fail decoding.
+    ///
+    /// Enums defined in proto3 files get an extra `Unrecognized(i32)` variant,
+    /// which keeps the raw value and encodes it unchanged. Enums defined in
+    /// proto2 files are closed, the records of unknown values of them are kept
+    /// in the `unknown_fields` of the message, and written back on encoding.
+    pub fn open_enums(&mut self, open_enums: bool) -> &mut Self {
+        self.open_enums = open_enums;
+        self
+    }
+
+    /// Drop fields marked `[deprecated = true]` from generated structs.
+    ///
+    /// By default they are kept with a `#[deprecated]` attribute, since older
+    /// peers might still send them. Once dropped, they are skipped as unknown
+    /// fields on decoding.
+    pub fn skip_deprecated_fields(&mut self, skip: bool) -> &mut Self {
+        self.skip_deprecated_fields = skip;
+        self
+    }
+
+    /// Derive `Clone` for messages and oneofs, on by default. Enums are
+    /// always `Clone` and `Copy`.
+    ///
+    /// Types of `field_type` must implement the derives turned on.
+    pub fn derive_clone(&mut self, derive: bool) -> &mut Self {
+        self.derive_clone = derive;
+        self
+    }
+
+    /// Derive `PartialEq` for messages and oneofs, on by default. Enums are
+    /// always `PartialEq`.
+    pub fn derive_partial_eq(&mut self, derive: bool) -> &mut Self {
+        self.derive_partial_eq = derive;
+        self
+    }
+
+    /// Derive `Eq` along with `PartialEq`, on by default. Messages and oneofs
+    /// get it only if no float is reachable from their fields, nor a type of
+    /// `field_type`, `string_type`, `bytes_type`, `repeated_type` or
+    /// `extern_path`, which might not implement it. Add it with
+    /// `message_attribute` for such types that do.
+    pub fn derive_eq(&mut self, derive: bool) -> &mut Self {
+        self.derive_eq = derive;
+        self
+    }
+
+    /// Derive `Hash`, on by default, with the same rules as `derive_eq`. Map
+    /// fields must be `BTreeMap` too, see `btree_map`.
+    pub fn derive_hash(&mut self, derive: bool) -> &mut Self {
+        self.derive_hash = derive;
+        self
+    }
+
+    /// Write a `FileDescriptorSet` describing the compiled files and all
+    /// the files they import, source info included, to `path`.
+    ///
+    /// Files are named by the path relative to the include directory they
+    /// are found in, like `protoc --include_imports --include_source_info`.
+    pub fn file_descriptor_set_path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.file_descriptor_set_path = Some(path.into());
+        self
+    }
+
+    /// Embed the descriptors of files defining services in the generated
+    /// code, as `pub const FILE_DESCRIPTOR_SET: &[u8]` holding an encoded
+    /// `FileDescriptorSet` of the file and its imports, without source info.
+    ///
+    /// This is what gRPC server reflection serves, see `prust-reflection`.
+    pub fn embed_descriptors(&mut self, embed: bool) -> &mut Self {
+        self.embed_descriptors = embed;
+        self
+    }
+
+    /// Name the file generated for the package of the compiled files,
+    /// instead of the package name. Files of other packages they import
+    /// keep their names, compiling files of several packages fails.
     pub fn filename(&mut self, name: &str) -> &mut Self {
         self.filename = Some(name.to_string());
         self
     }
 
-    /// set custom attributes for a message
+    /// Write a file named `name` next to the generated ones, which nests the
+    /// modules of packages and includes the file of each, e.g.
     ///
-    /// path could be something like `foo.bar`, `foo.bar.SomeMessage`
+    /// ```text
+    /// pub mod foo {
+    ///     pub mod bar {
+    ///         include!("foo_bar.rs");
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Packages refer to each other by paths in this tree, so it's the easy
+    /// way to use them, `include!` the file in a module of the crate.
+    pub fn include_file(&mut self, name: &str) -> &mut Self {
+        self.include_file = Some(name.to_string());
+        self
+    }
+
+    /// Use types defined by a crate already, instead of generating them.
+    ///
+    /// `proto_path` is a fully qualified package or type, e.g.
+    /// `.company.common` or `.company.common.Id`, and `rust_path` the path
+    /// of it in rust, e.g. `::common_proto`. Packages under `proto_path`
+    /// are mapped too, `.company.common.v1.Id` is `::common_proto::v1::Id`.
+    /// Packages mapped are not generated, unless the compiled files are of
+    /// them.
+    pub fn extern_path<P: ToString, R: ToString>(
+        &mut self,
+        proto_path: P,
+        rust_path: R,
+    ) -> &mut Self {
+        let proto_path = proto_path.to_string();
+        self.extern_paths.insert(
+            proto_path.trim_start_matches('.').to_string(),
+            rust_path.to_string(),
+        );
+        self
+    }
+
+    /// Add an attribute to messages, e.g. `#[derive(serde::Serialize)]`.
+    ///
+    /// Paths of this and the other selectors, like `field_attribute`,
+    /// `btree_map` and `skip_serialize`, are one of
+    ///
+    /// - `.`, everything
+    /// - fully qualified, e.g. `.foo.bar` or `.foo.bar.Data`, the element
+    ///   and everything defined in it, so a package selects all of it
+    /// - relative, e.g. `Data` or `Outer.Data`, every element whose fully
+    ///   qualified name ends with it
+    ///
+    /// `*` in a path matches any characters of a segment, e.g. `.foo.*` or
+    /// `*Request`.
+    ///
+    /// Attributes of every path matching are added. For the other
+    /// selectors, the most specific path wins, in the order of the fully
+    /// qualified name itself, relative paths, prefixes and `.`. Longer paths
+    /// win among them, then ones without `*`, then the ones set later.
     pub fn message_attribute<P: ToString, A: ToString>(
         &mut self,
         path: P,
         attribute: A,
     ) -> &mut Self {
         self.message_attributes
-            .entry(path.to_string())
-            .and_modify(|attrs| {
-                attrs.push(attribute.to_string());
-            })
-            .or_insert_with(|| vec![attribute.to_string()]);
+            .insert(path.to_string(), attribute.to_string());
         self
     }
 
-    /// set custom attributes for an enum
-    ///
-    /// path could be something like `foo.bar`, `foo.bar.SomeMessage`
+    /// Add an attribute to enums, see `message_attribute` for paths
     pub fn enum_attribute<P: ToString, A: ToString>(&mut self, path: P, attribute: A) -> &mut Self {
         self.enum_attributes
-            .entry(path.to_string())
-            .and_modify(|attrs| {
-                attrs.push(attribute.to_string());
-            })
-            .or_insert_with(|| vec![attribute.to_string()]);
+            .insert(path.to_string(), attribute.to_string());
         self
     }
 
+    /// Add an attribute to the enums generated for oneofs, the path of a
+    /// oneof is `.foo.bar.Data.choice`. See `message_attribute` for paths.
     pub fn oneof_attribute<P: ToString, A: ToString>(
         &mut self,
         path: P,
         attribute: A,
     ) -> &mut Self {
         self.oneof_attributes
-            .entry(path.to_string())
-            .and_modify(|attrs| {
-                attrs.push(attribute.to_string());
-            })
-            .or_insert_with(|| vec![attribute.to_string()]);
+            .insert(path.to_string(), attribute.to_string());
         self
     }
 
+    /// Add an attribute to fields, the path of a field is
+    /// `.foo.bar.Data.field`. See `message_attribute` for paths.
     pub fn field_attribute<P: ToString, A: ToString>(
         &mut self,
         path: P,
         attribute: A,
     ) -> &mut Self {
         self.field_attributes
-            .entry(path.to_string())
-            .and_modify(|attrs| {
-                attrs.push(attribute.to_string());
-            })
-            .or_insert_with(|| vec![attribute.to_string()]);
+            .insert(path.to_string(), attribute.to_string());
         self
     }
 
+    /// Generate `BTreeMap` for map fields under the paths, e.g. `.` for all
+    /// of them. See `message_attribute` for paths.
     pub fn btree_map<I, S>(&mut self, paths: I) -> &mut Self
     where
         I: IntoIterator<Item = S>,
@@ -149,6 +301,8 @@ impl Config {
         self
     }
 
+    /// Generate `HashMap` for map fields under the paths, which is the
+    /// default, e.g. to exclude some of `btree_map`.
     pub fn hashmap<I, S>(&mut self, paths: I) -> &mut Self
     where
         I: IntoIterator<Item = S>,
@@ -162,12 +316,64 @@ impl Config {
         self
     }
 
+    /// Use a rust type for fields instead of the one of the proto type, e.g.
+    /// `std::net::Ipv4Addr` for a `fixed32` field. See `message_attribute`
+    /// for paths.
+    ///
+    /// The type converts to and from the proto type with
+    /// `prust::ProtoScalar`, or `prust::ProtoMessage` for message fields,
+    /// a conversion failed is a `DecodeError::Conversion`. It must be
+    /// `Debug`. Unless the field is optional or repeated, the default of the
+    /// message converts the zero value of the proto type, and panics if that
+    /// fails. Map fields and fields with default values are not supported.
+    pub fn field_type<P: ToString, T: ToString>(&mut self, path: P, rust_type: T) -> &mut Self {
+        self.field_types
+            .insert(path.to_string(), rust_type.to_string());
+        self
+    }
+
+    /// Use a rust type for `string` fields instead of `String`, e.g.
+    /// `std::sync::Arc<str>`, or `smol_str::SmolStr` with the `smol_str`
+    /// feature of prust. See `message_attribute` for paths, `.foo` selects
+    /// the string fields of a package.
+    ///
+    /// The type implements `prust::ProtoString`. Values of map fields stay
+    /// `String`.
+    pub fn string_type<P: ToString, T: ToString>(&mut self, path: P, rust_type: T) -> &mut Self {
+        self.string_types
+            .insert(path.to_string(), rust_type.to_string());
+        self
+    }
+
+    /// Use a rust type for `bytes` fields instead of `Vec<u8>`, e.g.
+    /// `std::sync::Arc<[u8]>`, or `bytes::Bytes` with the `bytes` feature of
+    /// prust. The type implements `prust::ProtoBytes`, see `string_type`.
+    pub fn bytes_type<P: ToString, T: ToString>(&mut self, path: P, rust_type: T) -> &mut Self {
+        self.bytes_types
+            .insert(path.to_string(), rust_type.to_string());
+        self
+    }
+
+    /// Use a container for repeated fields instead of `Vec`, `_` in it is
+    /// the type of the values, e.g. `smallvec::SmallVec<[_; 4]>` with the
+    /// `smallvec` feature of prust. A container without `_`, like
+    /// `my::List`, gets it as the only parameter. See `message_attribute`
+    /// for paths.
+    ///
+    /// The container implements `prust::ProtoRepeated`. Map fields are not
+    /// affected.
+    pub fn repeated_type<P: ToString, T: ToString>(&mut self, path: P, container: T) -> &mut Self {
+        self.repeated_types
+            .insert(path.to_string(), container.to_string());
+        self
+    }
+
     /// This function prevent code generator to implement `Deserialize` for structs
     ///
     /// It's helpful for people to implement `Deserialize` manually
     pub fn skip_deserialize<T: ToString>(&mut self, paths: &[T]) -> &mut Self {
         for path in paths {
-            self.skip_deserialize.insert(path.to_string());
+            self.skip_deserialize.insert(path.to_string(), ());
         }
         self
     }
@@ -177,53 +383,224 @@ impl Config {
     /// It's helpful for people to implement `Serialize` manually
     pub fn skip_serialize<T: ToString>(&mut self, paths: &[T]) -> &mut Self {
         for path in paths {
-            self.skip_serialize.insert(path.to_string());
+            self.skip_serialize.insert(path.to_string(), ());
         }
         self
     }
 
+    /// Generate rust files from proto files, into `OUT_DIR` unless the
+    /// output is set.
+    ///
+    /// When run by a build script, `cargo::rerun-if-changed` is printed for
+    /// the files and every file they import, so cargo reruns it on changes
+    /// of any of them. Files with the same content already are not written
+    /// again, so crates using them are not rebuilt needlessly.
     pub fn compile<P: AsRef<Path>>(&mut self, includes: &[P], files: &[P]) -> Result<(), Error> {
         if files.is_empty() {
             return Ok(());
         }
 
-        for path in files {
-            // import -> fd
-            let mut imports = HashMap::new();
+        let loaded = load_files(includes, files)?;
 
-            let mut fd = load_proto(path)?;
-            for import in &fd.imports {
-                load_imports(import, includes, &mut imports)?;
+        // `OUT_DIR` is set for build scripts only
+        if std::env::var_os("OUT_DIR").is_some() {
+            for path in input_paths(includes, &loaded) {
+                println!("cargo::rerun-if-changed={}", path.display());
+            }
+        }
+
+        if let Some(output) = &self.file_descriptor_set_path {
+            let mut set = FileDescriptorSet::default();
+            for (path, fd, imports) in &loaded {
+                descriptor::add_file(&mut set, &import_name(includes, path), fd, imports)?;
+            }
+
+            let mut buf = vec![0u8; set.encoded_len()];
+            set.encode(&mut buf)
+                .expect("buffer is large enough for the descriptor set");
+
+            if let Some(parent) = output.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            write_if_changed(output, buf.as_slice())?;
+        }
+
+        let (inputs, files) = merge_files(includes, loaded);
+        for (filename, content) in self.render(&inputs, files)? {
+            self.write(filename, content)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check proto files compile, everything `compile` does except writing
+    /// files, so problems like unresolved types are found too
+    pub fn check<P: AsRef<Path>>(&mut self, includes: &[P], files: &[P]) -> Result<(), Error> {
+        let (inputs, files) = merge_files(includes, load_files(includes, files)?);
+        self.render(&inputs, files)?;
+
+        Ok(())
+    }
+
+    /// Generate code from a serialized `FileDescriptorSet`, e.g. the output
+    /// of `protoc --descriptor_set_out` or `buf build`, instead of `.proto`
+    /// files. Every file in the set is generated, the code is the same as
+    /// compiling the sources.
+    ///
+    /// Comments are only available if the set has source info.
+    pub fn compile_fds(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let set = FileDescriptorSet::decode(bytes)
+            .map_err(|err| Error::InvalidDescriptor(err.to_string()))?;
+
+        let mut inputs = Vec::with_capacity(set.file.len());
+        let mut files = HashMap::with_capacity(set.file.len());
+        for file in &set.file {
+            let name = file.name.clone().unwrap_or_default();
+            files.insert(name.clone(), descriptor::file_descriptor(file)?);
+            inputs.push(name);
+        }
+
+        for (filename, content) in self.render(&inputs, files)? {
+            self.write(filename, content)?;
+        }
+
+        Ok(())
+    }
+
+    // the generated files and the names of them, one per package. `inputs`
+    // are the files to compile, `files` has them and all their imports
+    fn render(
+        &self,
+        inputs: &[String],
+        mut files: HashMap<String, FileDescriptor>,
+    ) -> Result<Vec<(String, String)>, Error> {
+        // imports are generated too, after the files to compile
+        let mut imported: Vec<&String> =
+            files.keys().filter(|name| !inputs.contains(name)).collect();
+        imported.sort_unstable();
+
+        // package -> files of it
+        let mut packages: Vec<(String, Vec<String>)> = vec![];
+        for name in inputs.iter().chain(imported) {
+            let package = package_of(name, &files[name]);
+            if !inputs.contains(name) && self.is_extern(&package) {
+                continue;
+            }
+
+            match packages.iter_mut().find(|(other, _)| *other == package) {
+                Some((_, names)) => names.push(name.clone()),
+                None => packages.push((package, vec![name.clone()])),
+            }
+        }
+
+        // one file can't hold several packages
+        if self.filename.is_some() {
+            let compiled = packages
+                .iter()
+                .filter(|(_, names)| names.iter().any(|name| inputs.contains(name)))
+                .map(|(package, _)| format!("`{package}`"))
+                .collect::<Vec<_>>();
+            if compiled.len() > 1 {
+                return Err(Error::InvalidConfig(format!(
+                    "`filename` needs the compiled files to be in one package, found {}",
+                    compiled.join(", ")
+                )));
             }
+        }
+
+        // describe the files as written, before any fields are dropped
+        let mut embedded = Vec::with_capacity(packages.len());
+        for (_, names) in &packages {
+            embedded.push(self.embedded_descriptors(names, &files)?);
+        }
+
+        if self.skip_deprecated_fields {
+            for fd in files.values_mut() {
+                remove_deprecated_fields(&mut fd.messages);
+            }
+        }
 
+        let mut outputs = Vec::with_capacity(packages.len() + 1);
+        for ((package, names), embedded) in packages.iter().zip(embedded) {
             let mut buf = Buffer::default();
             buf.push("use prust::*;\n");
 
-            let mut cx = Context {
-                fd: &mut fd,
-                config: &self,
-                imports: &mut imports,
-                messages: Vec::new(),
-            };
-            generate_proto(&mut buf, &mut cx)?;
+            if names.iter().any(|name| !files[name].imports.is_empty()) {
+                let supers = vec!["super"; package.split('.').count()].join("::");
+                buf.push("#[allow(unused_imports)]\n");
+                buf.push(format!("use {supers} as {ROOT};\n"));
+            }
+
+            let mut files = names
+                .iter()
+                .map(|name| Context {
+                    fd: &files[name],
+                    config: self,
+                    imports: &files,
+                    messages: Vec::new(),
+                })
+                .collect::<Vec<_>>();
+            generate_package(&mut buf, &mut files)?;
+
+            if let Some(set) = embedded {
+                generate_file_descriptor_set(&mut buf, &set);
+            }
 
             let filename = match &self.filename {
-                Some(name) => name.to_string(),
-                None => match fd.package.as_ref() {
-                    Some(package) => sanitize_filepath(package),
-                    None => path
-                        .as_ref()
-                        .file_stem()
-                        .unwrap()
-                        .to_string_lossy()
-                        .to_string(),
-                },
+                Some(filename) if names.iter().any(|name| inputs.contains(name)) => {
+                    filename.to_string()
+                }
+                _ => sanitize_filepath(package),
             };
+            outputs.push((format!("{filename}.rs"), buf.into_inner()));
+        }
 
-            self.write(format!("{filename}.rs"), buf.into_inner())?;
+        if let Some(include_file) = &self.include_file {
+            let packages = packages
+                .iter()
+                .map(|(package, _)| package.as_str())
+                .zip(outputs.iter().map(|(filename, _)| filename.as_str()));
+            let content = generate_include_file(packages);
+            outputs.push((include_file.clone(), content));
         }
 
-        Ok(())
+        Ok(outputs)
+    }
+
+    // whether the package is mapped by `extern_path` as a whole
+    fn is_extern(&self, package: &str) -> bool {
+        self.extern_paths.keys().any(|path| {
+            path == package
+                || package
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    // descriptors of the files with services and all their imports
+    fn embedded_descriptors(
+        &self,
+        names: &[String],
+        files: &HashMap<String, FileDescriptor>,
+    ) -> Result<Option<FileDescriptorSet>, Error> {
+        if !self.embed_descriptors {
+            return Ok(None);
+        }
+
+        let mut set = FileDescriptorSet::default();
+        for name in names {
+            if !files[name].services.is_empty() {
+                descriptor::add_file(&mut set, name, &files[name], files)?;
+            }
+        }
+        if set.file.is_empty() {
+            return Ok(None);
+        }
+
+        for file in &mut set.file {
+            file.source_code_info = None;
+        }
+        Ok(Some(set))
     }
 
     fn write<P: AsRef<Path>>(&self, filename: P, content: String) -> Result<(), Error> {
@@ -235,7 +612,7 @@ impl Config {
 
         std::fs::create_dir_all(path.parent().unwrap())?;
 
-        if let Err(err) = std::fs::write(&path, content) {
+        if let Err(err) = write_if_changed(&path, content.as_bytes()) {
             panic!("Error writing file {path:?}: {err}");
         }
 
@@ -243,7 +620,202 @@ impl Config {
     }
 }
 
-fn load_imports<P: AsRef<Path>>(
+// all files by the import path, and the names of the ones to compile
+fn merge_files<P: AsRef<Path>>(
+    includes: &[P],
+    loaded: Vec<Loaded<P>>,
+) -> (Vec<String>, HashMap<String, FileDescriptor>) {
+    let mut inputs = Vec::with_capacity(loaded.len());
+    let mut files = HashMap::new();
+    for (path, fd, imports) in loaded {
+        // a file given twice is generated once
+        let name = import_name(includes, path);
+        if !inputs.contains(&name) {
+            files.insert(name.clone(), fd);
+            inputs.push(name);
+        }
+
+        for (name, fd) in imports {
+            files.entry(name).or_insert(fd);
+        }
+    }
+
+    (inputs, files)
+}
+
+// nested modules of packages, each includes the file generated for it
+fn generate_include_file<'a>(packages: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    #[derive(Default)]
+    struct Module<'a> {
+        filename: Option<&'a str>,
+        children: Vec<(String, Module<'a>)>,
+    }
+
+    fn generate(buf: &mut Buffer, module: &Module) {
+        if let Some(filename) = module.filename {
+            buf.push(format!("include!(\"{filename}\");\n"));
+        }
+
+        for (name, child) in &module.children {
+            buf.push(format!("pub mod {name} {{\n"));
+            buf.indent += 1;
+            generate(buf, child);
+            buf.indent -= 1;
+            buf.push("}\n");
+        }
+    }
+
+    let mut root = Module::default();
+    for (package, filename) in packages {
+        let mut module = &mut root;
+        for segment in package.split('.') {
+            let name = snake(segment);
+            let index = match module.children.iter().position(|(other, _)| *other == name) {
+                Some(index) => index,
+                None => {
+                    module.children.push((name, Module::default()));
+                    module.children.len() - 1
+                }
+            };
+            module = &mut module.children[index].1;
+        }
+        module.filename = Some(filename);
+    }
+
+    let mut buf = Buffer::default();
+    generate(&mut buf, &root);
+    buf.into_inner()
+}
+
+// the modification time is kept if the content is the same, cargo decides
+// what to rebuild by it
+fn write_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if std::fs::read(path).is_ok_and(|old| old == content) {
+        return Ok(());
+    }
+
+    std::fs::write(path, content)
+}
+
+// files to compile and the files they import, every file `load_imports`
+// reads is included, even the ones shadowed by files of the same path
+fn input_paths<P: AsRef<Path>>(includes: &[P], loaded: &[Loaded<P>]) -> BTreeSet<PathBuf> {
+    let mut paths = BTreeSet::new();
+    for (path, _, imports) in loaded {
+        paths.insert(path.as_ref().to_path_buf());
+
+        for name in imports.keys() {
+            paths.extend(
+                includes
+                    .iter()
+                    .map(|include| include.as_ref().join(name))
+                    .filter(|path| path.exists()),
+            );
+        }
+    }
+
+    paths
+}
+
+// a file to compile, with the files it imports by the import path
+pub(crate) type Loaded<'p, P> = (&'p P, FileDescriptor, HashMap<String, FileDescriptor>);
+
+// load all files first, so problems of every file are reported at once
+pub(crate) fn load_files<'p, P: AsRef<Path>>(
+    includes: &[P],
+    files: &'p [P],
+) -> Result<Vec<Loaded<'p, P>>, Error> {
+    let mut loaded = Vec::with_capacity(files.len());
+    let mut errors = vec![];
+    for path in files {
+        // import -> fd
+        let mut imports = HashMap::new();
+
+        let result = load_proto(path).and_then(|fd| {
+            for import in &fd.imports {
+                load_imports(&import.path, includes, &mut imports)?;
+            }
+
+            Ok(fd)
+        });
+
+        match result {
+            Ok(fd) => loaded.push((path, fd, imports)),
+            Err(Error::Parse(partial)) => {
+                // imports shared by files might be reported already
+                for err in partial {
+                    if !errors.iter().any(|other: &parse::Error| {
+                        other.file() == err.file()
+                            && other.line() == err.line()
+                            && other.column() == err.column()
+                    }) {
+                        errors.push(err);
+                    }
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if !errors.is_empty() {
+        let err = Error::Parse(errors);
+
+        // build scripts show nothing but the panic message, so render
+        // them as warnings
+        if std::env::var_os("OUT_DIR").is_some() {
+            for line in err.to_string().lines() {
+                println!("cargo::warning={line}");
+            }
+        }
+
+        return Err(err);
+    }
+
+    Ok(loaded)
+}
+
+// the path a file would be imported by, files out of includes are named by
+// the path given
+pub(crate) fn import_name<P: AsRef<Path>>(includes: &[P], path: &P) -> String {
+    let path = path.as_ref();
+    let relative = includes
+        .iter()
+        .find_map(|include| path.strip_prefix(include).ok())
+        .unwrap_or(path);
+
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// the encoded set as a byte string literal, printable ascii is kept as is
+fn generate_file_descriptor_set(buf: &mut Buffer, set: &FileDescriptorSet) {
+    let mut bytes = vec![0u8; set.encoded_len()];
+    set.encode(&mut bytes)
+        .expect("buffer is large enough for the descriptor set");
+
+    let literal = bytes
+        .iter()
+        .flat_map(|b| std::ascii::escape_default(*b))
+        .map(char::from)
+        .collect::<String>();
+
+    buf.push("/// Encoded `FileDescriptorSet` of this file and its imports\n");
+    buf.push(format!(
+        "pub const FILE_DESCRIPTOR_SET: &[u8] = b\"{literal}\";\n"
+    ));
+}
+
+fn remove_deprecated_fields(messages: &mut [Message]) {
+    for msg in messages {
+        msg.fields.retain(|field| !field.deprecated());
+        remove_deprecated_fields(&mut msg.messages);
+    }
+}
+
+pub(crate) fn load_imports<P: AsRef<Path>>(
     name: &str,
     includes: &[P],
     imports: &mut HashMap<String, FileDescriptor>,
@@ -262,7 +834,7 @@ fn load_imports<P: AsRef<Path>>(
             let fd = load_proto(path)?;
 
             for import in &fd.imports {
-                load_imports(import, includes, imports)?;
+                load_imports(&import.path, includes, imports)?;
             }
 
             imports.insert(name.to_string(), fd);
@@ -272,18 +844,127 @@ fn load_imports<P: AsRef<Path>>(
     Ok(())
 }
 
-fn load_proto<P: AsRef<Path>>(path: P) -> Result<FileDescriptor, Error> {
+pub(crate) fn load_proto<P: AsRef<Path>>(path: P) -> Result<FileDescriptor, Error> {
+    load_proto_recovering(path, |_| false)
+}
+
+// like `load_proto`, see `parse::parse_recovering`
+pub(crate) fn load_proto_recovering<P: AsRef<Path>>(
+    path: P,
+    recoverable: impl Fn(&parse::ErrorKind) -> bool,
+) -> Result<FileDescriptor, Error> {
     let content = std::fs::read(&path)?;
-    let mut fd = parse::parse(&content).map_err(Error::Parse)?;
-    if fd.package.is_none() {
-        fd.package = Some(
-            path.as_ref()
-                .file_stem()
-                .unwrap()
-                .to_string_lossy()
-                .to_string(),
+    parse::parse_recovering(&content, recoverable).map_err(|errors| {
+        Error::Parse(
+            errors
+                .into_iter()
+                .map(|mut err| {
+                    err.set_file(path.as_ref());
+                    err
+                })
+                .collect(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inputs_and_outputs() {
+        let dir = std::env::temp_dir().join(format!("prust-build-config-{}", std::process::id()));
+        let protos = dir.join("protos");
+        std::fs::create_dir_all(protos.join("common")).unwrap();
+        std::fs::write(
+            protos.join("main.proto"),
+            "syntax = \"proto3\";\nimport \"common/types.proto\";\nmessage Main { common.Id id = 1; }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            protos.join("common/types.proto"),
+            "syntax = \"proto3\";\npackage common;\nmessage Id { uint64 value = 1; }\n",
+        )
+        .unwrap();
+
+        // the imported file is an input too
+        let includes = [protos.clone()];
+        let files = [protos.join("main.proto")];
+        let loaded = load_files(&includes, &files).unwrap();
+        assert_eq!(
+            input_paths(&includes, &loaded)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [protos.join("common/types.proto"), protos.join("main.proto")]
+        );
+
+        // unchanged files keep the modification time
+        let output = dir.join("common.rs");
+        write_if_changed(&output, b"content").unwrap();
+        let modified = std::fs::metadata(&output).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        write_if_changed(&output, b"content").unwrap();
+        assert_eq!(
+            std::fs::metadata(&output).unwrap().modified().unwrap(),
+            modified
+        );
+        write_if_changed(&output, b"changed").unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"changed");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn filename() {
+        let files = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| {
+                    let src = format!("package {};", name.trim_end_matches(".proto"));
+                    (name.to_string(), crate::parse(src.as_bytes()).unwrap())
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let inputs = ["a.proto".to_string(), "b.proto".to_string()];
+
+        let mut config = Config::default();
+        config.filename("out");
+        let outputs = config.render(&inputs[..1], files(&["a.proto"])).unwrap();
+        assert_eq!(outputs[0].0, "out.rs");
+
+        // the packages would overwrite each other
+        let err = config
+            .render(&inputs, files(&["a.proto", "b.proto"]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid config: `filename` needs the compiled files to be in one package, found `a`, `b`"
         );
     }
 
-    Ok(fd)
+    #[test]
+    fn include_file() {
+        let packages = [
+            ("foo.bar", "foo_bar.rs"),
+            ("main", "main.rs"),
+            ("foo", "foo.rs"),
+            ("foo.baz", "foo_baz.rs"),
+        ];
+        assert_eq!(
+            generate_include_file(packages.into_iter()),
+            r#"pub mod foo {
+    include!("foo.rs");
+    pub mod bar {
+        include!("foo_bar.rs");
+    }
+    pub mod baz {
+        include!("foo_baz.rs");
+    }
+}
+pub mod main {
+    include!("main.rs");
+}
+"#
+        );
+    }
 }
@@ -1,12 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 
-use super::config::Config;
-use super::sanitize::{sanitize_type_name, sanitize_variant, snake, upper_camel};
+use super::config::{Config, MapType};
+use super::sanitize::{sanitize_filepath, sanitize_type_name, sanitize_variant, snake};
+use crate::Error;
 use crate::ast::{
-    Enum, Field, FieldCardinality, FieldType, FileDescriptor, Label, Message, Syntax,
+    Enum, Extend, Field, FieldCardinality, FieldType, FileDescriptor, ImportKind, Label, Message,
+    OneOf, Syntax,
 };
 use crate::codegen::sizeof::sizeof_varint;
 
+/// Alias of the root of the package tree, every package module has it, so
+/// other packages are referred as `_root::foo::bar::Type` however deep the
+/// reference is. The leading underscore keeps it apart from generated names.
+pub const ROOT: &str = "_root";
+
 #[derive(Debug)]
 pub enum Container<'a> {
     Message(&'a Message),
@@ -32,90 +40,421 @@ pub struct Context<'a> {
     pub messages: Vec<&'a Message>,
 }
 
-impl Context<'_> {
-    pub fn lookup_type(&self, typ: &str) -> Option<(String, Container<'_>)> {
-        let mut segments = typ.split('.');
-        let first = segments.next().unwrap();
-
-        // 1. lookup in current stack
-        for msg in self.messages.iter().rev() {
-            if let Some(en) = msg.enums.iter().find(|en| en.name == first) {
-                if !typ.contains('.') {
-                    return Some((
-                        format!("{}::{}", snake(&msg.name), upper_camel(typ)),
-                        Container::Enum(en),
-                    ));
-                }
+/// A definition found by its fully qualified name
+enum Symbol<'a> {
+    Package,
+    Type(Resolved<'a>),
+}
+
+/// A type resolved, `import` is the direct import it's visible through,
+/// `None` for types defined in the current file
+pub struct Resolved<'a> {
+    pub path: String,
+    /// Fully qualified name, without the leading dot
+    pub full_name: String,
+    pub container: Container<'a>,
+    pub import: Option<&'a str>,
+}
+
+/// The derives a message or a oneof can have on top of `Clone` and
+/// `PartialEq`, see `Context::derivable`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Derivable {
+    pub eq: bool,
+    pub hash: bool,
+}
+
+impl Derivable {
+    const ALL: Self = Self {
+        eq: true,
+        hash: true,
+    };
+    const NONE: Self = Self {
+        eq: false,
+        hash: false,
+    };
+
+    fn and(self, other: Self) -> Self {
+        Self {
+            eq: self.eq && other.eq,
+            hash: self.hash && other.hash,
+        }
+    }
+}
+
+/// A file visible to the one being generated
+#[derive(Clone)]
+struct Visible<'a> {
+    /// Import path of the file, `None` for the current file
+    name: Option<&'a str>,
+    /// The direct import of the current file it comes from
+    direct: Option<&'a str>,
+    /// Module the file is generated in
+    module: String,
+    fd: &'a FileDescriptor,
+}
+
+impl<'a> Context<'a> {
+    /// Resolve a type name with the scoping rules of protobuf, the name is
+    /// looked up from the innermost scope to the outermost one, e.g. `Foo.Bar`
+    /// referenced in `pkg.Outer.Inner` tries `pkg.Outer.Inner.Foo`,
+    /// `pkg.Outer.Foo`, `pkg.Foo` and `Foo` in order. Once the first
+    /// component `Foo` is found, the rest must be defined in it, outer
+    /// scopes are not searched anymore. Names start with `.` are fully
+    /// qualified already.
+    ///
+    /// The path returned is relative to the module of the file being
+    /// generated.
+    pub fn resolve(&self, typ: &str) -> Result<(String, Container<'a>), Error> {
+        self.resolve_symbol(typ)
+            .map(|resolved| (resolved.path, resolved.container))
+    }
+
+    /// Like `resolve`, but tells where the type comes from
+    pub fn resolve_symbol(&self, typ: &str) -> Result<Resolved<'a>, Error> {
+        let unresolved = || Error::UnresolvedType {
+            name: typ.to_string(),
+            scope: self.full_name(""),
+        };
+
+        if let Some(full) = typ.strip_prefix('.') {
+            return match self.find(typ, full)? {
+                Some(Symbol::Type(resolved)) => Ok(resolved),
+                _ => Err(unresolved()),
+            };
+        }
+
+        let first = typ.split('.').next().unwrap_or(typ);
+        let mut scope = self.full_name("");
+        loop {
+            let prefix = if scope.is_empty() {
+                String::new()
+            } else {
+                format!("{scope}.")
+            };
+
+            if self.find(typ, &format!("{prefix}{first}"))?.is_some() {
+                return match self.find(typ, &format!("{prefix}{typ}"))? {
+                    Some(Symbol::Type(resolved)) => Ok(resolved),
+                    _ => Err(unresolved()),
+                };
             }
 
-            for sub in msg.messages.iter() {
-                if sub.name != first {
-                    continue;
-                }
+            match scope.rsplit_once('.') {
+                Some((parent, _)) => scope = parent.to_string(),
+                None if !scope.is_empty() => scope.clear(),
+                None => return Err(unresolved()),
+            }
+        }
+    }
 
-                return lookup(sub, segments).map(|(path, c)| {
-                    let path = format!("{}::{}", snake(&msg.name), path);
-                    (path, c)
-                });
+    /// Resolve every type referenced by the file, so code is never generated
+    /// with types that don't exist
+    pub fn check_types(&mut self) -> Result<(), Error> {
+        let fd = self.fd;
+
+        for msg in &fd.messages {
+            self.check_message(msg)?;
+        }
+        for extend in &fd.extends {
+            self.check_extend(extend)?;
+        }
+        for svc in &fd.services {
+            for func in &svc.functions {
+                self.resolve(&func.request)?;
+                self.resolve(&func.response)?;
             }
         }
 
-        // 2. lookup in current file descriptor
-        for msg in self.fd.messages.iter() {
-            if msg.name != first {
-                continue;
+        Ok(())
+    }
+
+    fn check_message(&mut self, msg: &'a Message) -> Result<(), Error> {
+        self.messages.push(msg);
+
+        let result = (|| {
+            for field in &msg.fields {
+                self.check_field_type(&field.typ)?;
+                self.check_custom_type(field)?;
+            }
+            for oneof in &msg.oneofs {
+                for variant in &oneof.variants {
+                    self.check_field_type(&variant.typ)?;
+                }
+            }
+            for extend in &msg.extends {
+                self.check_extend(extend)?;
             }
+            for msg in &msg.messages {
+                self.check_message(msg)?;
+            }
+
+            Ok(())
+        })();
+
+        self.messages.pop();
+        result
+    }
 
-            return lookup(msg, segments);
+    fn check_extend(&self, extend: &Extend) -> Result<(), Error> {
+        self.extendee(extend)?;
+        for field in &extend.fields {
+            self.check_field_type(&field.typ)?;
         }
 
-        // enum in file descriptor
-        for en in self.fd.enums.iter() {
-            if en.name == first && segments.next().is_none() {
-                return Some((upper_camel(first), Container::Enum(en)));
+        Ok(())
+    }
+
+    /// The message extended and the path of it, every field must be in the
+    /// extension ranges of it
+    pub fn extendee(&self, extend: &Extend) -> Result<(String, &'a Message), Error> {
+        let resolved = self.resolve_symbol(&extend.extendee)?;
+        let Container::Message(msg) = resolved.container else {
+            return Err(Error::InvalidExtension(format!(
+                "`{}` is not a message",
+                resolved.full_name
+            )));
+        };
+
+        for field in &extend.fields {
+            if !msg.extensions.iter().any(|ext| ext.contains(field.number)) {
+                return Err(Error::InvalidExtension(format!(
+                    "`{}` of `{}` is not in the extension ranges of `{}`",
+                    field.number,
+                    self.full_name(&field.name),
+                    resolved.full_name
+                )));
             }
         }
 
-        // 3. lookup in imports
-        for (path, fd) in self.imports {
-            let pkg = match fd.package.as_ref() {
-                Some(pkg) => pkg,
-                None => path.strip_suffix(".proto").unwrap_or(path),
-            };
+        Ok((resolved.path, msg))
+    }
+
+    fn check_custom_type(&self, field: &Field) -> Result<(), Error> {
+        if self.field_type(field).is_none() {
+            return Ok(());
+        }
+
+        let unsupported = if matches!(field.typ, FieldType::Map(_, _)) {
+            "map fields"
+        } else if field.default_value().is_some() {
+            "fields with default values"
+        } else {
+            return Ok(());
+        };
+        Err(Error::InvalidConfig(format!(
+            "`field_type` of `{}`, {unsupported} are not supported",
+            self.full_name(&field.name)
+        )))
+    }
+
+    fn check_field_type(&self, typ: &FieldType) -> Result<(), Error> {
+        match typ {
+            FieldType::Message(name) => self.resolve(name).map(|_| ()),
+            FieldType::Map(key, value) => {
+                self.check_field_type(key)?;
+                self.check_field_type(value)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the type is the message itself, such fields are boxed. It's
+    /// told by resolving, since the type might be written in any form, e.g.
+    /// `Node`, `Tree.Node` or `.pkg.Tree.Node`.
+    pub fn is_recursive(&self, typ: &FieldType, msg: &Message) -> bool {
+        match typ {
+            FieldType::Message(typ) => matches!(
+                self.lookup_type(typ),
+                Some((_, Container::Message(target))) if std::ptr::eq(target, msg)
+            ),
+            _ => false,
+        }
+    }
 
-            let stripped = typ.strip_prefix(&format!("{pkg}.")).unwrap_or(typ);
+    #[inline]
+    pub fn lookup_type(&self, typ: &str) -> Option<(String, Container<'a>)> {
+        self.resolve(typ).ok()
+    }
 
-            let mut segments = stripped.split('.');
-            let first = segments.next().unwrap();
+    /// Fully qualified name of a definition in the current scope, without
+    /// the leading dot
+    pub fn full_name(&self, name: &str) -> String {
+        self.fd
+            .package
+            .iter()
+            .map(String::as_str)
+            .chain(self.messages.iter().map(|msg| msg.name.as_str()))
+            .chain((!name.is_empty()).then_some(name))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
 
-            for msg in fd.messages.iter() {
-                if msg.name != first {
+    /// Files visible to the current one, itself first and then the imports
+    /// in declaration order. Files imported publicly by an import are
+    /// visible too, transitively.
+    ///
+    /// Each file comes with the import path and the module it's generated
+    /// in. Files of the same package share the module, other packages are
+    /// referred from the root of the package tree, see `ROOT`.
+    fn files(&self) -> Vec<Visible<'a>> {
+        fn visit<'a>(
+            fd: &'a FileDescriptor,
+            direct: Option<&'a str>,
+            current: Option<&str>,
+            imports: &'a HashMap<String, FileDescriptor>,
+            files: &mut Vec<Visible<'a>>,
+        ) {
+            for import in &fd.imports {
+                if direct.is_some() && import.kind != ImportKind::Public {
+                    continue;
+                }
+                // weak imports are optional
+                let Some(imported) = imports.get(&import.path) else {
+                    continue;
+                };
+                if files
+                    .iter()
+                    .any(|file| file.name == Some(import.path.as_str()))
+                {
                     continue;
                 }
 
-                return match lookup(msg, segments) {
-                    Some((path, c)) => {
-                        let prefix = pkg.split('.').map(snake).collect::<Vec<_>>().join("::");
-
-                        Some((format!("{}::{}", prefix, path), c))
-                    }
-                    None => None,
+                let module = match imported.package.as_deref() {
+                    Some(package) if current == Some(package) => String::new(),
+                    _ => format!("{ROOT}::{}", package_module(&import.path, imported)),
                 };
+                let direct = direct.or(Some(import.path.as_str()));
+
+                files.push(Visible {
+                    name: Some(import.path.as_str()),
+                    direct,
+                    module,
+                    fd: imported,
+                });
+                visit(imported, direct, current, imports, files);
             }
+        }
+
+        let mut files = vec![Visible {
+            name: None,
+            direct: None,
+            module: String::new(),
+            fd: self.fd,
+        }];
+        visit(
+            self.fd,
+            None,
+            self.fd.package.as_deref(),
+            self.imports,
+            &mut files,
+        );
+        files
+    }
 
-            // lookup enum in imported file descriptor
-            for en in fd.enums.iter() {
-                if en.name == first && segments.next().is_none() {
-                    let prefix = pkg.split('.').map(snake).collect::<Vec<_>>().join("::");
-                    return Some((
-                        format!("{prefix}::{}", upper_camel(first)),
-                        Container::Enum(en),
-                    ));
+    // lookup a fully qualified name, `typ` is the name referenced, it's for
+    // error reporting only
+    fn find(&self, typ: &str, full: &str) -> Result<Option<Symbol<'a>>, Error> {
+        let mut package = false;
+        let mut types = vec![];
+
+        for file in self.files() {
+            let pkg = file.fd.package.as_deref().unwrap_or_default();
+            if pkg == full || pkg.starts_with(&format!("{full}.")) {
+                package = true;
+                continue;
+            }
+
+            let rest = if pkg.is_empty() {
+                full
+            } else {
+                match full.strip_prefix(pkg).and_then(|s| s.strip_prefix('.')) {
+                    Some(rest) => rest,
+                    None => continue,
                 }
+            };
+
+            if let Some((path, container)) = lookup(file.fd, rest) {
+                let path = match self.extern_path(pkg, full) {
+                    // the package generated is never extern
+                    Some(path) if file.fd.package != self.fd.package => path,
+                    _ if file.module.is_empty() => path,
+                    _ => format!("{}::{path}", file.module),
+                };
+
+                types.push((
+                    file.name,
+                    Resolved {
+                        path,
+                        full_name: full.to_string(),
+                        container,
+                        import: file.direct,
+                    },
+                ));
             }
         }
 
-        None
+        if types.len() > 1 || (package && !types.is_empty()) {
+            let mut candidates = types
+                .into_iter()
+                .map(|(name, _)| match name {
+                    Some(name) => format!("`{full}` in `{name}`"),
+                    None => format!("`{full}` in the current file"),
+                })
+                .collect::<Vec<_>>();
+            if package {
+                candidates.push(format!("package `{full}`"));
+            }
+
+            return Err(Error::AmbiguousType {
+                name: typ.to_string(),
+                candidates,
+            });
+        }
+
+        Ok(match types.pop() {
+            Some((_, resolved)) => Some(Symbol::Type(resolved)),
+            None if package => Some(Symbol::Package),
+            None => None,
+        })
+    }
+
+    // the rust path of a type mapped by `Config::extern_path`, the longest
+    // proto path matched wins. `package` is the package of the type.
+    fn extern_path(&self, package: &str, full: &str) -> Option<String> {
+        let (proto, rust) = self
+            .config
+            .extern_paths
+            .iter()
+            .filter(|(proto, _)| {
+                full.strip_prefix(proto.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+            .max_by_key(|(proto, _)| proto.len())?;
+
+        let rest = full[proto.len()..].trim_start_matches('.');
+        if rest.is_empty() {
+            return Some(rust.to_string());
+        }
+
+        // types nested in a message mapped are in the module of it
+        let mut path = match rust.rsplit_once("::") {
+            Some((parent, name)) if proto.len() > package.len() => {
+                format!("{parent}::{}", snake(name))
+            }
+            None if proto.len() > package.len() => snake(rust),
+            _ => rust.to_string(),
+        };
+
+        let mut segments = rest.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            let segment = match segments.peek() {
+                Some(_) => snake(segment),
+                None => sanitize_type_name(segment),
+            };
+            path = format!("{path}::{segment}");
+        }
+        Some(path)
     }
 
     pub fn tag(&self, field: &Field) -> u32 {
@@ -158,16 +497,16 @@ impl Context<'_> {
             | FieldType::Sfixed64
             | FieldType::Sint32
             | FieldType::Sint64
-            | FieldType::Bool => match field.options.get("packed") {
+            | FieldType::Bool => match field.options.get_bool("packed") {
                 // In proto3, `repeated` fields of scalar numeric types uses `packed`
                 // encoding by default
                 None => self.fd.syntax == Syntax::Proto3,
-                Some(b) => b == "true",
+                Some(b) => b,
             },
             FieldType::Message(typ) => match self.lookup_type(typ) {
-                Some((_, Container::Enum(_))) => match field.options.get("packed") {
+                Some((_, Container::Enum(_))) => match field.options.get_bool("packed") {
                     None => self.fd.syntax == Syntax::Proto3,
-                    Some(b) => b == "true",
+                    Some(b) => b,
                 },
                 _ => false,
             },
@@ -183,64 +522,112 @@ impl Context<'_> {
             .join(".")
     }
 
-    pub fn message_attributes(&self) -> &[String] {
-        if let Some(attrs) = self.config.message_attributes.get("") {
-            return attrs.as_slice();
-        }
+    /// Attributes of the current message, see `Config::message_attribute`
+    pub fn message_attributes(&self) -> Vec<&'a String> {
+        self.config.message_attributes.get_all(&self.selector(""))
+    }
 
-        let path = self.path();
+    pub fn enum_attributes(&self, en: &Enum) -> Vec<&'a String> {
         self.config
-            .message_attributes
-            .iter()
-            .find_map(|(key, attrs)| {
-                if key.starts_with(&path) {
-                    Some(attrs.as_slice())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(&[])
+            .enum_attributes
+            .get_all(&self.selector(&en.name))
     }
 
-    pub fn enum_attributes(&self) -> &[String] {
-        if let Some(attrs) = self.config.enum_attributes.get("") {
-            return attrs.as_slice();
-        }
+    pub fn oneof_attributes(&self, oneof: &OneOf) -> Vec<&'a String> {
+        self.config
+            .oneof_attributes
+            .get_all(&self.selector(&oneof.name))
+    }
 
-        let path = self.path();
+    pub fn field_attributes(&self, field: &Field) -> Vec<&'a String> {
         self.config
-            .enum_attributes
-            .iter()
-            .find_map(|(key, attrs)| {
-                if key.starts_with(&path) {
-                    Some(attrs.as_slice())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(&[])
+            .field_attributes
+            .get_all(&self.selector(&field.name))
     }
 
-    pub fn oneof_attributes(&self) -> &[String] {
-        if let Some(attrs) = self.config.message_attributes.get("") {
-            return attrs.as_slice();
+    pub fn map_type(&self, field: &Field) -> &'a MapType {
+        self.config
+            .tree_map
+            .get(&self.selector(&field.name))
+            .unwrap_or(&MapType::HashMap)
+    }
+
+    /// The rust type of a field, see `Config::field_type`
+    pub fn field_type(&self, field: &Field) -> Option<&'a String> {
+        self.config.field_types.get(&self.selector(&field.name))
+    }
+
+    /// The rust type of a `string` or `bytes` field or oneof variant, see
+    /// `Config::string_type` and `Config::bytes_type`
+    pub fn value_type(&self, name: &str, typ: &FieldType) -> Option<&'a String> {
+        let types = match typ {
+            FieldType::String => &self.config.string_types,
+            FieldType::Bytes => &self.config.bytes_types,
+            _ => return None,
+        };
+        types.get(&self.selector(name))
+    }
+
+    /// The container of a repeated field, if not `Vec`, see
+    /// `Config::repeated_type`
+    pub fn repeated_type(&self, field: &Field) -> Option<&'a String> {
+        match self.cardinality(field) {
+            FieldCardinality::Repeated => {
+                self.config.repeated_types.get(&self.selector(&field.name))
+            }
+            _ => None,
         }
+    }
 
-        let path = self.path();
-        self.config
-            .oneof_attributes
-            .iter()
-            .find_map(|(key, attrs)| {
-                if key.starts_with(&path) {
-                    Some(attrs.as_slice())
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(&[])
+    /// The type of a repeated field with values of `typ`
+    pub fn repeated(&self, field: &Field, typ: &str) -> String {
+        let Some(container) = self.repeated_type(field) else {
+            return format!("Vec<{typ}>");
+        };
+
+        // `_` as a whole identifier is the placeholder
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        let mut result = String::new();
+        let mut found = false;
+        let mut rest = container.as_str();
+        while let Some(index) = rest.find('_') {
+            let before = rest[..index].chars().next_back();
+            let after = rest[index + 1..].chars().next();
+            result.push_str(&rest[..index]);
+            if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+                result.push('_');
+            } else {
+                result.push_str(typ);
+                found = true;
+            }
+            rest = &rest[index + 1..];
+        }
+        result.push_str(rest);
+
+        if found {
+            result
+        } else {
+            format!("{container}<{typ}>")
+        }
+    }
+
+    /// Whether `Serialize` is skipped for the current message
+    pub fn skip_serialize(&self) -> bool {
+        self.config.skip_serialize.contains(&self.selector(""))
     }
 
-    pub fn cardinality<'a>(&self, field: &'a Field) -> FieldCardinality<'a> {
+    /// Whether `Deserialize` is skipped for the current message
+    pub fn skip_deserialize(&self) -> bool {
+        self.config.skip_deserialize.contains(&self.selector(""))
+    }
+
+    // name of a definition in the current scope, as the paths of `Config`
+    // selectors see it
+    fn selector(&self, name: &str) -> String {
+        format!(".{}", self.full_name(name))
+    }
+
+    pub fn cardinality<'f>(&self, field: &'f Field) -> FieldCardinality<'f> {
         if let FieldType::Map(key, value) = &field.typ {
             return FieldCardinality::Map(key.as_ref(), value.as_ref());
         }
@@ -252,13 +639,9 @@ impl Context<'_> {
         match self.fd.syntax {
             Syntax::Proto2 => match field.label {
                 Label::Required => FieldCardinality::Required,
-                Label::Optional => {
-                    if field.default_value().is_some() {
-                        FieldCardinality::Required
-                    } else {
-                        FieldCardinality::Optional
-                    }
-                }
+                // defaults are returned by the accessors, the field keeps
+                // presence
+                Label::Optional => FieldCardinality::Optional,
                 _ => unreachable!(),
             },
             Syntax::Proto3 => {
@@ -305,7 +688,7 @@ impl Context<'_> {
     pub fn default_value(&self, field: &Field) -> Option<String> {
         let value = match (self.fd.syntax, field.default_value()) {
             (Syntax::Proto2, None) => return None,
-            (Syntax::Proto2, Some(value)) => value.to_string(),
+            (Syntax::Proto2, Some(value)) => value,
             // default value is not support in proto3
             (Syntax::Proto3, _) => match &field.typ {
                 FieldType::Double | FieldType::Float => "0.0".to_string(),
@@ -324,7 +707,7 @@ impl Context<'_> {
                 FieldType::Message(typ) => {
                     return match self.lookup_type(typ) {
                         Some((path, Container::Enum(en))) => {
-                            let first = &en.variants.first().unwrap().0;
+                            let first = &en.variants.first().unwrap().name;
 
                             Some(format!("{}::{}", path, sanitize_variant(&en.name, first)))
                         }
@@ -339,12 +722,8 @@ impl Context<'_> {
 
         let value = match &field.typ {
             FieldType::Message(typ) => match self.lookup_type(typ) {
-                Some((_, Container::Enum(en))) => {
-                    format!(
-                        "{}::{}",
-                        upper_camel(&en.name),
-                        sanitize_variant(&en.name, &value)
-                    )
+                Some((path, Container::Enum(en))) => {
+                    format!("{}::{}", path, sanitize_variant(&en.name, &value))
                 }
                 Some((_, Container::Message(_))) => {
                     unreachable!()
@@ -369,76 +748,249 @@ impl Context<'_> {
         Some(value)
     }
 
+    /// The value an implicit field is not written with. Proto2 fields have
+    /// presence, so required ones are written even if they are the default.
+    pub fn implicit_default(&self, field: &Field) -> Option<String> {
+        match self.fd.syntax {
+            Syntax::Proto2 => None,
+            _ => self.default_value(field),
+        }
+    }
+
     pub fn maybe_fixed_size(&self, typ: &FieldType) -> Option<usize> {
         match typ {
             FieldType::Bool => Some(1),
             FieldType::Fixed64 | FieldType::Sfixed64 | FieldType::Double => Some(8),
             FieldType::Fixed32 | FieldType::Sfixed32 | FieldType::Float => Some(4),
             FieldType::Message(typ) => match self.lookup_type(typ) {
-                Some((_, Container::Enum(en))) => maybe_fixed_size_enum(en),
+                Some((_, Container::Enum(en))) => self.maybe_fixed_size_enum(en),
                 _ => None,
             },
             _ => None,
         }
     }
-}
 
-fn lookup<'a, 'b>(
-    mut msg: &'a Message,
-    mut segments: impl Iterator<Item = &'b str>,
-) -> Option<(String, Container<'a>)> {
-    let mut stack = vec![msg.name.clone()];
+    /// Whether the message can derive `Eq` and `Hash`, every message reachable
+    /// from its fields is checked too. Floats rule out both, a `HashMap` rules
+    /// out `Hash`. Custom field types, string, bytes and repeated types and
+    /// extern types are not known to implement them, so they rule out both
+    /// as well.
+    pub fn derivable(&self, msg: &'a Message) -> Derivable {
+        let mut visited = HashSet::from([self.full_name("")]);
+        self.message_derivable(msg, &mut visited)
+    }
 
-    loop {
-        let Some(segment) = segments.next() else {
-            break;
-        };
+    /// Like `derivable`, for the enum of a oneof in the current message
+    pub fn oneof_derivable(&self, oneof: &'a OneOf) -> Derivable {
+        self.variants_derivable(oneof, &mut HashSet::new())
+    }
 
-        match msg.messages.iter().find(|m| m.name == segment) {
-            Some(m) => {
-                msg = m;
+    // messages in `visited` are checked already, or being checked, so a
+    // cycle adds nothing
+    fn message_derivable(&self, msg: &'a Message, visited: &mut HashSet<String>) -> Derivable {
+        let mut derivable = Derivable::ALL;
+        for field in &msg.fields {
+            if self.field_type(field).is_some()
+                || self.value_type(&field.name, &field.typ).is_some()
+                || self.repeated_type(field).is_some()
+            {
+                return Derivable::NONE;
             }
-            None => {
-                if segments.next().is_none() {
-                    // maybe enum
-                    msg.enums.iter().find(|e| e.name == segment)?;
+            if matches!(field.typ, FieldType::Map(..))
+                && matches!(self.map_type(field), MapType::HashMap)
+            {
+                derivable.hash = false;
+            }
+            derivable = derivable.and(self.type_derivable(&field.typ, visited));
+        }
+        for oneof in &msg.oneofs {
+            derivable = derivable.and(self.variants_derivable(oneof, visited));
+        }
+        derivable
+    }
+
+    fn variants_derivable(&self, oneof: &'a OneOf, visited: &mut HashSet<String>) -> Derivable {
+        oneof
+            .variants
+            .iter()
+            .fold(Derivable::ALL, |derivable, variant| {
+                if self.value_type(&variant.name, &variant.typ).is_some() {
+                    return Derivable::NONE;
+                }
+                derivable.and(self.type_derivable(&variant.typ, visited))
+            })
+    }
+
+    fn type_derivable(&self, typ: &FieldType, visited: &mut HashSet<String>) -> Derivable {
+        match typ {
+            FieldType::Double | FieldType::Float => Derivable::NONE,
+            FieldType::Map(key, value) => self
+                .type_derivable(key, visited)
+                .and(self.type_derivable(value, visited)),
+            FieldType::Message(name) => {
+                // unresolved types are reported by `check_types`
+                let Ok(resolved) = self.resolve_symbol(name) else {
+                    return Derivable::ALL;
+                };
+                let Some((fd, messages)) = self.definition(&resolved.full_name) else {
+                    return Derivable::ALL;
+                };
+                let package = fd.package.as_deref().unwrap_or_default();
+                if fd.package != self.fd.package
+                    && self.extern_path(package, &resolved.full_name).is_some()
+                {
+                    return Derivable::NONE;
+                }
+
+                match resolved.container {
+                    Container::Message(msg) if visited.insert(resolved.full_name) => {
+                        // fields of the message resolve from its own scope
+                        let cx = Context {
+                            fd,
+                            config: self.config,
+                            imports: self.imports,
+                            messages,
+                        };
+                        cx.message_derivable(msg, visited)
+                    }
+                    _ => Derivable::ALL,
+                }
+            }
+            _ => Derivable::ALL,
+        }
+    }
+
+    // the file a type is defined in, and the messages it's nested in, a
+    // message itself included
+    fn definition(&self, full: &str) -> Option<(&'a FileDescriptor, Vec<&'a Message>)> {
+        'files: for fd in iter::once(self.fd).chain(self.imports.values()) {
+            let rest = match fd.package.as_deref() {
+                Some(package) => match full.strip_prefix(package).and_then(|s| s.strip_prefix('.'))
+                {
+                    Some(rest) => rest,
+                    None => continue,
+                },
+                None => full,
+            };
+
+            let mut messages = vec![];
+            let (mut nested, mut enums) = (&fd.messages, &fd.enums);
+            let mut segments = rest.split('.').peekable();
+            while let Some(segment) = segments.next() {
+                if let Some(msg) = nested.iter().find(|msg| msg.name == segment) {
+                    messages.push(msg);
+                    (nested, enums) = (&msg.messages, &msg.enums);
+                } else if segments.peek().is_none() && enums.iter().any(|en| en.name == segment) {
+                    break;
                 } else {
-                    return None;
+                    continue 'files;
                 }
             }
+            return Some((fd, messages));
         }
+        None
+    }
 
-        stack.push(segment.to_string());
+    // an open enum can hold any i32, so the encoded size of it is never fixed
+    pub fn maybe_fixed_size_enum(&self, en: &Enum) -> Option<usize> {
+        if self.open_enum(en) {
+            return None;
+        }
+
+        maybe_fixed_size_enum(en)
     }
 
-    if let [previous @ .., last] = stack.as_slice() {
-        let path = if previous.is_empty() {
-            sanitize_type_name(last)
-        } else {
-            format!(
-                "{}::{}",
-                previous
-                    .iter()
-                    .map(|s| snake(s))
-                    .collect::<Vec<_>>()
-                    .join("::"),
-                sanitize_type_name(last)
-            )
+    /// Open enums keep unknown values in the `Unrecognized` variant
+    #[inline]
+    pub fn open_enum(&self, en: &Enum) -> bool {
+        self.config.open_enums && !en.closed
+    }
+
+    /// Unknown values of closed enums are kept like unknown fields, instead
+    /// of failing the decoding
+    #[inline]
+    pub fn skip_unknown_variant(&self, en: &Enum) -> bool {
+        self.config.open_enums && en.closed
+    }
+
+    /// Messages with fields of closed enums keep the unknown values of them
+    /// in `unknown_fields`, see `skip_unknown_variant`
+    pub fn keeps_unknown_fields(&self, msg: &Message) -> bool {
+        let closed = |typ: &FieldType| {
+            let typ = match typ {
+                FieldType::Map(_, value) => value,
+                typ => typ,
+            };
+            match typ {
+                FieldType::Message(name) => matches!(
+                    self.lookup_type(name),
+                    Some((_, Container::Enum(en))) if self.skip_unknown_variant(en)
+                ),
+                _ => false,
+            }
         };
 
-        if let Some(en) = msg.enums.iter().find(|e| &e.name == last) {
-            Some((path, Container::Enum(en)))
-        } else {
-            Some((path, Container::Message(msg)))
+        msg.fields.iter().any(|field| closed(&field.typ))
+            || msg
+                .oneofs
+                .iter()
+                .flat_map(|oneof| &oneof.variants)
+                .any(|variant| closed(&variant.typ))
+    }
+}
+
+// lookup a message or enum by the name relative to the package of the file,
+// the path returned is relative to the module of the file
+fn lookup<'a>(fd: &'a FileDescriptor, name: &str) -> Option<(String, Container<'a>)> {
+    let mut segments = name.split('.').peekable();
+    let mut messages = &fd.messages;
+    let mut enums = &fd.enums;
+    let mut path = vec![];
+
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none()
+            && let Some(en) = enums.iter().find(|en| en.name == segment)
+        {
+            path.push(sanitize_type_name(segment));
+            return Some((path.join("::"), Container::Enum(en)));
         }
-    } else {
-        None
+
+        let msg = messages.iter().find(|msg| msg.name == segment)?;
+        if segments.peek().is_none() {
+            path.push(sanitize_type_name(segment));
+            return Some((path.join("::"), Container::Message(msg)));
+        }
+
+        path.push(snake(segment));
+        messages = &msg.messages;
+        enums = &msg.enums;
     }
+
+    None
+}
+
+/// Package of a file, files without `package` are named after the import
+/// path
+pub fn package_of(name: &str, fd: &FileDescriptor) -> String {
+    match &fd.package {
+        Some(pkg) => pkg.to_string(),
+        None => sanitize_filepath(name),
+    }
+}
+
+/// Path of the module a file is generated in, from the root of the package
+/// tree, e.g. `foo::bar` for package `foo.bar`
+pub fn package_module(name: &str, fd: &FileDescriptor) -> String {
+    package_of(name, fd)
+        .split('.')
+        .map(snake)
+        .collect::<Vec<_>>()
+        .join("::")
 }
 
 // a little optimize for enums which don't have dynamic size
 pub fn maybe_fixed_size_enum(en: &Enum) -> Option<usize> {
-    let mut values = en.variants.iter().map(|(_variant, value)| value);
+    let mut values = en.variants.iter().map(|variant| &variant.value);
     let size = sizeof_varint(*values.next()? as u64);
 
     for other in values {
@@ -449,3 +1001,455 @@ pub fn maybe_fixed_size_enum(en: &Enum) -> Option<usize> {
 
     Some(size)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let imported = crate::parse(
+            br#"
+            syntax = "proto3";
+            package foo.baz;
+
+            message Outer {
+                message Middle {
+                    message Inner {
+                        enum Color { RED = 0; }
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let fd = crate::parse(
+            br#"
+            syntax = "proto3";
+            package foo.bar;
+
+            import "imported.proto";
+
+            message Outer {
+                message Data {}
+            }
+            message Data {}
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::from([("imported.proto".to_string(), imported)]);
+        let config = Config::default();
+        let mut cx = Context {
+            fd: &fd,
+            config: &config,
+            imports: &imports,
+            messages: vec![],
+        };
+
+        let path = |cx: &Context, typ: &str| cx.resolve(typ).map(|(path, _)| path);
+
+        // relative to parent packages
+        assert_eq!(path(&cx, "Outer").unwrap(), "Outer");
+        assert_eq!(path(&cx, "baz.Outer").unwrap(), "_root::foo::baz::Outer");
+        assert_eq!(
+            path(&cx, "baz.Outer.Middle.Inner.Color").unwrap(),
+            "_root::foo::baz::outer::middle::inner::Color"
+        );
+        assert_eq!(
+            path(&cx, ".foo.baz.Outer").unwrap(),
+            "_root::foo::baz::Outer"
+        );
+
+        // the innermost scope wins
+        cx.messages.push(&fd.messages[0]);
+        assert_eq!(path(&cx, "Data").unwrap(), "outer::Data");
+        assert_eq!(path(&cx, ".foo.bar.Data").unwrap(), "Data");
+
+        // `Outer` is found in `foo.bar`, outer scopes are not searched anymore
+        assert!(matches!(
+            cx.resolve("Outer.Middle"),
+            Err(Error::UnresolvedType { .. })
+        ));
+        assert!(matches!(
+            cx.resolve("Missing"),
+            Err(Error::UnresolvedType { .. })
+        ));
+        // a package is not a type
+        assert!(matches!(
+            cx.resolve("foo.baz"),
+            Err(Error::UnresolvedType { .. })
+        ));
+    }
+
+    #[test]
+    fn public_import() {
+        let inner = crate::parse(b"package inner; message Inner {}").unwrap();
+        let hidden = crate::parse(b"package hidden; message Hidden {}").unwrap();
+        let middle = crate::parse(
+            br#"
+            package middle;
+            import public "inner.proto";
+            import "hidden.proto";
+            "#,
+        )
+        .unwrap();
+        let fd = crate::parse(
+            br#"
+            package top;
+            import "middle.proto";
+            import weak "missing.proto";
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::from([
+            ("inner.proto".to_string(), inner),
+            ("hidden.proto".to_string(), hidden),
+            ("middle.proto".to_string(), middle),
+        ]);
+        let config = Config::default();
+        let cx = Context {
+            fd: &fd,
+            config: &config,
+            imports: &imports,
+            messages: vec![],
+        };
+
+        // public imports are visible transitively, in the module of their
+        // own package
+        assert_eq!(cx.resolve("inner.Inner").unwrap().0, "_root::inner::Inner");
+        assert!(matches!(
+            cx.resolve("hidden.Hidden"),
+            Err(Error::UnresolvedType { .. })
+        ));
+    }
+
+    #[test]
+    fn ambiguous() {
+        let first = crate::parse(b"package dup; message Data {}").unwrap();
+        let second = crate::parse(b"package dup; message Data {}").unwrap();
+        let fd = crate::parse(
+            br#"
+            import "first.proto";
+            import "second.proto";
+            package dup;
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::from([
+            ("first.proto".to_string(), first),
+            ("second.proto".to_string(), second),
+        ]);
+        let config = Config::default();
+        let cx = Context {
+            fd: &fd,
+            config: &config,
+            imports: &imports,
+            messages: vec![],
+        };
+
+        match cx.resolve("Data") {
+            Err(Error::AmbiguousType { candidates, .. }) => {
+                assert_eq!(
+                    candidates,
+                    [
+                        "`dup.Data` in `first.proto`",
+                        "`dup.Data` in `second.proto`"
+                    ]
+                )
+            }
+            other => panic!("unexpected {:?}", other.map(|(path, _)| path)),
+        }
+    }
+
+    #[test]
+    fn repeated_type() {
+        let fd = crate::parse(
+            br#"
+            package foo;
+            message Data {
+                repeated string names = 1;
+                repeated uint32 ids = 2;
+                repeated bytes blobs = 3;
+                map<string, string> labels = 4;
+                optional string name = 5;
+            }
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::new();
+        let mut config = Config::default();
+        config
+            .repeated_type(".foo", "smallvec::SmallVec<[_; 4]>")
+            .repeated_type("ids", "my_crate::IdList")
+            .repeated_type("blobs", "Box<Foo<_, my_type>>")
+            .string_type(".foo.Data.name", "std::sync::Arc<str>");
+        let mut cx = Context {
+            fd: &fd,
+            config: &config,
+            imports: &imports,
+            messages: vec![],
+        };
+        cx.messages.push(&fd.messages[0]);
+
+        let field = |name: &str| {
+            fd.messages[0]
+                .fields
+                .iter()
+                .find(|f| f.name == name)
+                .unwrap()
+        };
+        assert_eq!(
+            cx.repeated(field("names"), "String"),
+            "smallvec::SmallVec<[String; 4]>"
+        );
+        assert_eq!(cx.repeated(field("ids"), "u32"), "my_crate::IdList<u32>");
+        assert_eq!(
+            cx.repeated(field("blobs"), "Vec<u8>"),
+            "Box<Foo<Vec<u8>, my_type>>"
+        );
+        assert!(cx.repeated_type(field("labels")).is_none());
+
+        let name = field("name");
+        assert_eq!(
+            cx.value_type(&name.name, &name.typ).unwrap(),
+            "std::sync::Arc<str>"
+        );
+        let ids = field("ids");
+        assert!(cx.value_type(&ids.name, &ids.typ).is_none());
+    }
+
+    #[test]
+    fn extern_path() {
+        let common =
+            crate::parse(b"package company.common; message Id { message Part {} } message Time {}")
+                .unwrap();
+        let v1 = crate::parse(b"package company.common.v1; message Id {}").unwrap();
+        let fd = crate::parse(
+            br#"
+            package company.app;
+            import "common.proto";
+            import "v1.proto";
+            message Data {}
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::from([
+            ("common.proto".to_string(), common),
+            ("v1.proto".to_string(), v1),
+        ]);
+        let mut config = Config::default();
+        config
+            .extern_path(".company.common", "::common_proto")
+            .extern_path(".company.common.Time", "::chrono_proto::Timestamp")
+            .extern_path(".company.app", "::app_proto");
+        let cx = Context {
+            fd: &fd,
+            config: &config,
+            imports: &imports,
+            messages: vec![],
+        };
+
+        let path = |typ: &str| cx.resolve(typ).unwrap().0;
+        assert_eq!(path("common.Id"), "::common_proto::Id");
+        assert_eq!(path("common.Id.Part"), "::common_proto::id::Part");
+        assert_eq!(path("common.v1.Id"), "::common_proto::v1::Id");
+        // the longest path wins
+        assert_eq!(path("common.Time"), "::chrono_proto::Timestamp");
+        // the package generated is never extern
+        assert_eq!(path("Data"), "Data");
+    }
+
+    #[test]
+    fn derivable() {
+        let geo = crate::parse(
+            br#"
+            syntax = "proto3";
+            package geo;
+            message Inner { int32 value = 1; }
+            message Point {
+                int64 x = 1;
+                Inner inner = 2;
+                message Inner { Unit unit = 1; }
+                enum Unit { UNIT_UNSPECIFIED = 0; }
+            }
+            message Measure {
+                Inner inner = 1;
+                message Inner { double value = 1; }
+            }
+            "#,
+        )
+        .unwrap();
+        let ext =
+            crate::parse(b"syntax = \"proto3\"; package ext; message Thing { int32 value = 1; }")
+                .unwrap();
+        let fd = crate::parse(
+            br#"
+            syntax = "proto3";
+            package app;
+            import "geo.proto";
+            import "ext.proto";
+            message Shape {
+                geo.Point center = 1;
+                repeated Shape children = 2;
+                oneof area {
+                    geo.Measure measure = 3;
+                    int32 side = 4;
+                }
+                oneof anchor {
+                    geo.Point point = 5;
+                    string name = 6;
+                }
+            }
+            message Labels { map<string, geo.Point> labels = 1; }
+            message Sorted {
+                map<string, geo.Point> labels = 1;
+                Sorted next = 2;
+            }
+            message Custom { int32 id = 1; }
+            message External { ext.Thing thing = 1; }
+            message Containers {
+                repeated int32 ids = 1;
+                oneof data {
+                    bytes raw = 2;
+                    int32 size = 3;
+                }
+                string name = 4;
+            }
+            message Text { string text = 1; }
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::from([
+            ("geo.proto".to_string(), geo),
+            ("ext.proto".to_string(), ext),
+        ]);
+        let mut config = Config::default();
+        config
+            .btree_map(["Sorted.labels"])
+            .field_type("Custom.id", "MyId")
+            .repeated_type("Containers.ids", "MyList")
+            .bytes_type("Containers.raw", "MyBytes")
+            .string_type("Text.text", "MyStr")
+            .extern_path(".ext", "::ext");
+
+        let check = |name: &str| {
+            let msg = fd.messages.iter().find(|msg| msg.name == name).unwrap();
+            let cx = Context {
+                fd: &fd,
+                config: &config,
+                imports: &imports,
+                messages: vec![msg],
+            };
+            let oneofs = msg
+                .oneofs
+                .iter()
+                .map(|oneof| cx.oneof_derivable(oneof))
+                .collect::<Vec<_>>();
+            (cx.derivable(msg), oneofs)
+        };
+        let derivable = |eq, hash| Derivable { eq, hash };
+
+        // `Measure.inner` is the nested message with a float
+        assert_eq!(
+            check("Shape"),
+            (
+                derivable(false, false),
+                vec![derivable(false, false), derivable(true, true)]
+            )
+        );
+        assert_eq!(check("Labels").0, derivable(true, false));
+        assert_eq!(check("Sorted").0, derivable(true, true));
+        assert_eq!(check("Custom").0, derivable(false, false));
+        assert_eq!(check("External").0, derivable(false, false));
+        assert_eq!(
+            check("Containers"),
+            (derivable(false, false), vec![derivable(false, false)])
+        );
+        assert_eq!(check("Text").0, derivable(false, false));
+    }
+
+    #[test]
+    fn invalid_extension() {
+        let check = |input: &str| {
+            let fd = crate::parse(input.as_bytes()).unwrap();
+            let imports = HashMap::new();
+            let config = Config::default();
+            let mut cx = Context {
+                fd: &fd,
+                config: &config,
+                imports: &imports,
+                messages: vec![],
+            };
+            cx.check_types().map_err(|err| err.to_string())
+        };
+
+        let input = r#"
+            syntax = "proto2";
+            package pkg;
+            message Foo { extensions 10 to 20; }
+            enum Bar { BAR = 0; }
+        "#;
+        assert_eq!(
+            check(&format!(
+                "{input} extend Foo {{ optional int32 foo = 10; }}"
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            check(&format!("{input} extend Foo {{ optional int32 foo = 9; }}")),
+            Err(
+                "invalid extension: `9` of `pkg.foo` is not in the extension ranges of `pkg.Foo`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check(&format!("{input} extend Bar {{ optional int32 bar = 1; }}")),
+            Err("invalid extension: `pkg.Bar` is not a message".to_string())
+        );
+        assert_eq!(
+            check(&format!("{input} extend Baz {{ optional int32 baz = 1; }}")),
+            Err("type `Baz` is not defined in scope `pkg`".to_string())
+        );
+    }
+
+    #[test]
+    fn unsupported_field_type() {
+        let fd = crate::parse(
+            br#"
+            syntax = "proto2";
+            package pkg;
+            message Data {
+                map<string, int32> map = 1;
+                optional int32 count = 2 [default = 1];
+                optional int32 other = 3;
+            }
+            "#,
+        )
+        .unwrap();
+        let imports = HashMap::new();
+        let check = |path: &str| {
+            let mut config = Config::default();
+            config.field_type(path, "Custom");
+            let mut cx = Context {
+                fd: &fd,
+                config: &config,
+                imports: &imports,
+                messages: vec![],
+            };
+            cx.check_types().map_err(|err| err.to_string())
+        };
+
+        assert_eq!(
+            check("Data.map"),
+            Err(
+                "invalid config: `field_type` of `pkg.Data.map`, map fields are not supported"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check("Data.count"),
+            Err("invalid config: `field_type` of `pkg.Data.count`, fields with default values are not supported".to_string())
+        );
+        assert_eq!(check("Data.other"), Ok(()));
+    }
+}
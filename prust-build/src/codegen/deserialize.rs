@@ -1,16 +1,20 @@
 use super::Buffer;
 use super::context::{Container, Context};
+use super::generate::conversion;
 use super::sanitize::{sanitize_type_name, snake, upper_camel};
-use crate::ast::{FieldCardinality, FieldType, Message};
+use crate::ast::{Field, FieldCardinality, FieldType, Label, Message};
 
 pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
+    if msg.has_deprecated_fields() {
+        buf.push("#[allow(deprecated)]\n");
+    }
     buf.push(format!(
         "impl Deserialize for {} {{\n",
         sanitize_type_name(&msg.name)
     ));
     buf.indent += 1;
 
-    if msg.fields.is_empty() && msg.oneofs.is_empty() {
+    if msg.is_empty() {
         buf.push("fn decode(_: &[u8]) -> Result<Self, DecodeError> { Ok(Self) }\n");
         buf.indent -= 1;
         buf.push("}\n");
@@ -20,7 +24,7 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
     buf.push("fn decode(src: &[u8]) -> Result<Self, DecodeError> {\n");
     buf.indent += 1;
 
-    if msg.fields.is_empty() && msg.oneofs.is_empty() {
+    if msg.is_empty() {
         buf.push("Ok(Self::default())\n");
     } else {
         buf.push("let mut buf = Reader::new(src);\n");
@@ -38,6 +42,14 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
             buf.push("    match buf.read_uint32()? {\n");
         }
         for field in &msg.fields {
+            if let Some(rust_type) = cx.field_type(field) {
+                buf.push(format!(
+                    "        {},\n",
+                    custom_decode(field, rust_type, cx)
+                ));
+                continue;
+            }
+
             let mut tag = cx.tag(field);
 
             match cx.cardinality(field) {
@@ -45,29 +57,37 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
                     let assignment = match &field.typ {
                         FieldType::Message(typ) => match cx.lookup_type(typ) {
                             Some((_path, Container::Message(_))) => {
-                                if &msg.name == typ {
+                                if cx.is_recursive(&field.typ, msg) {
                                     format!(
                                         "msg.{} = Some(Box::new({}?))",
                                         snake(&field.name),
-                                        read_field(&field.typ, cx)
+                                        read_value(&field.name, &field.typ, cx)
                                     )
                                 } else {
                                     format!(
                                         "msg.{} = Some({}?)",
                                         snake(&field.name),
-                                        read_field(&field.typ, cx)
+                                        read_value(&field.name, &field.typ, cx)
                                     )
                                 }
                             }
-                            Some((_path, Container::Enum(_))) => {
+                            Some((_path, Container::Enum(en))) => {
                                 tag = field.number << 3 | 0;
-                                format!("msg.{} = Some(buf.read_enum()?)", snake(&field.name),)
+                                if cx.skip_unknown_variant(en) {
+                                    format!(
+                                        "if let Some(v) = {}? {{ msg.{} = Some(v) }}",
+                                        read_closed_enum(tag),
+                                        snake(&field.name)
+                                    )
+                                } else {
+                                    format!("msg.{} = Some(buf.read_enum()?)", snake(&field.name),)
+                                }
                             }
                             None => {
                                 format!(
                                     "msg.{} = Some({}?)",
                                     snake(&field.name),
-                                    read_field(&field.typ, cx)
+                                    read_value(&field.name, &field.typ, cx)
                                 )
                             }
                         },
@@ -75,7 +95,7 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
                             format!(
                                 "msg.{} = Some({}?)",
                                 snake(&field.name),
-                                read_field(&field.typ, cx)
+                                read_value(&field.name, &field.typ, cx)
                             )
                         }
                     };
@@ -90,49 +110,90 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
                         tag = field.number << 3 | 0;
                     }
 
-                    buf.push(format!(
-                        "        {tag} => msg.{} = {}?,\n",
-                        snake(&field.name),
-                        read_field(&field.typ, cx)
-                    ));
+                    if skip_unknown_variant(&field.typ, cx) {
+                        buf.push(format!(
+                            "        {tag} => if let Some(v) = {}? {{ msg.{} = v }},\n",
+                            read_closed_enum(tag),
+                            snake(&field.name),
+                        ));
+                    } else {
+                        buf.push(format!(
+                            "        {tag} => msg.{} = {}?,\n",
+                            snake(&field.name),
+                            read_value(&field.name, &field.typ, cx)
+                        ));
+                    }
                 }
                 FieldCardinality::Repeated => {
                     let assignment = if cx.packed(field) {
                         tag = field.number << 3 | 2;
 
-                        if cx.maybe_fixed_size(&field.typ).is_some() {
-                            format!(
-                                "msg.{} = buf.read_packed_fixed()?",
-                                snake(&field.name),
-                            )
+                        // enums are not fixed size in memory, even if all
+                        // variants can be encoded with the same size
+                        let read = if field.typ.fixed_size().is_some() {
+                            "buf.read_packed_fixed()".to_string()
+                        } else if skip_unknown_variant(&field.typ, cx) {
+                            read_packed_closed_enum(field)
                         } else {
-                            format!(
-                                "msg.{} = buf.read_packed({})?",
-                                snake(&field.name),
-                                read_func(&field.typ, cx)
-                            )
+                            format!("buf.read_packed({})", read_func(&field.typ, cx))
+                        };
+
+                        // other containers are filled one by one
+                        match cx.repeated_type(field) {
+                            Some(_) => {
+                                format!("for v in {read}? {{ msg.{}.push(v) }}", snake(&field.name))
+                            }
+                            None => format!("msg.{} = {read}?", snake(&field.name)),
                         }
+                    } else if skip_unknown_variant(&field.typ, cx) {
+                        format!(
+                            "if let Some(v) = {}? {{ msg.{}.push(v) }}",
+                            read_closed_enum(tag),
+                            snake(&field.name),
+                        )
                     } else {
                         format!(
                             "msg.{}.push({}?)",
                             snake(&field.name),
-                            read_field(&field.typ, cx)
+                            read_value(&field.name, &field.typ, cx)
                         )
                     };
 
                     buf.push(format!("        {tag} => {assignment},\n"));
+
+                    // unknown values are written back unpacked
+                    if cx.packed(field) && skip_unknown_variant(&field.typ, cx) {
+                        let tag = field.number << 3;
+                        buf.push(format!(
+                            "        {tag} => if let Some(v) = {}? {{ msg.{}.push(v) }},\n",
+                            read_closed_enum(tag),
+                            snake(&field.name),
+                        ));
+                    }
                 }
                 FieldCardinality::Map(key, value) => {
                     buf.indent += 2;
                     buf.push(format!("{tag} => {{\n"));
                     buf.indent += 1;
 
-                    buf.push(format!(
-                        "let (k, v) = buf.read_key_value({}, {})?;\n",
-                        read_func(key, cx),
-                        read_func(value, cx)
-                    ));
-                    buf.push(format!("msg.{}.insert(k, v);\n", snake(&field.name)));
+                    if skip_unknown_variant(value, cx) {
+                        // the whole entry is kept if the value is unknown
+                        buf.push(format!(
+                            "let entry = buf.read_closed_enum_entry({tag}, {}, &mut msg.unknown_fields)?;\n",
+                            read_func(key, cx),
+                        ));
+                        buf.push(format!(
+                            "if let Some((k, v)) = entry {{ msg.{}.insert(k, v); }}\n",
+                            snake(&field.name)
+                        ));
+                    } else {
+                        buf.push(format!(
+                            "let (k, v) = buf.read_key_value({}, {})?;\n",
+                            read_func(key, cx),
+                            read_func(value, cx)
+                        ));
+                        buf.push(format!("msg.{}.insert(k, v);\n", snake(&field.name)));
+                    }
 
                     buf.indent -= 1;
                     buf.push("}\n");
@@ -160,6 +221,19 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
                     FieldType::Fixed32 | FieldType::Sfixed32 | FieldType::Float => 5,
                 };
 
+                if skip_unknown_variant(&variant.typ, cx) {
+                    let tag = variant.number << 3 | wire_type;
+                    buf.push(format!(
+                        "        {tag} => if let Some(v) = {}? {{ msg.{} = Some({}::{}::{}(v)) }},\n",
+                        read_closed_enum(tag),
+                        snake(&oneof.name),
+                        snake(&msg.name),
+                        upper_camel(&oneof.name),
+                        upper_camel(&variant.name),
+                    ));
+                    continue;
+                }
+
                 // todo: handle type properly
                 buf.push(format!(
                     "        {} => msg.{} = Some({}::{}({}?)),\n",
@@ -167,12 +241,24 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
                     snake(&oneof.name),
                     format!("{}::{}", snake(&msg.name), upper_camel(&oneof.name)),
                     upper_camel(&variant.name),
-                    read_field(&variant.typ, cx),
+                    read_value(&variant.name, &variant.typ, cx),
                 ));
             }
         }
 
-        if small_message(msg, cx) {
+        if msg.extendable() {
+            let ranges = msg
+                .extensions
+                .iter()
+                .map(|ext| format!("{}..={}", ext.start << 3, ext.end << 3 | 0x7))
+                .collect::<Vec<_>>()
+                .join(" | ");
+
+            buf.push(format!(
+                "        tag @ ({ranges}) => buf.read_extension(tag, &mut msg.extensions)?,\n"
+            ));
+            buf.push("        tag => buf.read_unknown(tag)?,\n");
+        } else if small_message(msg, cx) {
             buf.push("        _ => {\n");
             buf.push("            buf.pos -= 1;\n");
             buf.push("            let tag = buf.read_uint32()?;\n");
@@ -193,6 +279,67 @@ pub fn generate_deserialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
     buf.push("}\n");
 }
 
+// the match arm decoding a field of a custom rust type, the value read is
+// converted from the proto type, see `Config::field_type`
+fn custom_decode(field: &Field, rust_type: &str, cx: &Context) -> String {
+    let name = snake(&field.name);
+    let from = conversion(field, rust_type, cx).from;
+    let tag = cx.tag(field);
+
+    if field.label == Label::Repeated && cx.packed(field) {
+        let read = if field.typ.fixed_size().is_some() {
+            "buf.read_packed_fixed()".to_string()
+        } else if skip_unknown_variant(&field.typ, cx) {
+            read_packed_closed_enum(field)
+        } else {
+            format!("buf.read_packed({})", read_func(&field.typ, cx))
+        };
+
+        let packed = format!("{tag} => for v in {read}? {{ msg.{name}.push({from}(v)?) }}");
+        if !skip_unknown_variant(&field.typ, cx) {
+            return packed;
+        }
+
+        // unknown values are written back unpacked
+        let tag = field.number << 3;
+        return format!(
+            "{packed},\n        {tag} => if let Some(v) = {}? {{ msg.{name}.push({from}(v)?) }}",
+            read_closed_enum(tag)
+        );
+    }
+
+    let value = match cx.cardinality(field) {
+        FieldCardinality::Optional => format!("Some({from}(v)?)"),
+        FieldCardinality::Required => format!("{from}(v)?"),
+        FieldCardinality::Repeated => {
+            if skip_unknown_variant(&field.typ, cx) {
+                return format!(
+                    "{tag} => if let Some(v) = {}? {{ msg.{name}.push({from}(v)?) }}",
+                    read_closed_enum(tag)
+                );
+            }
+
+            return format!(
+                "{tag} => msg.{name}.push({from}({}?)?)",
+                read_field(&field.typ, cx)
+            );
+        }
+        FieldCardinality::Map(_, _) => unreachable!("rejected by `Context::check_types`"),
+    };
+
+    if skip_unknown_variant(&field.typ, cx) {
+        format!(
+            "{tag} => if let Some(v) = {}? {{ msg.{name} = {value} }}",
+            read_closed_enum(tag)
+        )
+    } else {
+        format!(
+            "{tag} => {{ let v = {}?; msg.{name} = {value} }}",
+            read_field(&field.typ, cx)
+        )
+    }
+}
+
 fn read_func(typ: &FieldType, cx: &Context) -> &'static str {
     match typ {
         FieldType::Double => "Reader::read_double",
@@ -219,6 +366,17 @@ fn read_func(typ: &FieldType, cx: &Context) -> &'static str {
     }
 }
 
+// reads a value of a field or oneof variant, `string` and `bytes` ones are converted to the
+// type selected, see `Config::string_type`
+fn read_value(name: &str, typ: &FieldType, cx: &Context) -> String {
+    let read = read_field(typ, cx);
+    match (typ, cx.value_type(name, typ)) {
+        (FieldType::String, Some(_)) => format!("{read}.map(ProtoString::from_string)"),
+        (FieldType::Bytes, Some(_)) => format!("{read}.map(ProtoBytes::from_vec)"),
+        _ => read.to_string(),
+    }
+}
+
 fn read_field(typ: &FieldType, cx: &Context) -> &'static str {
     match typ {
         FieldType::Double => "buf.read_double()",
@@ -244,11 +402,43 @@ fn read_field(typ: &FieldType, cx: &Context) -> &'static str {
     }
 }
 
+// Unknown values of closed enums are skipped like unknown fields,
+// see `Config::open_enums`
+fn skip_unknown_variant(typ: &FieldType, cx: &Context) -> bool {
+    match typ {
+        FieldType::Message(typ) => match cx.lookup_type(typ) {
+            Some((_path, Container::Enum(en))) => cx.skip_unknown_variant(en),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// reads a value of a closed enum, an unknown one is kept in the unknown
+// fields of the message as a record of `tag`
+fn read_closed_enum(tag: u32) -> String {
+    format!("buf.read_closed_enum({tag}, &mut msg.unknown_fields)")
+}
+
+// unknown values are kept unpacked, like protobuf does
+fn read_packed_closed_enum(field: &Field) -> String {
+    format!(
+        "buf.read_packed_closed_enum({}, &mut msg.unknown_fields)",
+        field.number << 3
+    )
+}
+
 // Is the msg is small, and all tag is small enough to use only 1 byte
 //
 // This optimization can reduce some function calls, and bound checks,
 // in the perf test, this can increase around 10% throughput.
 fn small_message(msg: &Message, cx: &Context) -> bool {
+    // extension fields are handled in the fallback branch, so the whole tag
+    // is needed
+    if msg.extendable() {
+        return false;
+    }
+
     for field in &msg.fields {
         if cx.tag(field) > 0x7F {
             return false;
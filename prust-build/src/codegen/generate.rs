@@ -1,85 +1,89 @@
-use super::context::{Container, Context};
+use super::context::{Container, Context, Derivable};
 use super::deserialize::generate_deserialize;
 use super::sanitize::{
-    sanitize_field, sanitize_filepath, sanitize_type, sanitize_type_name, sanitize_variant,
+    sanitize_doc, sanitize_field, sanitize_type, sanitize_type_name, sanitize_variant,
 };
 use super::sanitize::{snake, upper_camel};
 use super::serialize::generate_serialize;
 use crate::Error;
-use crate::ast::{Enum, Field, FieldCardinality, FieldType, Message, OneOf};
+use crate::ast::{
+    Comments, Enum, Extend, Field, FieldCardinality, FieldType, FileDescriptor, ImportKind, Label,
+    Message, OneOf, Syntax,
+};
 use crate::codegen::Buffer;
 use crate::codegen::config::MapType;
 use crate::codegen::service::generate_service;
 
-pub fn generate_proto<'a>(buf: &mut Buffer, cx: &mut Context<'a>) -> Result<(), Error> {
-    for path in &cx.fd.imports {
-        let Some(import) = cx.imports.get(path) else {
-            return Err(Error::ImportNotFound(path.to_string()));
-        };
-
-        let path = match &import.package {
-            Some(pkg) => pkg.to_string(),
-            None => match path.strip_suffix(".proto") {
-                Some(pkg) => sanitize_filepath(pkg),
-                None => sanitize_filepath(path),
-            },
-        };
-
-        let base_indent = buf.indent;
-        for name in path.split('.') {
-            buf.push(format!("pub mod {} {{\n", snake(name)));
-            buf.push("    use super::*;\n");
-            buf.indent += 1;
+/// Generate the definitions of all files of a package, which share one
+/// module. Imported files of other packages are generated on their own.
+pub fn generate_package<'a>(buf: &mut Buffer, files: &mut [Context<'a>]) -> Result<(), Error> {
+    for cx in files.iter_mut() {
+        for import in &cx.fd.imports {
+            // weak imports are optional dependencies
+            if import.kind != ImportKind::Weak && !cx.imports.contains_key(&import.path) {
+                return Err(Error::ImportNotFound(import.path.to_string()));
+            }
         }
 
-        let mut cx = Context {
-            fd: import,
-            config: cx.config,
-            imports: cx.imports,
-            messages: vec![],
-        };
+        cx.check_types()?;
+    }
 
-        generate_proto(buf, &mut cx)?;
+    for cx in files.iter_mut() {
+        let fd = cx.fd;
 
-        while buf.indent != base_indent {
-            buf.indent -= 1;
-            buf.push("}\n");
+        for msg in &fd.messages {
+            cx.messages.push(msg);
+            let result = generate_struct(buf, msg, cx);
+            cx.messages.pop();
+            result?;
         }
-    }
 
-    for msg in &cx.fd.messages {
-        cx.messages.push(msg);
-        generate_struct(buf, msg, cx);
-        cx.messages.pop();
-    }
+        for en in &fd.enums {
+            generate_enum(buf, en, cx);
+        }
 
-    for en in &cx.fd.enums {
-        generate_enum(buf, en, cx);
-    }
+        for extend in &fd.extends {
+            generate_extend(buf, extend, cx)?;
+        }
 
-    for svc in &cx.fd.services {
-        generate_service(buf, svc, cx);
+        for svc in &fd.services {
+            generate_service(buf, svc, cx);
+        }
     }
+    generate_register_extensions(buf, files.iter().map(|cx| cx.fd));
 
     Ok(())
 }
 
-fn generate_struct<'a>(buf: &mut Buffer, msg: &'a Message, cx: &mut Context<'a>) {
+fn generate_struct<'a>(
+    buf: &mut Buffer,
+    msg: &'a Message,
+    cx: &mut Context<'a>,
+) -> Result<(), Error> {
     generate_simple_struct(buf, msg, cx);
+    generate_accessors(buf, msg, cx);
 
-    let path = cx.path();
-    if !cx.config.skip_deserialize.contains(&path) {
+    if !cx.skip_deserialize() {
         generate_deserialize(buf, msg, cx);
     }
-    if !cx.config.skip_serialize.contains(&path) {
+    if !cx.skip_serialize() {
         generate_serialize(buf, msg, cx);
     }
+    if msg.extendable() {
+        generate_extendable(buf, msg, cx);
+    }
 
-    if msg.messages.is_empty() && msg.enums.is_empty() && msg.oneofs.is_empty() {
-        return;
+    if msg.messages.is_empty()
+        && msg.enums.is_empty()
+        && msg.oneofs.is_empty()
+        && msg.extends.is_empty()
+    {
+        return Ok(());
     }
 
     buf.push(format!("pub mod {} {{\n", snake(&msg.name)));
+    // oneofs of plain types don't refer to the parent module
+    buf.push("    #[allow(unused_imports)]\n");
     buf.push("    use super::*;\n");
 
     {
@@ -87,8 +91,9 @@ fn generate_struct<'a>(buf: &mut Buffer, msg: &'a Message, cx: &mut Context<'a>)
 
         for msg in &msg.messages {
             cx.messages.push(msg);
-            generate_struct(buf, msg, cx);
+            let result = generate_struct(buf, msg, cx);
             cx.messages.pop();
+            result?;
         }
         for en in &msg.enums {
             generate_enum(buf, en, cx);
@@ -96,23 +101,47 @@ fn generate_struct<'a>(buf: &mut Buffer, msg: &'a Message, cx: &mut Context<'a>)
         for oneof in &msg.oneofs {
             generate_oneof(buf, oneof, cx);
         }
+        for extend in &msg.extends {
+            generate_extend(buf, extend, cx)?;
+        }
 
         buf.indent -= 1;
     }
 
     buf.push("}\n");
+    Ok(())
+}
+
+/// Leading and trailing comments as rustdoc, a blank line between them
+pub fn generate_comments(buf: &mut Buffer, comments: &Comments) {
+    for (index, text) in comments
+        .leading
+        .iter()
+        .chain(&comments.trailing)
+        .enumerate()
+    {
+        if index != 0 {
+            buf.push("///\n");
+        }
+
+        for line in sanitize_doc(text) {
+            if line.is_empty() {
+                buf.push("///\n");
+            } else {
+                buf.push(format!("/// {line}\n"));
+            }
+        }
+    }
 }
 
-fn generate_simple_struct(buf: &mut Buffer, msg: &Message, cx: &Context) {
+fn generate_simple_struct<'a>(buf: &mut Buffer, msg: &'a Message, cx: &Context<'a>) {
+    generate_comments(buf, &msg.comments);
     for attr in cx.message_attributes() {
         buf.push(format!("{attr}\n"))
     }
 
-    if msg.fields.iter().any(|f| f.default_value().is_some()) {
-        buf.push("#[derive(Debug)]\n");
-    } else {
-        buf.push("#[derive(Debug, Default)]\n");
-    }
+    let derives = derives(cx, cx.derivable(msg), !has_default_values(msg, cx));
+    buf.push(format!("#[derive({})]\n", derives.join(", ")));
 
     if msg.is_empty() {
         buf.push(format!("pub struct {};\n", sanitize_type_name(&msg.name)));
@@ -122,10 +151,6 @@ fn generate_simple_struct(buf: &mut Buffer, msg: &Message, cx: &Context) {
     buf.push(format!("pub struct {} {{\n", sanitize_type_name(&msg.name)));
 
     for field in &msg.fields {
-        if field.deprecated() {
-            continue;
-        }
-
         #[cfg(feature = "debug")]
         buf.push(format!(
             "    // {} {} {} = {}\n",
@@ -135,33 +160,27 @@ fn generate_simple_struct(buf: &mut Buffer, msg: &Message, cx: &Context) {
             field.number
         ));
 
-        let typ = generate_field_type(&field.typ, cx);
+        let typ = value_type(&field.name, &field.typ, cx);
         let typ = match cx.cardinality(field) {
             FieldCardinality::Optional => {
-                if typ == msg.name {
+                if cx.is_recursive(&field.typ, msg) {
                     format!("Option<Box<{typ}>>")
                 } else {
                     format!("Option<{typ}>")
                 }
             }
             FieldCardinality::Required => {
-                if typ == msg.name {
+                if cx.is_recursive(&field.typ, msg) {
                     format!("Box<{typ}>")
                 } else {
                     typ
                 }
             }
-            FieldCardinality::Repeated => {
-                format!("Vec<{typ}>")
-            }
+            FieldCardinality::Repeated => cx.repeated(field, &typ),
             FieldCardinality::Map(key, value) => {
-                let path = match &cx.fd.package {
-                    Some(pkg) => format!("{}.{}.{}", pkg, cx.path(), field.name),
-                    None => format!("{}.{}", cx.path(), field.name),
-                };
-                let map_type = match cx.config.tree_map.get(&path) {
-                    Some(MapType::BTreeMap) => "BTreeMap",
-                    _ => "HashMap",
+                let map_type = match cx.map_type(field) {
+                    MapType::BTreeMap => "BTreeMap",
+                    MapType::HashMap => "HashMap",
                 };
 
                 format!(
@@ -172,12 +191,25 @@ fn generate_simple_struct(buf: &mut Buffer, msg: &Message, cx: &Context) {
                 )
             }
         };
+        let typ = match cx.field_type(field) {
+            Some(rust_type) => match cx.cardinality(field) {
+                FieldCardinality::Optional => format!("Option<{rust_type}>"),
+                FieldCardinality::Repeated => cx.repeated(field, rust_type),
+                _ => rust_type.to_string(),
+            },
+            None => typ,
+        };
 
-        let path = format!("{}.{}", cx.path(), field.name);
-        if let Some(attrs) = cx.config.field_attributes.get(&path) {
-            for attr in attrs {
-                buf.push(format!("    {attr}\n"));
-            }
+        buf.indent += 1;
+        generate_comments(buf, &field.comments);
+        buf.indent -= 1;
+
+        if field.deprecated() {
+            buf.push("    #[deprecated]\n");
+        }
+
+        for attr in cx.field_attributes(field) {
+            buf.push(format!("    {attr}\n"));
         }
 
         buf.push(format!(
@@ -188,6 +220,10 @@ fn generate_simple_struct(buf: &mut Buffer, msg: &Message, cx: &Context) {
     }
 
     for oneof in &msg.oneofs {
+        buf.indent += 1;
+        generate_comments(buf, &oneof.comments);
+        buf.indent -= 1;
+
         buf.push(format!(
             "    pub {}: Option<{}::{}>,\n",
             sanitize_field(&oneof.name),
@@ -196,14 +232,128 @@ fn generate_simple_struct(buf: &mut Buffer, msg: &Message, cx: &Context) {
         ));
     }
 
+    if msg.extendable() {
+        buf.push("    pub extensions: ExtensionSet,\n");
+    }
+    if cx.keeps_unknown_fields(msg) {
+        buf.push("    pub unknown_fields: UnknownFields,\n");
+    }
+
     buf.push("}\n");
 
-    if msg.fields.iter().any(|f| f.default_value().is_some()) {
+    if has_default_values(msg, cx) {
         generate_struct_default(buf, msg, cx);
     }
 }
 
+// whether fields are set to their default values, optional ones are unset
+// and their accessors return them. Custom types are converted from the zero
+// value, they might not implement `Default`.
+fn has_default_values(msg: &Message, cx: &Context) -> bool {
+    msg.fields.iter().any(|field| {
+        (field.default_value().is_some() || cx.field_type(field).is_some())
+            && matches!(cx.cardinality(field), FieldCardinality::Required)
+    })
+}
+
+// `foo()`, `has_foo()`, `set_foo()` and `clear_foo()` of proto2 optional
+// fields, the getter returns the default value when the field is unset
+fn generate_accessors(buf: &mut Buffer, msg: &Message, cx: &Context) {
+    let fields = msg
+        .fields
+        .iter()
+        .filter(|field| has_accessors(field, cx))
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        return;
+    }
+
+    // accessors of fields that aren't read are unused
+    buf.push("#[allow(dead_code)]\n");
+    if msg.has_deprecated_fields() {
+        buf.push("#[allow(deprecated)]\n");
+    }
+    buf.push(format!("impl {} {{\n", sanitize_type_name(&msg.name)));
+    buf.indent += 1;
+
+    for field in fields {
+        let name = sanitize_field(&field.name);
+        // `has_type`, not `has_r#type`
+        let method = snake(&field.name).trim_start_matches("r#").to_string();
+        let default = match field.default_value() {
+            Some(value) => Some(generate_default_value(field, &value, cx)),
+            None => match &field.typ {
+                FieldType::String => Some("\"\"".to_string()),
+                FieldType::Bytes => Some("b\"\"".to_string()),
+                FieldType::Message(typ) => match cx.lookup_type(typ) {
+                    Some((path, Container::Enum(en))) => Some(format!(
+                        "{path}::{}",
+                        sanitize_variant(&en.name, en.default_value())
+                    )),
+                    _ => None,
+                },
+                _ => None,
+            },
+        };
+        let (typ, get) = match (&field.typ, default) {
+            (FieldType::String, Some(default)) => (
+                "&str".to_string(),
+                format!("self.{name}.as_deref().unwrap_or({default})"),
+            ),
+            (FieldType::Bytes, Some(default)) => (
+                "&[u8]".to_string(),
+                format!("self.{name}.as_deref().unwrap_or({default})"),
+            ),
+            (_, Some(default)) => (
+                generate_field_type(&field.typ, cx),
+                format!("self.{name}.unwrap_or({default})"),
+            ),
+            (_, None) => (
+                generate_field_type(&field.typ, cx),
+                format!("self.{name}.unwrap_or_default()"),
+            ),
+        };
+        let value = value_type(&field.name, &field.typ, cx);
+
+        let methods = [
+            format!("pub fn {name}(&self) -> {typ} {{ {get} }}\n"),
+            format!("pub fn has_{method}(&self) -> bool {{ self.{name}.is_some() }}\n"),
+            format!(
+                "pub fn set_{method}(&mut self, value: {value}) {{ self.{name} = Some(value) }}\n"
+            ),
+            format!("pub fn clear_{method}(&mut self) {{ self.{name} = None }}\n"),
+        ];
+        for method in methods {
+            if field.deprecated() {
+                buf.push("#[deprecated]\n");
+            }
+            buf.push(method);
+        }
+    }
+
+    buf.indent -= 1;
+    buf.push("}\n");
+}
+
+// proto2 optional fields of scalars, strings, bytes and enums, messages are
+// `Option` anyway
+fn has_accessors(field: &Field, cx: &Context) -> bool {
+    let scalar = match &field.typ {
+        FieldType::Message(typ) => cx.lookup_type(typ).is_some_and(|(_, c)| c.is_enum()),
+        FieldType::Map(_, _) => false,
+        _ => true,
+    };
+
+    scalar
+        && cx.fd.syntax == Syntax::Proto2
+        && field.label == Label::Optional
+        && cx.field_type(field).is_none()
+}
+
 fn generate_struct_default(buf: &mut Buffer, msg: &Message, cx: &Context) {
+    if msg.has_deprecated_fields() {
+        buf.push("#[allow(deprecated)]\n");
+    }
     buf.push(format!(
         "impl Default for {} {{\n",
         sanitize_type_name(&msg.name)
@@ -217,29 +367,24 @@ fn generate_struct_default(buf: &mut Buffer, msg: &Message, cx: &Context) {
 
         match cx.cardinality(field) {
             FieldCardinality::Optional => {
-                let Some(default) = field.default_value() else {
-                    buf.push(format!("        {field_name}: None,\n"));
-                    continue;
-                };
-
-                let default = generate_default_value(field, default, cx);
-                let default = match &field.typ {
-                    FieldType::Bytes => format!("Vec::from({default})"),
-                    FieldType::String => format!("String::from({default})"),
-                    _ => default,
-                };
-                buf.push(format!(
-                    "        {}: Some({default}),\n",
-                    snake(&field.name),
-                ));
+                buf.push(format!("        {field_name}: None,\n"));
             }
             FieldCardinality::Required => {
+                if let Some(rust_type) = cx.field_type(field) {
+                    let from = conversion(field, rust_type, cx).from;
+                    buf.push(format!(
+                        "        {field_name}: {from}(Default::default()).expect(\"the zero value converts\"),\n"
+                    ));
+                    continue;
+                }
+
                 if let Some(default) = cx.default_value(field) {
                     let default = match &field.typ {
                         FieldType::Bytes => format!("Vec::from(\"{default}\")"),
                         FieldType::String => format!("String::from(\"{default}\")"),
                         _ => default,
                     };
+                    let default = from_default(field, default, cx);
 
                     buf.push(format!("        {field_name}: {default},\n"));
 
@@ -267,6 +412,13 @@ fn generate_struct_default(buf: &mut Buffer, msg: &Message, cx: &Context) {
         buf.push(format!("        {}: None,\n", snake(&oneof.name)));
     }
 
+    if msg.extendable() {
+        buf.push("        extensions: Default::default(),\n");
+    }
+    if cx.keeps_unknown_fields(msg) {
+        buf.push("        unknown_fields: Default::default(),\n");
+    }
+
     buf.push("    }\n");
     buf.push("}\n");
     buf.indent -= 1;
@@ -300,8 +452,9 @@ pub fn generate_default_value(field: &Field, value: &str, cx: &Context) -> Strin
         FieldType::Bool => value.to_string(),
         FieldType::Message(typ) => match cx.lookup_type(typ) {
             Some((path, Container::Enum(en))) => {
-                if en.variants.iter().any(|(variant, _value)| variant == value) {
-                    format!("{}::{}", path, sanitize_variant(&en.name, value))
+                if let Some(variant) = en.variants.iter().find(|variant| variant.name == value) {
+                    let variant = en.canonical(variant);
+                    format!("{}::{}", path, sanitize_variant(&en.name, &variant.name))
                 } else {
                     format!(
                         "{}::{}",
@@ -321,34 +474,89 @@ pub fn generate_default_value(field: &Field, value: &str, cx: &Context) -> Strin
 }
 
 fn generate_enum(buf: &mut Buffer, en: &Enum, cx: &Context) {
-    for attr in cx.enum_attributes() {
+    generate_comments(buf, &en.comments);
+    for attr in cx.enum_attributes(en) {
         buf.push(format!("{attr}\n"))
     }
 
-    buf.push("#[derive(Clone, Copy, Debug, Default, PartialEq)]\n");
-    buf.push("#[repr(i32)]\n");
+    let open = cx.open_enum(en);
+
+    // generated code copies and compares enums, whatever the config is
+    let mut derives = vec!["Clone", "Copy", "Debug", "Default", "PartialEq"];
+    if cx.config.derive_eq {
+        derives.push("Eq");
+    }
+    if cx.config.derive_hash {
+        derives.push("Hash");
+    }
+    buf.push(format!("#[derive({})]\n", derives.join(", ")));
+    if !open {
+        buf.push("#[repr(i32)]\n");
+    }
     buf.push(format!("pub enum {} {{\n", upper_camel(&en.name)));
 
     buf.indent += 1;
     let mut first = true;
-    for (variant, value) in &en.variants {
+    for variant in en.variants.iter().filter(|variant| !en.is_alias(variant)) {
+        generate_comments(buf, &variant.comments);
         if first {
             first = false;
             buf.push("#[default]\n");
         }
+        if variant.deprecated() {
+            buf.push("#[deprecated]\n");
+        }
 
-        buf.push(format!(
-            "{} = {},\n",
-            sanitize_variant(&en.name, variant),
-            value
-        ))
+        if open {
+            buf.push(format!("{},\n", sanitize_variant(&en.name, &variant.name)));
+        } else {
+            buf.push(format!(
+                "{} = {},\n",
+                sanitize_variant(&en.name, &variant.name),
+                variant.value
+            ))
+        }
+    }
+    if open {
+        // the same name as Java's `UNRECOGNIZED`, `Unknown` is used by too
+        // many protos already, e.g. `STATE_UNKNOWN = 0`
+        buf.push("Unrecognized(i32),\n");
     }
     buf.indent -= 1;
 
     buf.push("}\n");
 
+    // aliases, only the first variant of the same value is generated, the
+    // others are associated constants point to it
+    if en.variants.iter().any(|variant| en.is_alias(variant)) {
+        buf.push("#[allow(deprecated)]\n");
+        buf.push(format!("impl {} {{\n", upper_camel(&en.name)));
+        for variant in en.variants.iter().filter(|variant| en.is_alias(variant)) {
+            let canonical = en.canonical(variant);
+
+            buf.push(format!(
+                "    /// Alias of `{}::{}`.\n",
+                upper_camel(&en.name),
+                sanitize_variant(&en.name, &canonical.name)
+            ));
+            if variant.deprecated() {
+                buf.push("    #[deprecated]\n");
+            }
+            buf.push("    #[allow(non_upper_case_globals)]\n");
+            buf.push(format!(
+                "    pub const {}: {} = {}::{};\n",
+                sanitize_variant(&en.name, &variant.name),
+                upper_camel(&en.name),
+                upper_camel(&en.name),
+                sanitize_variant(&en.name, &canonical.name)
+            ));
+        }
+        buf.push("}\n");
+    }
+
     // try from
     {
+        buf.push("#[allow(deprecated)]\n");
         buf.push(format!(
             "impl TryFrom<i32> for {} {{\n",
             upper_camel(&en.name)
@@ -357,18 +565,25 @@ fn generate_enum(buf: &mut Buffer, en: &Enum, cx: &Context) {
         buf.push("    fn try_from(value: i32) -> Result<Self, DecodeError> {\n");
         buf.push("        match value {\n");
 
-        for (variant, value) in &en.variants {
+        for variant in en.variants.iter().filter(|variant| !en.is_alias(variant)) {
             buf.push(format!(
                 "            {} => Ok({}::{}),\n",
-                value,
+                variant.value,
                 upper_camel(&en.name),
-                sanitize_variant(&en.name, variant)
+                sanitize_variant(&en.name, &variant.name)
+            ));
+        }
+        if open {
+            buf.push(format!(
+                "            _ => Ok({}::Unrecognized(value)),\n",
+                upper_camel(&en.name)
+            ));
+        } else {
+            buf.push(format!(
+                "            _ => Err(DecodeError::UnknownVariant(\"{}\", value)),\n",
+                upper_camel(&en.name)
             ));
         }
-        buf.push(format!(
-            "            _ => Err(DecodeError::UnknownVariant(\"{}\", value)),\n",
-            upper_camel(&en.name)
-        ));
         buf.push("        }\n");
         buf.push("    }\n");
         buf.push("}\n");
@@ -376,45 +591,249 @@ fn generate_enum(buf: &mut Buffer, en: &Enum, cx: &Context) {
 
     // from
     {
+        buf.push("#[allow(deprecated)]\n");
         buf.push(format!("impl From<{}> for i32 {{\n", upper_camel(&en.name)));
         buf.push(format!(
             "    fn from(value: {}) -> Self {{\n",
             upper_camel(&en.name)
         ));
 
-        buf.push("        value as i32\n");
+        if open {
+            buf.push("        match value {\n");
+            for variant in en.variants.iter().filter(|variant| !en.is_alias(variant)) {
+                buf.push(format!(
+                    "            {}::{} => {},\n",
+                    upper_camel(&en.name),
+                    sanitize_variant(&en.name, &variant.name),
+                    variant.value
+                ));
+            }
+            buf.push(format!(
+                "            {}::Unrecognized(value) => value,\n",
+                upper_camel(&en.name)
+            ));
+            buf.push("        }\n");
+        } else {
+            buf.push("        value as i32\n");
+        }
 
         buf.push("    }\n");
         buf.push("}\n");
     }
 }
 
-fn generate_oneof<'a>(buf: &mut Buffer, oneof: &OneOf, cx: &Context<'a>) {
-    for attr in cx.oneof_attributes() {
+fn generate_extendable(buf: &mut Buffer, msg: &Message, cx: &Context) {
+    let name = match &cx.fd.package {
+        Some(pkg) => format!("{}.{}", pkg, cx.path()),
+        None => cx.path(),
+    };
+
+    buf.push(format!(
+        "impl Extendable for {} {{\n",
+        sanitize_type_name(&msg.name)
+    ));
+    buf.push(format!("    const NAME: &'static str = \"{name}\";\n"));
+    buf.push("    fn extensions(&self) -> &ExtensionSet { &self.extensions }\n");
+    buf.push("    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }\n");
+    buf.push("}\n");
+}
+
+// extension identifiers are named after the field, e.g. `foo_bar` -> `FOO_BAR`
+fn extension_name(field: &Field) -> String {
+    snake(&field.name).to_uppercase()
+}
+
+fn generate_extend(buf: &mut Buffer, extend: &Extend, cx: &Context) -> Result<(), Error> {
+    let (extendee, _) = cx.extendee(extend)?;
+
+    let scope = match &cx.fd.package {
+        Some(pkg) if cx.messages.is_empty() => pkg.to_string(),
+        Some(pkg) => format!("{}.{}", pkg, cx.path()),
+        None => cx.path(),
+    };
+
+    for field in &extend.fields {
+        let codec = match &field.typ {
+            FieldType::Double => "extension::Double".to_string(),
+            FieldType::Float => "extension::Float".to_string(),
+            FieldType::Int64 => "extension::Int64".to_string(),
+            FieldType::Uint64 => "extension::Uint64".to_string(),
+            FieldType::Int32 => "extension::Int32".to_string(),
+            FieldType::Fixed64 => "extension::Fixed64".to_string(),
+            FieldType::Fixed32 => "extension::Fixed32".to_string(),
+            FieldType::Bool => "extension::Bool".to_string(),
+            FieldType::String => "extension::String".to_string(),
+            FieldType::Bytes => "extension::Bytes".to_string(),
+            FieldType::Uint32 => "extension::Uint32".to_string(),
+            FieldType::Sfixed32 => "extension::Sfixed32".to_string(),
+            FieldType::Sfixed64 => "extension::Sfixed64".to_string(),
+            FieldType::Sint32 => "extension::Sint32".to_string(),
+            FieldType::Sint64 => "extension::Sint64".to_string(),
+            FieldType::Message(typ) => match cx.resolve(typ)? {
+                (path, Container::Message(_)) => format!("extension::Message<{path}>"),
+                (path, Container::Enum(_)) => format!("extension::Enum<{path}>"),
+            },
+            FieldType::Map(_, _) => {
+                return Err(Error::InvalidExtension(format!(
+                    "`{}` is a map",
+                    cx.full_name(&field.name)
+                )));
+            }
+        };
+        let codec = match field.label {
+            Label::Repeated if cx.packed(field) => format!("extension::Packed<{codec}>"),
+            Label::Repeated => format!("extension::Repeated<{codec}>"),
+            _ => codec,
+        };
+
+        let name = if scope.is_empty() {
+            field.name.to_string()
+        } else {
+            format!("{}.{}", scope, field.name)
+        };
+
+        generate_comments(buf, &field.comments);
+        buf.push("#[allow(dead_code)]\n");
+        buf.push(format!(
+            "pub const {}: Extension<{extendee}, {codec}> = Extension::new({}, \"{name}\");\n",
+            extension_name(field),
+            field.number,
+        ));
+    }
+
+    Ok(())
+}
+
+// one function registers the extensions of every file of the package,
+// including the nested ones
+fn generate_register_extensions<'a>(
+    buf: &mut Buffer,
+    files: impl Iterator<Item = &'a FileDescriptor>,
+) {
+    fn collect(prefix: &str, msg: &Message, names: &mut Vec<String>) {
+        let prefix = format!("{prefix}{}::", snake(&msg.name));
+
+        for extend in &msg.extends {
+            for field in &extend.fields {
+                names.push(format!("{prefix}{}", extension_name(field)));
+            }
+        }
+
+        for msg in &msg.messages {
+            collect(&prefix, msg, names);
+        }
+    }
+
+    let mut names = vec![];
+    for fd in files {
+        for extend in &fd.extends {
+            for field in &extend.fields {
+                names.push(extension_name(field));
+            }
+        }
+        for msg in &fd.messages {
+            collect("", msg, &mut names);
+        }
+    }
+
+    if names.is_empty() {
+        return;
+    }
+
+    buf.push("#[allow(dead_code)]\n");
+    buf.push("pub fn register_extensions(registry: &mut ExtensionRegistry) {\n");
+    for name in names {
+        buf.push(format!("    registry.register(&{name});\n"));
+    }
+    buf.push("}\n");
+}
+
+fn generate_oneof<'a>(buf: &mut Buffer, oneof: &'a OneOf, cx: &Context<'a>) {
+    generate_comments(buf, &oneof.comments);
+    for attr in cx.oneof_attributes(oneof) {
         buf.push(format!("{attr}\n"))
     }
 
-    buf.push("#[derive(Debug)]\n");
+    let derives = derives(cx, cx.oneof_derivable(oneof), false);
+    buf.push(format!("#[derive({})]\n", derives.join(", ")));
     buf.push(format!("pub enum {} {{\n", upper_camel(&oneof.name)));
     for variant in &oneof.variants {
-        let typ = match variant.typ.rust_type() {
-            Some(typ) => typ.to_string(),
-            None => match &variant.typ {
-                FieldType::Message(typ) => match typ.rsplit_once(".") {
-                    Some((path, typ)) => {
-                        format!("{}::{}", path.replace('.', "::"), upper_camel(typ))
-                    }
-                    None => typ.to_string(),
-                },
-                _ => unreachable!(),
-            },
-        };
+        let typ = value_type(&variant.name, &variant.typ, cx);
+
+        buf.indent += 1;
+        generate_comments(buf, &variant.comments);
+        buf.indent -= 1;
 
         buf.push(format!("    {}({}),\n", upper_camel(&variant.name), typ));
     }
     buf.push("}\n");
 }
 
+// derives of messages and oneofs, `Default` is derived unless implemented
+// for default values. See `Context::derivable` for `Eq` and `Hash`.
+fn derives(cx: &Context, derivable: Derivable, default: bool) -> Vec<&'static str> {
+    let config = cx.config;
+    let mut derives = vec![];
+    if config.derive_clone {
+        derives.push("Clone");
+    }
+    derives.push("Debug");
+    if default {
+        derives.push("Default");
+    }
+    if config.derive_partial_eq {
+        derives.push("PartialEq");
+        if config.derive_eq && derivable.eq {
+            derives.push("Eq");
+        }
+    }
+    if config.derive_hash && derivable.hash {
+        derives.push("Hash");
+    }
+    derives
+}
+
+/// Conversions of a field of a custom rust type from and to the proto type,
+/// see `Config::field_type`
+pub struct Conversion {
+    pub to: String,
+    pub from: String,
+}
+
+pub fn conversion(field: &Field, rust_type: &str, cx: &Context) -> Conversion {
+    let proto = generate_field_type(&field.typ, cx);
+    let (to, from) = match &field.typ {
+        FieldType::Message(typ) if !cx.lookup_type(typ).is_some_and(|(_, c)| c.is_enum()) => (
+            format!("<{rust_type} as ProtoMessage<{proto}>>::to_message"),
+            format!("<{rust_type} as ProtoMessage<{proto}>>::from_message"),
+        ),
+        _ => (
+            format!("<{rust_type} as ProtoScalar<{proto}>>::to_proto"),
+            format!("<{rust_type} as ProtoScalar<{proto}>>::from_proto"),
+        ),
+    };
+
+    Conversion { to, from }
+}
+
+// `value`, a `String` or `Vec<u8>`, as the type of the field
+fn from_default(field: &Field, value: String, cx: &Context) -> String {
+    match (&field.typ, cx.value_type(&field.name, &field.typ)) {
+        (FieldType::String, Some(_)) => format!("ProtoString::from_string({value})"),
+        (FieldType::Bytes, Some(_)) => format!("ProtoBytes::from_vec({value})"),
+        _ => value,
+    }
+}
+
+// type of a value of a field or oneof variant, `string` and `bytes` ones may be replaced,
+// see `Config::string_type`
+fn value_type(name: &str, typ: &FieldType, cx: &Context) -> String {
+    match cx.value_type(name, typ) {
+        Some(rust_type) => rust_type.to_string(),
+        None => generate_field_type(typ, cx),
+    }
+}
+
 fn generate_field_type(typ: &FieldType, cx: &Context) -> String {
     match typ {
         FieldType::Double => "f64".to_string(),
@@ -1,8 +1,9 @@
-mod config;
-mod context;
+pub(crate) mod config;
+pub(crate) mod context;
 mod deserialize;
 mod generate;
-mod sanitize;
+mod path;
+pub(crate) mod sanitize;
 mod serialize;
 mod service;
 mod sizeof;
@@ -0,0 +1,214 @@
+/// Values of `Config` options selected by proto paths, a path is one of
+///
+/// - `.` or empty, everything
+/// - fully qualified, with a leading dot, e.g. `.foo.bar` or `.foo.bar.Data`,
+///   the element and everything defined in it, so a package prefix selects
+///   all of the package
+/// - relative, e.g. `Data.field` or `field`, every element whose fully
+///   qualified name ends with the path
+///
+/// Segments may have `*` wildcards, which match any characters within the
+/// segment, e.g. `.foo.*` or `*Request`.
+///
+/// When several paths match, the most specific one wins, in the order of
+/// the fully qualified name itself, relative paths, prefixes and `.`. Longer
+/// paths win in each of them, then ones without wildcards, then the ones
+/// added later.
+#[derive(Debug)]
+pub struct PathMap<T> {
+    entries: Vec<(String, T)>,
+}
+
+impl<T> Default for PathMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+// how a path matches a name, smaller ones are more specific
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Rank {
+    kind: Kind,
+    // more segments are more specific
+    segments: std::cmp::Reverse<usize>,
+    wildcard: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Exact,
+    Suffix,
+    Prefix,
+    All,
+}
+
+impl<T> PathMap<T> {
+    pub fn insert(&mut self, path: impl Into<String>, value: T) {
+        self.entries.push((path.into(), value));
+    }
+
+    /// The value of the most specific path matching `name`, which is fully
+    /// qualified with a leading dot
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (path, value))| Some((rank(path, name)?, index, value)))
+            .min_by(|(rank, index, _), (other, other_index, _)| {
+                rank.cmp(other).then(other_index.cmp(index))
+            })
+            .map(|(_, _, value)| value)
+    }
+
+    /// Values of all paths matching `name`, in the order they were added
+    pub fn get_all(&self, name: &str) -> Vec<&T> {
+        self.entries
+            .iter()
+            .filter(|(path, _)| rank(path, name).is_some())
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+}
+
+fn rank(path: &str, name: &str) -> Option<Rank> {
+    if path.is_empty() || path == "." {
+        return Some(Rank {
+            kind: Kind::All,
+            segments: std::cmp::Reverse(0),
+            wildcard: false,
+        });
+    }
+
+    let name = name.trim_start_matches('.').split('.').collect::<Vec<_>>();
+    let (kind, segments) = match path.strip_prefix('.') {
+        Some(path) => {
+            let segments = path.split('.').collect::<Vec<_>>();
+            if segments.len() > name.len() || !all_match(&segments, &name[..segments.len()]) {
+                return None;
+            }
+
+            let kind = if segments.len() == name.len() {
+                Kind::Exact
+            } else {
+                Kind::Prefix
+            };
+            (kind, segments)
+        }
+        None => {
+            let segments = path.split('.').collect::<Vec<_>>();
+            if segments.len() > name.len()
+                || !all_match(&segments, &name[name.len() - segments.len()..])
+            {
+                return None;
+            }
+
+            (Kind::Suffix, segments)
+        }
+    };
+
+    Some(Rank {
+        kind,
+        segments: std::cmp::Reverse(segments.len()),
+        wildcard: path.contains('*'),
+    })
+}
+
+fn all_match(patterns: &[&str], segments: &[&str]) -> bool {
+    patterns
+        .iter()
+        .zip(segments)
+        .all(|(pattern, segment)| glob(pattern, segment))
+}
+
+// `*` matches any characters
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard at all
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let name = ".foo.bar.Data.field";
+        for path in [
+            "",
+            ".",
+            ".foo",
+            ".foo.bar.Data",
+            ".foo.bar.Data.field",
+            "field",
+            "Data.field",
+            ".foo.*",
+            "*.field",
+            "D*a.f*",
+            "*",
+        ] {
+            assert!(rank(path, name).is_some(), "{path}");
+        }
+        for path in [
+            ".bar",
+            ".foo.ba",
+            "Data",
+            "bar.field",
+            ".foo.bar.Data.field.more",
+            "*Request",
+            "D*x.field",
+        ] {
+            assert!(rank(path, name).is_none(), "{path}");
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        let mut map = PathMap::default();
+        map.insert(".", "all");
+        map.insert(".foo", "package");
+        map.insert(".foo.bar.Data", "message");
+        map.insert("field", "name");
+        map.insert("*.field", "wildcard");
+        map.insert("Data.field", "relative");
+        map.insert(".foo.bar.Data.field", "exact");
+
+        assert_eq!(map.get(".foo.bar.Data.field"), Some(&"exact"));
+        assert_eq!(map.get(".foo.bar.Other.field"), Some(&"wildcard"));
+        assert_eq!(map.get(".foo.bar.Data.other"), Some(&"message"));
+        assert_eq!(map.get(".foo.Other"), Some(&"package"));
+        assert_eq!(map.get(".other.Data"), Some(&"all"));
+
+        // the later one wins on ties
+        map.insert(".foo", "again");
+        assert_eq!(map.get(".foo.Other"), Some(&"again"));
+
+        assert_eq!(
+            map.get_all(".foo.bar.Data.other"),
+            [&"all", &"package", &"message", &"again"]
+        );
+    }
+}
@@ -147,8 +147,10 @@ pub fn snake(ident: &str) -> String {
 pub fn upper_camel(ident: &str) -> String {
     let mut buf = String::new();
 
+    // `Foo_` and `foo__bar` have empty words
     for word in ident
         .split('_')
+        .filter(|word| !word.is_empty())
         .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
     {
         if !word.contains(|ch: char| ch.is_ascii_lowercase()) {
@@ -163,3 +165,135 @@ pub fn upper_camel(ident: &str) -> String {
 
     buf
 }
+
+/// Turn comments of proto files into lines of rustdoc. Comments are plain
+/// text, so things rustdoc would treat as markdown links, html tags or doc
+/// tests are escaped.
+pub fn sanitize_doc(text: &str) -> Vec<String> {
+    // indented code blocks of markdown
+    fn indented(line: &str) -> bool {
+        line.starts_with("    ") || line.starts_with('\t')
+    }
+
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut output = Vec::with_capacity(lines.len());
+    let mut fenced = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+
+        if line.trim_start().starts_with("```") {
+            // fences without language are rust code for rustdoc
+            if !fenced && line.trim() == "```" {
+                output.push(line.replace("```", "```text"));
+            } else {
+                output.push(line.to_string());
+            }
+
+            fenced = !fenced;
+            continue;
+        }
+
+        if fenced {
+            output.push(line.to_string());
+            continue;
+        }
+
+        let paragraph = index == 1 || lines[index - 2].trim().is_empty();
+        if indented(line) && paragraph {
+            output.push("```text".to_string());
+            output.push(line.to_string());
+            while index < lines.len() && indented(lines[index]) {
+                output.push(lines[index].to_string());
+                index += 1;
+            }
+            output.push("```".to_string());
+            continue;
+        }
+
+        output.push(escape_markdown(line));
+    }
+
+    // unclosed fence
+    if fenced {
+        output.push("```".to_string());
+    }
+
+    output
+}
+
+// escape brackets and angle brackets, which are links and html tags for
+// rustdoc, and wrap bare urls. Inline code is left as it is.
+fn escape_markdown(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut code = false;
+    let mut rest = line;
+
+    while let Some(ch) = rest.chars().next() {
+        if !code && (rest.starts_with("http://") || rest.starts_with("https://")) {
+            let end = rest
+                .find(|ch: char| ch.is_whitespace() || ch == '<' || ch == '>' || ch == '`')
+                .unwrap_or(rest.len());
+            let url = rest[..end].trim_end_matches(['.', ',', ';', ':', ')', ']', '\'', '"']);
+            output.push('<');
+            output.push_str(url);
+            output.push('>');
+            rest = &rest[url.len()..];
+            continue;
+        }
+
+        match ch {
+            '`' => code = !code,
+            '[' | ']' | '<' | '>' | '\\' if !code => output.push('\\'),
+            _ => {}
+        }
+
+        output.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camel() {
+        assert_eq!(upper_camel("foo_bar"), "FooBar");
+        assert_eq!(upper_camel("FOO_BAR"), "FooBar");
+        assert_eq!(upper_camel("Foo_"), "Foo");
+        assert_eq!(upper_camel("foo__bar"), "FooBar");
+        assert_eq!(upper_camel("_foo"), "Foo");
+    }
+
+    #[test]
+    fn doc() {
+        let text = "Gets a [Message] by <name>, see https://example.com/doc.\n\
+                    Use `map[string]` here.\n\
+                    \n    \
+                    let a = 1;\n    \
+                    let b = 2;\n\
+                    ```\n\
+                    code\n\
+                    ```";
+
+        assert_eq!(
+            sanitize_doc(text),
+            [
+                "Gets a \\[Message\\] by \\<name\\>, see <https://example.com/doc>.",
+                "Use `map[string]` here.",
+                "",
+                "```text",
+                "    let a = 1;",
+                "    let b = 2;",
+                "```",
+                "```text",
+                "code",
+                "```",
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use super::Buffer;
-use super::context::{Container, Context, maybe_fixed_size_enum};
+use super::context::{Container, Context};
+use super::generate::conversion;
 use super::sanitize::{sanitize_type_name, sanitize_variant, snake, upper_camel};
 use super::sizeof::sizeof_varint;
-use crate::ast::{FieldCardinality, FieldType, Message};
+use crate::ast::{Field, FieldCardinality, FieldType, Message};
 
 fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
-    if msg.fields.is_empty() && msg.oneofs.is_empty() {
+    if msg.is_empty() {
         buf.push("0\n");
         return;
     }
@@ -19,6 +20,14 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
             ""
         };
 
+        if let Some(rust_type) = cx.field_type(field) {
+            buf.push(format!(
+                "{prefix}({})\n",
+                custom_encoded_len(field, rust_type, cx)
+            ));
+            continue;
+        }
+
         match cx.cardinality(field) {
             FieldCardinality::Optional => {
                 let tag = field.number << 3 | field.typ.wire_type();
@@ -50,7 +59,7 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
                     | FieldType::Double => "_",
                     FieldType::Message(typ) => match cx.lookup_type(typ) {
                         Some((_path, Container::Enum(en)))
-                            if maybe_fixed_size_enum(en).is_some() =>
+                            if cx.maybe_fixed_size_enum(en).is_some() =>
                         {
                             "_"
                         }
@@ -71,7 +80,7 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
                 let tag_size = sizeof_varint(tag as u64);
                 let size_of = type_size(&field.typ, &format!("self.{}", snake(&field.name)), cx);
 
-                match cx.default_value(field).as_ref() {
+                match cx.implicit_default(field).as_ref() {
                     Some(default) => {
                         let comparison = match (&field.typ, default.as_str()) {
                             (FieldType::Bool, "true") => {
@@ -91,14 +100,14 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
                                 if value == "" {
                                     format!("!self.{}.is_empty()", snake(&field.name))
                                 } else {
-                                    format!("self.{} != b\"{}\"", snake(&field.name), value)
+                                    format!("{} != b\"{}\"", compared(field, cx), value)
                                 }
                             }
                             (FieldType::String, value) => {
                                 if value == "" {
                                     format!("!self.{}.is_empty()", snake(&field.name))
                                 } else {
-                                    format!("self.{} != \"{}\"", snake(&field.name), default)
+                                    format!("{} != \"{}\"", compared(field, cx), default)
                                 }
                             }
                             _ => format!("self.{} != {}", snake(&field.name), default),
@@ -156,9 +165,9 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
                         format!("sizeof_len({field_name}.len())")
                     }
                     FieldType::Message(typ) => match cx.lookup_type(typ) {
-                        Some((_path, Container::Enum(en))) => match maybe_fixed_size_enum(en) {
+                        Some((_path, Container::Enum(en))) => match cx.maybe_fixed_size_enum(en) {
                             Some(size) => size.to_string(),
-                            None => format!("sizeof_varint({field_name} as u64)"),
+                            None => format!("sizeof_int32(i32::from({field_name}))"),
                         },
                         _ => {
                             format!("sizeof_len({field_name}.encoded_len())")
@@ -291,12 +300,12 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
                                 sanitize_variant(&en.name, en.default_value())
                             );
 
-                            match maybe_fixed_size_enum(en) {
+                            match cx.maybe_fixed_size_enum(en) {
                                 Some(size) => {
                                     format!("if *v == {default} {{ 0 }} else {{ 1 + {size} }}")
                                 }
                                 None => format!(
-                                    "if *v == {default} {{ 0 }} else {{ 1 + sizeof_int32(*v as i32) }}"
+                                    "if *v == {default} {{ 0 }} else {{ 1 + sizeof_int32(i32::from(*v)) }}"
                                 ),
                             }
                         }
@@ -328,11 +337,12 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
             let tag = variant.tag();
             let tag_size = sizeof_varint(tag as u64);
 
-            let field_name = match variant.typ {
-                FieldType::Message(_)
-                | FieldType::Map(_, _)
-                | FieldType::String
-                | FieldType::Bytes => "v",
+            let field_name = match &variant.typ {
+                FieldType::Message(typ) => match cx.lookup_type(typ) {
+                    Some((_path, Container::Enum(_))) => "*v",
+                    _ => "v",
+                },
+                FieldType::Map(_, _) | FieldType::String | FieldType::Bytes => "v",
                 _ => "*v",
             };
 
@@ -354,10 +364,23 @@ fn generate_encoded_len(buf: &mut Buffer, msg: &Message, cx: &Context) {
         buf.push("}\n");
         buf.indent -= 1;
     }
+
+    if msg.extendable() {
+        let prefix = if adding { "    + " } else { "" };
+        buf.push(format!("{prefix}self.extensions.encoded_len()\n"));
+    }
+    if cx.keeps_unknown_fields(msg) {
+        let prefix = if adding || msg.extendable() {
+            "    + "
+        } else {
+            ""
+        };
+        buf.push(format!("{prefix}self.unknown_fields.encoded_len()\n"));
+    }
 }
 
 fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
-    if msg.fields.is_empty() && msg.oneofs.is_empty() {
+    if msg.is_empty() {
         buf.push("Ok(0)\n");
         return;
     }
@@ -365,12 +388,17 @@ fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
     buf.push("let mut buf = Writer::new(buf);\n");
 
     for field in &msg.fields {
+        if let Some(rust_type) = cx.field_type(field) {
+            buf.push(custom_encode(field, rust_type, cx));
+            continue;
+        }
+
         let tag = cx.tag(field);
         match cx.cardinality(field) {
             FieldCardinality::Optional => {
                 let field_name = match &field.typ {
                     FieldType::Message(typ) => {
-                        if &msg.name == typ {
+                        if cx.is_recursive(&field.typ, msg) {
                             "v.as_ref()"
                         } else if let Some((_path, c)) = cx.lookup_type(typ)
                             && c.is_enum()
@@ -413,7 +441,7 @@ fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
                 };
 
                 let write = encode_type(&field.typ, &field_name, tag, cx);
-                match cx.default_value(field) {
+                match cx.implicit_default(field) {
                     Some(default) => {
                         let comparison = match (&field.typ, &default) {
                             (FieldType::Bool, value) => {
@@ -436,14 +464,14 @@ fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
                                 if value == "" {
                                     format!("!self.{}.is_empty()", snake(&field.name))
                                 } else {
-                                    format!("self.{} != b\"{}\"", snake(&field.name), value)
+                                    format!("{} != b\"{}\"", compared(field, cx), value)
                                 }
                             }
                             (FieldType::String, value) => {
                                 if value == "" {
                                     format!("!self.{}.is_empty()", snake(&field.name))
                                 } else {
-                                    format!("self.{} != \"{}\"", snake(&field.name), value)
+                                    format!("{} != \"{}\"", compared(field, cx), value)
                                 }
                             }
                             _ => format!("self.{} != {}", snake(&field.name), default),
@@ -465,79 +493,17 @@ fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
                 };
 
                 if cx.packed(field) {
-                    match &field.typ {
-                        FieldType::Bool
-                        | FieldType::Double
-                        | FieldType::Float
-                        | FieldType::Fixed64
-                        | FieldType::Fixed32
-                        | FieldType::Sfixed32
-                        | FieldType::Sfixed64 => {
-                            buf.push(format!(
-                                "buf.write_packed({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-
-                        FieldType::Int32 => {
-                            buf.push(format!(
-                                "buf.write_packed_int32({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-                        FieldType::Int64 => {
-                            buf.push(format!(
-                                "buf.write_packed_int64({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-                        FieldType::Uint32 => {
-                            buf.push(format!(
-                                "buf.write_packed_uint32({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-                        FieldType::Uint64 => {
-                            buf.push(format!(
-                                "buf.write_packed_uint64({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-                        FieldType::Sint32 => {
-                            buf.push(format!(
-                                "buf.write_packed_sint32({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-                        FieldType::Sint64 => {
-                            buf.push(format!(
-                                "buf.write_packed_sint64({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-
-                        FieldType::Bytes | FieldType::String => {
-                            unreachable!("Protobuf does not support packed bytes and string");
-                        }
-
-                        FieldType::Message(typ) => {
-                            let Some((_path, Container::Enum(_))) = cx.lookup_type(typ) else {
-                                unreachable!()
-                            };
-
-                            buf.push(format!(
-                                "buf.write_packed_enum({tag}, &self.{})?;\n",
-                                snake(&field.name),
-                            ));
-                        }
-                        FieldType::Map(_, _) => unreachable!(),
-                    }
+                    let values = format!("&self.{}", snake(&field.name));
+                    buf.push(format!(
+                        "{}?;\n",
+                        write_packed(&field.typ, &values, tag, cx)
+                    ));
 
                     continue;
                 } else {
                     buf.push(format!(
-                        "for v in &self.{} {{ {}? }}\n",
-                        snake(&field.name),
+                        "for v in {} {{ {}? }}\n",
+                        values(field, cx),
                         encode_type(&field.typ, &field_name, tag, cx)
                     ));
                 }
@@ -616,14 +582,18 @@ fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
                             }
                         }
                         Some((path, Container::Enum(en))) => {
-                            let default = format!("{}::{}", path, en.default_value());
+                            let default = format!(
+                                "{}::{}",
+                                path,
+                                sanitize_variant(&en.name, en.default_value())
+                            );
 
-                            match maybe_fixed_size_enum(en) {
+                            match cx.maybe_fixed_size_enum(en) {
                                 Some(size) => {
                                     format!("if *v == {default} {{ 0 }} else {{ 1 + {size} }}")
                                 }
                                 None => format!(
-                                    "if *v == {default} {{ 0 }} else {{ 1 + sizeof_int32(*v as i32) }}"
+                                    "if *v == {default} {{ 0 }} else {{ 1 + sizeof_int32(i32::from(*v)) }}"
                                 ),
                             }
                         }
@@ -719,10 +689,20 @@ fn generate_encode(buf: &mut Buffer, msg: &Message, cx: &Context) {
         buf.push("}\n");
     }
 
+    if msg.extendable() {
+        buf.push("buf.write_extensions(&self.extensions)?;\n");
+    }
+    if cx.keeps_unknown_fields(msg) {
+        buf.push("buf.write_unknown_fields(&self.unknown_fields)?;\n");
+    }
+
     buf.push("Ok(buf.pos)\n");
 }
 
 pub fn generate_serialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
+    if msg.has_deprecated_fields() {
+        buf.push("#[allow(deprecated)]\n");
+    }
     buf.push(format!(
         "impl Serialize for {} {{\n",
         sanitize_type_name(&msg.name)
@@ -730,7 +710,7 @@ pub fn generate_serialize(buf: &mut Buffer, msg: &Message, cx: &Context) {
     buf.indent += 1;
 
     // empty msg
-    if msg.fields.is_empty() && msg.oneofs.is_empty() {
+    if msg.is_empty() {
         buf.push("fn encoded_len(&self) -> usize { 0 }\n");
         buf.push("fn encode(&self, _: &mut [u8]) -> Result<usize, EncodeError> { Ok(0) }\n");
 
@@ -787,10 +767,10 @@ fn type_size(typ: &FieldType, field_name: &str, cx: &Context) -> String {
             format!("sizeof_len({field_name}.len())")
         }
         FieldType::Message(typ) => match cx.lookup_type(typ) {
-            Some((_path, Container::Enum(en))) => match maybe_fixed_size_enum(en) {
+            Some((_path, Container::Enum(en))) => match cx.maybe_fixed_size_enum(en) {
                 Some(size) => size.to_string(),
                 None => {
-                    format!("sizeof_varint({field_name} as u64)")
+                    format!("sizeof_int32(i32::from({field_name}))")
                 }
             },
             Some((_path, Container::Message(msg))) if msg.is_empty() => "1".to_string(),
@@ -802,6 +782,147 @@ fn type_size(typ: &FieldType, field_name: &str, cx: &Context) -> String {
     }
 }
 
+// size of a field of a custom rust type, which is converted to the proto
+// type first, see `Config::field_type`
+fn custom_encoded_len(field: &Field, rust_type: &str, cx: &Context) -> String {
+    let name = snake(&field.name);
+    let to = conversion(field, rust_type, cx).to;
+    let tag_size = sizeof_varint(cx.tag(field) as u64);
+    let size = type_size(&field.typ, "v", cx);
+    // the size doesn't depend on the value
+    let fixed = size.parse::<usize>().is_ok();
+
+    match cx.cardinality(field) {
+        FieldCardinality::Optional if fixed => {
+            format!("self.{name}.as_ref().map_or(0, |_| {tag_size} + {size})")
+        }
+        FieldCardinality::Optional => format!(
+            "self.{name}.as_ref().map_or(0, |v| {{ let v = {to}(v); {tag_size} + {size} }})"
+        ),
+        FieldCardinality::Required => match cx.implicit_default(field) {
+            Some(_) => format!(
+                "{{ let v = {to}(&self.{name}); if {} {{ {tag_size} + {size} }} else {{ 0 }} }}",
+                not_default(&field.typ, cx)
+            ),
+            None if fixed => format!("{tag_size} + {size}"),
+            None => format!("{{ let v = {to}(&self.{name}); {tag_size} + {size} }}"),
+        },
+        FieldCardinality::Repeated if cx.packed(field) => {
+            let len = if fixed {
+                format!("self.{name}.len() * {size}")
+            } else {
+                format!("self.{name}.iter().fold(0, |acc, v| {{ let v = {to}(v); acc + {size} }})")
+            };
+
+            format!("if self.{name}.is_empty() {{ 0 }} else {{ {tag_size} + sizeof_len({len}) }}")
+        }
+        FieldCardinality::Repeated if fixed => format!("self.{name}.len() * ({tag_size} + {size})"),
+        FieldCardinality::Repeated => format!(
+            "self.{name}.iter().fold(0, |acc, v| {{ let v = {to}(v); acc + {tag_size} + {size} }})"
+        ),
+        FieldCardinality::Map(_, _) => unreachable!("rejected by `Context::check_types`"),
+    }
+}
+
+fn custom_encode(field: &Field, rust_type: &str, cx: &Context) -> String {
+    let name = snake(&field.name);
+    let to = conversion(field, rust_type, cx).to;
+    let tag = cx.tag(field);
+    let value = match &field.typ {
+        FieldType::Message(typ) if !cx.lookup_type(typ).is_some_and(|(_, c)| c.is_enum()) => "&v",
+        _ => "v",
+    };
+    let write = encode_type(&field.typ, value, tag, cx);
+
+    match cx.cardinality(field) {
+        FieldCardinality::Optional => {
+            format!("if let Some(v) = &self.{name} {{ let v = {to}(v); {write}? }}\n")
+        }
+        FieldCardinality::Required => match cx.implicit_default(field) {
+            Some(_) => format!(
+                "{{ let v = {to}(&self.{name}); if {} {{ {write}? }} }}\n",
+                not_default(&field.typ, cx)
+            ),
+            None => format!("{{ let v = {to}(&self.{name}); {write}?; }}\n"),
+        },
+        FieldCardinality::Repeated if cx.packed(field) => {
+            let values = format!("&self.{name}.iter().map({to}).collect::<Vec<_>>()");
+            format!("{}?;\n", write_packed(&field.typ, &values, tag, cx))
+        }
+        FieldCardinality::Repeated => {
+            format!(
+                "for v in {} {{ let v = {to}(v); {write}? }}\n",
+                values(field, cx)
+            )
+        }
+        FieldCardinality::Map(_, _) => unreachable!("rejected by `Context::check_types`"),
+    }
+}
+
+// the field as `&str` or `&[u8]` to compare with the default value, when
+// it's of another type, see `Config::string_type`
+fn compared(field: &Field, cx: &Context) -> String {
+    let name = snake(&field.name);
+    match (&field.typ, cx.value_type(&field.name, &field.typ)) {
+        (FieldType::String, Some(_)) => format!("self.{name}.as_str()"),
+        (FieldType::Bytes, Some(_)) => format!("self.{name}.as_slice()"),
+        _ => format!("self.{name}"),
+    }
+}
+
+// values of a repeated field to iterate, containers other than `Vec` may
+// not implement `IntoIterator` for references
+fn values(field: &Field, cx: &Context) -> String {
+    match cx.repeated_type(field) {
+        Some(_) => format!("self.{}.iter()", snake(&field.name)),
+        None => format!("&self.{}", snake(&field.name)),
+    }
+}
+
+// whether `v` of the proto type is not the default, implicit fields are
+// written only then
+fn not_default(typ: &FieldType, cx: &Context) -> &'static str {
+    match typ {
+        FieldType::Bool => "v",
+        FieldType::String | FieldType::Bytes => "!v.is_empty()",
+        FieldType::Float | FieldType::Double => "v != 0.0",
+        FieldType::Message(typ) if cx.lookup_type(typ).is_some_and(|(_, c)| c.is_enum()) => {
+            "i32::from(v) != 0"
+        }
+        _ => "v != 0",
+    }
+}
+
+// write `values`, a slice of the proto type, in packed encoding
+fn write_packed(typ: &FieldType, values: &str, tag: u32, cx: &Context) -> String {
+    match typ {
+        FieldType::Bool
+        | FieldType::Double
+        | FieldType::Float
+        | FieldType::Fixed64
+        | FieldType::Fixed32
+        | FieldType::Sfixed32
+        | FieldType::Sfixed64 => format!("buf.write_packed({tag}, {values})"),
+        FieldType::Int32 => format!("buf.write_packed_int32({tag}, {values})"),
+        FieldType::Int64 => format!("buf.write_packed_int64({tag}, {values})"),
+        FieldType::Uint32 => format!("buf.write_packed_uint32({tag}, {values})"),
+        FieldType::Uint64 => format!("buf.write_packed_uint64({tag}, {values})"),
+        FieldType::Sint32 => format!("buf.write_packed_sint32({tag}, {values})"),
+        FieldType::Sint64 => format!("buf.write_packed_sint64({tag}, {values})"),
+        FieldType::Bytes | FieldType::String => {
+            unreachable!("Protobuf does not support packed bytes and string");
+        }
+        FieldType::Message(typ) => {
+            let Some((_path, Container::Enum(_))) = cx.lookup_type(typ) else {
+                unreachable!()
+            };
+
+            format!("buf.write_packed_enum({tag}, {values})")
+        }
+        FieldType::Map(_, _) => unreachable!(),
+    }
+}
+
 fn encode_type(typ: &FieldType, field_name: &str, tag: u32, cx: &Context) -> String {
     match typ {
         FieldType::Bool => format!("buf.write_bool({tag}, {field_name})"),
@@ -825,7 +946,7 @@ fn encode_type(typ: &FieldType, field_name: &str, tag: u32, cx: &Context) -> Str
         }
         FieldType::Message(typ) => match cx.lookup_type(typ) {
             Some((_path, Container::Enum(_))) => {
-                format!("buf.write_int32({tag}, {field_name} as i32)")
+                format!("buf.write_int32({tag}, i32::from({field_name}))")
             }
             _ => format!("buf.write_msg({tag}, {field_name})"),
         },
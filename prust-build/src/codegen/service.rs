@@ -1,6 +1,7 @@
 use super::context::{Container, Context};
 use crate::ast::{Function, Method, Service};
 use crate::codegen::Buffer;
+use crate::codegen::generate::generate_comments;
 use crate::codegen::sanitize::{snake, upper_camel};
 
 pub fn generate_service(buf: &mut Buffer, svc: &Service, cx: &mut Context) {
@@ -31,6 +32,7 @@ fn generate_client(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
     buf.push("use tonic::codegen::*;\n");
 
     // generate
+    generate_comments(buf, &svc.comments);
     buf.push("#[derive(Clone, Debug)]\n");
     buf.push(format!(
         "pub struct {}Client<T> {{\n",
@@ -127,6 +129,7 @@ fn generate_client(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
     buf.push("}\n");
 
     for func in &svc.functions {
+        generate_comments(buf, &func.comments);
         generate_client_method(svc, func, buf, cx);
     }
 
@@ -138,7 +141,7 @@ fn generate_client(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
 }
 
 fn generate_client_method(svc: &Service, func: &Function, buf: &mut Buffer, cx: &mut Context) {
-    let package = cx.fd.package.clone().unwrap_or_default();
+    let service = cx.full_name(&svc.name);
     let Some((req, Container::Message(_req))) = cx.lookup_type(&func.request) else {
         panic!("Request type {} not found", func.request);
     };
@@ -169,14 +172,14 @@ fn generate_client_method(svc: &Service, func: &Function, buf: &mut Buffer, cx:
 
             buf.push("let codec = prust::tonic_codec::Codec::default();\n");
             buf.push(format!(
-                "let path = http::uri::PathAndQuery::from_static(\"/{}.{}/{}\");\n",
-                package, svc.name, func.name
+                "let path = http::uri::PathAndQuery::from_static(\"/{}/{}\");\n",
+                service, func.name
             ));
             buf.push("let mut req = req.into_request();\n");
             buf.push("req.extensions_mut()\n");
             buf.push(format!(
-                "    .insert(GrpcMethod::new(\"{}.{}\", \"{}\"));\n",
-                package, svc.name, func.name
+                "    .insert(GrpcMethod::new(\"{}\", \"{}\"));\n",
+                service, func.name
             ));
             buf.push("self.inner.unary(req, path, codec).await\n");
 
@@ -205,16 +208,13 @@ fn generate_client_method(svc: &Service, func: &Function, buf: &mut Buffer, cx:
 
             buf.push("let codec = prust::tonic_codec::Codec::default();\n");
             buf.push("let path = http::uri::PathAndQuery::from_static(\n");
-            buf.push(format!(
-                "    \"/{}.{}/{}\",\n",
-                package, svc.name, func.name
-            ));
+            buf.push(format!("    \"/{}/{}\",\n", service, func.name));
             buf.push(");\n");
             buf.push("let mut req = req.into_streaming_request();\n");
             buf.push("req.extensions_mut()\n");
             buf.push(format!(
-                "    .insert(GrpcMethod::new(\"{}.{}\", \"{}\"));\n",
-                package, svc.name, func.name
+                "    .insert(GrpcMethod::new(\"{}\", \"{}\"));\n",
+                service, func.name
             ));
             buf.push("self.inner.client_streaming(req, path, codec).await\n");
 
@@ -243,17 +243,14 @@ fn generate_client_method(svc: &Service, func: &Function, buf: &mut Buffer, cx:
 
             buf.push("let codec = prust::tonic_codec::Codec::default();\n");
             buf.push("let path = http::uri::PathAndQuery::from_static(\n");
-            buf.push(format!(
-                "    \"/{}.{}/{}\",\n",
-                package, svc.name, func.name
-            ));
+            buf.push(format!("    \"/{}/{}\",\n", service, func.name));
             buf.push(");\n");
 
             buf.push("let mut req = req.into_request();\n");
             buf.push("req.extensions_mut()\n");
             buf.push(format!(
-                "    .insert(GrpcMethod::new(\"{}.{}\", \"{}\"));\n",
-                package, svc.name, func.name
+                "    .insert(GrpcMethod::new(\"{}\", \"{}\"));\n",
+                service, func.name
             ));
             buf.push("self.inner.server_streaming(req, path, codec).await\n");
 
@@ -285,17 +282,14 @@ fn generate_client_method(svc: &Service, func: &Function, buf: &mut Buffer, cx:
 
             buf.push("let codec = prust::tonic_codec::Codec::default();\n");
             buf.push("let path = http::uri::PathAndQuery::from_static(\n");
-            buf.push(format!(
-                "    \"/{}.{}/{}\",\n",
-                package, svc.name, func.name
-            ));
+            buf.push(format!("    \"/{}/{}\",\n", service, func.name));
             buf.push(");\n");
 
             buf.push("let mut req = req.into_streaming_request();\n");
             buf.push("req.extensions_mut()\n");
             buf.push(format!(
-                "    .insert(GrpcMethod::new(\"{}.{}\", \"{}\"));\n",
-                package, svc.name, func.name
+                "    .insert(GrpcMethod::new(\"{}\", \"{}\"));\n",
+                service, func.name
             ));
             buf.push("self.inner.streaming(req, path, codec).await\n");
 
@@ -306,7 +300,7 @@ fn generate_client_method(svc: &Service, func: &Function, buf: &mut Buffer, cx:
 }
 
 fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
-    let package = cx.fd.package.clone().unwrap_or_default();
+    let service = cx.full_name(&svc.name);
 
     buf.push(format!("pub mod {}_server {{\n", snake(&svc.name)));
     buf.indent += 1;
@@ -316,6 +310,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
     buf.push("use super::*;\n");
     buf.push("use tonic::codegen::*;\n");
 
+    generate_comments(buf, &svc.comments);
     buf.push("#[async_trait]\n");
     buf.push(format!(
         "pub trait {}: Send + Sync + 'static {{\n",
@@ -334,6 +329,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
 
         match &func.method {
             Method::Unary => {
+                generate_comments(buf, &func.comments);
                 buf.push(format!("async fn {}(\n", snake(&func.name)));
                 buf.push("    &self,\n");
                 buf.push(format!("    req: tonic::Request<{}>,\n", req));
@@ -344,6 +340,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
             }
 
             Method::ClientStreaming => {
+                generate_comments(buf, &func.comments);
                 buf.push(format!("async fn {}(\n", snake(&func.name)));
                 buf.push("    &self,\n");
                 buf.push(format!(
@@ -363,6 +360,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
                 buf.push(format!("    Item = Result<{}, tonic::Status>,\n", resp));
                 buf.push("> + Send + 'static;\n");
 
+                generate_comments(buf, &func.comments);
                 buf.push(format!("async fn {}(\n", snake(&func.name)));
                 buf.push("    &self,\n");
                 buf.push(format!("    req: tonic::Request<{}>,\n", req));
@@ -379,6 +377,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
                 buf.push(format!("    Item = Result<{}, tonic::Status>,\n", resp));
                 buf.push("> + Send + 'static;\n");
 
+                generate_comments(buf, &func.comments);
                 buf.push(format!("async fn {}(\n", snake(&func.name)));
                 buf.push("    &self,\n");
                 buf.push(format!(
@@ -478,11 +477,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
         "impl<T> tonic::server::NamedService for {}Server<T> {{\n",
         upper_camel(&svc.name)
     ));
-    buf.push(format!(
-        "    const NAME: &'static str = \"{}.{}\";\n",
-        package,
-        upper_camel(&svc.name)
-    ));
+    buf.push(format!("    const NAME: &'static str = \"{service}\";\n"));
     buf.push("}\n");
 
     buf.push(format!(
@@ -562,7 +557,7 @@ fn generate_server(svc: &Service, buf: &mut Buffer, cx: &mut Context) {
 }
 
 fn generate_server_handle(svc: &Service, func: &Function, buf: &mut Buffer, cx: &mut Context) {
-    let package = cx.fd.package.clone().unwrap_or_default();
+    let service = cx.full_name(&svc.name);
     let Some((req, Container::Message(_req))) = cx.lookup_type(&func.request) else {
         panic!("Request type {} not found", func.request);
     };
@@ -571,9 +566,8 @@ fn generate_server_handle(svc: &Service, func: &Function, buf: &mut Buffer, cx:
     };
 
     buf.push(format!(
-        "\"/{}.{}/{}\" => {{\n",
-        package,
-        upper_camel(&svc.name),
+        "\"/{}/{}\" => {{\n",
+        service,
         upper_camel(&func.name)
     ));
     buf.indent += 1;
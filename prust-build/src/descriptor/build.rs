@@ -0,0 +1,1087 @@
+// ast -> descriptor
+
+use std::collections::HashMap;
+
+use prust::descriptor::{
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions,
+    ExtensionRangeOptions, FieldDescriptorProto, FieldOptions, FileDescriptorProto,
+    FileDescriptorSet, FileOptions, MessageOptions, MethodDescriptorProto, MethodOptions,
+    OneofDescriptorProto, OneofOptions, ServiceDescriptorProto, ServiceOptions, SourceCodeInfo,
+    UninterpretedOption, descriptor_proto, enum_descriptor_proto, field_descriptor_proto,
+    field_options, file_options, method_options, source_code_info, uninterpreted_option,
+};
+
+use super::*;
+use crate::ast::{
+    self, Comments, Enum, Extend, Field, FieldType, FileDescriptor, ImportKind, Label, Message,
+    Method, OptionValue, Options, ProtoOption, Reserved, Service, Syntax,
+};
+use crate::codegen::context::{Container, Context};
+use crate::lint::json_name;
+use crate::{Config, Error};
+
+/// Append a file and the files it imports to `set`, imports go first like
+/// `protoc --include_imports` does. Files in the set already are skipped.
+///
+/// `imports` are all files imported directly or transitively, keyed by the
+/// import path.
+pub(crate) fn add_file(
+    set: &mut FileDescriptorSet,
+    name: &str,
+    fd: &FileDescriptor,
+    imports: &HashMap<String, FileDescriptor>,
+) -> Result<(), Error> {
+    if set
+        .file
+        .iter()
+        .any(|file| file.name.as_deref() == Some(name))
+    {
+        return Ok(());
+    }
+
+    for import in &fd.imports {
+        // weak imports are allowed to be missing
+        if let Some(imported) = imports.get(&import.path) {
+            add_file(set, &import.path, imported, imports)?;
+        }
+    }
+
+    // an import cycle would add the file already
+    if !set
+        .file
+        .iter()
+        .any(|file| file.name.as_deref() == Some(name))
+    {
+        set.file.push(file_descriptor_proto(name, fd, imports)?);
+    }
+
+    Ok(())
+}
+
+/// Describe a parsed file, `name` is the path it's imported by
+pub(crate) fn file_descriptor_proto(
+    name: &str,
+    fd: &FileDescriptor,
+    imports: &HashMap<String, FileDescriptor>,
+) -> Result<FileDescriptorProto, Error> {
+    let config = Config::default();
+    let mut builder = Builder {
+        cx: Context {
+            fd,
+            config: &config,
+            imports,
+            messages: vec![],
+        },
+        locations: vec![],
+    };
+
+    let mut file = FileDescriptorProto {
+        name: Some(name.to_string()),
+        package: fd.package.clone(),
+        // protoc leaves it empty for proto2
+        syntax: match fd.syntax {
+            Syntax::Proto3 => Some("proto3".to_string()),
+            _ => None,
+        },
+        options: options(fd.options.iter(), file_option, |opts: &mut FileOptions| {
+            &mut opts.uninterpreted_option
+        }),
+        ..Default::default()
+    };
+
+    for (index, import) in fd.imports.iter().enumerate() {
+        file.dependency.push(import.path.clone());
+        match import.kind {
+            ImportKind::Normal => {}
+            ImportKind::Public => file.public_dependency.push(index as i32),
+            ImportKind::Weak => file.weak_dependency.push(index as i32),
+        }
+    }
+
+    for (index, msg) in fd.messages.iter().enumerate() {
+        let path = vec![FILE_MESSAGE_TYPE, index as i32];
+        file.message_type.push(builder.message(msg, path)?);
+    }
+    for (index, en) in fd.enums.iter().enumerate() {
+        let path = vec![FILE_ENUM_TYPE, index as i32];
+        file.enum_type.push(builder.enumeration(en, path));
+    }
+    for (index, svc) in fd.services.iter().enumerate() {
+        let path = vec![FILE_SERVICE, index as i32];
+        file.service.push(builder.service(svc, path)?);
+    }
+    builder.extends(&fd.extends, &[FILE_EXTENSION], &mut file.extension)?;
+
+    file.source_code_info = Some(SourceCodeInfo {
+        location: builder.locations,
+    });
+
+    Ok(file)
+}
+
+struct Builder<'a> {
+    cx: Context<'a>,
+    locations: Vec<source_code_info::Location>,
+}
+
+// a field, or a field of oneof, in declaration order
+struct Member<'a> {
+    location: ast::Location,
+    comments: &'a Comments,
+    field: FieldDescriptorProto,
+}
+
+// nested definitions of a message, map entries are generated in place
+enum Nested<'a> {
+    Message(&'a Message),
+    MapEntry(&'a Field),
+}
+
+impl<'a> Builder<'a> {
+    fn record(&mut self, path: Vec<i32>, location: &ast::Location, comments: &Comments) {
+        // definitions made up, e.g. map entries, have no location
+        if *location == ast::Location::default() {
+            return;
+        }
+
+        // the end line is omitted if it's the same as the start line
+        let span = if location.start_line == location.end_line {
+            vec![
+                location.start_line as i32,
+                location.start_column as i32,
+                location.end_column as i32,
+            ]
+        } else {
+            vec![
+                location.start_line as i32,
+                location.start_column as i32,
+                location.end_line as i32,
+                location.end_column as i32,
+            ]
+        };
+
+        self.locations.push(source_code_info::Location {
+            path,
+            span,
+            leading_comments: comments.leading.as_deref().map(comment),
+            trailing_comments: comments.trailing.as_deref().map(comment),
+            leading_detached_comments: comments.detached.iter().map(|text| comment(text)).collect(),
+        });
+    }
+
+    fn message(&mut self, msg: &'a Message, path: Vec<i32>) -> Result<DescriptorProto, Error> {
+        self.record(path.clone(), &msg.location, &msg.comments);
+
+        self.cx.messages.push(msg);
+        let result = self.message_body(msg, &path);
+        self.cx.messages.pop();
+
+        result
+    }
+
+    fn message_body(&mut self, msg: &'a Message, path: &[i32]) -> Result<DescriptorProto, Error> {
+        let mut desc = DescriptorProto {
+            name: Some(msg.name.clone()),
+            options: options(
+                msg.options.iter(),
+                message_option,
+                |opts: &mut MessageOptions| &mut opts.uninterpreted_option,
+            ),
+            ..Default::default()
+        };
+
+        // fields of oneofs are listed with the others, in declaration order
+        let mut members = vec![];
+        for field in &msg.fields {
+            members.push(Member {
+                location: field.location,
+                comments: &field.comments,
+                field: self.field(field, None)?,
+            });
+        }
+        for (index, oneof) in msg.oneofs.iter().enumerate() {
+            for variant in &oneof.variants {
+                let mut field = self.field_of(
+                    &variant.name,
+                    variant.number,
+                    field_descriptor_proto::Label::Optional,
+                    &variant.typ,
+                    &variant.options,
+                )?;
+                field.oneof_index = Some(index as i32);
+
+                members.push(Member {
+                    location: variant.location,
+                    comments: &variant.comments,
+                    field,
+                });
+            }
+        }
+        members.sort_by_key(|member| (member.location.start_line, member.location.start_column));
+
+        for oneof in &msg.oneofs {
+            desc.oneof_decl.push(OneofDescriptorProto {
+                name: Some(oneof.name.clone()),
+                options: options(
+                    oneof.options.iter(),
+                    |_, _, _| false,
+                    |opts: &mut OneofOptions| &mut opts.uninterpreted_option,
+                ),
+            });
+        }
+        for (index, oneof) in msg.oneofs.iter().enumerate() {
+            let path = child(path, MESSAGE_ONEOF_DECL, index);
+            self.record(path, &oneof.location, &oneof.comments);
+        }
+
+        for (index, mut member) in members.into_iter().enumerate() {
+            // proto3 optional fields are in oneofs of their own, they go
+            // after the real ones
+            if member.field.proto3_optional == Some(true) {
+                let name = format!("_{}", member.field.name.as_deref().unwrap_or_default());
+                member.field.oneof_index = Some(desc.oneof_decl.len() as i32);
+                desc.oneof_decl.push(OneofDescriptorProto {
+                    name: Some(name),
+                    options: None,
+                });
+            }
+
+            let path = child(path, MESSAGE_FIELD, index);
+            self.record(path, &member.location, member.comments);
+            desc.field.push(member.field);
+        }
+
+        let mut nested = msg
+            .messages
+            .iter()
+            .map(|msg| (msg.location, Nested::Message(msg)))
+            .chain(
+                msg.fields
+                    .iter()
+                    .filter(|field| matches!(field.typ, FieldType::Map(_, _)))
+                    .map(|field| (field.location, Nested::MapEntry(field))),
+            )
+            .collect::<Vec<_>>();
+        nested.sort_by_key(|(location, _)| (location.start_line, location.start_column));
+
+        for (index, (_, nested)) in nested.into_iter().enumerate() {
+            let entry = match nested {
+                Nested::Message(msg) => {
+                    self.message(msg, child(path, MESSAGE_NESTED_TYPE, index))?
+                }
+                Nested::MapEntry(field) => self.map_entry(field)?,
+            };
+            desc.nested_type.push(entry);
+        }
+
+        for (index, en) in msg.enums.iter().enumerate() {
+            let path = child(path, MESSAGE_ENUM_TYPE, index);
+            desc.enum_type.push(self.enumeration(en, path));
+        }
+
+        let mut extension = path.to_vec();
+        extension.push(MESSAGE_EXTENSION);
+        self.extends(&msg.extends, &extension, &mut desc.extension)?;
+
+        for range in &msg.extensions {
+            desc.extension_range.push(descriptor_proto::ExtensionRange {
+                start: Some(range.start as i32),
+                // exclusive in descriptors
+                end: Some(range.end as i32 + 1),
+                options: options(
+                    range.properties.iter(),
+                    |_, _, _| false,
+                    |opts: &mut ExtensionRangeOptions| &mut opts.uninterpreted_option,
+                ),
+            });
+        }
+
+        for reserved in &msg.reserved {
+            match reserved {
+                Reserved::Single(number) => {
+                    desc.reserved_range.push(descriptor_proto::ReservedRange {
+                        start: Some(*number as i32),
+                        end: Some(*number as i32 + 1),
+                    })
+                }
+                Reserved::Range(start, end) => {
+                    desc.reserved_range.push(descriptor_proto::ReservedRange {
+                        start: Some(*start as i32),
+                        end: Some(*end as i32 + 1),
+                    })
+                }
+                Reserved::Field(name) => desc.reserved_name.push(name.clone()),
+            }
+        }
+
+        Ok(desc)
+    }
+
+    // `extend` blocks are flattened, `path` is up to the repeated field
+    // extensions are stored in
+    fn extends(
+        &mut self,
+        extends: &'a [Extend],
+        path: &[i32],
+        fields: &mut Vec<FieldDescriptorProto>,
+    ) -> Result<(), Error> {
+        for extend in extends {
+            let extendee = self.type_name(&extend.extendee)?.0;
+            self.record(path.to_vec(), &extend.location, &extend.comments);
+
+            for field in &extend.fields {
+                let mut desc = self.field(field, Some(extendee.clone()))?;
+                // extensions are never in oneofs
+                desc.proto3_optional = None;
+
+                let mut path = path.to_vec();
+                path.push(fields.len() as i32);
+                self.record(path, &field.location, &field.comments);
+                fields.push(desc);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn field(
+        &self,
+        field: &Field,
+        extendee: Option<String>,
+    ) -> Result<FieldDescriptorProto, Error> {
+        let label = match (&field.typ, &field.label, self.cx.fd.syntax) {
+            (FieldType::Map(_, _), _, _) | (_, Label::Repeated, _) => {
+                field_descriptor_proto::Label::Repeated
+            }
+            (_, Label::Required, Syntax::Proto2) => field_descriptor_proto::Label::Required,
+            _ => field_descriptor_proto::Label::Optional,
+        };
+
+        let mut desc =
+            self.field_of(&field.name, field.number, label, &field.typ, &field.options)?;
+        desc.extendee = extendee;
+        if self.cx.fd.syntax == Syntax::Proto3 && field.label == Label::Optional {
+            desc.proto3_optional = Some(true);
+        }
+
+        Ok(desc)
+    }
+
+    fn field_of(
+        &self,
+        name: &str,
+        number: u32,
+        label: field_descriptor_proto::Label,
+        typ: &FieldType,
+        opts: &Options,
+    ) -> Result<FieldDescriptorProto, Error> {
+        let (typ, type_name) = match typ {
+            FieldType::Map(_, _) => (
+                field_descriptor_proto::Type::Message,
+                Some(format!(".{}", self.cx.full_name(&map_entry_name(name)))),
+            ),
+            FieldType::Message(typ) => {
+                let (name, typ) = self.type_name(typ)?;
+                (typ, Some(name))
+            }
+            typ => (scalar(typ), None),
+        };
+
+        let default_value = opts.get("default").map(|value| match value {
+            OptionValue::String(bytes) if typ == field_descriptor_proto::Type::Bytes => {
+                c_escape(bytes)
+            }
+            OptionValue::String(s) => String::from_utf8_lossy(s).into_owned(),
+            value => value.to_string(),
+        });
+
+        Ok(FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number as i32),
+            label: Some(label),
+            r#type: Some(typ),
+            type_name,
+            default_value,
+            json_name: Some(json_name(name, opts)),
+            options: options(
+                opts.iter().filter(|option| {
+                    let name = option.name.to_string();
+                    name != "default" && name != "json_name"
+                }),
+                field_option,
+                |opts: &mut FieldOptions| &mut opts.uninterpreted_option,
+            ),
+            ..Default::default()
+        })
+    }
+
+    fn map_entry(&self, field: &Field) -> Result<DescriptorProto, Error> {
+        let FieldType::Map(key, value) = &field.typ else {
+            unreachable!()
+        };
+
+        let none = Options::default();
+        let optional = field_descriptor_proto::Label::Optional;
+        Ok(DescriptorProto {
+            name: Some(map_entry_name(&field.name)),
+            field: vec![
+                self.field_of("key", 1, optional, key, &none)?,
+                self.field_of("value", 2, optional, value, &none)?,
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    // fully qualified name with the leading dot, and whether it's a message
+    // or an enum
+    fn type_name(&self, typ: &str) -> Result<(String, field_descriptor_proto::Type), Error> {
+        let resolved = self.cx.resolve_symbol(typ)?;
+        let typ = match resolved.container {
+            Container::Message(_) => field_descriptor_proto::Type::Message,
+            Container::Enum(_) => field_descriptor_proto::Type::Enum,
+        };
+
+        Ok((format!(".{}", resolved.full_name), typ))
+    }
+
+    fn enumeration(&mut self, en: &Enum, path: Vec<i32>) -> EnumDescriptorProto {
+        self.record(path.clone(), &en.location, &en.comments);
+
+        let mut desc = EnumDescriptorProto {
+            name: Some(en.name.clone()),
+            options: options(en.options.iter(), enum_option, |opts: &mut EnumOptions| {
+                &mut opts.uninterpreted_option
+            }),
+            ..Default::default()
+        };
+
+        for (index, variant) in en.variants.iter().enumerate() {
+            self.record(
+                child(&path, ENUM_VALUE, index),
+                &variant.location,
+                &variant.comments,
+            );
+            desc.value.push(EnumValueDescriptorProto {
+                name: Some(variant.name.clone()),
+                number: Some(variant.value),
+                options: options(
+                    variant.options.iter(),
+                    enum_value_option,
+                    |opts: &mut EnumValueOptions| &mut opts.uninterpreted_option,
+                ),
+            });
+        }
+
+        // both ends are inclusive for enums
+        for reserved in &en.reserved {
+            match reserved {
+                Reserved::Single(value) => {
+                    desc.reserved_range
+                        .push(enum_descriptor_proto::EnumReservedRange {
+                            start: Some(*value as i32),
+                            end: Some(*value as i32),
+                        })
+                }
+                Reserved::Range(start, end) => {
+                    desc.reserved_range
+                        .push(enum_descriptor_proto::EnumReservedRange {
+                            start: Some(*start as i32),
+                            end: Some(*end as i32),
+                        })
+                }
+                Reserved::Field(name) => desc.reserved_name.push(name.clone()),
+            }
+        }
+
+        desc
+    }
+
+    fn service(&mut self, svc: &Service, path: Vec<i32>) -> Result<ServiceDescriptorProto, Error> {
+        self.record(path.clone(), &svc.location, &svc.comments);
+
+        let mut desc = ServiceDescriptorProto {
+            name: Some(svc.name.clone()),
+            options: options(
+                svc.options.iter(),
+                service_option,
+                |opts: &mut ServiceOptions| &mut opts.uninterpreted_option,
+            ),
+            ..Default::default()
+        };
+
+        for (index, func) in svc.functions.iter().enumerate() {
+            self.record(
+                child(&path, SERVICE_METHOD, index),
+                &func.location,
+                &func.comments,
+            );
+            desc.method.push(MethodDescriptorProto {
+                name: Some(func.name.clone()),
+                input_type: Some(self.type_name(&func.request)?.0),
+                output_type: Some(self.type_name(&func.response)?.0),
+                options: options(
+                    func.options.iter(),
+                    method_option,
+                    |opts: &mut MethodOptions| &mut opts.uninterpreted_option,
+                ),
+                // only set for streams, like protoc does
+                client_streaming: matches!(
+                    func.method,
+                    Method::ClientStreaming | Method::BidiStreaming
+                )
+                .then_some(true),
+                server_streaming: matches!(
+                    func.method,
+                    Method::ServerStreaming | Method::BidiStreaming
+                )
+                .then_some(true),
+            });
+        }
+
+        Ok(desc)
+    }
+}
+
+// comments are written like protoc does, each line keeps the space after
+// `//` and ends with a newline
+fn comment(text: &str) -> String {
+    text.lines().map(|line| format!(" {line}\n")).collect()
+}
+
+#[inline]
+fn child(path: &[i32], field: i32, index: usize) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field);
+    path.push(index as i32);
+    path
+}
+
+/// `foo_bar` -> `FooBarEntry`, the name protoc gives to map entries
+fn map_entry_name(field: &str) -> String {
+    let mut name = String::with_capacity(field.len() + 5);
+    let mut upper = true;
+    for ch in field.chars() {
+        if ch == '_' {
+            upper = true;
+        } else if upper {
+            name.push(ch.to_ascii_uppercase());
+            upper = false;
+        } else {
+            name.push(ch);
+        }
+    }
+    name.push_str("Entry");
+    name
+}
+
+fn scalar(typ: &FieldType) -> field_descriptor_proto::Type {
+    use field_descriptor_proto::Type;
+
+    match typ {
+        FieldType::Double => Type::Double,
+        FieldType::Float => Type::Float,
+        FieldType::Int64 => Type::Int64,
+        FieldType::Uint64 => Type::Uint64,
+        FieldType::Int32 => Type::Int32,
+        FieldType::Fixed64 => Type::Fixed64,
+        FieldType::Fixed32 => Type::Fixed32,
+        FieldType::Bool => Type::Bool,
+        FieldType::String => Type::String,
+        FieldType::Bytes => Type::Bytes,
+        FieldType::Uint32 => Type::Uint32,
+        FieldType::Sfixed32 => Type::Sfixed32,
+        FieldType::Sfixed64 => Type::Sfixed64,
+        FieldType::Sint32 => Type::Sint32,
+        FieldType::Sint64 => Type::Sint64,
+        FieldType::Message(_) | FieldType::Map(_, _) => unreachable!(),
+    }
+}
+
+// default values of bytes fields are C escaped
+fn c_escape(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len());
+    for b in bytes {
+        match b {
+            b'\n' => s.push_str("\\n"),
+            b'\r' => s.push_str("\\r"),
+            b'\t' => s.push_str("\\t"),
+            b'"' => s.push_str("\\\""),
+            b'\'' => s.push_str("\\'"),
+            b'\\' => s.push_str("\\\\"),
+            0x20..=0x7e => s.push(*b as char),
+            _ => s.push_str(&format!("\\{b:03o}")),
+        }
+    }
+    s
+}
+
+/// Convert options, `set` stores an option into the typed field and returns
+/// `true` if it's known, the others are kept in `uninterpreted_option`.
+/// `None` if there is no option at all.
+fn options<'o, T: Default>(
+    options: impl Iterator<Item = &'o ProtoOption>,
+    set: impl Fn(&mut T, &str, &OptionValue) -> bool,
+    uninterpreted: impl Fn(&mut T) -> &mut Vec<UninterpretedOption>,
+) -> Option<T> {
+    let mut result = None;
+
+    for option in options {
+        let opts = result.get_or_insert_with(T::default);
+
+        let simple = match option.name.0.as_slice() {
+            [part] if !part.extension => Some(part.name.as_str()),
+            _ => None,
+        };
+        if let Some(name) = simple
+            && set(opts, name, &option.value)
+        {
+            continue;
+        }
+
+        uninterpreted(opts).push(uninterpreted_option(option));
+    }
+
+    result
+}
+
+fn uninterpreted_option(option: &ProtoOption) -> UninterpretedOption {
+    let mut uninterpreted = UninterpretedOption {
+        name: option
+            .name
+            .0
+            .iter()
+            .map(|part| uninterpreted_option::NamePart {
+                name_part: part.name.clone(),
+                is_extension: part.extension,
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    match &option.value {
+        OptionValue::Ident(ident) => uninterpreted.identifier_value = Some(ident.clone()),
+        OptionValue::Integer(value) if *value < 0 => {
+            uninterpreted.negative_int_value = Some(*value)
+        }
+        OptionValue::Integer(value) => uninterpreted.positive_int_value = Some(*value as u64),
+        OptionValue::Float(value) => uninterpreted.double_value = Some(*value),
+        OptionValue::String(s) => uninterpreted.string_value = Some(s.clone()),
+        // the text between braces
+        value => {
+            let text = value.to_string();
+            let text = text
+                .strip_prefix('{')
+                .and_then(|text| text.strip_suffix('}'))
+                .unwrap_or(&text)
+                .trim()
+                .to_string();
+            uninterpreted.aggregate_value = Some(text);
+        }
+    }
+
+    uninterpreted
+}
+
+fn file_option(opts: &mut FileOptions, name: &str, value: &OptionValue) -> bool {
+    if let Some(value) = value.as_bool() {
+        match name {
+            "java_multiple_files" => opts.set_java_multiple_files(value),
+            "java_string_check_utf8" => opts.set_java_string_check_utf8(value),
+            "cc_generic_services" => opts.set_cc_generic_services(value),
+            "java_generic_services" => opts.set_java_generic_services(value),
+            "py_generic_services" => opts.set_py_generic_services(value),
+            "deprecated" => opts.set_deprecated(value),
+            "cc_enable_arenas" => opts.set_cc_enable_arenas(value),
+            _ => return false,
+        }
+
+        return true;
+    }
+
+    let Some(value) = value.as_str() else {
+        return false;
+    };
+
+    match name {
+        "java_package" => opts.java_package = Some(value.to_string()),
+        "java_outer_classname" => opts.java_outer_classname = Some(value.to_string()),
+        "go_package" => opts.go_package = Some(value.to_string()),
+        "objc_class_prefix" => opts.objc_class_prefix = Some(value.to_string()),
+        "csharp_namespace" => opts.csharp_namespace = Some(value.to_string()),
+        "swift_prefix" => opts.swift_prefix = Some(value.to_string()),
+        "php_class_prefix" => opts.php_class_prefix = Some(value.to_string()),
+        "php_namespace" => opts.php_namespace = Some(value.to_string()),
+        "php_metadata_namespace" => opts.php_metadata_namespace = Some(value.to_string()),
+        "ruby_package" => opts.ruby_package = Some(value.to_string()),
+        "optimize_for" => opts.set_optimize_for(match value {
+            "SPEED" => file_options::OptimizeMode::Speed,
+            "CODE_SIZE" => file_options::OptimizeMode::CodeSize,
+            "LITE_RUNTIME" => file_options::OptimizeMode::LiteRuntime,
+            _ => return false,
+        }),
+        _ => return false,
+    }
+
+    true
+}
+
+fn message_option(opts: &mut MessageOptions, name: &str, value: &OptionValue) -> bool {
+    let Some(value) = value.as_bool() else {
+        return false;
+    };
+
+    match name {
+        "message_set_wire_format" => opts.set_message_set_wire_format(value),
+        "no_standard_descriptor_accessor" => opts.set_no_standard_descriptor_accessor(value),
+        "deprecated" => opts.set_deprecated(value),
+        _ => return false,
+    }
+
+    true
+}
+
+fn field_option(opts: &mut FieldOptions, name: &str, value: &OptionValue) -> bool {
+    match (name, value.as_str()) {
+        ("ctype", Some("STRING")) => opts.set_ctype(field_options::CType::String),
+        ("ctype", Some("CORD")) => opts.set_ctype(field_options::CType::Cord),
+        ("ctype", Some("STRING_PIECE")) => opts.set_ctype(field_options::CType::StringPiece),
+        ("jstype", Some("JS_NORMAL")) => opts.set_jstype(field_options::JSType::JsNormal),
+        ("jstype", Some("JS_STRING")) => opts.set_jstype(field_options::JSType::JsString),
+        ("jstype", Some("JS_NUMBER")) => opts.set_jstype(field_options::JSType::JsNumber),
+        _ => {
+            let Some(value) = value.as_bool() else {
+                return false;
+            };
+
+            match name {
+                "packed" => opts.packed = Some(value),
+                "lazy" => opts.set_lazy(value),
+                "unverified_lazy" => opts.set_unverified_lazy(value),
+                "deprecated" => opts.set_deprecated(value),
+                "weak" => opts.set_weak(value),
+                "debug_redact" => opts.set_debug_redact(value),
+                _ => return false,
+            }
+        }
+    }
+
+    true
+}
+
+fn enum_option(opts: &mut EnumOptions, name: &str, value: &OptionValue) -> bool {
+    let Some(value) = value.as_bool() else {
+        return false;
+    };
+
+    match name {
+        "allow_alias" => opts.allow_alias = Some(value),
+        "deprecated" => opts.set_deprecated(value),
+        _ => return false,
+    }
+
+    true
+}
+
+fn enum_value_option(opts: &mut EnumValueOptions, name: &str, value: &OptionValue) -> bool {
+    let Some(value) = value.as_bool() else {
+        return false;
+    };
+
+    match name {
+        "deprecated" => opts.set_deprecated(value),
+        "debug_redact" => opts.set_debug_redact(value),
+        _ => return false,
+    }
+
+    true
+}
+
+fn service_option(opts: &mut ServiceOptions, name: &str, value: &OptionValue) -> bool {
+    match (name, value.as_bool()) {
+        ("deprecated", Some(value)) => opts.set_deprecated(value),
+        _ => return false,
+    }
+
+    true
+}
+
+fn method_option(opts: &mut MethodOptions, name: &str, value: &OptionValue) -> bool {
+    use method_options::IdempotencyLevel;
+
+    match (name, value.as_str()) {
+        ("deprecated", _) => match value.as_bool() {
+            Some(value) => opts.set_deprecated(value),
+            None => return false,
+        },
+        ("idempotency_level", Some("IDEMPOTENCY_UNKNOWN")) => {
+            opts.set_idempotency_level(IdempotencyLevel::IdempotencyUnknown)
+        }
+        ("idempotency_level", Some("NO_SIDE_EFFECTS")) => {
+            opts.set_idempotency_level(IdempotencyLevel::NoSideEffects)
+        }
+        ("idempotency_level", Some("IDEMPOTENT")) => {
+            opts.set_idempotency_level(IdempotencyLevel::Idempotent)
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use prust::{Deserialize, Serialize};
+
+    use super::*;
+
+    fn describe(src: &str, imports: &HashMap<String, FileDescriptor>) -> FileDescriptorProto {
+        let fd = crate::parse(src.as_bytes()).unwrap();
+        file_descriptor_proto("test.proto", &fd, imports).unwrap()
+    }
+
+    #[test]
+    fn message() {
+        let file = describe(
+            r#"syntax = "proto3";
+package foo.bar;
+
+// leading
+message Outer {
+  enum Kind { KIND_UNKNOWN = 0; }
+  message Inner {}
+
+  Inner inner = 1;
+  map<string, Kind> kinds = 2;
+  oneof choice {
+    string name = 3;
+    int32 id = 4;
+  }
+  optional uint64 count = 5 [json_name = "total", (foo.bar.custom) = true];
+  repeated .foo.bar.Outer.Inner children = 6;
+  reserved 10 to 12, 15;
+  reserved "old";
+}
+"#,
+            &HashMap::new(),
+        );
+
+        assert_eq!(file.package.as_deref(), Some("foo.bar"));
+        assert_eq!(file.syntax.as_deref(), Some("proto3"));
+
+        let outer = &file.message_type[0];
+        let names = outer
+            .field
+            .iter()
+            .map(|field| field.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["inner", "kinds", "name", "id", "count", "children"]);
+
+        let inner = &outer.field[0];
+        assert_eq!(inner.r#type, Some(field_descriptor_proto::Type::Message));
+        assert_eq!(inner.type_name.as_deref(), Some(".foo.bar.Outer.Inner"));
+        assert_eq!(inner.label, Some(field_descriptor_proto::Label::Optional));
+
+        let kinds = &outer.field[1];
+        assert_eq!(kinds.label, Some(field_descriptor_proto::Label::Repeated));
+        assert_eq!(
+            kinds.type_name.as_deref(),
+            Some(".foo.bar.Outer.KindsEntry")
+        );
+        let entry = &outer.nested_type[1];
+        assert_eq!(entry.name.as_deref(), Some("KindsEntry"));
+        assert_eq!(entry.options.as_ref().unwrap().map_entry, Some(true));
+        assert_eq!(
+            entry.field[1].r#type,
+            Some(field_descriptor_proto::Type::Enum)
+        );
+        assert_eq!(
+            entry.field[1].type_name.as_deref(),
+            Some(".foo.bar.Outer.Kind")
+        );
+
+        assert_eq!(outer.field[2].oneof_index, Some(0));
+        assert_eq!(outer.field[3].oneof_index, Some(0));
+
+        let count = &outer.field[4];
+        assert_eq!(count.proto3_optional, Some(true));
+        assert_eq!(count.oneof_index, Some(1));
+        assert_eq!(count.json_name.as_deref(), Some("total"));
+        let options = count.options.as_ref().unwrap();
+        assert_eq!(options.uninterpreted_option.len(), 1);
+        assert_eq!(
+            options.uninterpreted_option[0].name[0].name_part,
+            "foo.bar.custom"
+        );
+        assert!(options.uninterpreted_option[0].name[0].is_extension);
+        assert_eq!(
+            options.uninterpreted_option[0].identifier_value.as_deref(),
+            Some("true")
+        );
+
+        let oneofs = outer
+            .oneof_decl
+            .iter()
+            .map(|oneof| oneof.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(oneofs, ["choice", "_count"]);
+
+        let reserved = outer
+            .reserved_range
+            .iter()
+            .map(|range| (range.start.unwrap(), range.end.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(reserved, [(10, 13), (15, 16)]);
+        assert_eq!(outer.reserved_name, ["old"]);
+
+        let info = file.source_code_info.as_ref().unwrap();
+        let location = info
+            .location
+            .iter()
+            .find(|location| location.path == [4, 0])
+            .unwrap();
+        assert_eq!(location.span, [4, 0, 18, 1]);
+        assert_eq!(location.leading_comments.as_deref(), Some(" leading\n"));
+        let location = info
+            .location
+            .iter()
+            .find(|location| location.path == [4, 0, 2, 1])
+            .unwrap();
+        assert_eq!(location.span, [9, 2, 30]);
+    }
+
+    #[test]
+    fn proto2() {
+        let mut imports = HashMap::new();
+        imports.insert(
+            "other.proto".to_string(),
+            crate::parse(
+                b"syntax = \"proto2\"; package other; message Base { extensions 100 to max; }",
+            )
+            .unwrap(),
+        );
+
+        let file = describe(
+            r#"syntax = "proto2";
+package foo;
+import public "other.proto";
+option java_package = "com.example.foo";
+option optimize_for = LITE_RUNTIME;
+
+message Msg {
+  required int32 id = 1;
+  optional bytes data = 2 [default = "\001a"];
+  optional Kind kind = 3 [default = B, deprecated = true];
+  repeated int32 values = 4 [packed = true];
+  extensions 100 to 199;
+}
+
+enum Kind {
+  A = 1;
+  B = 2;
+  reserved 5 to 7;
+}
+
+extend other.Base {
+  optional string short_note = 100;
+}
+
+service Api {
+  rpc Call(Msg) returns (stream other.Base) { option deprecated = true; }
+}
+"#,
+            &imports,
+        );
+
+        assert_eq!(file.syntax, None);
+        assert_eq!(file.dependency, ["other.proto"]);
+        assert_eq!(file.public_dependency, [0]);
+
+        let options = file.options.as_ref().unwrap();
+        assert_eq!(options.java_package.as_deref(), Some("com.example.foo"));
+        assert_eq!(
+            options.optimize_for,
+            Some(file_options::OptimizeMode::LiteRuntime)
+        );
+        assert!(options.uninterpreted_option.is_empty());
+
+        let msg = &file.message_type[0];
+        assert_eq!(
+            msg.field[0].label,
+            Some(field_descriptor_proto::Label::Required)
+        );
+        assert_eq!(msg.field[1].default_value.as_deref(), Some("\\001a"));
+        assert_eq!(msg.field[2].default_value.as_deref(), Some("B"));
+        assert_eq!(
+            msg.field[2].options.as_ref().unwrap().deprecated,
+            Some(true)
+        );
+        assert_eq!(msg.field[3].options.as_ref().unwrap().packed, Some(true));
+        assert_eq!(msg.extension_range[0].start, Some(100));
+        assert_eq!(msg.extension_range[0].end, Some(200));
+
+        let en = &file.enum_type[0];
+        assert_eq!(en.value.len(), 2);
+        assert_eq!(en.reserved_range[0].start, Some(5));
+        assert_eq!(en.reserved_range[0].end, Some(7));
+
+        assert_eq!(file.extension[0].extendee.as_deref(), Some(".other.Base"));
+        assert_eq!(file.extension[0].number, Some(100));
+        assert_eq!(file.extension[0].json_name.as_deref(), Some("shortNote"));
+
+        let method = &file.service[0].method[0];
+        assert_eq!(method.input_type.as_deref(), Some(".foo.Msg"));
+        assert_eq!(method.output_type.as_deref(), Some(".other.Base"));
+        assert_eq!(method.client_streaming, None);
+        assert_eq!(method.server_streaming, Some(true));
+        assert!(method.options.as_ref().unwrap().deprecated());
+
+        // imports go first, and the set survives a round trip
+        let fd = crate::parse(b"syntax = \"proto2\"; import \"other.proto\";").unwrap();
+        let mut set = FileDescriptorSet::default();
+        add_file(&mut set, "test.proto", &fd, &imports).unwrap();
+        let names = set
+            .file
+            .iter()
+            .map(|file| file.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["other.proto", "test.proto"]);
+
+        let mut buf = vec![0u8; set.encoded_len()];
+        set.encode(&mut buf).unwrap();
+        let decoded = FileDescriptorSet::decode(&buf).unwrap();
+        assert_eq!(decoded.file.len(), 2);
+        assert_eq!(
+            decoded.file[0].message_type[0].extension_range[0].end,
+            Some(536870912)
+        );
+    }
+
+    // `prust::descriptor` is generated by prust itself, make sure it's
+    // regenerated once the code generator changes
+    #[test]
+    fn bootstrap() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../prust/src/descriptor");
+        let output = std::env::temp_dir().join("prust-descriptor-bootstrap");
+
+        Config::default()
+            .output(&output)
+            .filename("generated")
+            .compile(&[&dir], &[&dir.join("descriptor.proto")])
+            .unwrap();
+
+        let expected = std::fs::read_to_string(output.join("generated.rs")).unwrap();
+        let actual = std::fs::read_to_string(dir.join("generated.rs")).unwrap();
+        assert!(
+            expected == actual,
+            "prust/src/descriptor/generated.rs is out of date, regenerate it with \
+             `prust-build -d prust/src/descriptor prust/src/descriptor/descriptor.proto` \
+             and rename the output to generated.rs"
+        );
+    }
+}
@@ -0,0 +1,889 @@
+// descriptor -> ast
+
+use std::collections::{HashMap, HashSet};
+
+use prust::descriptor::{
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueOptions, FieldDescriptorProto,
+    FileDescriptorProto, FileOptions, MessageOptions, MethodOptions, ServiceDescriptorProto,
+    ServiceOptions, UninterpretedOption, field_descriptor_proto, field_options, file_options,
+    method_options,
+};
+
+use super::*;
+use crate::Error;
+use crate::ast::{
+    Comments, Enum, EnumVariant, Extend, Extension, Field, FieldType, FileDescriptor, Function,
+    Import, ImportKind, Label, Location, Message, Method, NamePart, OneOf, OneOfVarint, OptionName,
+    OptionValue, Options, ProtoOption, Reserved, Service, Syntax,
+};
+use crate::lint::json_name;
+use crate::parse::{parse_aggregate_value, unescape_c_escape_string};
+
+/// Convert a descriptor back into what the parser produces for the source
+/// file, so the code generated from either one is the same. Comments and
+/// locations are taken from `source_code_info` if there is.
+pub(crate) fn file_descriptor(file: &FileDescriptorProto) -> Result<FileDescriptor, Error> {
+    let name = file.name.as_deref().unwrap_or_default();
+    let syntax = match file.syntax.as_deref() {
+        None | Some("") | Some("proto2") => Syntax::Proto2,
+        Some("proto3") => Syntax::Proto3,
+        Some(syntax) => {
+            return Err(Error::InvalidDescriptor(format!(
+                "syntax `{syntax}` of `{name}` is not supported"
+            )));
+        }
+    };
+
+    let mut loader = Loader {
+        file: name,
+        syntax,
+        info: HashMap::new(),
+        blocks: HashMap::new(),
+    };
+    for location in file
+        .source_code_info
+        .iter()
+        .flat_map(|info| info.location.iter())
+    {
+        let path = location.path.clone();
+        let comments = Comments {
+            leading: location.leading_comments.as_deref().map(comment),
+            trailing: location.trailing_comments.as_deref().map(comment),
+            detached: location
+                .leading_detached_comments
+                .iter()
+                .map(|text| comment(text))
+                .collect(),
+            closing: vec![],
+        };
+        let location = match location.span[..] {
+            [start_line, start_column, end_column] => Location {
+                start_line: start_line as u32,
+                start_column: start_column as u32,
+                end_line: start_line as u32,
+                end_column: end_column as u32,
+            },
+            [start_line, start_column, end_line, end_column] => Location {
+                start_line: start_line as u32,
+                start_column: start_column as u32,
+                end_line: end_line as u32,
+                end_column: end_column as u32,
+            },
+            _ => Location::default(),
+        };
+
+        // repeated fields without index, e.g. every `extend` block is
+        // recorded with the path of the extension fields
+        if path.len() % 2 == 1 {
+            loader
+                .blocks
+                .entry(path.clone())
+                .or_default()
+                .push((comments.clone(), location));
+        }
+
+        // the first one wins, protoc records parts of definitions too
+        loader.info.entry(path).or_insert((comments, location));
+    }
+
+    let mut fd = FileDescriptor {
+        syntax,
+        package: file.package.clone().filter(|package| !package.is_empty()),
+        options: file.options.as_ref().map(file_options).unwrap_or_default(),
+        ..Default::default()
+    };
+
+    for (index, path) in file.dependency.iter().enumerate() {
+        let index = index as i32;
+        let kind = if file.public_dependency.contains(&index) {
+            ImportKind::Public
+        } else if file.weak_dependency.contains(&index) {
+            ImportKind::Weak
+        } else {
+            ImportKind::Normal
+        };
+
+        fd.imports.push(Import {
+            path: path.clone(),
+            kind,
+            comments: Default::default(),
+        });
+    }
+
+    for (index, msg) in file.message_type.iter().enumerate() {
+        let path = vec![FILE_MESSAGE_TYPE, index as i32];
+        fd.messages.push(loader.message(msg, path)?);
+    }
+    for (index, en) in file.enum_type.iter().enumerate() {
+        fd.enums
+            .push(loader.enumeration(en, vec![FILE_ENUM_TYPE, index as i32]));
+    }
+    for (index, svc) in file.service.iter().enumerate() {
+        fd.services
+            .push(loader.service(svc, vec![FILE_SERVICE, index as i32])?);
+    }
+    fd.extends = loader.extends(&file.extension, &[FILE_EXTENSION])?;
+
+    Ok(fd)
+}
+
+struct Loader<'a> {
+    file: &'a str,
+    syntax: Syntax,
+    /// comments and location of definitions, by the path of `SourceCodeInfo`
+    info: HashMap<Vec<i32>, (Comments, Location)>,
+    /// locations recorded for the whole repeated field, by the same path
+    blocks: HashMap<Vec<i32>, Vec<(Comments, Location)>>,
+}
+
+impl Loader<'_> {
+    fn info(&self, path: &[i32]) -> (Comments, Location) {
+        self.info.get(path).cloned().unwrap_or_default()
+    }
+
+    fn invalid(&self, what: impl std::fmt::Display) -> Error {
+        Error::InvalidDescriptor(format!("{what} in `{}`", self.file))
+    }
+
+    fn message(&self, desc: &DescriptorProto, path: Vec<i32>) -> Result<Message, Error> {
+        let name = desc
+            .name
+            .clone()
+            .ok_or_else(|| self.invalid("message without name"))?;
+        let (comments, location) = self.info(&path);
+
+        let mut msg = Message {
+            name,
+            options: desc
+                .options
+                .as_ref()
+                .map(message_options)
+                .unwrap_or_default(),
+            comments,
+            location,
+            ..Default::default()
+        };
+
+        // map fields refer to entries nested in the message
+        let entries = desc
+            .nested_type
+            .iter()
+            .filter(|nested| {
+                nested
+                    .options
+                    .as_ref()
+                    .is_some_and(|options| options.map_entry == Some(true))
+            })
+            .filter_map(|nested| Some((nested.name.as_deref()?, nested)))
+            .collect::<HashMap<_, _>>();
+
+        // oneofs made up for proto3 optional fields
+        let synthetic = desc
+            .field
+            .iter()
+            .filter(|field| field.proto3_optional == Some(true))
+            .filter_map(|field| field.oneof_index)
+            .collect::<HashSet<_>>();
+
+        let mut oneofs = HashMap::new();
+        for (index, oneof) in desc.oneof_decl.iter().enumerate() {
+            if synthetic.contains(&(index as i32)) {
+                continue;
+            }
+
+            let (comments, location) = self.info(&child(&path, MESSAGE_ONEOF_DECL, index));
+            oneofs.insert(index as i32, msg.oneofs.len());
+            msg.oneofs.push(OneOf {
+                name: oneof.name.clone().unwrap_or_default(),
+                variants: vec![],
+                options: oneof
+                    .options
+                    .as_ref()
+                    .map(|options| uninterpreted(&options.uninterpreted_option))
+                    .unwrap_or_default(),
+                comments,
+                location,
+            });
+        }
+
+        for (index, field) in desc.field.iter().enumerate() {
+            let path = child(&path, MESSAGE_FIELD, index);
+
+            if let Some(oneof) = field.oneof_index.and_then(|index| oneofs.get(&index)) {
+                let (comments, location) = self.info(&path);
+                let (name, number) = self.name_and_number(field)?;
+                msg.oneofs[*oneof].variants.push(OneOfVarint {
+                    number,
+                    typ: self.field_type(field)?,
+                    options: field_options(field),
+                    name,
+                    comments,
+                    location,
+                });
+                continue;
+            }
+
+            let entry = field
+                .type_name
+                .as_deref()
+                .and_then(|name| entries.get(name.rsplit('.').next().unwrap_or(name)))
+                .filter(|_| field.label == Some(field_descriptor_proto::Label::Repeated));
+            let field = match entry {
+                Some(entry) => {
+                    let mut field = self.field(field, path)?;
+                    field.label = Label::Required;
+                    field.typ = self.map_type(entry)?;
+                    field
+                }
+                None => self.field(field, path)?,
+            };
+            msg.fields.push(field);
+        }
+
+        for (index, nested) in desc.nested_type.iter().enumerate() {
+            if nested
+                .options
+                .as_ref()
+                .is_some_and(|options| options.map_entry == Some(true))
+            {
+                continue;
+            }
+
+            let path = child(&path, MESSAGE_NESTED_TYPE, index);
+            msg.messages.push(self.message(nested, path)?);
+        }
+        for (index, en) in desc.enum_type.iter().enumerate() {
+            let path = child(&path, MESSAGE_ENUM_TYPE, index);
+            msg.enums.push(self.enumeration(en, path));
+        }
+
+        let mut extension = path.clone();
+        extension.push(MESSAGE_EXTENSION);
+        msg.extends = self.extends(&desc.extension, &extension)?;
+
+        for range in &desc.extension_range {
+            msg.extensions.push(Extension {
+                start: range.start.unwrap_or_default() as u32,
+                // exclusive in descriptors
+                end: range.end.unwrap_or_default() as u32 - 1,
+                properties: range
+                    .options
+                    .as_ref()
+                    .map(|options| uninterpreted(&options.uninterpreted_option))
+                    .unwrap_or_default(),
+                comments: Comments::default(),
+            });
+        }
+
+        for range in &desc.reserved_range {
+            let start = range.start.unwrap_or_default() as i64;
+            let end = range.end.unwrap_or_default() as i64 - 1;
+            msg.reserved.push(if start == end {
+                Reserved::Single(start)
+            } else {
+                Reserved::Range(start, end)
+            });
+        }
+        msg.reserved
+            .extend(desc.reserved_name.iter().cloned().map(Reserved::Field));
+
+        Ok(msg)
+    }
+
+    fn name_and_number(&self, field: &FieldDescriptorProto) -> Result<(String, u32), Error> {
+        match (&field.name, field.number) {
+            (Some(name), Some(number)) => Ok((name.clone(), number as u32)),
+            _ => Err(self.invalid("field without name or number")),
+        }
+    }
+
+    fn field(&self, desc: &FieldDescriptorProto, path: Vec<i32>) -> Result<Field, Error> {
+        use field_descriptor_proto::Label as L;
+
+        let (name, number) = self.name_and_number(desc)?;
+        let (comments, location) = self.info(&path);
+
+        let label = match (desc.label, self.syntax) {
+            (Some(L::Repeated), _) => Label::Repeated,
+            (Some(L::Required), _) => Label::Required,
+            (_, Syntax::Proto3) if desc.extendee.is_some() => Label::Optional,
+            (_, Syntax::Proto3) if desc.proto3_optional != Some(true) => Label::Required,
+            _ => Label::Optional,
+        };
+
+        Ok(Field {
+            label,
+            typ: self.field_type(desc)?,
+            options: field_options(desc),
+            name,
+            number,
+            comments,
+            location,
+        })
+    }
+
+    fn field_type(&self, desc: &FieldDescriptorProto) -> Result<FieldType, Error> {
+        use field_descriptor_proto::Type;
+
+        Ok(match desc.r#type {
+            Some(Type::Double) => FieldType::Double,
+            Some(Type::Float) => FieldType::Float,
+            Some(Type::Int64) => FieldType::Int64,
+            Some(Type::Uint64) => FieldType::Uint64,
+            Some(Type::Int32) => FieldType::Int32,
+            Some(Type::Fixed64) => FieldType::Fixed64,
+            Some(Type::Fixed32) => FieldType::Fixed32,
+            Some(Type::Bool) => FieldType::Bool,
+            Some(Type::String) => FieldType::String,
+            Some(Type::Bytes) => FieldType::Bytes,
+            Some(Type::Uint32) => FieldType::Uint32,
+            Some(Type::Sfixed32) => FieldType::Sfixed32,
+            Some(Type::Sfixed64) => FieldType::Sfixed64,
+            Some(Type::Sint32) => FieldType::Sint32,
+            Some(Type::Sint64) => FieldType::Sint64,
+            Some(Type::Group) => {
+                return Err(self.invalid(format_args!(
+                    "group field `{}`",
+                    desc.name.as_deref().unwrap_or_default()
+                )));
+            }
+            // the type might be left for the type name to tell
+            Some(Type::Message) | Some(Type::Enum) | None => match &desc.type_name {
+                Some(name) => FieldType::Message(name.clone()),
+                None => {
+                    return Err(self.invalid(format_args!(
+                        "field `{}` without type",
+                        desc.name.as_deref().unwrap_or_default()
+                    )));
+                }
+            },
+        })
+    }
+
+    fn map_type(&self, entry: &DescriptorProto) -> Result<FieldType, Error> {
+        let find = |number| {
+            entry
+                .field
+                .iter()
+                .find(|field| field.number == Some(number))
+                .ok_or_else(|| {
+                    self.invalid(format_args!(
+                        "map entry `{}` without key or value",
+                        entry.name.as_deref().unwrap_or_default()
+                    ))
+                })
+        };
+
+        Ok(FieldType::Map(
+            Box::new(self.field_type(find(1)?)?),
+            Box::new(self.field_type(find(2)?)?),
+        ))
+    }
+
+    // extensions of the same extendee next to each other are grouped, like
+    // they are in one `extend` block, blocks recorded in `SourceCodeInfo`
+    // split them further
+    fn extends(&self, fields: &[FieldDescriptorProto], path: &[i32]) -> Result<Vec<Extend>, Error> {
+        let mut extends: Vec<Extend> = vec![];
+        let blocks = self.blocks.get(path).map(Vec::as_slice).unwrap_or_default();
+        let mut current = None;
+
+        for (index, desc) in fields.iter().enumerate() {
+            let extendee = desc
+                .extendee
+                .clone()
+                .ok_or_else(|| self.invalid("extension without extendee"))?;
+
+            let mut path = path.to_vec();
+            path.push(index as i32);
+            let field = self.field(desc, path)?;
+
+            let block = blocks.iter().position(|(_, location)| {
+                (location.start_line..=location.end_line).contains(&field.location.start_line)
+            });
+
+            match extends.last_mut() {
+                Some(last) if last.extendee == extendee && block == current => {
+                    last.fields.push(field)
+                }
+                _ => {
+                    let (comments, location) =
+                        block.map(|block| blocks[block].clone()).unwrap_or_default();
+                    extends.push(Extend {
+                        extendee,
+                        fields: vec![field],
+                        comments,
+                        location,
+                    })
+                }
+            }
+            current = block;
+        }
+
+        Ok(extends)
+    }
+
+    fn enumeration(&self, desc: &EnumDescriptorProto, path: Vec<i32>) -> Enum {
+        let (comments, location) = self.info(&path);
+
+        let variants = desc
+            .value
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let (comments, location) = self.info(&child(&path, ENUM_VALUE, index));
+                EnumVariant {
+                    name: value.name.clone().unwrap_or_default(),
+                    value: value.number.unwrap_or_default(),
+                    options: value
+                        .options
+                        .as_ref()
+                        .map(enum_value_options)
+                        .unwrap_or_default(),
+                    comments,
+                    location,
+                }
+            })
+            .collect();
+
+        // both ends are inclusive for enums
+        let mut reserved = desc
+            .reserved_range
+            .iter()
+            .map(|range| {
+                let start = range.start.unwrap_or_default() as i64;
+                let end = range.end.unwrap_or_default() as i64;
+                if start == end {
+                    Reserved::Single(start)
+                } else {
+                    Reserved::Range(start, end)
+                }
+            })
+            .collect::<Vec<_>>();
+        reserved.extend(desc.reserved_name.iter().cloned().map(Reserved::Field));
+
+        Enum {
+            name: desc.name.clone().unwrap_or_default(),
+            variants,
+            reserved,
+            reserved_comments: Comments::default(),
+            options: desc.options.as_ref().map(enum_options).unwrap_or_default(),
+            comments,
+            location,
+            closed: self.syntax == Syntax::Proto2,
+        }
+    }
+
+    fn service(&self, desc: &ServiceDescriptorProto, path: Vec<i32>) -> Result<Service, Error> {
+        let (comments, location) = self.info(&path);
+
+        let mut functions = vec![];
+        for (index, method) in desc.method.iter().enumerate() {
+            let (comments, location) = self.info(&child(&path, SERVICE_METHOD, index));
+            let (Some(request), Some(response)) = (&method.input_type, &method.output_type) else {
+                return Err(self.invalid("method without input or output type"));
+            };
+
+            functions.push(Function {
+                name: method.name.clone().unwrap_or_default(),
+                method: match (method.client_streaming(), method.server_streaming()) {
+                    (false, false) => Method::Unary,
+                    (true, false) => Method::ClientStreaming,
+                    (false, true) => Method::ServerStreaming,
+                    (true, true) => Method::BidiStreaming,
+                },
+                request: request.clone(),
+                response: response.clone(),
+                options: method
+                    .options
+                    .as_ref()
+                    .map(method_options)
+                    .unwrap_or_default(),
+                comments,
+                location,
+            });
+        }
+
+        Ok(Service {
+            name: desc.name.clone().unwrap_or_default(),
+            functions,
+            options: desc
+                .options
+                .as_ref()
+                .map(service_options)
+                .unwrap_or_default(),
+            comments,
+            location,
+        })
+    }
+}
+
+#[inline]
+fn child(path: &[i32], field: i32, index: usize) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field);
+    path.push(index as i32);
+    path
+}
+
+// comments of protoc keep the space after `//`, and end with a newline
+fn comment(text: &str) -> String {
+    text.lines()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn uninterpreted(options: &[UninterpretedOption]) -> Options {
+    let mut result = Options::default();
+
+    for option in options {
+        let name = OptionName(
+            option
+                .name
+                .iter()
+                .map(|part| NamePart {
+                    name: part.name_part.clone(),
+                    extension: part.is_extension,
+                })
+                .collect(),
+        );
+
+        let value = if let Some(ident) = &option.identifier_value {
+            OptionValue::Ident(ident.clone())
+        } else if let Some(value) = option.positive_int_value {
+            OptionValue::Integer(value as i64)
+        } else if let Some(value) = option.negative_int_value {
+            OptionValue::Integer(value)
+        } else if let Some(value) = option.double_value {
+            OptionValue::Float(value)
+        } else if let Some(s) = &option.string_value {
+            OptionValue::String(s.clone())
+        } else if let Some(text) = &option.aggregate_value {
+            // keep the text if it can't be parsed, rather than dropping it
+            match parse_aggregate_value(text) {
+                Some(fields) => OptionValue::Aggregate(fields),
+                None => OptionValue::String(text.clone().into_bytes()),
+            }
+        } else {
+            continue;
+        };
+
+        result.push(ProtoOption { name, value });
+    }
+
+    result
+}
+
+#[inline]
+fn ident(value: impl Into<String>) -> OptionValue {
+    OptionValue::Ident(value.into())
+}
+
+#[inline]
+fn bool(value: bool) -> OptionValue {
+    ident(if value { "true" } else { "false" })
+}
+
+#[inline]
+fn string(value: &str) -> OptionValue {
+    OptionValue::String(value.as_bytes().to_vec())
+}
+
+fn file_options(opts: &FileOptions) -> Options {
+    let mut options = Options::default();
+
+    let strings = [
+        ("java_package", &opts.java_package),
+        ("java_outer_classname", &opts.java_outer_classname),
+        ("go_package", &opts.go_package),
+        ("objc_class_prefix", &opts.objc_class_prefix),
+        ("csharp_namespace", &opts.csharp_namespace),
+        ("swift_prefix", &opts.swift_prefix),
+        ("php_class_prefix", &opts.php_class_prefix),
+        ("php_namespace", &opts.php_namespace),
+        ("php_metadata_namespace", &opts.php_metadata_namespace),
+        ("ruby_package", &opts.ruby_package),
+    ];
+    for (name, value) in strings {
+        if let Some(value) = value {
+            options.insert(name, string(value));
+        }
+    }
+
+    // defaults can't be told from unset ones, so they are left out
+    let bools = [
+        ("java_multiple_files", opts.java_multiple_files(), false),
+        (
+            "java_string_check_utf8",
+            opts.java_string_check_utf8(),
+            false,
+        ),
+        ("cc_generic_services", opts.cc_generic_services(), false),
+        ("java_generic_services", opts.java_generic_services(), false),
+        ("py_generic_services", opts.py_generic_services(), false),
+        ("deprecated", opts.deprecated(), false),
+        ("cc_enable_arenas", opts.cc_enable_arenas(), true),
+    ];
+    for (name, value, default) in bools {
+        if value != default {
+            options.insert(name, bool(value));
+        }
+    }
+
+    match opts.optimize_for() {
+        file_options::OptimizeMode::Speed => {}
+        file_options::OptimizeMode::CodeSize => options.insert("optimize_for", ident("CODE_SIZE")),
+        file_options::OptimizeMode::LiteRuntime => {
+            options.insert("optimize_for", ident("LITE_RUNTIME"))
+        }
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+fn message_options(opts: &MessageOptions) -> Options {
+    let mut options = Options::default();
+
+    if opts.message_set_wire_format() {
+        options.insert("message_set_wire_format", bool(true));
+    }
+    if opts.no_standard_descriptor_accessor() {
+        options.insert("no_standard_descriptor_accessor", bool(true));
+    }
+    if opts.deprecated() {
+        options.insert("deprecated", bool(true));
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+// `default` and `json_name` are options in proto files, but fields of
+// descriptors
+fn field_options(desc: &FieldDescriptorProto) -> Options {
+    let mut options = Options::default();
+
+    if let Some(value) = &desc.default_value {
+        options.insert("default", default_value(desc, value));
+    }
+    if let (Some(name), Some(json)) = (&desc.name, &desc.json_name)
+        && json_name(name, &Options::default()) != *json
+    {
+        options.insert("json_name", string(json));
+    }
+
+    let Some(opts) = &desc.options else {
+        return options;
+    };
+    match opts.ctype() {
+        field_options::CType::String => {}
+        field_options::CType::Cord => options.insert("ctype", ident("CORD")),
+        field_options::CType::StringPiece => options.insert("ctype", ident("STRING_PIECE")),
+    }
+    if let Some(packed) = opts.packed {
+        options.insert("packed", bool(packed));
+    }
+    match opts.jstype() {
+        field_options::JSType::JsNormal => {}
+        field_options::JSType::JsString => options.insert("jstype", ident("JS_STRING")),
+        field_options::JSType::JsNumber => options.insert("jstype", ident("JS_NUMBER")),
+    }
+
+    let bools = [
+        ("lazy", opts.lazy()),
+        ("unverified_lazy", opts.unverified_lazy()),
+        ("deprecated", opts.deprecated()),
+        ("weak", opts.weak()),
+        ("debug_redact", opts.debug_redact()),
+    ];
+    for (name, value) in bools {
+        if value {
+            options.insert(name, bool(true));
+        }
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+// default values are kept in text, parse them the way the lexer does
+fn default_value(desc: &FieldDescriptorProto, value: &str) -> OptionValue {
+    use field_descriptor_proto::Type;
+
+    match desc.r#type {
+        Some(Type::String) => string(value),
+        Some(Type::Bytes) => OptionValue::String(
+            unescape_c_escape_string(value).unwrap_or_else(|_| value.as_bytes().to_vec()),
+        ),
+        Some(Type::Bool) | Some(Type::Enum) => ident(value),
+        _ => match value {
+            "inf" | "nan" => ident(value),
+            "-inf" => OptionValue::Float(f64::NEG_INFINITY),
+            _ => match value.parse::<i64>() {
+                Ok(value) => OptionValue::Integer(value),
+                Err(_) => value
+                    .parse::<f64>()
+                    .map(OptionValue::Float)
+                    .unwrap_or_else(|_| ident(value)),
+            },
+        },
+    }
+}
+
+fn enum_options(opts: &EnumOptions) -> Options {
+    let mut options = Options::default();
+
+    if let Some(allow_alias) = opts.allow_alias {
+        options.insert("allow_alias", bool(allow_alias));
+    }
+    if opts.deprecated() {
+        options.insert("deprecated", bool(true));
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+fn enum_value_options(opts: &EnumValueOptions) -> Options {
+    let mut options = Options::default();
+
+    if opts.deprecated() {
+        options.insert("deprecated", bool(true));
+    }
+    if opts.debug_redact() {
+        options.insert("debug_redact", bool(true));
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+fn service_options(opts: &ServiceOptions) -> Options {
+    let mut options = Options::default();
+
+    if opts.deprecated() {
+        options.insert("deprecated", bool(true));
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+fn method_options(opts: &MethodOptions) -> Options {
+    use method_options::IdempotencyLevel;
+
+    let mut options = Options::default();
+
+    if opts.deprecated() {
+        options.insert("deprecated", bool(true));
+    }
+    match opts.idempotency_level() {
+        IdempotencyLevel::IdempotencyUnknown => {}
+        IdempotencyLevel::NoSideEffects => {
+            options.insert("idempotency_level", ident("NO_SIDE_EFFECTS"))
+        }
+        IdempotencyLevel::Idempotent => options.insert("idempotency_level", ident("IDEMPOTENT")),
+    }
+
+    extend(&mut options, uninterpreted(&opts.uninterpreted_option));
+    options
+}
+
+#[inline]
+fn extend(options: &mut Options, other: Options) {
+    for option in other.iter() {
+        options.push(option.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptor::build::file_descriptor_proto;
+
+    // the parser and descriptors must end up with the same ast
+    fn round_trip(src: &str, imports: &HashMap<String, FileDescriptor>) {
+        let parsed = crate::parse(src.as_bytes()).unwrap();
+        let desc = file_descriptor_proto("test.proto", &parsed, imports).unwrap();
+        let loaded = file_descriptor(&desc).unwrap();
+
+        // types are written fully qualified in the sources, like descriptors
+        // keep them
+        let expected = format!("{parsed:#?}");
+        let got = format!("{loaded:#?}");
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn proto3() {
+        round_trip(
+            r#"syntax = "proto3";
+package foo;
+
+// A message
+message Msg {
+  // Nested
+  message Inner {}
+  enum Kind {
+    KIND_UNKNOWN = 0; // unknown
+    KIND_A = 1 [deprecated = true];
+  }
+
+  .foo.Msg.Inner inner = 1;
+  map<string, .foo.Msg.Kind> kinds = 2;
+  oneof choice {
+    string name = 3;
+    int32 id = 4;
+  }
+  optional uint64 count = 5 [json_name = "total"];
+  repeated int32 values = 6 [packed = false];
+  reserved 10 to 12, 15;
+  reserved "old";
+}
+
+service Api {
+  rpc Call(.foo.Msg) returns (stream .foo.Msg) {}
+}
+"#,
+            &HashMap::new(),
+        );
+    }
+
+    #[test]
+    fn proto2() {
+        round_trip(
+            r#"syntax = "proto2";
+package foo;
+
+option java_package = "com.example";
+
+message Msg {
+  required int32 id = 1;
+  optional bytes data = 2 [default = "\001a"];
+  optional double ratio = 3 [default = -inf];
+  optional .foo.Kind kind = 4 [default = B];
+  extensions 100 to max;
+
+  extend .foo.Msg {
+    optional string note = 100;
+  }
+}
+
+enum Kind {
+  option allow_alias = true;
+  A = 1;
+  B = 2;
+  C = 2;
+  reserved 5 to 7, 9;
+}
+
+extend .foo.Msg {
+  repeated int32 tags = 101 [(custom) = { a: 1 b: "x" }];
+}
+"#,
+            &HashMap::new(),
+        );
+    }
+}
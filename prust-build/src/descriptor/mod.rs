@@ -0,0 +1,27 @@
+//! Conversion between parsed files and `google.protobuf.FileDescriptorProto`,
+//! the form other tools exchange schemas in, e.g. gRPC reflection, buf or
+//! Envoy.
+//!
+//! Types are resolved to fully qualified names, map fields get their
+//! `XxxEntry` messages, and proto3 `optional` fields get synthetic oneofs,
+//! just like `protoc` does. Options known by `descriptor.proto` are set to
+//! the typed fields, all the others are left uninterpreted.
+
+mod build;
+mod load;
+
+pub(crate) use build::add_file;
+pub(crate) use load::file_descriptor;
+
+// field numbers of repeated fields, they make up paths of `SourceCodeInfo`
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const MESSAGE_EXTENSION: i32 = 6;
+const MESSAGE_ONEOF_DECL: i32 = 8;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
@@ -0,0 +1,1460 @@
+//! Schema driven decoding and encoding of messages, without generated code.
+//!
+//! A [`Schema`] is built from proto files or an encoded `FileDescriptorSet`.
+//! Payloads are decoded into a [`Message`], which prints as text format with
+//! `Display` or as JSON with [`Message::to_json`], and text format or JSON is
+//! encoded back into the wire format. [`decode_raw`] decodes payloads without
+//! any schema, like `protoc --decode_raw` does.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+use std::path::Path;
+
+use prust::descriptor::field_descriptor_proto::{Label, Type};
+use prust::descriptor::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet,
+};
+use prust::{DecodeError, Deserialize, Reader};
+
+use crate::Error;
+use crate::ast::OptionValue;
+use crate::codegen::config::{import_name, load_files};
+use crate::descriptor;
+use crate::lint::push_json_string;
+use crate::parse::parse_text_format;
+
+/// Message and enum types of a set of files, keyed by the full names
+pub struct Schema {
+    messages: HashMap<String, MessageType>,
+    enums: HashMap<String, EnumDescriptorProto>,
+}
+
+struct MessageType {
+    descriptor: DescriptorProto,
+    // repeated scalars are packed unless `packed = false`, proto2 is the
+    // other way around
+    packed: bool,
+}
+
+impl Schema {
+    /// Load proto files and their imports
+    pub fn load<P: AsRef<Path>>(includes: &[P], files: &[P]) -> Result<Schema, Error> {
+        let mut set = FileDescriptorSet::default();
+        for (path, fd, imports) in load_files(includes, files)? {
+            descriptor::add_file(&mut set, &import_name(includes, path), &fd, &imports)?;
+        }
+
+        Ok(Schema::from_set(set))
+    }
+
+    /// Use files of an encoded `FileDescriptorSet`, imports must be included
+    pub fn from_descriptor_set(bytes: &[u8]) -> Result<Schema, Error> {
+        let set = FileDescriptorSet::decode(bytes)
+            .map_err(|err| Error::InvalidDescriptor(err.to_string()))?;
+
+        Ok(Schema::from_set(set))
+    }
+
+    fn from_set(set: FileDescriptorSet) -> Schema {
+        let mut schema = Schema {
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+        };
+
+        for file in set.file {
+            let packed = file
+                .syntax
+                .as_deref()
+                .is_some_and(|syntax| syntax != "proto2");
+            let scope = match file.package.as_deref() {
+                Some(package) if !package.is_empty() => format!(".{package}"),
+                _ => String::new(),
+            };
+
+            for enumeration in file.enum_type {
+                let name = format!(
+                    "{scope}.{}",
+                    enumeration.name.as_deref().unwrap_or_default()
+                );
+                schema.enums.insert(name, enumeration);
+            }
+            for message in file.message_type {
+                schema.add_message(&scope, message, packed);
+            }
+        }
+
+        schema
+    }
+
+    fn add_message(&mut self, scope: &str, mut message: DescriptorProto, packed: bool) {
+        let name = format!("{scope}.{}", message.name.as_deref().unwrap_or_default());
+
+        for enumeration in std::mem::take(&mut message.enum_type) {
+            let full_name = format!("{name}.{}", enumeration.name.as_deref().unwrap_or_default());
+            self.enums.insert(full_name, enumeration);
+        }
+        for nested in std::mem::take(&mut message.nested_type) {
+            self.add_message(&name, nested, packed);
+        }
+
+        self.messages.insert(
+            name,
+            MessageType {
+                descriptor: message,
+                packed,
+            },
+        );
+    }
+
+    // `pkg.Type` and `.pkg.Type` are both fine
+    fn message(&self, name: &str) -> Result<&MessageType, Error> {
+        let full_name = match name.strip_prefix('.') {
+            Some(_) => name.to_string(),
+            None => format!(".{name}"),
+        };
+
+        self.messages
+            .get(&full_name)
+            .ok_or_else(|| Error::UnresolvedType {
+                name: name.to_string(),
+                scope: String::new(),
+            })
+    }
+
+    /// Decode `bytes` as the message type `name`
+    pub fn decode(&self, name: &str, bytes: &[u8]) -> Result<Message, Error> {
+        let message = self.message(name)?;
+        self.decode_message(message, bytes)
+            .map_err(Failure::into_error)
+    }
+
+    /// Encode a message of type `name` in text format
+    pub fn encode_text(&self, name: &str, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let message = self.message(name)?;
+        let fields = parse_text_format(input).map_err(|err| Error::Parse(vec![err]))?;
+
+        let mut buf = vec![];
+        self.encode_message(message, &fields, false, &mut buf)
+            .map_err(Failure::into_error)?;
+        Ok(buf)
+    }
+
+    /// Encode a message of type `name` in the JSON mapping of proto3
+    pub fn encode_json(&self, name: &str, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let message = self.message(name)?;
+        let fields = match JsonParser::parse(input)? {
+            OptionValue::Aggregate(fields) => fields,
+            _ => return Err(Error::InvalidPayload("expected a JSON object".to_string())),
+        };
+
+        let mut buf = vec![];
+        self.encode_message(message, &fields, true, &mut buf)
+            .map_err(Failure::into_error)?;
+        Ok(buf)
+    }
+
+    fn decode_message(&self, message: &MessageType, src: &[u8]) -> Result<Message, Failure> {
+        let mut reader = Reader::new(src);
+        let mut fields = vec![];
+
+        while reader.pos < src.len() {
+            let (number, wire_type) = read_tag(&mut reader)?;
+            let Some(field) = message
+                .descriptor
+                .field
+                .iter()
+                .find(|field| field.number == Some(number as i32))
+            else {
+                let value = raw_value(&mut reader, wire_type).map_err(|err| err.at(number))?;
+                fields.push(Field::unknown(number, value));
+                continue;
+            };
+
+            self.decode_field(field, wire_type, &mut reader, &mut fields)
+                .map_err(|err| err.at(field_name(field)))?;
+        }
+
+        Ok(Message { fields })
+    }
+
+    fn decode_field(
+        &self,
+        field: &FieldDescriptorProto,
+        wire_type: u8,
+        reader: &mut Reader,
+        fields: &mut Vec<Field>,
+    ) -> Result<(), Failure> {
+        let typ = field.r#type.unwrap_or_default();
+        let cardinality = self.cardinality(field);
+        let push = |fields: &mut Vec<Field>, value| {
+            fields.push(Field {
+                name: field_name(field).to_string(),
+                json_name: field
+                    .json_name
+                    .clone()
+                    .unwrap_or_else(|| field_name(field).to_string()),
+                number: field.number.unwrap_or_default() as u32,
+                cardinality,
+                value,
+            })
+        };
+
+        // parsers must accept both packed and unpacked repeated scalars
+        if cardinality == Cardinality::Repeated && wire_type == 2 && packable(typ) {
+            let src = read_slice(reader)?;
+            let mut packed = Reader::new(src);
+            while packed.pos < src.len() {
+                let value = self.decode_scalar(field, typ, &mut packed)?;
+                push(fields, value);
+            }
+            return Ok(());
+        }
+
+        if wire_type != expected_wire_type(typ) {
+            return Err(Failure::new(format!(
+                "wire type {wire_type} doesn't match type `{}`",
+                type_str(field, typ)
+            )));
+        }
+
+        let value = match typ {
+            Type::Message => {
+                let name = field.type_name.as_deref().unwrap_or_default();
+                let nested = self
+                    .message(name)
+                    .map_err(|err| Failure::new(err.to_string()))?;
+                let mut value = self.decode_message(nested, read_slice(reader)?)?;
+                if cardinality == Cardinality::Map {
+                    self.fill_map_entry(nested, &mut value);
+                }
+                Value::Message(value)
+            }
+            Type::Group => return Err(Failure::new("groups are not supported")),
+            _ => self.decode_scalar(field, typ, reader)?,
+        };
+        push(fields, value);
+
+        Ok(())
+    }
+
+    fn decode_scalar(
+        &self,
+        field: &FieldDescriptorProto,
+        typ: Type,
+        reader: &mut Reader,
+    ) -> Result<Value, Failure> {
+        let value = match typ {
+            Type::Double => Value::F64(reader.read_double()?),
+            Type::Float => Value::F32(reader.read_float()?),
+            Type::Int64 => Value::I64(reader.read_int64()?),
+            Type::Uint64 => Value::U64(reader.read_uint64()?),
+            Type::Int32 => Value::I32(reader.read_int32()?),
+            Type::Fixed64 => Value::U64(reader.read_fixed64()?),
+            Type::Fixed32 => Value::U32(reader.read_fixed32()?),
+            Type::Bool => Value::Bool(reader.read_bool()?),
+            Type::String => Value::String(reader.read_string()?),
+            Type::Bytes => Value::Bytes(read_slice(reader)?.to_vec()),
+            Type::Uint32 => Value::U32(reader.read_uint32()?),
+            Type::Sfixed32 => Value::I32(reader.read_sfixed32()?),
+            Type::Sfixed64 => Value::I64(reader.read_sfixed64()?),
+            Type::Sint32 => Value::I32(reader.read_sint32()?),
+            Type::Sint64 => Value::I64(reader.read_sint64()?),
+            Type::Enum => {
+                let number = reader.read_int32()?;
+                Value::Enum(number, self.enum_value_name(field, number))
+            }
+            Type::Group | Type::Message => unreachable!("not a scalar type"),
+        };
+
+        Ok(value)
+    }
+
+    fn enum_value_name(&self, field: &FieldDescriptorProto, number: i32) -> Option<String> {
+        self.enums
+            .get(field.type_name.as_deref().unwrap_or_default())?
+            .value
+            .iter()
+            .find(|value| value.number == Some(number))
+            .and_then(|value| value.name.clone())
+    }
+
+    fn cardinality(&self, field: &FieldDescriptorProto) -> Cardinality {
+        if field.label != Some(Label::Repeated) {
+            return Cardinality::Single;
+        }
+
+        let map = field.r#type == Some(Type::Message)
+            && self
+                .messages
+                .get(field.type_name.as_deref().unwrap_or_default())
+                .and_then(|entry| entry.descriptor.options.as_ref())
+                .and_then(|options| options.map_entry)
+                .unwrap_or_default();
+        if map {
+            Cardinality::Map
+        } else {
+            Cardinality::Repeated
+        }
+    }
+
+    // missing keys and values of map entries are the default values
+    fn fill_map_entry(&self, entry: &MessageType, value: &mut Message) {
+        for (index, field) in entry.descriptor.field.iter().enumerate().take(2) {
+            let number = field.number.unwrap_or_default() as u32;
+            if value.fields.iter().any(|present| present.number == number) {
+                continue;
+            }
+
+            let default = match field.r#type.unwrap_or_default() {
+                Type::Double => Value::F64(0.0),
+                Type::Float => Value::F32(0.0),
+                Type::Int64 | Type::Sfixed64 | Type::Sint64 => Value::I64(0),
+                Type::Uint64 | Type::Fixed64 => Value::U64(0),
+                Type::Int32 | Type::Sfixed32 | Type::Sint32 => Value::I32(0),
+                Type::Uint32 | Type::Fixed32 => Value::U32(0),
+                Type::Bool => Value::Bool(false),
+                Type::String => Value::String(String::new()),
+                Type::Bytes => Value::Bytes(vec![]),
+                Type::Enum => Value::Enum(0, self.enum_value_name(field, 0)),
+                Type::Group | Type::Message => Value::Message(Message::default()),
+            };
+
+            let index = index.min(value.fields.len());
+            value.fields.insert(
+                index,
+                Field {
+                    name: field_name(field).to_string(),
+                    json_name: field_name(field).to_string(),
+                    number,
+                    cardinality: Cardinality::Single,
+                    value: default,
+                },
+            );
+        }
+    }
+
+    fn encode_message(
+        &self,
+        message: &MessageType,
+        fields: &[(String, OptionValue)],
+        json: bool,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Failure> {
+        // the values of a packed field are gathered in one record, where the
+        // field first appears
+        let mut records: Vec<(Option<u32>, Vec<u8>)> = vec![];
+        for (name, value) in fields {
+            let Some(field) = message.descriptor.field.iter().find(|field| {
+                field.name.as_deref() == Some(name)
+                    || (json && field.json_name.as_deref() == Some(name))
+            }) else {
+                return Err(Failure::new(format!(
+                    "`{}` has no field `{name}`",
+                    message.descriptor.name.as_deref().unwrap_or_default()
+                )));
+            };
+
+            let packed = self
+                .packed(message, field)
+                .then(|| field.number.unwrap_or_default() as u32);
+            let index = match records
+                .iter()
+                .position(|(number, _)| packed.is_some() && *number == packed)
+            {
+                Some(index) => index,
+                None => {
+                    records.push((packed, vec![]));
+                    records.len() - 1
+                }
+            };
+            self.encode_field(message, field, value, json, &mut records[index].1)
+                .map_err(|err| err.at(name))?;
+        }
+
+        for (packed, record) in records {
+            match packed {
+                Some(_) if record.is_empty() => {}
+                Some(number) => {
+                    write_tag(buf, number, 2);
+                    write_varint(buf, record.len() as u64);
+                    buf.extend(record);
+                }
+                None => buf.extend(record),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encode_field(
+        &self,
+        message: &MessageType,
+        field: &FieldDescriptorProto,
+        value: &OptionValue,
+        json: bool,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Failure> {
+        let typ = field.r#type.unwrap_or_default();
+        let number = field.number.unwrap_or_default() as u32;
+        let cardinality = self.cardinality(field);
+
+        // JSON objects of map fields, the keys are always strings
+        let entries;
+        let values: Vec<&OptionValue> = match value {
+            OptionValue::Aggregate(fields) if json && cardinality == Cardinality::Map => {
+                entries = fields
+                    .iter()
+                    .map(|(key, value)| {
+                        OptionValue::Aggregate(vec![
+                            (
+                                "key".to_string(),
+                                OptionValue::String(key.as_bytes().to_vec()),
+                            ),
+                            ("value".to_string(), value.clone()),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+                entries.iter().collect()
+            }
+            OptionValue::List(values) if cardinality != Cardinality::Single => {
+                values.iter().collect()
+            }
+            OptionValue::List(_) => return Err(Failure::new("the field is not repeated")),
+            value => vec![value],
+        };
+
+        // the tag and length of packed values are written by the caller
+        if self.packed(message, field) {
+            for value in values {
+                self.encode_scalar(field, typ, value, json, buf)?;
+            }
+            return Ok(());
+        }
+
+        for value in values {
+            write_tag(buf, number, expected_wire_type(typ));
+            match typ {
+                Type::Message => {
+                    let OptionValue::Aggregate(fields) = value else {
+                        return Err(Failure::expected("a message", value));
+                    };
+
+                    let name = field.type_name.as_deref().unwrap_or_default();
+                    let nested = self
+                        .message(name)
+                        .map_err(|err| Failure::new(err.to_string()))?;
+                    let mut encoded = vec![];
+                    self.encode_message(nested, fields, json, &mut encoded)?;
+                    write_varint(buf, encoded.len() as u64);
+                    buf.extend(encoded);
+                }
+                Type::Group => return Err(Failure::new("groups are not supported")),
+                _ => self.encode_scalar(field, typ, value, json, buf)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn packed(&self, message: &MessageType, field: &FieldDescriptorProto) -> bool {
+        self.cardinality(field) == Cardinality::Repeated
+            && packable(field.r#type.unwrap_or_default())
+            && field
+                .options
+                .as_ref()
+                .and_then(|options| options.packed)
+                .unwrap_or(message.packed)
+    }
+
+    fn encode_scalar(
+        &self,
+        field: &FieldDescriptorProto,
+        typ: Type,
+        value: &OptionValue,
+        json: bool,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Failure> {
+        match typ {
+            Type::Double => buf.extend(float(value, json)?.to_le_bytes()),
+            Type::Float => buf.extend((float(value, json)? as f32).to_le_bytes()),
+            Type::Int64 => write_varint(buf, integer(value, json, i64::MIN, i64::MAX)? as u64),
+            Type::Uint64 => write_varint(buf, integer(value, json, 0, u64::MAX)? as u64),
+            Type::Int32 => {
+                write_varint(buf, integer(value, json, i32::MIN, i32::MAX)? as i64 as u64)
+            }
+            Type::Fixed64 => buf.extend((integer(value, json, 0, u64::MAX)? as u64).to_le_bytes()),
+            Type::Fixed32 => buf.extend((integer(value, json, 0, u32::MAX)? as u32).to_le_bytes()),
+            Type::Bool => {
+                let value = match value {
+                    OptionValue::Ident(ident) if ident == "true" => true,
+                    OptionValue::Ident(ident) if ident == "false" => false,
+                    OptionValue::Integer(value @ (0 | 1)) if !json => *value == 1,
+                    _ => return Err(Failure::expected("a bool", value)),
+                };
+                buf.push(value as u8);
+            }
+            Type::String => {
+                let OptionValue::String(bytes) = value else {
+                    return Err(Failure::expected("a string", value));
+                };
+                if std::str::from_utf8(bytes).is_err() {
+                    return Err(Failure::new("string is not valid UTF-8"));
+                }
+                write_varint(buf, bytes.len() as u64);
+                buf.extend(bytes);
+            }
+            Type::Bytes => {
+                let OptionValue::String(bytes) = value else {
+                    return Err(Failure::expected("a string", value));
+                };
+                let decoded;
+                let bytes = if json {
+                    decoded = base64_decode(bytes).ok_or_else(|| Failure::new("invalid base64"))?;
+                    &decoded
+                } else {
+                    bytes
+                };
+                write_varint(buf, bytes.len() as u64);
+                buf.extend(bytes);
+            }
+            Type::Uint32 => write_varint(buf, integer(value, json, 0, u32::MAX)? as u64),
+            Type::Enum => {
+                let number = match value {
+                    OptionValue::Ident(name) if !json => self.enum_value_number(field, name)?,
+                    OptionValue::String(name) if json => {
+                        self.enum_value_number(field, &String::from_utf8_lossy(name))?
+                    }
+                    value => integer(value, json, i32::MIN, i32::MAX)? as i32,
+                };
+                write_varint(buf, number as i64 as u64);
+            }
+            Type::Sfixed32 => {
+                buf.extend((integer(value, json, i32::MIN, i32::MAX)? as i32).to_le_bytes())
+            }
+            Type::Sfixed64 => {
+                buf.extend((integer(value, json, i64::MIN, i64::MAX)? as i64).to_le_bytes())
+            }
+            Type::Sint32 => {
+                let value = integer(value, json, i32::MIN, i32::MAX)? as i32;
+                write_varint(buf, ((value << 1) ^ (value >> 31)) as u32 as u64);
+            }
+            Type::Sint64 => {
+                let value = integer(value, json, i64::MIN, i64::MAX)? as i64;
+                write_varint(buf, ((value << 1) ^ (value >> 63)) as u64);
+            }
+            Type::Group | Type::Message => unreachable!("not a scalar type"),
+        }
+
+        Ok(())
+    }
+
+    fn enum_value_number(&self, field: &FieldDescriptorProto, name: &str) -> Result<i32, Failure> {
+        let type_name = field.type_name.as_deref().unwrap_or_default();
+        self.enums
+            .get(type_name)
+            .and_then(|enumeration| {
+                enumeration
+                    .value
+                    .iter()
+                    .find(|value| value.name.as_deref() == Some(name))
+            })
+            .map(|value| value.number.unwrap_or_default())
+            .ok_or_else(|| Failure::new(format!("`{}` has no value `{name}`", &type_name[1..])))
+    }
+}
+
+/// Decode a payload without schema, like `protoc --decode_raw`
+///
+/// Fields are named by the numbers. Varints are decoded as unsigned integers,
+/// and length-delimited values as strings if they are printable, otherwise
+/// messages if they can be, otherwise strings or bytes.
+pub fn decode_raw(bytes: &[u8]) -> Result<Message, Error> {
+    raw_message(bytes).map_err(Failure::into_error)
+}
+
+fn raw_message(src: &[u8]) -> Result<Message, Failure> {
+    let mut reader = Reader::new(src);
+    let mut fields = vec![];
+
+    while reader.pos < src.len() {
+        let (number, wire_type) = read_tag(&mut reader)?;
+        let value = raw_value(&mut reader, wire_type).map_err(|err| err.at(number))?;
+        fields.push(Field::unknown(number, value));
+    }
+
+    Ok(Message { fields })
+}
+
+fn raw_value(reader: &mut Reader, wire_type: u8) -> Result<Value, Failure> {
+    let value = match wire_type {
+        0 => Value::U64(reader.read_varint()?),
+        1 => Value::U64(reader.read_fixed64()?),
+        2 => {
+            let src = read_slice(reader)?;
+            let text = std::str::from_utf8(src).ok();
+            match raw_message(src) {
+                // short words like `hi` are messages too
+                _ if text.is_some_and(|text| !text.chars().any(char::is_control)) => {
+                    Value::String(text.unwrap_or_default().to_string())
+                }
+                Ok(message) => Value::Message(message),
+                Err(_) => match text {
+                    Some(text) => Value::String(text.to_string()),
+                    None => Value::Bytes(src.to_vec()),
+                },
+            }
+        }
+        5 => Value::U32(reader.read_fixed32()?),
+        3 | 4 => return Err(Failure::new("groups are not supported")),
+        _ => return Err(DecodeError::WireType(wire_type).into()),
+    };
+
+    Ok(value)
+}
+
+fn read_tag(reader: &mut Reader) -> Result<(u32, u8), Failure> {
+    let tag = reader.read_varint()?;
+    let number = tag >> 3;
+    if number == 0 || number > 536_870_911 {
+        return Err(Failure::new(format!("invalid field number {number}")));
+    }
+
+    Ok((number as u32, (tag & 0x7) as u8))
+}
+
+fn read_slice<'a>(reader: &mut Reader<'a>) -> Result<&'a [u8], Failure> {
+    let len = reader.read_varint()? as usize;
+    if reader.src.len() - reader.pos < len {
+        return Err(DecodeError::Eof.into());
+    }
+
+    let src = &reader.src[reader.pos..reader.pos + len];
+    reader.pos += len;
+    Ok(src)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_tag(buf: &mut Vec<u8>, number: u32, wire_type: u8) {
+    write_varint(buf, ((number as u64) << 3) | wire_type as u64);
+}
+
+fn expected_wire_type(typ: Type) -> u8 {
+    match typ {
+        Type::Double | Type::Fixed64 | Type::Sfixed64 => 1,
+        Type::Float | Type::Fixed32 | Type::Sfixed32 => 5,
+        Type::String | Type::Bytes | Type::Message => 2,
+        Type::Group => 3,
+        _ => 0,
+    }
+}
+
+fn packable(typ: Type) -> bool {
+    !matches!(
+        typ,
+        Type::String | Type::Bytes | Type::Message | Type::Group
+    )
+}
+
+fn field_name(field: &FieldDescriptorProto) -> &str {
+    field.name.as_deref().unwrap_or_default()
+}
+
+fn type_str(field: &FieldDescriptorProto, typ: Type) -> String {
+    match typ {
+        Type::Message | Type::Enum | Type::Group => {
+            field.type_name.as_deref().unwrap_or_default()[1..].to_string()
+        }
+        _ => format!("{typ:?}").to_lowercase(),
+    }
+}
+
+// integers of text format, JSON has them quoted sometimes
+fn integer(
+    value: &OptionValue,
+    json: bool,
+    min: impl Into<i128>,
+    max: impl Into<i128>,
+) -> Result<i128, Failure> {
+    let parsed = match value {
+        OptionValue::Integer(value) => Some(*value as i128),
+        OptionValue::Float(value) if json && value.fract() == 0.0 => Some(*value as i128),
+        OptionValue::String(s) if json => std::str::from_utf8(s).ok().and_then(|s| s.parse().ok()),
+        _ => None,
+    };
+
+    match parsed {
+        Some(parsed) if parsed >= min.into() && parsed <= max.into() => Ok(parsed),
+        Some(_) => Err(Failure::new(format!("{value} is out of range"))),
+        None => Err(Failure::expected("an integer", value)),
+    }
+}
+
+fn float(value: &OptionValue, json: bool) -> Result<f64, Failure> {
+    let parsed = match value {
+        OptionValue::Integer(value) => Some(*value as f64),
+        OptionValue::Float(value) => Some(*value),
+        OptionValue::Ident(ident) if !json => match ident.to_lowercase().as_str() {
+            "inf" | "infinity" => Some(f64::INFINITY),
+            "nan" => Some(f64::NAN),
+            _ => None,
+        },
+        OptionValue::String(s) if json => match s.as_slice() {
+            b"NaN" => Some(f64::NAN),
+            b"Infinity" => Some(f64::INFINITY),
+            b"-Infinity" => Some(f64::NEG_INFINITY),
+            s => std::str::from_utf8(s).ok().and_then(|s| s.parse().ok()),
+        },
+        _ => None,
+    };
+
+    parsed.ok_or_else(|| Failure::expected("a number", value))
+}
+
+// a problem and the path of fields to it, which is built from the innermost
+struct Failure {
+    path: Vec<String>,
+    reason: String,
+}
+
+impl Failure {
+    fn new(reason: impl Into<String>) -> Self {
+        Failure {
+            path: vec![],
+            reason: reason.into(),
+        }
+    }
+
+    fn expected(what: &str, value: &OptionValue) -> Self {
+        Failure::new(format!("expected {what}, found {value}"))
+    }
+
+    fn at(mut self, field: impl Display) -> Self {
+        self.path.push(field.to_string());
+        self
+    }
+
+    fn into_error(mut self) -> Error {
+        if self.path.is_empty() {
+            return Error::InvalidPayload(self.reason);
+        }
+
+        self.path.reverse();
+        Error::InvalidPayload(format!("`{}`: {}", self.path.join("."), self.reason))
+    }
+}
+
+impl From<DecodeError> for Failure {
+    fn from(err: DecodeError) -> Self {
+        Failure::new(err.to_string())
+    }
+}
+
+/// A decoded message, fields are in the order of the payload
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// The number for unknown fields
+    pub name: String,
+    pub json_name: String,
+    pub number: u32,
+    pub cardinality: Cardinality,
+    pub value: Value,
+}
+
+impl Field {
+    fn unknown(number: u32, value: Value) -> Self {
+        Field {
+            name: number.to_string(),
+            json_name: number.to_string(),
+            number,
+            cardinality: Cardinality::Single,
+            value,
+        }
+    }
+}
+
+/// Every element of repeated fields and entry of maps is a field of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    Single,
+    Repeated,
+    /// Values are the entry messages with a `key` and a `value` field
+    Map,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    /// The number and the name if it's a known value
+    Enum(i32, Option<String>),
+    Message(Message),
+}
+
+/// Text format, one field per line
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_text(f, self, 0)
+    }
+}
+
+fn write_text(f: &mut Formatter<'_>, message: &Message, depth: usize) -> std::fmt::Result {
+    let indent = "  ".repeat(depth);
+    for field in &message.fields {
+        match &field.value {
+            Value::Message(nested) => {
+                writeln!(f, "{indent}{} {{", field.name)?;
+                write_text(f, nested, depth + 1)?;
+                writeln!(f, "{indent}}}")?;
+            }
+            value => writeln!(f, "{indent}{}: {value}", field.name)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Scalar values in text format
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(value) => value.fmt(f),
+            Value::I32(value) => value.fmt(f),
+            Value::I64(value) => value.fmt(f),
+            Value::U32(value) => value.fmt(f),
+            Value::U64(value) => value.fmt(f),
+            Value::F32(value) => write_float(f, *value as f64, &format!("{value:?}")),
+            Value::F64(value) => write_float(f, *value, &format!("{value:?}")),
+            Value::String(s) => write_text_string(f, s.as_bytes(), true),
+            Value::Bytes(bytes) => write_text_string(f, bytes, false),
+            Value::Enum(_, Some(name)) => f.write_str(name),
+            Value::Enum(number, None) => number.fmt(f),
+            Value::Message(message) => write!(
+                f,
+                "{{ {} }}",
+                message.to_string().trim_end().replace('\n', " ")
+            ),
+        }
+    }
+}
+
+// `Debug` switches to exponents for large numbers, which are still floats
+// when parsed again
+fn write_float(f: &mut Formatter<'_>, value: f64, text: &str) -> std::fmt::Result {
+    if value.is_nan() {
+        f.write_str("nan")
+    } else if value.is_infinite() {
+        f.write_str(if value > 0.0 { "inf" } else { "-inf" })
+    } else {
+        f.write_str(text)
+    }
+}
+
+// non-ascii characters of strings are kept as is, but escaped in bytes
+fn write_text_string(f: &mut Formatter<'_>, bytes: &[u8], utf8: bool) -> std::fmt::Result {
+    if !utf8 {
+        return OptionValue::String(bytes.to_vec()).fmt(f);
+    }
+
+    f.write_char('"')?;
+    for ch in String::from_utf8_lossy(bytes).chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            ch if ch.is_control() => write!(f, "\\{:03o}", ch as u32)?,
+            ch => f.write_char(ch)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl Message {
+    /// The JSON mapping of proto3, pretty printed
+    ///
+    /// Repeated fields are arrays and maps are objects. 64-bit integers are
+    /// strings, so are bytes in base64. Unknown fields are keyed by the
+    /// numbers, they are arrays if there are more than one of them.
+    pub fn to_json(&self) -> String {
+        let mut buf = String::new();
+        write_json_message(&mut buf, self, 0);
+        buf
+    }
+}
+
+fn write_json_message(buf: &mut String, message: &Message, depth: usize) {
+    // occurrences of a field are merged, in the order they appear first
+    let mut numbers: Vec<u32> = vec![];
+    for field in &message.fields {
+        if !numbers.contains(&field.number) {
+            numbers.push(field.number);
+        }
+    }
+
+    if numbers.is_empty() {
+        buf.push_str("{}");
+        return;
+    }
+
+    let indent = "  ".repeat(depth + 1);
+    buf.push_str("{\n");
+    for (index, number) in numbers.into_iter().enumerate() {
+        if index != 0 {
+            buf.push_str(",\n");
+        }
+
+        let fields: Vec<&Field> = message
+            .fields
+            .iter()
+            .filter(|field| field.number == number)
+            .collect();
+        let first = fields[0];
+
+        buf.push_str(&indent);
+        push_json_string(buf, &first.json_name);
+        buf.push_str(": ");
+
+        match first.cardinality {
+            Cardinality::Map => {
+                buf.push_str("{\n");
+                for (index, field) in fields.iter().enumerate() {
+                    if index != 0 {
+                        buf.push_str(",\n");
+                    }
+
+                    let Value::Message(entry) = &field.value else {
+                        continue;
+                    };
+                    let key = entry.fields.iter().find(|field| field.number == 1);
+                    let value = entry.fields.iter().find(|field| field.number == 2);
+
+                    buf.push_str(&indent);
+                    buf.push_str("  ");
+                    match key.map(|key| &key.value) {
+                        Some(Value::String(key)) => push_json_string(buf, key),
+                        Some(key) => push_json_string(buf, &key.to_string()),
+                        None => buf.push_str("\"\""),
+                    }
+                    buf.push_str(": ");
+                    match value {
+                        Some(value) => write_json_value(buf, &value.value, depth + 2),
+                        None => buf.push_str("null"),
+                    }
+                }
+                buf.push('\n');
+                buf.push_str(&indent);
+                buf.push('}');
+            }
+            Cardinality::Single if fields.len() == 1 || first.name != first.number.to_string() => {
+                // the last one wins for known fields
+                write_json_value(buf, &fields[fields.len() - 1].value, depth + 1);
+            }
+            _ => {
+                buf.push('[');
+                for (index, field) in fields.iter().enumerate() {
+                    if index != 0 {
+                        buf.push_str(", ");
+                    }
+                    write_json_value(buf, &field.value, depth + 1);
+                }
+                buf.push(']');
+            }
+        }
+    }
+    buf.push('\n');
+    buf.push_str(&"  ".repeat(depth));
+    buf.push('}');
+}
+
+fn write_json_value(buf: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Bool(value) => buf.push_str(if *value { "true" } else { "false" }),
+        Value::I32(value) => buf.push_str(&value.to_string()),
+        Value::U32(value) => buf.push_str(&value.to_string()),
+        Value::I64(value) => push_json_string(buf, &value.to_string()),
+        Value::U64(value) => push_json_string(buf, &value.to_string()),
+        Value::F32(value) => write_json_float(buf, *value as f64, &format!("{value:?}")),
+        Value::F64(value) => write_json_float(buf, *value, &format!("{value:?}")),
+        Value::String(s) => push_json_string(buf, s),
+        Value::Bytes(bytes) => push_json_string(buf, &base64_encode(bytes)),
+        Value::Enum(_, Some(name)) => push_json_string(buf, name),
+        Value::Enum(number, None) => buf.push_str(&number.to_string()),
+        Value::Message(message) => write_json_message(buf, message, depth),
+    }
+}
+
+fn write_json_float(buf: &mut String, value: f64, text: &str) {
+    if value.is_nan() {
+        buf.push_str("\"NaN\"");
+    } else if value.is_infinite() {
+        buf.push_str(if value > 0.0 {
+            "\"Infinity\""
+        } else {
+            "\"-Infinity\""
+        });
+    } else {
+        buf.push_str(text);
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut buf = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (index, b)| n | (*b as u32) << (16 - index * 8));
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                buf.push(BASE64[(n >> (18 - index * 6)) as usize & 0x3f] as char);
+            } else {
+                buf.push('=');
+            }
+        }
+    }
+    buf
+}
+
+// both the standard and the URL-safe alphabets are accepted, with or without
+// the padding
+fn base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let input = input
+        .strip_suffix(b"==")
+        .or_else(|| input.strip_suffix(b"="))
+        .unwrap_or(input);
+
+    let mut buf = Vec::with_capacity(input.len() * 3 / 4);
+    let mut n = 0u32;
+    let mut bits = 0;
+    for ch in input {
+        let value = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+
+        n = n << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            buf.push((n >> bits) as u8);
+        }
+    }
+
+    // a single character left can't make a byte
+    if bits >= 6 {
+        return None;
+    }
+
+    Some(buf)
+}
+
+// JSON input of `encode_json`, converted to the text format tree. `null`
+// members are dropped, they mean default values
+struct JsonParser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn parse(src: &'a [u8]) -> Result<OptionValue, Error> {
+        let mut parser = JsonParser { src, pos: 0 };
+        let value = parser.value().and_then(|value| {
+            parser.skip_whitespace();
+            match parser.src.get(parser.pos) {
+                None => Ok(value),
+                Some(_) => Err("trailing characters".to_string()),
+            }
+        });
+
+        match value {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(Error::InvalidPayload("expected a JSON object".to_string())),
+            Err(reason) => {
+                let consumed = &parser.src[..parser.pos.min(parser.src.len())];
+                let line = consumed.iter().filter(|ch| **ch == b'\n').count() + 1;
+                let column = consumed.iter().rev().take_while(|ch| **ch != b'\n').count() + 1;
+                Err(Error::InvalidPayload(format!(
+                    "{reason} at line {line} column {column}"
+                )))
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.src.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, ch: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.src.get(self.pos) == Some(&ch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}`", ch as char))
+        }
+    }
+
+    // `None` is `null`
+    fn value(&mut self) -> Result<Option<OptionValue>, String> {
+        self.skip_whitespace();
+        let value = match self.src.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = vec![];
+                self.skip_whitespace();
+                if self.src.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key = String::from_utf8(self.string()?)
+                            .map_err(|_| "keys must be valid UTF-8".to_string())?;
+                        self.expect(b':')?;
+                        if let Some(value) = self.value()? {
+                            fields.push((key, value));
+                        }
+
+                        self.skip_whitespace();
+                        match self.src.get(self.pos) {
+                            Some(b',') => self.pos += 1,
+                            Some(b'}') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err("expected `,` or `}`".to_string()),
+                        }
+                    }
+                }
+                OptionValue::Aggregate(fields)
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.src.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        match self.value()? {
+                            Some(value) => values.push(value),
+                            None => return Err("`null` can't be an element".to_string()),
+                        }
+
+                        self.skip_whitespace();
+                        match self.src.get(self.pos) {
+                            Some(b',') => self.pos += 1,
+                            Some(b']') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err("expected `,` or `]`".to_string()),
+                        }
+                    }
+                }
+                OptionValue::List(values)
+            }
+            Some(b'"') => OptionValue::String(self.string()?),
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(_) => {
+                for (keyword, value) in [("true", true), ("false", false)] {
+                    if self.src[self.pos..].starts_with(keyword.as_bytes()) {
+                        self.pos += keyword.len();
+                        return Ok(Some(OptionValue::Ident(value.to_string())));
+                    }
+                }
+                if self.src[self.pos..].starts_with(b"null") {
+                    self.pos += 4;
+                    return Ok(None);
+                }
+                return Err("expected a value".to_string());
+            }
+            None => return Err("unexpected end of input".to_string()),
+        };
+
+        Ok(Some(value))
+    }
+
+    fn number(&mut self) -> Result<OptionValue, String> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.src.get(self.pos) {
+            self.pos += 1;
+        }
+
+        // it's all ascii
+        let text = std::str::from_utf8(&self.src[start..self.pos]).unwrap_or_default();
+        if let Ok(value) = text.parse::<i64>() {
+            Ok(OptionValue::Integer(value))
+        } else if let Ok(value) = text.parse::<f64>() {
+            Ok(OptionValue::Float(value))
+        } else {
+            self.pos = start;
+            Err("invalid number".to_string())
+        }
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, String> {
+        if self.src.get(self.pos) != Some(&b'"') {
+            return Err("expected a string".to_string());
+        }
+        self.pos += 1;
+
+        let mut buf = vec![];
+        loop {
+            let Some(&ch) = self.src.get(self.pos) else {
+                return Err("unterminated string".to_string());
+            };
+            self.pos += 1;
+
+            match ch {
+                b'"' => return Ok(buf),
+                b'\\' => {
+                    let Some(&escaped) = self.src.get(self.pos) else {
+                        return Err("unterminated string".to_string());
+                    };
+                    self.pos += 1;
+
+                    let ch = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let high = self.hex4()?;
+                            let code = if (0xd800..0xdc00).contains(&high) {
+                                // a surrogate pair
+                                if !self.src[self.pos..].starts_with(b"\\u") {
+                                    return Err("unpaired surrogate".to_string());
+                                }
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                0x10000
+                                    + ((high - 0xd800) << 10)
+                                    + (low.wrapping_sub(0xdc00) & 0x3ff)
+                            } else {
+                                high
+                            };
+                            char::from_u32(code)
+                                .ok_or_else(|| "invalid unicode escape".to_string())?
+                        }
+                        _ => return Err("invalid escape".to_string()),
+                    };
+                    let mut utf8 = [0; 4];
+                    buf.extend(ch.encode_utf8(&mut utf8).as_bytes());
+                }
+                ch => buf.push(ch),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let hex = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| "invalid unicode escape".to_string())?;
+        self.pos += 4;
+        Ok(hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(proto: &str) -> Schema {
+        let fd = crate::parse(proto.as_bytes()).unwrap();
+        let mut set = FileDescriptorSet::default();
+        descriptor::add_file(&mut set, "test.proto", &fd, &HashMap::new()).unwrap();
+        Schema::from_set(set)
+    }
+
+    const PROTO: &str = r#"
+        syntax = "proto3";
+        package test;
+
+        message Sample {
+            string name = 1;
+            repeated int64 values = 2;
+            Kind kind = 3;
+            map<string, Sample> children = 4;
+            bytes data = 5;
+            double ratio = 6;
+            sint32 delta = 7;
+        }
+
+        enum Kind {
+            KIND_UNSPECIFIED = 0;
+            KIND_GAUGE = 1;
+        }
+    "#;
+
+    #[test]
+    fn text() {
+        let schema = schema(PROTO);
+        let input = br#"
+            name: "cpu"
+            values: [1, -2]
+            kind: KIND_GAUGE
+            children { key: "a" value { name: "nested" } }
+            data: "\001\002"
+            ratio: 0.5
+            delta: -3
+        "#;
+
+        let encoded = schema.encode_text("test.Sample", input).unwrap();
+        let decoded = schema.decode("test.Sample", &encoded).unwrap();
+        assert_eq!(
+            decoded.to_string(),
+            r#"name: "cpu"
+values: 1
+values: -2
+kind: KIND_GAUGE
+children {
+  key: "a"
+  value {
+    name: "nested"
+  }
+}
+data: "\001\002"
+ratio: 0.5
+delta: -3
+"#
+        );
+
+        // printed text encodes the same message again, the elements of
+        // `values` are packed in one record
+        let again = schema
+            .encode_text(".test.Sample", decoded.to_string().as_bytes())
+            .unwrap();
+        assert_eq!(again, encoded);
+
+        let encoded = schema
+            .encode_text("test.Sample", b"values: 1 name: \"a\" values: [2, 3]")
+            .unwrap();
+        assert_eq!(encoded, [0x12, 0x03, 0x01, 0x02, 0x03, 0x0a, 0x01, b'a']);
+    }
+
+    #[test]
+    fn json() {
+        let schema = schema(PROTO);
+        let input = br#"{
+            "name": "cpu",
+            "values": ["1", 2],
+            "kind": "KIND_GAUGE",
+            "children": {"a": {}, "b": {"ratio": "NaN"}},
+            "data": "AQI=",
+            "delta": null
+        }"#;
+
+        let encoded = schema.encode_json("test.Sample", input).unwrap();
+        let decoded = schema.decode("test.Sample", &encoded).unwrap();
+        assert_eq!(
+            decoded.to_json(),
+            r#"{
+  "name": "cpu",
+  "values": ["1", "2"],
+  "kind": "KIND_GAUGE",
+  "children": {
+    "a": {},
+    "b": {
+      "ratio": "NaN"
+    }
+  },
+  "data": "AQI="
+}"#
+        );
+
+        assert_eq!(
+            schema
+                .encode_json("test.Sample", decoded.to_json().as_bytes())
+                .unwrap(),
+            encoded
+        );
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let schema = schema(PROTO);
+        // `name: "a"`, then field 9 as a varint
+        let decoded = schema.decode("test.Sample", b"\x0a\x01a\x48\x05").unwrap();
+        assert_eq!(decoded.to_string(), "name: \"a\"\n9: 5\n");
+
+        let err = schema.decode("test.Sample", b"\x0a\x05a").unwrap_err();
+        assert_eq!(err.to_string(), "invalid payload: `name`: unexpected EOF");
+
+        let err = schema.encode_text("test.Sample", b"size: 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid payload: `Sample` has no field `size`"
+        );
+
+        let err = schema.encode_text("test.Sample", b"children { value { kind: 1.5 } }");
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "invalid payload: `children.value.kind`: expected an integer, found 1.5"
+        );
+
+        assert!(matches!(
+            schema.decode("test.Missing", b""),
+            Err(Error::UnresolvedType { .. })
+        ));
+    }
+
+    #[test]
+    fn raw() {
+        // `1: 150`, `2 { 1: "hi" }`, `3: "hello"`, `4: fixed32 7`
+        let decoded =
+            decode_raw(b"\x08\x96\x01\x12\x04\x0a\x02hi\x1a\x05hello\x25\x07\x00\x00\x00").unwrap();
+        assert_eq!(
+            decoded.to_string(),
+            "1: 150\n2 {\n  1: \"hi\"\n}\n3: \"hello\"\n4: 7\n"
+        );
+
+        assert!(decode_raw(b"\x08").is_err());
+    }
+
+    #[test]
+    fn base64() {
+        for input in [&b""[..], b"f", b"fo", b"foo", b"foob", b"\xff\xfe\xfd"] {
+            let encoded = base64_encode(input);
+            assert_eq!(base64_decode(encoded.as_bytes()).unwrap(), input);
+        }
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_decode(b"_-8").unwrap(), b"\xff\xef");
+        assert_eq!(base64_decode(b"Zm9v!"), None);
+    }
+}
@@ -0,0 +1,1007 @@
+//! Canonical formatting of proto files. The file is parsed and printed again,
+//! so the layout doesn't depend on how it was written:
+//!
+//! - `syntax`, `package`, imports sorted by path and file options come first,
+//!   definitions follow in the order they are declared
+//! - options, then `reserved` and `extensions` come first in blocks, members
+//!   follow in the order they are declared
+//! - two spaces of indentation, blank lines around nested blocks, and blank
+//!   lines between members are kept, but not more than one
+//! - `=` of consecutive fields, enum values and options are aligned
+//! - comments are written with `//`
+//!
+//! Comments are attached to definitions, the ones the AST can't hold, e.g.
+//! comments of `reserved` or inside option values, make formatting fail
+//! instead of being dropped silently.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::Error;
+use crate::ast::{
+    Comments, Enum, Extend, Extension, Field, FieldType, FileDescriptor, Function, ImportKind,
+    Label, Location, Message, Method, OneOf, OptionValue, Options, ProtoOption, Reserved, Service,
+    Syntax,
+};
+use crate::parse;
+
+const INDENT: &str = "  ";
+
+// lines with field options longer than it are split, one option per line
+const MAX_WIDTH: usize = 100;
+
+// `max` in field number ranges
+const MAX_FIELD_NUMBER: i64 = (1 << 29) - 1;
+
+/// Format a proto file in the canonical style
+pub fn format(input: &[u8]) -> Result<String, Error> {
+    let fd = parse::parse(input).map_err(Error::Parse)?;
+    let output = Printer::new(fd.syntax).file(&fd);
+    verify(input, &output)?;
+
+    Ok(output)
+}
+
+/// Format the proto file at `path`, errors point to the file
+pub fn format_file<P: AsRef<Path>>(path: P) -> Result<String, Error> {
+    let path = path.as_ref();
+    let content = std::fs::read(path)?;
+
+    format(&content).map_err(|err| match err {
+        Error::Parse(errors) => Error::Parse(
+            errors
+                .into_iter()
+                .map(|mut err| {
+                    err.set_file(path);
+                    err
+                })
+                .collect(),
+        ),
+        Error::Format(reason) => Error::Format(format!("{}: {reason}", path.display())),
+        err => err,
+    })
+}
+
+// every word of source comments must be in the output, and the output must
+// be valid, formatting never loses anything silently
+fn verify(input: &[u8], output: &str) -> Result<(), Error> {
+    if let Err(errors) = parse::parse(output.as_bytes()) {
+        let reason = errors
+            .iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(Error::Format(format!(
+            "the formatted file is invalid\n{reason}"
+        )));
+    }
+
+    let mut words = HashMap::<&str, usize>::new();
+    let comments = parse::comments(output.as_bytes());
+    for (_, text) in &comments {
+        for word in text.split_whitespace() {
+            *words.entry(word).or_default() += 1;
+        }
+    }
+
+    for (line, text) in parse::comments(input) {
+        for word in text.split_whitespace() {
+            match words.get_mut(word) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => {
+                    return Err(Error::Format(format!(
+                        "the comment at line {line} can't be kept, only comments around \
+                         definitions, imports, `syntax` and `package` are supported"
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+enum Body {
+    /// `head = tail`, heads of consecutive ones are padded to the same width
+    Assign { head: String, tail: String },
+    /// Lines written as they are
+    Lines(Vec<String>),
+}
+
+// a statement in a block, with the comments before and after it
+struct Entry<'a> {
+    detached: &'a [String],
+    leading: Option<&'a str>,
+    trailing: Option<&'a str>,
+    // there is a blank line before it
+    blank: bool,
+    // blocks are separated by blank lines
+    block: bool,
+    body: Body,
+}
+
+impl<'a> Entry<'a> {
+    fn new(comments: &'a Comments, body: Body) -> Self {
+        Self {
+            detached: &comments.detached,
+            leading: comments.leading.as_deref(),
+            trailing: comments.trailing.as_deref(),
+            blank: false,
+            block: false,
+            body,
+        }
+    }
+
+    fn line(line: String) -> Self {
+        Self {
+            detached: &[],
+            leading: None,
+            trailing: None,
+            blank: false,
+            block: false,
+            body: Body::Lines(vec![line]),
+        }
+    }
+
+    // the trailing comment is written after the left brace already
+    fn block(comments: &'a Comments, lines: Vec<String>) -> Self {
+        Self {
+            trailing: None,
+            block: true,
+            ..Self::new(comments, Body::Lines(lines))
+        }
+    }
+
+    #[inline]
+    fn blank(mut self, blank: bool) -> Self {
+        self.blank = blank;
+        self
+    }
+}
+
+// statements of a block, grouped in sections separated by blank lines
+#[derive(Default)]
+struct Entries<'a> {
+    list: Vec<Entry<'a>>,
+    section: bool,
+}
+
+impl<'a> Entries<'a> {
+    // the next statement starts a new section
+    #[inline]
+    fn section(&mut self) {
+        self.section = !self.list.is_empty();
+    }
+
+    fn push(&mut self, mut entry: Entry<'a>) {
+        entry.blank |= std::mem::take(&mut self.section);
+        self.list.push(entry);
+    }
+
+    fn render(&self, closing: &[String]) -> Vec<String> {
+        let entries = &self.list;
+        let spaced = |index: usize| {
+            let entry = &entries[index];
+            index > 0
+                && (entry.blank
+                    || entry.block
+                    || entries[index - 1].block
+                    || !entry.detached.is_empty())
+        };
+
+        // consecutive assignments are aligned, runs are keyed by the first
+        // entry of them
+        let mut runs = vec![0; entries.len()];
+        let mut widths = vec![0; entries.len()];
+        for (index, entry) in entries.iter().enumerate() {
+            let assign = matches!(entry.body, Body::Assign { .. });
+            runs[index] = if index > 0
+                && assign
+                && !spaced(index)
+                && matches!(entries[index - 1].body, Body::Assign { .. })
+            {
+                runs[index - 1]
+            } else {
+                index
+            };
+
+            if let Body::Assign { head, .. } = &entry.body {
+                let width = &mut widths[runs[index]];
+                *width = (*width).max(head.chars().count());
+            }
+        }
+
+        let mut lines = vec![];
+        for (index, entry) in entries.iter().enumerate() {
+            if spaced(index) {
+                lines.push(String::new());
+            }
+
+            for detached in entry.detached {
+                lines.extend(comment(detached));
+                lines.push(String::new());
+            }
+            if let Some(leading) = entry.leading {
+                lines.extend(comment(leading));
+            }
+
+            match &entry.body {
+                Body::Assign { head, tail } => {
+                    let width = widths[runs[index]];
+                    lines.push(format!("{head:<width$} {tail}"));
+                }
+                Body::Lines(body) => lines.extend(body.iter().cloned()),
+            }
+
+            if let Some(trailing) = entry.trailing
+                && let Some(last) = lines.last_mut()
+            {
+                last.push_str(&trailing_comment(trailing));
+            }
+        }
+
+        for (index, block) in closing.iter().enumerate() {
+            if index > 0 || !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(comment(block));
+        }
+
+        lines
+    }
+}
+
+fn comment(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines().map(|line| {
+        if line.is_empty() {
+            "//".to_string()
+        } else {
+            format!("// {line}")
+        }
+    })
+}
+
+// trailing comments are kept in one line
+fn trailing_comment(text: &str) -> String {
+    let text = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(" // {text}")
+}
+
+fn indent(lines: Vec<String>) -> impl Iterator<Item = String> {
+    lines.into_iter().map(|line| {
+        if line.is_empty() {
+            line
+        } else {
+            format!("{INDENT}{line}")
+        }
+    })
+}
+
+// lines a leading comment takes in the source, `/* */` comments are counted
+// as they are written with `//`
+fn comment_lines(comments: &Comments) -> u32 {
+    comments
+        .leading
+        .as_deref()
+        .map_or(0, |leading| leading.lines().count() as u32)
+}
+
+// whether there is a blank line between the previous definition ending at
+// `end` and the one at `location`
+fn gap(end: Option<u32>, location: &Location, comments: &Comments) -> bool {
+    end.is_some_and(|end| location.start_line.saturating_sub(comment_lines(comments)) > end + 1)
+}
+
+fn float(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        // keeps the decimal point, `1.0` doesn't become `1`
+        format!("{value:?}")
+    }
+}
+
+// option values in one line
+fn value(value: &OptionValue) -> String {
+    match value {
+        OptionValue::Float(value) => float(*value),
+        OptionValue::Aggregate(fields) if fields.is_empty() => "{}".to_string(),
+        OptionValue::Aggregate(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, field)| match field {
+                    OptionValue::Aggregate(_) => format!("{name} {}", self::value(field)),
+                    _ => format!("{name}: {}", self::value(field)),
+                })
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join(" "))
+        }
+        OptionValue::List(values) => {
+            let values = values.iter().map(self::value).collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+// fields of aggregate values, one per line
+fn aggregate(fields: &[(String, OptionValue)]) -> Vec<String> {
+    let mut lines = vec![];
+
+    for (name, field) in fields {
+        match field {
+            OptionValue::Aggregate(nested) if !nested.is_empty() => {
+                lines.push(format!("{name} {{"));
+                lines.extend(indent(aggregate(nested)));
+                lines.push("}".to_string());
+            }
+            OptionValue::Aggregate(_) => lines.push(format!("{name} {{}}")),
+            _ => lines.push(format!("{name}: {}", value(field))),
+        }
+    }
+
+    lines
+}
+
+// `option name = value;`, aggregate values take more lines
+fn option(option: &ProtoOption) -> Body {
+    match &option.value {
+        OptionValue::Aggregate(fields) if !fields.is_empty() => {
+            let mut lines = vec![format!("option {} = {{", option.name)];
+            lines.extend(indent(aggregate(fields)));
+            lines.push("};".to_string());
+            Body::Lines(lines)
+        }
+        _ => Body::Assign {
+            head: format!("option {}", option.name),
+            tail: format!("= {};", value(&option.value)),
+        },
+    }
+}
+
+fn options<'a>(entries: &mut Entries<'a>, options: &Options) {
+    entries.section();
+    for item in options.iter() {
+        entries.push(Entry {
+            detached: &[],
+            leading: None,
+            trailing: None,
+            blank: false,
+            block: false,
+            body: option(item),
+        });
+    }
+}
+
+// `[a = 1, b = 2]` after fields, enum values and extension ranges
+fn inline_options(options: &Options) -> String {
+    if options.is_empty() {
+        return String::new();
+    }
+
+    let options = options
+        .iter()
+        .map(|option| format!("{} = {}", option.name, value(&option.value)))
+        .collect::<Vec<_>>();
+    format!(" [{}]", options.join(", "))
+}
+
+fn range(start: i64, end: i64, max: i64) -> String {
+    match end {
+        _ if start == end => start.to_string(),
+        _ if end == max => format!("{start} to max"),
+        _ => format!("{start} to {end}"),
+    }
+}
+
+// numbers and names of all `reserved` statements are merged, in two
+// statements at most
+fn reserved<'a>(
+    entries: &mut Entries<'a>,
+    reserved: &[Reserved],
+    comments: &'a Comments,
+    max: i64,
+) {
+    let numbers = reserved
+        .iter()
+        .filter_map(|reserved| match reserved {
+            Reserved::Single(number) => Some(number.to_string()),
+            Reserved::Range(start, end) => Some(range(*start, *end, max)),
+            Reserved::Field(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let names = reserved
+        .iter()
+        .filter_map(|reserved| match reserved {
+            Reserved::Field(name) => Some(format!("{:?}", name)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut comments = Some(comments);
+    for list in [numbers, names] {
+        if !list.is_empty() {
+            let line = format!("reserved {};", list.join(", "));
+            entries.push(match comments.take() {
+                Some(comments) => Entry::new(comments, Body::Lines(vec![line])),
+                None => Entry::line(line),
+            });
+        }
+    }
+}
+
+// ranges with the same properties next to each other are written in one
+// statement, like they are declared, comments are kept by the first range
+fn extensions<'a>(entries: &mut Entries<'a>, extensions: &'a [Extension]) {
+    let mut index = 0;
+    while index < extensions.len() {
+        let properties = &extensions[index].properties;
+        let count = 1 + extensions[index + 1..]
+            .iter()
+            .take_while(|extension| {
+                extension.properties == *properties && extension.comments.is_empty()
+            })
+            .count();
+
+        let ranges = extensions[index..index + count]
+            .iter()
+            .map(|extension| {
+                range(
+                    extension.start as i64,
+                    extension.end as i64,
+                    MAX_FIELD_NUMBER,
+                )
+            })
+            .collect::<Vec<_>>();
+        let line = format!(
+            "extensions {}{};",
+            ranges.join(", "),
+            inline_options(properties)
+        );
+        entries.push(Entry::new(
+            &extensions[index].comments,
+            Body::Lines(vec![line]),
+        ));
+
+        index += count;
+    }
+}
+
+// definitions in a message or a file, in the order they are declared
+enum Member<'a> {
+    Field(&'a Field),
+    OneOf(&'a OneOf),
+    Message(&'a Message),
+    Enum(&'a Enum),
+    Service(&'a Service),
+    Extend(&'a Extend),
+}
+
+impl Member<'_> {
+    fn location(&self) -> &Location {
+        match self {
+            Member::Field(field) => &field.location,
+            Member::OneOf(oneof) => &oneof.location,
+            Member::Message(msg) => &msg.location,
+            Member::Enum(en) => &en.location,
+            Member::Service(svc) => &svc.location,
+            Member::Extend(extend) => &extend.location,
+        }
+    }
+
+    fn comments(&self) -> &Comments {
+        match self {
+            Member::Field(field) => &field.comments,
+            Member::OneOf(oneof) => &oneof.comments,
+            Member::Message(msg) => &msg.comments,
+            Member::Enum(en) => &en.comments,
+            Member::Service(svc) => &svc.comments,
+            Member::Extend(extend) => &extend.comments,
+        }
+    }
+}
+
+fn sorted(mut members: Vec<Member<'_>>) -> Vec<Member<'_>> {
+    members.sort_by_key(|member| {
+        let location = member.location();
+        (location.start_line, location.start_column)
+    });
+    members
+}
+
+struct Printer {
+    syntax: Syntax,
+    // nesting level of the block printed now
+    depth: usize,
+}
+
+impl Printer {
+    fn new(syntax: Syntax) -> Self {
+        Self { syntax, depth: 0 }
+    }
+
+    fn file(&mut self, fd: &FileDescriptor) -> String {
+        let mut entries = Entries::default();
+
+        let syntax = match fd.syntax {
+            Syntax::Proto2 => "syntax = \"proto2\";".to_string(),
+            Syntax::Proto3 => "syntax = \"proto3\";".to_string(),
+            Syntax::Edition(edition) => format!("edition = \"{edition}\";"),
+        };
+        entries.push(Entry::new(&fd.comments, Body::Lines(vec![syntax])));
+
+        if let Some(package) = &fd.package {
+            entries.section();
+            entries.push(Entry::new(
+                &fd.package_comments,
+                Body::Lines(vec![format!("package {package};")]),
+            ));
+        }
+
+        entries.section();
+        let mut imports = fd.imports.iter().collect::<Vec<_>>();
+        imports.sort_by(|a, b| a.path.cmp(&b.path));
+        for import in imports {
+            let kind = match import.kind {
+                ImportKind::Normal => "",
+                ImportKind::Public => "public ",
+                ImportKind::Weak => "weak ",
+            };
+            entries.push(Entry::new(
+                &import.comments,
+                Body::Lines(vec![format!("import {kind}{:?};", import.path)]),
+            ));
+        }
+
+        options(&mut entries, &fd.options);
+
+        entries.section();
+        let mut members = vec![];
+        members.extend(fd.messages.iter().map(Member::Message));
+        members.extend(fd.enums.iter().map(Member::Enum));
+        members.extend(fd.services.iter().map(Member::Service));
+        members.extend(fd.extends.iter().map(Member::Extend));
+        for member in sorted(members) {
+            let entry = self.member(&member);
+            entries.push(entry);
+        }
+
+        let mut output = entries.render(&fd.comments.closing).join("\n");
+        output.push('\n');
+        output
+    }
+
+    fn member<'a>(&mut self, member: &Member<'a>) -> Entry<'a> {
+        match *member {
+            Member::Field(field) => self.field(field, false),
+            Member::OneOf(oneof) => Entry::block(&oneof.comments, self.oneof(oneof)),
+            Member::Message(msg) => Entry::block(&msg.comments, self.message(msg)),
+            Member::Enum(en) => Entry::block(&en.comments, self.enumeration(en)),
+            Member::Service(svc) => Entry::block(&svc.comments, self.service(svc)),
+            Member::Extend(extend) => Entry::block(&extend.comments, self.extend(extend)),
+        }
+    }
+
+    // `head {` and the statements indented, `head {}` if it's empty
+    fn block(&mut self, head: String, comments: &Comments, entries: &Entries) -> Vec<String> {
+        let body = entries.render(&comments.closing);
+        let trailing = comments
+            .trailing
+            .as_deref()
+            .map(trailing_comment)
+            .unwrap_or_default();
+
+        // the trailing comment must be after the left brace
+        if body.is_empty() && trailing.is_empty() {
+            return vec![format!("{head} {{}}")];
+        }
+
+        let mut lines = vec![format!("{head} {{{trailing}")];
+        lines.extend(indent(body));
+        lines.push("}".to_string());
+        lines
+    }
+
+    // `head = tail` is split if it's too long, one option per line
+    fn assign(&self, head: String, number: String, options: &Options) -> Body {
+        let tail = format!("= {number}{};", inline_options(options));
+        let width = self.depth * INDENT.len() + head.len() + 1 + tail.len();
+        if width <= MAX_WIDTH || options.len() < 2 {
+            return Body::Assign { head, tail };
+        }
+
+        let mut lines = vec![format!("{head} = {number} [")];
+        for (index, option) in options.iter().enumerate() {
+            let separator = if index + 1 < options.len() { "," } else { "" };
+            lines.push(format!(
+                "{INDENT}{} = {}{separator}",
+                option.name,
+                value(&option.value)
+            ));
+        }
+        lines.push("];".to_string());
+        Body::Lines(lines)
+    }
+
+    fn field<'a>(&self, field: &'a Field, extend: bool) -> Entry<'a> {
+        let label = match (&field.typ, &field.label) {
+            (FieldType::Map(..), _) => "",
+            (_, Label::Required) if self.syntax == Syntax::Proto3 => "",
+            (_, Label::Required) => "required ",
+            // proto3 extensions are optional without the label
+            (_, Label::Optional) if self.syntax == Syntax::Proto3 && extend => "",
+            (_, Label::Optional) => "optional ",
+            (_, Label::Repeated) => "repeated ",
+        };
+
+        let head = format!("{label}{} {}", field.typ, field.name);
+        let body = self.assign(head, field.number.to_string(), &field.options);
+        Entry::new(&field.comments, body)
+    }
+
+    fn message(&mut self, msg: &Message) -> Vec<String> {
+        self.depth += 1;
+
+        let mut entries = Entries::default();
+        options(&mut entries, &msg.options);
+
+        entries.section();
+        reserved(
+            &mut entries,
+            &msg.reserved,
+            &msg.reserved_comments,
+            MAX_FIELD_NUMBER,
+        );
+        extensions(&mut entries, &msg.extensions);
+
+        entries.section();
+        let mut members = vec![];
+        members.extend(msg.fields.iter().map(Member::Field));
+        members.extend(msg.oneofs.iter().map(Member::OneOf));
+        members.extend(msg.messages.iter().map(Member::Message));
+        members.extend(msg.enums.iter().map(Member::Enum));
+        members.extend(msg.extends.iter().map(Member::Extend));
+
+        let mut end = None;
+        for member in sorted(members) {
+            let blank = gap(end, member.location(), member.comments());
+            end = Some(member.location().end_line);
+
+            let entry = self.member(&member).blank(blank);
+            entries.push(entry);
+        }
+
+        self.depth -= 1;
+        self.block(format!("message {}", msg.name), &msg.comments, &entries)
+    }
+
+    fn enumeration(&mut self, en: &Enum) -> Vec<String> {
+        self.depth += 1;
+
+        let mut entries = Entries::default();
+        options(&mut entries, &en.options);
+
+        entries.section();
+        reserved(
+            &mut entries,
+            &en.reserved,
+            &en.reserved_comments,
+            i32::MAX as i64,
+        );
+
+        entries.section();
+        let mut end = None;
+        for variant in &en.variants {
+            let blank = gap(end, &variant.location, &variant.comments);
+            end = Some(variant.location.end_line);
+
+            let body = self.assign(
+                variant.name.clone(),
+                variant.value.to_string(),
+                &variant.options,
+            );
+            entries.push(Entry::new(&variant.comments, body).blank(blank));
+        }
+
+        self.depth -= 1;
+        self.block(format!("enum {}", en.name), &en.comments, &entries)
+    }
+
+    fn oneof(&mut self, oneof: &OneOf) -> Vec<String> {
+        self.depth += 1;
+
+        let mut entries = Entries::default();
+        options(&mut entries, &oneof.options);
+
+        entries.section();
+        let mut end = None;
+        for variant in &oneof.variants {
+            let blank = gap(end, &variant.location, &variant.comments);
+            end = Some(variant.location.end_line);
+
+            let head = format!("{} {}", variant.typ, variant.name);
+            let body = self.assign(head, variant.number.to_string(), &variant.options);
+            entries.push(Entry::new(&variant.comments, body).blank(blank));
+        }
+
+        self.depth -= 1;
+        self.block(format!("oneof {}", oneof.name), &oneof.comments, &entries)
+    }
+
+    fn extend(&mut self, extend: &Extend) -> Vec<String> {
+        self.depth += 1;
+
+        let mut entries = Entries::default();
+        let mut end = None;
+        for field in &extend.fields {
+            let blank = gap(end, &field.location, &field.comments);
+            end = Some(field.location.end_line);
+
+            let entry = self.field(field, true).blank(blank);
+            entries.push(entry);
+        }
+
+        self.depth -= 1;
+        self.block(
+            format!("extend {}", extend.extendee),
+            &extend.comments,
+            &entries,
+        )
+    }
+
+    fn service(&mut self, svc: &Service) -> Vec<String> {
+        self.depth += 1;
+
+        let mut entries = Entries::default();
+        options(&mut entries, &svc.options);
+
+        entries.section();
+        let mut end = None;
+        for function in &svc.functions {
+            let blank = gap(end, &function.location, &function.comments);
+            end = Some(function.location.end_line);
+
+            let body = Body::Lines(self.function(function));
+            let mut entry = Entry::new(&function.comments, body).blank(blank);
+            entry.block = !function.options.is_empty();
+            entries.push(entry);
+        }
+
+        self.depth -= 1;
+        self.block(format!("service {}", svc.name), &svc.comments, &entries)
+    }
+
+    fn function(&mut self, function: &Function) -> Vec<String> {
+        let (request, response) = match function.method {
+            Method::Unary => ("", ""),
+            Method::ClientStreaming => ("stream ", ""),
+            Method::ServerStreaming => ("", "stream "),
+            Method::BidiStreaming => ("stream ", "stream "),
+        };
+        let head = format!(
+            "rpc {}({request}{}) returns ({response}{})",
+            function.name, function.request, function.response
+        );
+
+        if function.options.is_empty() {
+            return vec![format!("{head};")];
+        }
+
+        let mut entries = Entries::default();
+        options(&mut entries, &function.options);
+
+        let mut lines = vec![format!("{head} {{")];
+        lines.extend(indent(entries.render(&[])));
+        lines.push("}".to_string());
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str, expected: &str) {
+        let output = format(input.as_bytes()).unwrap();
+        assert_eq!(output, expected);
+        // formatting is idempotent
+        assert_eq!(format(output.as_bytes()).unwrap(), output);
+    }
+
+    #[test]
+    fn layout() {
+        check(
+            r#"
+// Copyright
+
+syntax="proto3";
+import "b.proto";  import public "a.proto";
+package foo.bar;
+option java_package="com.example";option (my.option) = { a: 1 nested { b: "x" } };
+option optimize_for=SPEED;
+enum Kind{KIND_UNSPECIFIED=0;KIND_A=1 [deprecated=true];reserved 5 to 7, 9;reserved "B";}
+message Foo {
+  // The id
+  int64 id = 1; // trailing
+  repeated string names = 2 [packed=false];
+  map<string,Foo> children = 3;
+
+  optional double ratio = 4;
+  oneof body { string text = 5; bytes raw = 6; }
+  message Empty{}
+  reserved 10 to max;
+  option deprecated=true;
+}
+service Search {
+  rpc Find(Foo) returns (stream Foo);
+  rpc Get(Foo) returns (Foo) { option idempotency_level = NO_SIDE_EFFECTS; }
+}
+"#,
+            r#"// Copyright
+
+syntax = "proto3";
+
+package foo.bar;
+
+import public "a.proto";
+import "b.proto";
+
+option java_package = "com.example";
+option (my.option) = {
+  a: 1
+  nested {
+    b: "x"
+  }
+};
+option optimize_for = SPEED;
+
+enum Kind {
+  reserved 5 to 7, 9;
+  reserved "B";
+
+  KIND_UNSPECIFIED = 0;
+  KIND_A           = 1 [deprecated = true];
+}
+
+message Foo {
+  option deprecated = true;
+
+  reserved 10 to max;
+
+  // The id
+  int64 id                  = 1; // trailing
+  repeated string names     = 2 [packed = false];
+  map<string, Foo> children = 3;
+
+  optional double ratio = 4;
+
+  oneof body {
+    string text = 5;
+    bytes raw   = 6;
+  }
+
+  message Empty {}
+}
+
+service Search {
+  rpc Find(Foo) returns (stream Foo);
+
+  rpc Get(Foo) returns (Foo) {
+    option idempotency_level = NO_SIDE_EFFECTS;
+  }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn proto2() {
+        check(
+            r#"syntax = "proto2";
+message Foo {
+  required int32 id = 1;
+  optional float ratio = 2 [default = 1];
+  // for plugins
+  extensions 100 to 199, 300 to max;
+  reserved 5; // removed
+}
+extend Foo {
+  optional string note = 100;
+}
+"#,
+            r#"syntax = "proto2";
+
+message Foo {
+  reserved 5; // removed
+  // for plugins
+  extensions 100 to 199, 300 to max;
+
+  required int32 id    = 1;
+  optional float ratio = 2 [default = 1];
+}
+
+extend Foo {
+  optional string note = 100;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn comments() {
+        check(
+            r#"syntax = "proto3";
+
+// detached
+
+/* A message,
+ * block comment */
+message Foo { // trailing of Foo
+  int32 a = 1;
+
+  // nothing follows
+}
+
+// the end
+"#,
+            r#"syntax = "proto3";
+
+// detached
+
+// A message,
+// block comment
+message Foo { // trailing of Foo
+  int32 a = 1;
+
+  // nothing follows
+}
+
+// the end
+"#,
+        );
+    }
+
+    #[test]
+    fn long_options() {
+        check(
+            r#"syntax = "proto3";
+message Foo {
+  string name = 1 [deprecated = true, json_name = "a_very_long_json_name_for_the_name_field_of_foo"];
+}
+"#,
+            r#"syntax = "proto3";
+
+message Foo {
+  string name = 1 [
+    deprecated = true,
+    json_name = "a_very_long_json_name_for_the_name_field_of_foo"
+  ];
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn lost_comments() {
+        let input = r#"syntax = "proto3";
+message Foo {
+  // why it's deprecated
+  option deprecated = true;
+}
+"#;
+
+        let err = format(input.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::Format(_)));
+        assert!(
+            err.to_string()
+                .contains("the comment at line 3 can't be kept")
+        );
+    }
+}
@@ -1,18 +1,104 @@
-mod ast;
+pub mod ast;
+pub mod breaking;
 mod codegen;
-mod parse;
+mod descriptor;
+pub mod dynamic;
+pub mod format;
+pub mod lint;
+pub mod parse;
 
+use std::fmt::{Display, Formatter};
 use std::path::Path;
 
+pub use breaking::breaking;
 pub use codegen::Config;
+pub use format::format;
+pub use lint::lint;
+pub use parse::parse;
 
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
 
-    Parse(parse::Error),
+    /// All problems found in the proto files
+    Parse(Vec<parse::Error>),
 
     ImportNotFound(String),
+
+    /// A type referenced can't be found from the scope it's used in
+    UnresolvedType {
+        name: String,
+        scope: String,
+    },
+
+    /// A descriptor can't be decoded, or it describes something not
+    /// supported
+    InvalidDescriptor(String),
+
+    /// A type name refers to more than one definition
+    AmbiguousType {
+        name: String,
+        candidates: Vec<String>,
+    },
+
+    /// A file can't be formatted without losing comments
+    Format(String),
+
+    /// A payload doesn't match the message type it's decoded or encoded as
+    InvalidPayload(String),
+
+    /// An option of `Config` can't be applied to what it selects
+    InvalidConfig(String),
+
+    /// An `extend` block extends something that can't be extended
+    InvalidExtension(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Parse(errors) => {
+                for (index, err) in errors.iter().enumerate() {
+                    if index != 0 {
+                        f.write_str("\n\n")?;
+                    }
+
+                    err.fmt(f)?;
+                }
+
+                Ok(())
+            }
+            Error::ImportNotFound(path) => write!(f, "import `{path}` not found"),
+            Error::UnresolvedType { name, scope } if scope.is_empty() => {
+                write!(f, "type `{name}` is not defined")
+            }
+            Error::UnresolvedType { name, scope } => {
+                write!(f, "type `{name}` is not defined in scope `{scope}`")
+            }
+            Error::InvalidDescriptor(reason) => write!(f, "invalid descriptor: {reason}"),
+            Error::AmbiguousType { name, candidates } => {
+                write!(
+                    f,
+                    "type `{name}` is ambiguous, it could be any of {}",
+                    candidates.join(", ")
+                )
+            }
+            Error::Format(reason) => f.write_str(reason),
+            Error::InvalidPayload(reason) => write!(f, "invalid payload: {reason}"),
+            Error::InvalidConfig(reason) => write!(f, "invalid config: {reason}"),
+            Error::InvalidExtension(reason) => write!(f, "invalid extension: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -0,0 +1,815 @@
+//! Semantic checks of proto files. The parser only cares about the grammar,
+//! things like duplicate names, conflicts with `reserved` or names colliding
+//! after converted to Rust are found here, before code generation.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::ast::{Enum, Extend, FieldType, FileDescriptor, ImportKind, Message, Options, Syntax};
+use crate::codegen::config::{load_imports, load_proto_recovering};
+use crate::codegen::context::Context;
+use crate::codegen::sanitize::{sanitize_field, sanitize_type_name, sanitize_variant, snake};
+use crate::parse::ErrorKind;
+use crate::{Config, Error};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Two definitions with the same name in one scope
+    DuplicateName,
+    /// Two fields with the same number in one message
+    DuplicateNumber,
+    /// A field uses a number reserved by the message
+    ReservedNumber,
+    /// A field uses a name reserved by the message
+    ReservedName,
+    /// Two fields have the same JSON name
+    JsonNameConflict,
+    /// The first value of a proto3 enum is not zero
+    EnumZeroValue,
+    /// A type can't be resolved, or it's ambiguous
+    UnresolvedType,
+    /// Nothing of an import is used
+    UnusedImport,
+    /// Two definitions end up with the same name in generated code
+    RustNameConflict,
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::DuplicateName => "duplicate-name",
+            Rule::DuplicateNumber => "duplicate-number",
+            Rule::ReservedNumber => "reserved-number",
+            Rule::ReservedName => "reserved-name",
+            Rule::JsonNameConflict => "json-name-conflict",
+            Rule::EnumZeroValue => "enum-zero-value",
+            Rule::UnresolvedType => "unresolved-type",
+            Rule::UnusedImport => "unused-import",
+            Rule::RustNameConflict => "rust-name-conflict",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::UnusedImport => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub rule: Rule,
+    /// Fully qualified name of the definition, e.g. `pkg.Message.field`, or
+    /// the import path for unused imports
+    pub path: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    #[inline]
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+
+    /// One line JSON object, for tools consuming the output
+    pub fn to_json(&self) -> String {
+        let mut buf = String::new();
+
+        buf.push_str("{\"file\":");
+        push_json_string(&mut buf, &self.file.to_string_lossy());
+        buf.push_str(",\"severity\":");
+        push_json_string(&mut buf, self.severity().as_str());
+        buf.push_str(",\"rule\":");
+        push_json_string(&mut buf, self.rule.as_str());
+        buf.push_str(",\"path\":");
+        push_json_string(&mut buf, &self.path);
+        buf.push_str(",\"message\":");
+        push_json_string(&mut buf, &self.message);
+        buf.push('}');
+
+        buf
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}] {}: {}",
+            self.file.display(),
+            self.severity().as_str(),
+            self.rule.as_str(),
+            self.path,
+            self.message
+        )
+    }
+}
+
+/// One line JSON object per problem of an error `lint` returns, like
+/// `Diagnostic::to_json`, so files that can't be linted are reported in the
+/// same format. Parse errors come with the rule `parse-error` and where they
+/// are, other errors like missing imports with the rule `load-error`.
+pub fn error_to_json(err: &Error) -> Vec<String> {
+    let Error::Parse(errors) = err else {
+        let mut buf = String::new();
+        buf.push_str("{\"severity\":\"error\",\"rule\":\"load-error\",\"message\":");
+        push_json_string(&mut buf, &err.to_string());
+        buf.push('}');
+        return vec![buf];
+    };
+
+    errors
+        .iter()
+        .map(|err| {
+            let mut buf = String::new();
+            buf.push_str("{\"file\":");
+            let file = err.file().map(Path::to_string_lossy).unwrap_or_default();
+            push_json_string(&mut buf, &file);
+            buf.push_str(",\"severity\":\"error\",\"rule\":\"parse-error\"");
+            let _ = write!(buf, ",\"line\":{},\"column\":{}", err.line(), err.column());
+            buf.push_str(",\"message\":");
+            push_json_string(&mut buf, &err.kind().to_string());
+            buf.push('}');
+            buf
+        })
+        .collect()
+}
+
+pub(crate) fn push_json_string(buf: &mut String, s: &str) {
+    buf.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(buf, "\\u{:04x}", ch as u32);
+            }
+            ch => buf.push(ch),
+        }
+    }
+    buf.push('"');
+}
+
+/// Lint proto files, imports are searched in `includes`. Parse errors and
+/// missing files are returned as `Err`, semantic problems are returned as
+/// diagnostics.
+pub fn lint<P: AsRef<Path>>(includes: &[P], files: &[P]) -> Result<Vec<Diagnostic>, Error> {
+    let mut diagnostics = vec![];
+
+    for path in files {
+        let fd = load_proto_recovering(path, recoverable)?;
+        let mut imports = HashMap::new();
+        for import in &fd.imports {
+            load_imports(&import.path, includes, &mut imports)?;
+        }
+
+        diagnostics.extend(lint_file(path.as_ref(), &fd, &imports));
+    }
+
+    Ok(diagnostics)
+}
+
+// parse errors reported as rules, the files are linted anyway
+fn recoverable(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::EnumZeroValue)
+}
+
+/// Lint a parsed file, `imports` are files it imports, keyed by the import
+/// path
+pub fn lint_file(
+    file: &Path,
+    fd: &FileDescriptor,
+    imports: &HashMap<String, FileDescriptor>,
+) -> Vec<Diagnostic> {
+    let config = Config::default();
+    let mut linter = Linter {
+        file,
+        cx: Context {
+            fd,
+            config: &config,
+            imports,
+            messages: vec![],
+        },
+        used: HashSet::new(),
+        diagnostics: vec![],
+    };
+
+    linter.check_file(fd);
+    linter.diagnostics
+}
+
+// a field, or a field of oneof
+struct Member<'a> {
+    name: &'a str,
+    number: u32,
+    typ: &'a FieldType,
+    options: &'a Options,
+}
+
+struct Linter<'a> {
+    file: &'a Path,
+    cx: Context<'a>,
+    /// imports types are resolved from
+    used: HashSet<&'a str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: Rule, path: impl Into<String>, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.to_path_buf(),
+            rule,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn check_file(&mut self, fd: &'a FileDescriptor) {
+        let scope = fd.package.clone().unwrap_or_default();
+
+        // enum values are siblings of the enum, just like C++
+        let mut names = vec![];
+        for msg in &fd.messages {
+            names.push((msg.name.as_str(), "message"));
+        }
+        for en in &fd.enums {
+            names.push((en.name.as_str(), "enum"));
+            for variant in &en.variants {
+                names.push((variant.name.as_str(), "enum value"));
+            }
+        }
+        for svc in &fd.services {
+            names.push((svc.name.as_str(), "service"));
+        }
+        for extend in &fd.extends {
+            for field in &extend.fields {
+                names.push((field.name.as_str(), "extension"));
+            }
+        }
+        self.check_names(&scope, &names);
+        self.check_types(&scope, &fd.messages, &fd.enums, &[]);
+
+        for msg in &fd.messages {
+            self.check_message(&scope, msg);
+        }
+        for en in &fd.enums {
+            self.check_enum(&scope, en);
+        }
+        for extend in &fd.extends {
+            self.check_extend(&scope, extend);
+        }
+        for svc in &fd.services {
+            for func in &svc.functions {
+                let path = join(&join(&scope, &svc.name), &func.name);
+                self.resolve(&path, &func.request);
+                self.resolve(&path, &func.response);
+            }
+        }
+
+        self.check_imports(fd);
+    }
+
+    fn check_message(&mut self, scope: &str, msg: &'a Message) {
+        let scope = join(scope, &msg.name);
+        self.cx.messages.push(msg);
+
+        // fields of oneofs are fields of the message too
+        let fields = msg
+            .fields
+            .iter()
+            .map(|field| Member {
+                name: &field.name,
+                number: field.number,
+                typ: &field.typ,
+                options: &field.options,
+            })
+            .chain(msg.oneofs.iter().flat_map(|oneof| {
+                oneof.variants.iter().map(|variant| Member {
+                    name: &variant.name,
+                    number: variant.number,
+                    typ: &variant.typ,
+                    options: &variant.options,
+                })
+            }))
+            .collect::<Vec<_>>();
+
+        // names
+        let mut names = vec![];
+        for field in &fields {
+            names.push((field.name, "field"));
+        }
+        for oneof in &msg.oneofs {
+            names.push((oneof.name.as_str(), "oneof"));
+        }
+        for nested in &msg.messages {
+            names.push((nested.name.as_str(), "message"));
+        }
+        for en in &msg.enums {
+            names.push((en.name.as_str(), "enum"));
+            for variant in &en.variants {
+                names.push((variant.name.as_str(), "enum value"));
+            }
+        }
+        for extend in &msg.extends {
+            for field in &extend.fields {
+                names.push((field.name.as_str(), "extension"));
+            }
+        }
+        self.check_names(&scope, &names);
+
+        // numbers
+        let mut numbers = HashMap::new();
+        for field in &fields {
+            if let Some(other) = numbers.insert(field.number, field.name) {
+                self.report(
+                    Rule::DuplicateNumber,
+                    join(&scope, field.name),
+                    format!("field number {} is used by `{other}` already", field.number),
+                );
+            }
+        }
+
+        // reserved
+        for field in &fields {
+            for reserved in &msg.reserved {
+                if reserved.contains(field.number as i64) {
+                    self.report(
+                        Rule::ReservedNumber,
+                        join(&scope, field.name),
+                        format!("field number {} is reserved", field.number),
+                    );
+                }
+                if reserved.contains_name(field.name) {
+                    self.report(
+                        Rule::ReservedName,
+                        join(&scope, field.name),
+                        format!("field name `{}` is reserved", field.name),
+                    );
+                }
+            }
+        }
+
+        // json names
+        let mut json_names = HashMap::new();
+        for field in &fields {
+            let json_name = json_name(field.name, field.options);
+            if let Some(other) = json_names.insert(json_name.clone(), field.name) {
+                self.report(
+                    Rule::JsonNameConflict,
+                    join(&scope, field.name),
+                    format!("JSON name `{json_name}` conflicts with field `{other}`"),
+                );
+            }
+        }
+
+        // rust names of fields, oneofs are fields of the struct too
+        let rust_names = msg
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .chain(msg.oneofs.iter().map(|oneof| oneof.name.as_str()))
+            .map(|name| (name, sanitize_field(name)))
+            .collect::<Vec<_>>();
+        self.check_rust_names(&scope, "field", &rust_names);
+
+        let oneofs = msg
+            .oneofs
+            .iter()
+            .map(|oneof| oneof.name.as_str())
+            .collect::<Vec<_>>();
+        self.check_types(&scope, &msg.messages, &msg.enums, &oneofs);
+
+        for field in &fields {
+            self.check_field_type(&join(&scope, field.name), field.typ);
+        }
+        for nested in &msg.messages {
+            self.check_message(&scope, nested);
+        }
+        for en in &msg.enums {
+            self.check_enum(&scope, en);
+        }
+        for extend in &msg.extends {
+            self.check_extend(&scope, extend);
+        }
+
+        self.cx.messages.pop();
+    }
+
+    fn check_enum(&mut self, scope: &str, en: &Enum) {
+        let scope = join(scope, &en.name);
+
+        if self.cx.fd.syntax == Syntax::Proto3
+            && let Some(first) = en.variants.first()
+            && first.value != 0
+        {
+            self.report(
+                Rule::EnumZeroValue,
+                join(&scope, &first.name),
+                "the first value of proto3 enums must be zero",
+            );
+        }
+
+        let variants = en
+            .variants
+            .iter()
+            .filter(|variant| !en.is_alias(variant))
+            .map(|variant| {
+                (
+                    variant.name.as_str(),
+                    sanitize_variant(&en.name, &variant.name),
+                )
+            })
+            .collect::<Vec<_>>();
+        self.check_rust_names(&scope, "enum value", &variants);
+    }
+
+    fn check_extend(&mut self, scope: &str, extend: &Extend) {
+        self.resolve(scope, &extend.extendee);
+        for field in &extend.fields {
+            self.check_field_type(&join(scope, &field.name), &field.typ);
+        }
+    }
+
+    fn check_field_type(&mut self, path: &str, typ: &FieldType) {
+        match typ {
+            FieldType::Message(name) => self.resolve(path, name),
+            FieldType::Map(key, value) => {
+                self.check_field_type(path, key);
+                self.check_field_type(path, value);
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve(&mut self, path: &str, typ: &str) {
+        match self.cx.resolve_symbol(typ) {
+            Ok(resolved) => {
+                if let Some(import) = resolved.import {
+                    self.used.insert(import);
+                }
+            }
+            Err(err) => self.report(Rule::UnresolvedType, path, err.to_string()),
+        }
+    }
+
+    fn check_names(&mut self, scope: &str, names: &[(&str, &str)]) {
+        let mut seen = HashMap::new();
+        for (name, kind) in names {
+            if let Some(other) = seen.insert(*name, *kind) {
+                self.report(
+                    Rule::DuplicateName,
+                    join(scope, name),
+                    format!("`{name}` is defined as {} already", article(other)),
+                );
+            }
+        }
+    }
+
+    // messages, enums and oneofs are generated in the same module, and
+    // messages come with a module of nested definitions
+    fn check_types(&mut self, scope: &str, messages: &[Message], enums: &[Enum], oneofs: &[&str]) {
+        let types = messages
+            .iter()
+            .map(|msg| msg.name.as_str())
+            .chain(enums.iter().map(|en| en.name.as_str()))
+            .chain(oneofs.iter().copied())
+            .map(|name| (name, sanitize_type_name(name)))
+            .collect::<Vec<_>>();
+        self.check_rust_names(scope, "type", &types);
+
+        let modules = messages
+            .iter()
+            .map(|msg| (msg.name.as_str(), snake(&msg.name)))
+            .collect::<Vec<_>>();
+        self.check_rust_names(scope, "module", &modules);
+    }
+
+    // names which differ in proto but not in Rust, exact duplicates are
+    // reported by `check_names` already
+    fn check_rust_names(&mut self, scope: &str, kind: &str, names: &[(&str, String)]) {
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        for (name, rust) in names {
+            match seen.get(rust.as_str()) {
+                Some(other) if other != name => {
+                    self.report(
+                        Rule::RustNameConflict,
+                        join(scope, name),
+                        format!("`{name}` and `{other}` are both generated as {kind} `{rust}`"),
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    seen.insert(rust, name);
+                }
+            }
+        }
+    }
+
+    fn check_imports(&mut self, fd: &'a FileDescriptor) {
+        let mut extensions = vec![];
+        collect_extension_options(fd, &mut extensions);
+
+        for import in &fd.imports {
+            // public imports are re-exported to importers
+            if import.kind == ImportKind::Public || self.used.contains(import.path.as_str()) {
+                continue;
+            }
+
+            // imports providing custom options
+            if let Some(imported) = self.cx.imports.get(&import.path) {
+                let defined = extension_names(imported);
+                if extensions.iter().any(|ext| {
+                    defined
+                        .iter()
+                        .any(|name| name == ext || name.ends_with(&format!(".{ext}")))
+                }) {
+                    continue;
+                }
+            }
+
+            self.report(
+                Rule::UnusedImport,
+                import.path.as_str(),
+                format!("import `{}` is not used", import.path),
+            );
+        }
+    }
+}
+
+pub(crate) fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{scope}.{name}")
+    }
+}
+
+fn article(kind: &str) -> String {
+    match kind.as_bytes().first() {
+        Some(b'a' | b'e' | b'i' | b'o' | b'u') => format!("an {kind}"),
+        _ => format!("a {kind}"),
+    }
+}
+
+/// JSON name of a field, `json_name` option or the lower camel case of the
+/// name, the same as protoc
+pub fn json_name(name: &str, options: &Options) -> String {
+    if let Some(name) = options.get("json_name").and_then(|value| value.as_str()) {
+        return name.to_string();
+    }
+
+    let mut buf = String::with_capacity(name.len());
+    let mut upper = false;
+    for ch in name.chars() {
+        if ch == '_' {
+            upper = true;
+        } else if upper {
+            buf.push(ch.to_ascii_uppercase());
+            upper = false;
+        } else {
+            buf.push(ch);
+        }
+    }
+
+    buf
+}
+
+// extension names used in options, e.g. `gogoproto.nullable`
+fn collect_extension_options<'a>(fd: &'a FileDescriptor, names: &mut Vec<&'a str>) {
+    fn options<'a>(options: &'a Options, names: &mut Vec<&'a str>) {
+        names.extend(options.iter().filter_map(|option| option.name.extension()));
+    }
+
+    fn message<'a>(msg: &'a Message, names: &mut Vec<&'a str>) {
+        options(&msg.options, names);
+        for field in &msg.fields {
+            options(&field.options, names);
+        }
+        for oneof in &msg.oneofs {
+            options(&oneof.options, names);
+            for variant in &oneof.variants {
+                options(&variant.options, names);
+            }
+        }
+        for en in &msg.enums {
+            enumeration(en, names);
+        }
+        for nested in &msg.messages {
+            message(nested, names);
+        }
+    }
+
+    fn enumeration<'a>(en: &'a Enum, names: &mut Vec<&'a str>) {
+        options(&en.options, names);
+        for variant in &en.variants {
+            options(&variant.options, names);
+        }
+    }
+
+    options(&fd.options, names);
+    for msg in &fd.messages {
+        message(msg, names);
+    }
+    for en in &fd.enums {
+        enumeration(en, names);
+    }
+    for svc in &fd.services {
+        options(&svc.options, names);
+        for func in &svc.functions {
+            options(&func.options, names);
+        }
+    }
+}
+
+// fully qualified names of extensions defined in a file
+fn extension_names(fd: &FileDescriptor) -> Vec<String> {
+    fn collect(scope: &str, extends: &[Extend], messages: &[Message], names: &mut Vec<String>) {
+        for extend in extends {
+            for field in &extend.fields {
+                names.push(join(scope, &field.name));
+            }
+        }
+        for msg in messages {
+            collect(&join(scope, &msg.name), &msg.extends, &msg.messages, names);
+        }
+    }
+
+    let mut names = vec![];
+    collect(
+        fd.package.as_deref().unwrap_or_default(),
+        &fd.extends,
+        &fd.messages,
+        &mut names,
+    );
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(input: &str, imports: &[(&str, &str)]) -> Vec<(Rule, String)> {
+        let fd = crate::parse::parse_recovering(input.as_bytes(), recoverable).unwrap();
+        let imports = imports
+            .iter()
+            .map(|(name, input)| (name.to_string(), crate::parse(input.as_bytes()).unwrap()))
+            .collect();
+
+        lint_file(Path::new("test.proto"), &fd, &imports)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.path))
+            .collect()
+    }
+
+    #[test]
+    fn message() {
+        let input = r#"
+        syntax = "proto3";
+        package pkg;
+
+        message Foo {
+            reserved 5, 10 to 12;
+            reserved "old";
+
+            string foo_bar = 1;
+            string fooBar = 2;
+            int32 old = 5;
+            int32 dup = 2;
+            Missing missing = 3;
+            oneof body {
+                string text = 11;
+            }
+        }
+
+        message Foo {}
+        message foo_bar {}
+        message FooBar {}
+        message Foo_ {}
+        "#;
+
+        assert_eq!(
+            rules(input, &[]),
+            [
+                (Rule::DuplicateName, "pkg.Foo".to_string()),
+                (Rule::RustNameConflict, "pkg.FooBar".to_string()),
+                (Rule::RustNameConflict, "pkg.Foo_".to_string()),
+                (Rule::RustNameConflict, "pkg.FooBar".to_string()),
+                (Rule::DuplicateNumber, "pkg.Foo.dup".to_string()),
+                (Rule::ReservedNumber, "pkg.Foo.old".to_string()),
+                (Rule::ReservedName, "pkg.Foo.old".to_string()),
+                (Rule::ReservedNumber, "pkg.Foo.text".to_string()),
+                (Rule::JsonNameConflict, "pkg.Foo.fooBar".to_string()),
+                (Rule::RustNameConflict, "pkg.Foo.fooBar".to_string()),
+                (Rule::UnresolvedType, "pkg.Foo.missing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn enums() {
+        let input = r#"
+        syntax = "proto3";
+        package pkg;
+
+        enum State {
+            STARTED = 1;
+            STOPPED = 0;
+        }
+
+        message Foo {
+            enum Kind { KIND_UNSPECIFIED = 0; }
+        }
+        "#;
+
+        assert_eq!(
+            rules(input, &[]),
+            [(Rule::EnumZeroValue, "pkg.State.STARTED".to_string())]
+        );
+    }
+
+    #[test]
+    fn imports() {
+        let input = r#"
+        syntax = "proto3";
+        package pkg;
+
+        import "types.proto";
+        import "options.proto";
+        import "unused.proto";
+        import public "public.proto";
+
+        message Foo {
+            types.Bar bar = 1 [(opts.tag) = "bar"];
+        }
+        "#;
+        let imports = [
+            ("types.proto", "package types; message Bar {}"),
+            (
+                "options.proto",
+                "package opts; extend google.protobuf.FieldOptions { optional string tag = 50000; }",
+            ),
+            ("unused.proto", "package unused; message Baz {}"),
+            ("public.proto", "package public; message Qux {}"),
+        ];
+
+        assert_eq!(
+            rules(input, &imports),
+            [(Rule::UnusedImport, "unused.proto".to_string())]
+        );
+    }
+
+    #[test]
+    fn error_json() {
+        let err = Error::Parse(
+            crate::parse(b"syntax = \"proto3\";\nmessage Foo { int32 a = 1 }").unwrap_err(),
+        );
+        assert_eq!(
+            error_to_json(&err),
+            [
+                r#"{"file":"","severity":"error","rule":"parse-error","line":2,"column":27,"message":"expected semicolon or left bracket, found `}`"}"#
+            ]
+        );
+
+        let err = Error::ImportNotFound("a.proto".to_string());
+        assert_eq!(
+            error_to_json(&err),
+            [r#"{"severity":"error","rule":"load-error","message":"import `a.proto` not found"}"#]
+        );
+    }
+
+    #[test]
+    fn json() {
+        let diagnostic = Diagnostic {
+            file: PathBuf::from("a.proto"),
+            rule: Rule::UnusedImport,
+            path: "b.proto".to_string(),
+            message: "import \"b.proto\" is not used".to_string(),
+        };
+
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"file":"a.proto","severity":"warning","rule":"unused-import","path":"b.proto","message":"import \"b.proto\" is not used"}"#
+        );
+    }
+}
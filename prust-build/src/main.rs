@@ -1,73 +1,671 @@
+use std::fmt::Display;
+use std::io::{Read, Write};
+
 use prust_build::Config;
+use prust_build::dynamic::Schema;
+use prust_build::lint::{Severity, error_to_json};
+
+// problems found, e.g. parse errors or lint errors
+const FAILURE: i32 = 1;
+// wrong arguments
+const USAGE: i32 = 2;
+
+const COMMANDS: [&str; 7] = [
+    "generate", "check", "lint", "breaking", "fmt", "decode", "encode",
+];
+
+const HELP: &str = "\
+A converter from proto files to rust files
+
+USAGE:
+  prust-build <COMMAND> [OPTIONS]
+
+COMMANDS:
+  generate    Generate rust files from proto files, the default command
+  check       Check proto files compile, without writing anything
+  lint        Check proto files for semantic problems
+  breaking    Find breaking changes between two versions of proto files
+  fmt         Format proto files in the canonical style
+  decode      Decode a binary message into text format or JSON
+  encode      Encode a message in text format or JSON into binary
+  help        Print help of a command
+
+Run `prust-build help <COMMAND>` for options of a command.";
+
+const GENERATE_HELP: &str = "\
+Generate rust files from proto files
+
+USAGE:
+  prust-build [generate] [OPTIONS] <FILES>...
+  prust-build [generate] [OPTIONS] --descriptor-set-in <FILE>
+
+OPTIONS:
+  -i, --include <DIR>                  Directory to search imports in
+  -d, --out-dir <DIR>                  Directory of generated files [default: .]
+  -o, --filename <NAME>                Name of the generated file, the package by default
+      --include-file <NAME>            Write a file nesting the modules of all packages
+      --descriptor-set-in <FILE>       Generate from an encoded `FileDescriptorSet`
+      --descriptor-set-out <FILE>      Write a `FileDescriptorSet` of the files and imports
+      --embed-descriptors              Embed descriptors of files with services
+      --no-std                         Generate code for `no_std`
+      --no-client                      Don't generate gRPC clients
+      --no-server                      Don't generate gRPC servers
+      --open-enums                     Keep unknown values of proto3 enums
+      --skip-deprecated-fields         Drop fields marked `deprecated`
+      --no-clone                       Don't derive `Clone` for messages and oneofs
+      --no-partial-eq                  Don't derive `PartialEq` for messages and oneofs
+      --no-eq                          Don't derive `Eq`
+      --no-hash                        Don't derive `Hash`
+      --btree-map <PATH>               Use `BTreeMap` for map fields under PATH
+      --hashmap <PATH>                 Use `HashMap` for map fields under PATH
+      --skip-serialize <PATH>          Don't implement `Serialize` for PATH
+      --skip-deserialize <PATH>        Don't implement `Deserialize` for PATH
+      --extern-path <PROTO=RUST>       Use the rust path for types under PROTO
+      --field-type <PATH=TYPE>         Use a custom rust type for fields under PATH
+      --string-type <PATH=TYPE>        Use TYPE for string fields under PATH
+      --bytes-type <PATH=TYPE>         Use TYPE for bytes fields under PATH
+      --repeated-type <PATH=TYPE>      Use TYPE for repeated fields under PATH
+      --message-attribute <PATH=ATTR>  Add an attribute to messages under PATH
+      --enum-attribute <PATH=ATTR>     Add an attribute to enums under PATH
+      --oneof-attribute <PATH=ATTR>    Add an attribute to oneofs under PATH
+      --field-attribute <PATH=ATTR>    Add an attribute to fields under PATH
+  -h, --help                           Print help
+
+Options taking values can be repeated, except the output ones. An empty
+PATH matches everything.";
+
+const CHECK_HELP: &str = "\
+Check proto files compile, without writing anything
+
+USAGE:
+  prust-build check [OPTIONS] <FILES>...
+
+OPTIONS:
+  -i, --include <DIR>    Directory to search imports in
+  -h, --help             Print help";
+
+const LINT_HELP: &str = "\
+Check proto files for semantic problems, exits with 1 if there are errors
+
+USAGE:
+  prust-build lint [OPTIONS] <FILES>...
+
+OPTIONS:
+  -i, --include <DIR>    Directory to search imports in
+      --format <FORMAT>  Output format, `text` or `json` [default: text]
+  -h, --help             Print help";
+
+const BREAKING_HELP: &str = "\
+Find breaking changes between two versions of proto files, files or
+directories, exits with 1 if there are any
+
+USAGE:
+  prust-build breaking [OPTIONS] <OLD> <NEW>
+
+OPTIONS:
+  -i, --include <DIR>    Directory to search imports in
+      --format <FORMAT>  Output format, `text` or `json` [default: text]
+  -h, --help             Print help";
+
+const FMT_HELP: &str = "\
+Format proto files in the canonical style
+
+USAGE:
+  prust-build fmt [OPTIONS] <FILES>...
+
+OPTIONS:
+      --check    List files not formatted instead of formatting them, exits
+                 with 1 if there are any
+  -h, --help     Print help";
+
+const DECODE_HELP: &str = "\
+Decode a binary message read from stdin, and print it in text format or JSON
 
-fn help() {
-    println!("A converter from proto files to rust files");
-    println!("USAGE:");
-    println!("  prust [OPTIONS] [FLAGS] [FILES]");
-    println!();
-    println!("FLAGS:");
-    println!("  -h, --help           Prints help information");
-    println!();
-    println!("OPTIONS:");
-    println!("  -i, --include        Path to search for imported protobuf files");
-    println!("  -d                   Output directory for generated files");
-    println!("  -o                   Generated file name");
+USAGE:
+  prust-build decode [OPTIONS] --type <NAME> <FILES>...
+  prust-build decode [OPTIONS] --type <NAME> --descriptor-set-in <FILE>
+  prust-build decode [OPTIONS] --raw
+
+OPTIONS:
+  -i, --include <DIR>               Directory to search imports in [default:
+                                    directories of the files]
+      --proto <FILE>                A proto file, the same as the positional ones
+      --descriptor-set-in <FILE>    Use an encoded `FileDescriptorSet` as the schema
+  -t, --type <NAME>                 Full name of the message type, e.g. `pkg.Message`
+      --raw                         Decode without schema, fields are named by numbers
+      --format <FORMAT>             Output format, `text` or `json` [default: text]
+  -h, --help                        Print help";
+
+const ENCODE_HELP: &str = "\
+Encode a message read from stdin in text format or JSON, and write it in the
+binary form to stdout
+
+USAGE:
+  prust-build encode [OPTIONS] --type <NAME> <FILES>...
+  prust-build encode [OPTIONS] --type <NAME> --descriptor-set-in <FILE>
+
+OPTIONS:
+  -i, --include <DIR>               Directory to search imports in [default:
+                                    directories of the files]
+      --proto <FILE>                A proto file, the same as the positional ones
+      --descriptor-set-in <FILE>    Use an encoded `FileDescriptorSet` as the schema
+  -t, --type <NAME>                 Full name of the message type, e.g. `pkg.Message`
+      --format <FORMAT>             Input format, `text` or `json` [default: text]
+  -h, --help                        Print help";
+
+fn help(command: Option<&str>) {
+    let text = match command {
+        None => HELP,
+        Some("generate") => GENERATE_HELP,
+        Some("check") => CHECK_HELP,
+        Some("lint") => LINT_HELP,
+        Some("breaking") => BREAKING_HELP,
+        Some("fmt") => FMT_HELP,
+        Some("decode") => DECODE_HELP,
+        Some("encode") => ENCODE_HELP,
+        Some(command) => usage_error(format!("unknown command `{command}`")),
+    };
+
+    // `prust-build help | head` closes stdout early
+    let _ = writeln!(std::io::stdout(), "{text}");
 }
 
-fn handle_error(code: i32, msg: &str) {
-    println!("{}", msg);
-    std::process::exit(code);
+fn usage_error(msg: impl Display) -> ! {
+    eprintln!("error: {msg}");
+    eprintln!();
+    eprintln!("Run `prust-build help` for usage");
+    std::process::exit(USAGE);
 }
 
-fn main() {
-    let mut args = std::env::args().skip(1);
+fn fail(err: impl Display) -> ! {
+    eprintln!("{err}");
+    std::process::exit(FAILURE);
+}
+
+// arguments of a command, `-h` and `--help` print the help of it and exit
+struct Args<I> {
+    command: &'static str,
+    inner: I,
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    fn new(command: &'static str, inner: I) -> Self {
+        Self { command, inner }
+    }
+
+    fn value(&mut self, flag: &str) -> String {
+        self.inner
+            .next()
+            .unwrap_or_else(|| usage_error(format!("`{flag}` needs a value")))
+    }
+
+    // `PATH=ATTR` of attribute options
+    fn pair(&mut self, flag: &str) -> (String, String) {
+        let value = self.value(flag);
+        match value.split_once('=') {
+            Some((path, attribute)) => (path.to_string(), attribute.to_string()),
+            None => usage_error(format!("`{flag}` needs a value like `PATH=ATTR`")),
+        }
+    }
+
+    fn json(&mut self) -> bool {
+        match self.value("--format").as_str() {
+            "text" => false,
+            "json" => true,
+            _ => usage_error("format must be `text` or `json`"),
+        }
+    }
+
+    // positional arguments are returned, flags are rejected
+    fn positional(&self, value: String) -> String {
+        if value.starts_with('-') {
+            usage_error(format!("unknown option `{value}` of `{}`", self.command));
+        }
+        value
+    }
+
+    fn help(&self) -> ! {
+        help(Some(self.command));
+        std::process::exit(0);
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for Args<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.inner.next()
+    }
+}
+
+fn generate(mut args: Args<impl Iterator<Item = String>>) {
+    let mut config = Config::default();
+    let mut includes = Vec::new();
+    let mut protos = Vec::new();
+    let mut descriptor_set = None;
+    let mut output = ".".to_string();
+
+    while let Some(value) = args.next() {
+        match value.as_str() {
+            "-i" | "--include" => includes.push(args.value(&value)),
+            "-d" | "--out-dir" => output = args.value(&value),
+            "-o" | "--filename" => {
+                let name = args.value(&value);
+                config.filename(name.strip_suffix(".rs").unwrap_or(&name));
+            }
+            "--include-file" => {
+                config.include_file(&args.value(&value));
+            }
+            "--descriptor-set-in" => descriptor_set = Some(args.value(&value)),
+            "--descriptor-set-out" => {
+                config.file_descriptor_set_path(args.value(&value));
+            }
+            "--embed-descriptors" => {
+                config.embed_descriptors(true);
+            }
+            "--no-std" => {
+                config.no_std(true);
+            }
+            "--no-client" => {
+                config.build_client(false);
+            }
+            "--no-server" => {
+                config.build_server(false);
+            }
+            "--open-enums" => {
+                config.open_enums(true);
+            }
+            "--skip-deprecated-fields" => {
+                config.skip_deprecated_fields(true);
+            }
+            "--no-clone" => {
+                config.derive_clone(false);
+            }
+            "--no-partial-eq" => {
+                config.derive_partial_eq(false);
+            }
+            "--no-eq" => {
+                config.derive_eq(false);
+            }
+            "--no-hash" => {
+                config.derive_hash(false);
+            }
+            "--btree-map" => {
+                config.btree_map([args.value(&value)]);
+            }
+            "--hashmap" => {
+                config.hashmap([args.value(&value)]);
+            }
+            "--skip-serialize" => {
+                config.skip_serialize(&[args.value(&value)]);
+            }
+            "--skip-deserialize" => {
+                config.skip_deserialize(&[args.value(&value)]);
+            }
+            "--extern-path" => {
+                let (proto, rust) = args.pair(&value);
+                config.extern_path(proto, rust);
+            }
+            "--field-type" => {
+                let (path, rust_type) = args.pair(&value);
+                config.field_type(path, rust_type);
+            }
+            "--string-type" => {
+                let (path, rust_type) = args.pair(&value);
+                config.string_type(path, rust_type);
+            }
+            "--bytes-type" => {
+                let (path, rust_type) = args.pair(&value);
+                config.bytes_type(path, rust_type);
+            }
+            "--repeated-type" => {
+                let (path, container) = args.pair(&value);
+                config.repeated_type(path, container);
+            }
+            "--message-attribute" => {
+                let (path, attribute) = args.pair(&value);
+                config.message_attribute(path, attribute);
+            }
+            "--enum-attribute" => {
+                let (path, attribute) = args.pair(&value);
+                config.enum_attribute(path, attribute);
+            }
+            "--oneof-attribute" => {
+                let (path, attribute) = args.pair(&value);
+                config.oneof_attribute(path, attribute);
+            }
+            "--field-attribute" => {
+                let (path, attribute) = args.pair(&value);
+                config.field_attribute(path, attribute);
+            }
+            "-h" | "--help" => args.help(),
+            _ => protos.push(args.positional(value)),
+        }
+    }
+
+    config.output(output);
+
+    let result = match descriptor_set {
+        Some(_) if !protos.is_empty() => {
+            usage_error("proto files can't be used with `--descriptor-set-in`")
+        }
+        Some(path) => std::fs::read(&path)
+            .map_err(|err| format!("can't read `{path}`: {err}"))
+            .and_then(|set| config.compile_fds(&set).map_err(|err| err.to_string())),
+        None if protos.is_empty() => usage_error("no proto files specified"),
+        None => config
+            .compile(&includes, &protos)
+            .map_err(|err| err.to_string()),
+    };
+
+    if let Err(err) = result {
+        fail(err);
+    }
+}
+
+fn check(mut args: Args<impl Iterator<Item = String>>) {
+    let mut includes = Vec::new();
+    let mut protos = Vec::new();
+
+    while let Some(value) = args.next() {
+        match value.as_str() {
+            "-i" | "--include" => includes.push(args.value(&value)),
+            "-h" | "--help" => args.help(),
+            _ => protos.push(args.positional(value)),
+        }
+    }
+
+    if protos.is_empty() {
+        usage_error("no proto files specified");
+    }
+
+    if let Err(err) = Config::default().check(&includes, &protos) {
+        fail(err);
+    }
+}
+
+// check semantics of proto files, one diagnostic per line
+fn lint(mut args: Args<impl Iterator<Item = String>>) {
     let mut includes = Vec::new();
     let mut protos = Vec::new();
-    let mut output = String::new();
+    let mut json = false;
+
+    while let Some(value) = args.next() {
+        match value.as_str() {
+            "-i" | "--include" => includes.push(args.value(&value)),
+            "--format" => json = args.json(),
+            "-h" | "--help" => args.help(),
+            _ => protos.push(args.positional(value)),
+        }
+    }
+
+    if protos.is_empty() {
+        usage_error("no proto files specified");
+    }
+
+    let diagnostics = match prust_build::lint(&includes, &protos) {
+        Ok(diagnostics) => diagnostics,
+        // still one JSON object per line
+        Err(err) if json => {
+            for line in error_to_json(&err) {
+                println!("{line}");
+            }
+            std::process::exit(FAILURE);
+        }
+        Err(err) => fail(err),
+    };
+
+    for diagnostic in &diagnostics {
+        if json {
+            println!("{}", diagnostic.to_json());
+        } else {
+            println!("{diagnostic}");
+        }
+    }
+
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity() == Severity::Error)
+    {
+        std::process::exit(FAILURE);
+    }
+}
+
+// compare two versions of proto files, files or directories, one breaking
+// change per line
+fn breaking(mut args: Args<impl Iterator<Item = String>>) {
+    let mut includes = Vec::new();
+    let mut paths = Vec::new();
+    let mut json = false;
+
+    while let Some(value) = args.next() {
+        match value.as_str() {
+            "-i" | "--include" => includes.push(args.value(&value)),
+            "--format" => json = args.json(),
+            "-h" | "--help" => args.help(),
+            _ => paths.push(args.positional(value)),
+        }
+    }
+
+    let [old, new] = paths.as_slice() else {
+        usage_error("the old and the new version must be specified");
+    };
+
+    let changes =
+        prust_build::breaking(&includes, old.clone(), new.clone()).unwrap_or_else(|err| fail(err));
+
+    for change in &changes {
+        if json {
+            println!("{}", change.to_json());
+        } else {
+            println!("{change}");
+        }
+    }
+
+    if !changes.is_empty() {
+        std::process::exit(FAILURE);
+    }
+}
+
+// format proto files in place, or list the ones not formatted with `--check`
+fn fmt(mut args: Args<impl Iterator<Item = String>>) {
+    let mut protos = Vec::new();
+    let mut check = false;
 
     while let Some(value) = args.next() {
         match value.as_str() {
-            "-i" | "--include" => {
-                let Some(value) = args.next() else {
-                    handle_error(1, "include value not provided");
-                    return;
-                };
+            "--check" => check = true,
+            "-h" | "--help" => args.help(),
+            _ => protos.push(args.positional(value)),
+        }
+    }
+
+    if protos.is_empty() {
+        usage_error("no proto files specified");
+    }
 
-                includes.push(value);
+    let mut unformatted = false;
+    for path in &protos {
+        let result = prust_build::format::format_file(path).and_then(|output| {
+            let content = std::fs::read(path)?;
+            if content == output.as_bytes() {
+                return Ok(());
             }
-            "-d" => {
-                let Some(value) = args.next() else {
-                    handle_error(1, "output directory not provided");
-                    panic!("-d needs a value");
-                };
 
-                output = value;
+            if check {
+                println!("{path}");
+                unformatted = true;
+                Ok(())
+            } else {
+                std::fs::write(path, output).map_err(Into::into)
             }
-            "-o" => {
-                let Some(value) = args.next() else {
-                    handle_error(1, "filename not provided");
-                    return;
-                };
+        });
+
+        if let Err(err) = result {
+            fail(err);
+        }
+    }
+
+    if unformatted {
+        std::process::exit(FAILURE);
+    }
+}
+
+// options shared by `decode` and `encode`, the schema and the message type
+#[derive(Default)]
+struct Payload {
+    includes: Vec<String>,
+    protos: Vec<String>,
+    descriptor_set: Option<String>,
+    message: Option<String>,
+    json: bool,
+}
+
+impl Payload {
+    // `false` if `value` is none of the options
+    fn option(&mut self, args: &mut Args<impl Iterator<Item = String>>, value: &str) -> bool {
+        match value {
+            "-i" | "--include" => self.includes.push(args.value(value)),
+            "--proto" => self.protos.push(args.value(value)),
+            "--descriptor-set-in" => self.descriptor_set = Some(args.value(value)),
+            "-t" | "--type" => self.message = Some(args.value(value)),
+            "--format" => self.json = args.json(),
+            "-h" | "--help" => args.help(),
+            _ => return false,
+        }
+        true
+    }
 
-                output = value;
+    fn schema(&mut self) -> (Schema, String) {
+        let Some(message) = self.message.take() else {
+            usage_error("the message type must be specified with `--type`");
+        };
+
+        let schema = match &self.descriptor_set {
+            Some(_) if !self.protos.is_empty() => {
+                usage_error("proto files can't be used with `--descriptor-set-in`")
             }
-            "-h" | "--help" => {
-                help();
-                return;
+            Some(path) => std::fs::read(path)
+                .map_err(|err| format!("can't read `{path}`: {err}"))
+                .and_then(|set| Schema::from_descriptor_set(&set).map_err(|err| err.to_string())),
+            None if self.protos.is_empty() => usage_error("no proto files specified"),
+            None => {
+                // imports are next to the files usually
+                if self.includes.is_empty() {
+                    for path in &self.protos {
+                        let dir = match std::path::Path::new(path).parent() {
+                            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy(),
+                            _ => ".".into(),
+                        };
+                        if !self.includes.iter().any(|include| *include == dir) {
+                            self.includes.push(dir.into_owned());
+                        }
+                    }
+                }
+
+                Schema::load(&self.includes, &self.protos).map_err(|err| err.to_string())
             }
-            _ => protos.push(value),
+        };
+
+        (schema.unwrap_or_else(|err| fail(err)), message)
+    }
+}
+
+fn read_stdin() -> Vec<u8> {
+    let mut input = vec![];
+    std::io::stdin()
+        .read_to_end(&mut input)
+        .unwrap_or_else(|err| fail(format!("can't read stdin: {err}")));
+    input
+}
+
+// decode a binary message from stdin
+fn decode(mut args: Args<impl Iterator<Item = String>>) {
+    let mut payload = Payload::default();
+    let mut raw = false;
+
+    while let Some(value) = args.next() {
+        match value.as_str() {
+            "--raw" => raw = true,
+            _ if payload.option(&mut args, &value) => {}
+            _ => payload.protos.push(args.positional(value)),
         }
     }
 
-    if protos.is_empty() {
-        panic!("`protos` must be specified");
+    let message = if raw {
+        if payload.message.is_some() || !payload.protos.is_empty() {
+            usage_error("`--raw` can't be used with a schema");
+        }
+        prust_build::dynamic::decode_raw(&read_stdin())
+    } else {
+        let (schema, message) = payload.schema();
+        schema.decode(&message, &read_stdin())
+    };
+
+    let message = message.unwrap_or_else(|err| fail(err));
+    let output = if payload.json {
+        format!("{}\n", message.to_json())
+    } else {
+        message.to_string()
+    };
+    let _ = std::io::stdout().write_all(output.as_bytes());
+}
+
+// encode a message in text format or JSON from stdin
+fn encode(mut args: Args<impl Iterator<Item = String>>) {
+    let mut payload = Payload::default();
+
+    while let Some(value) = args.next() {
+        if !payload.option(&mut args, &value) {
+            payload.protos.push(args.positional(value));
+        }
+    }
+
+    let (schema, message) = payload.schema();
+    let input = read_stdin();
+    let result = if payload.json {
+        schema.encode_json(&message, &input)
+    } else {
+        schema.encode_text(&message, &input)
+    };
+
+    let encoded = result.unwrap_or_else(|err| fail(err));
+    if let Err(err) = std::io::stdout().write_all(&encoded) {
+        fail(format!("can't write stdout: {err}"));
     }
+}
 
-    println!("includes: {:?}", includes);
-    println!("protos: {:?}", protos);
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
 
-    Config::default()
-        .output(output)
-        .compile(&includes, &protos)
-        .unwrap();
+    let command = match args.peek().map(String::as_str) {
+        None => {
+            help(None);
+            std::process::exit(USAGE);
+        }
+        Some("-h" | "--help") => return help(None),
+        Some("help") => {
+            args.next();
+            return help(args.next().as_deref());
+        }
+        Some(first) => match COMMANDS.into_iter().find(|command| *command == first) {
+            Some(command) => {
+                args.next();
+                command
+            }
+            // generating is the default, like before commands were added
+            None => "generate",
+        },
+    };
+
+    let args = Args::new(command, args);
+    match command {
+        "check" => check(args),
+        "lint" => lint(args),
+        "breaking" => breaking(args),
+        "fmt" => fmt(args),
+        "decode" => decode(args),
+        "encode" => encode(args),
+        _ => generate(args),
+    }
 }
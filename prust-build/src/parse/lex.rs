@@ -1,5 +1,5 @@
 use std::fmt::{Display, Formatter};
-use std::ops::{Neg, Range};
+use std::ops::Neg;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Span {
@@ -7,13 +7,6 @@ pub struct Span {
     pub end: usize,
 }
 
-impl Span {
-    #[inline]
-    pub fn range(&self) -> Range<usize> {
-        self.start..self.end
-    }
-}
-
 #[derive(Debug, PartialEq)]
 pub enum Token<'a> {
     Ident(&'a str),
@@ -60,11 +53,199 @@ impl Display for Token<'_> {
 pub struct Lexer<'a> {
     source: &'a [u8],
     pos: usize,
+    // span of the last token, errors without a better location point to it
+    last: Span,
+    // all comments met so far, in order, `//` comments end before the newline
+    comments: Vec<Span>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a [u8]) -> Self {
-        Self { source, pos: 0 }
+        Self {
+            source,
+            pos: 0,
+            last: Span { start: 0, end: 0 },
+            comments: vec![],
+        }
+    }
+
+    /// Returns the next token without consuming it
+    pub fn peek(&mut self) -> Option<Result<(Token<'a>, Span), Error>> {
+        let (pos, last) = (self.pos, self.last);
+        let next = self.next();
+        self.pos = pos;
+        self.last = last;
+        next
+    }
+
+    // `peek` lexes the same comments again
+    fn record_comment(&mut self, start: usize) {
+        if self
+            .comments
+            .last()
+            .is_none_or(|comment| comment.start < start)
+        {
+            self.comments.push(Span {
+                start,
+                end: self.pos.min(self.source.len()),
+            });
+        }
+    }
+
+    /// Comments between the previous token and `pos`, which start new lines.
+    /// Blocks are separated by blank lines, the last one is the leading
+    /// comment if no blank line follows it, and the others are detached.
+    pub fn comment_blocks(&self, pos: usize) -> (Vec<String>, Option<String>) {
+        let mut index = self.comments.partition_point(|comment| comment.end <= pos);
+        let mut next = pos;
+        let mut blocks = vec![];
+        let mut block = vec![];
+        // whether the block nearest to `pos` is followed by a blank line
+        let mut separated = false;
+
+        while index > 0 {
+            let comment = self.comments[index - 1];
+            let gap = &self.source[comment.end..next];
+            if !gap.iter().all(u8::is_ascii_whitespace) {
+                break;
+            }
+
+            // it trails something else
+            let line = &self.source[..comment.start];
+            let line = &line[line
+                .iter()
+                .rposition(|ch| *ch == b'\n')
+                .map_or(0, |i| i + 1)..];
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                break;
+            }
+
+            if gap.iter().filter(|ch| **ch == b'\n').count() > 1 {
+                if block.is_empty() {
+                    separated = true;
+                } else {
+                    blocks.push(std::mem::take(&mut block));
+                }
+            }
+
+            block.push(self.comment_text(comment));
+            next = comment.start;
+            index -= 1;
+        }
+        if !block.is_empty() {
+            blocks.push(block);
+        }
+
+        let mut blocks = blocks
+            .into_iter()
+            .rev()
+            .map(|mut block| {
+                block.reverse();
+                let text = block.join("\n");
+                let text = text.trim_matches('\n');
+                (!text.trim().is_empty()).then(|| text.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        let leading = if separated {
+            None
+        } else {
+            blocks.pop().flatten()
+        };
+        let blocks = blocks.into_iter().flatten().collect();
+        (blocks, leading)
+    }
+
+    /// Texts of all comments, in order
+    pub fn comments(&self) -> impl Iterator<Item = (Span, String)> + '_ {
+        self.comments
+            .iter()
+            .map(|comment| (*comment, self.comment_text(*comment)))
+    }
+
+    /// Comment starts after `pos` and on the same line
+    pub fn trailing_comment(&mut self, pos: usize) -> Option<String> {
+        // comments are recorded while skipping them
+        self.skip_whitespaces();
+
+        let index = self.comments.partition_point(|comment| comment.start < pos);
+        let comment = self.comments.get(index)?;
+        let gap = &self.source[pos..comment.start];
+        if !gap.iter().all(|ch| *ch == b' ' || *ch == b'\t') {
+            return None;
+        }
+
+        let text = self.comment_text(*comment);
+        let text = text.trim_matches('\n');
+        (!text.trim().is_empty()).then(|| text.to_string())
+    }
+
+    // strip comment markers, and the first space of each line
+    fn comment_text(&self, comment: Span) -> String {
+        let raw = String::from_utf8_lossy(&self.source[comment.start..comment.end]);
+
+        let lines = match raw.strip_prefix("/*") {
+            Some(rest) => {
+                let rest = rest.strip_suffix("*/").unwrap_or(rest);
+                rest.trim_start_matches('*')
+                    .lines()
+                    .map(|line| {
+                        let trimmed = line.trim_start();
+                        trimmed.strip_prefix('*').unwrap_or(trimmed).to_string()
+                    })
+                    .collect::<Vec<_>>()
+            }
+            None => vec![raw.trim_start_matches('/').to_string()],
+        };
+
+        lines
+            .iter()
+            .map(|line| {
+                let line = line.trim_end();
+                line.strip_prefix(' ').unwrap_or(line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Put the last token back, so the next call of `next` returns it again
+    #[inline]
+    pub fn unread(&mut self) {
+        self.pos = self.last.start;
+    }
+
+    /// Span of the last token returned by `next`
+    #[inline]
+    pub fn last_span(&self) -> Span {
+        self.last
+    }
+
+    /// Line and column of `pos`, both start from 1, and the whole line
+    pub fn locate(&self, pos: usize) -> (usize, usize, &'a str) {
+        let pos = pos.min(self.source.len());
+        let start = self.source[..pos]
+            .iter()
+            .rposition(|ch| *ch == b'\n')
+            .map_or(0, |index| index + 1);
+        let end = self.source[pos..]
+            .iter()
+            .position(|ch| *ch == b'\n')
+            .map_or(self.source.len(), |index| pos + index);
+
+        let line = self.source[..start]
+            .iter()
+            .filter(|ch| **ch == b'\n')
+            .count()
+            + 1;
+        let text = std::str::from_utf8(&self.source[start..end])
+            .unwrap_or_default()
+            .trim_end_matches('\r');
+        let column = String::from_utf8_lossy(&self.source[start..pos])
+            .chars()
+            .count()
+            + 1;
+
+        (line, column, text)
     }
 
     fn skip_whitespaces(&mut self) {
@@ -81,23 +262,29 @@ impl<'a> Lexer<'a> {
                     let next = self.source[self.pos + 1];
                     if next == b'/' {
                         // meet comments with double slash
+                        let start = self.pos;
                         while self.pos < self.source.len() && self.source[self.pos] != b'\n' {
                             self.pos += 1;
                         }
+                        self.record_comment(start);
 
                         // this line skipped already
                         self.pos += 1;
                     } else if next == b'*' {
                         /* meet comment with slash and start */
+                        let start = self.pos;
+                        self.pos += 2;
                         while self.pos < self.source.len() {
+                            let ch = self.source[self.pos];
                             self.pos += 1;
 
-                            let ch = self.source[self.pos];
-                            if ch == b'/' && self.source[self.pos - 1] == b'*' {
-                                self.pos += 1;
+                            if ch == b'/' && self.source[self.pos - 2] == b'*' {
                                 break;
                             }
                         }
+                        self.record_comment(start);
+                    } else {
+                        break;
                     }
 
                     continue;
@@ -107,53 +294,6 @@ impl<'a> Lexer<'a> {
             break;
         }
     }
-
-    pub fn diagnostic(&self, span: Span, msg: impl AsRef<str>) -> String {
-        use std::iter::repeat_n;
-
-        let mut pending = 0;
-        let mut start = 0;
-        let mut lines = 1;
-        for pos in (0..span.start).rev() {
-            let ch = self.source[pos];
-            if ch == b'\n' {
-                if pending != 0 {
-                    pending = pos;
-                }
-                lines += 1;
-
-                if start == 0 {
-                    start = pos + 1;
-                }
-            }
-        }
-
-        let mut end = span.end;
-        while end < self.source.len() {
-            if self.source[end] == b'\n' {
-                break;
-            }
-
-            end += 1;
-        }
-
-        let line_part = lines.to_string();
-
-        let mut output = String::new();
-        output.push_str(&format!(
-            "{}: {}\n",
-            line_part,
-            String::from_utf8_lossy(&self.source[start..end])
-        ));
-        output.push_str(&format!(
-            "{}{} {}",
-            repeat_n(' ', line_part.len() + 2 + pending).collect::<String>(),
-            repeat_n('-', span.end - span.start).collect::<String>(),
-            msg.as_ref()
-        ));
-
-        output
-    }
 }
 
 #[derive(Debug)]
@@ -165,13 +305,58 @@ pub enum Error {
     // underflow or overflow
     InvalidInteger,
 
-    InvalidFlota,
+    InvalidFloat,
+
+    UnterminatedString,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Unknown => f.write_str("unknown token"),
+            Error::Eof => f.write_str("unexpected end of file"),
+            Error::InvalidInteger => f.write_str("invalid integer"),
+            Error::InvalidFloat => f.write_str("invalid float"),
+            Error::UnterminatedString => f.write_str("unterminated string"),
+        }
+    }
 }
 
 impl<'a> Iterator for Lexer<'a> {
     type Item = Result<(Token<'a>, Span), Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespaces();
+
+        let start = self.pos;
+        let next = self.lex();
+        match &next {
+            Some(Ok((_, span))) => self.last = *span,
+            Some(Err(_)) => {
+                // always move forward, so the parser can recover from it
+                if self.pos == start {
+                    self.pos += 1;
+                }
+
+                self.last = Span {
+                    start,
+                    end: self.pos.min(self.source.len()),
+                };
+            }
+            None => {
+                self.last = Span {
+                    start: self.source.len(),
+                    end: self.source.len(),
+                }
+            }
+        }
+
+        next
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn lex(&mut self) -> Option<Result<(Token<'a>, Span), Error>> {
         loop {
             self.skip_whitespaces();
 
@@ -303,8 +488,11 @@ impl<'a> Iterator for Lexer<'a> {
                     b'\'' => {
                         let start = self.pos + 1;
 
-                        while self.pos < self.source.len() {
+                        loop {
                             self.pos += 1;
+                            if self.pos >= self.source.len() {
+                                return Some(Err(Error::UnterminatedString));
+                            }
 
                             let ch = self.source[self.pos];
                             if ch == b'\'' {
@@ -321,14 +509,15 @@ impl<'a> Iterator for Lexer<'a> {
                                 }
                             }
                         }
-
-                        return Some(Err(Error::Unknown));
                     }
                     b'"' => {
                         let start = self.pos + 1;
 
-                        while self.pos < self.source.len() {
+                        loop {
                             self.pos += 1;
+                            if self.pos >= self.source.len() {
+                                return Some(Err(Error::UnterminatedString));
+                            }
 
                             let ch = self.source[self.pos];
                             if ch == b'"' {
@@ -345,42 +534,30 @@ impl<'a> Iterator for Lexer<'a> {
                                 }
                             }
                         }
-
-                        return Some(Err(Error::Unknown));
                     }
-                    b'a'..=b'z' | b'A'..=b'Z' => {
+                    // a leading dot is allowed for full qualified names, e.g.
+                    // `.foo.Bar`, and sub paths of options, e.g. `(foo).bar`
+                    b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'.' => {
                         let start = self.pos;
                         while self.pos < self.source.len() {
                             let ch = self.source[self.pos];
-                            if ch.is_ascii_whitespace()
-                                || ch == b':'
-                                || ch == b';'
-                                || ch == b']'
-                                || ch == b')'
-                                || ch == b'('
-                                || ch == b','
-                                || ch == b'='
-                                || ch == b'-'
-                                || ch == b'<'
-                                || ch == b'>'
-                            {
-                                let ident = unsafe {
-                                    std::str::from_utf8_unchecked(&self.source[start..self.pos])
-                                };
-
-                                return Some(Ok((
-                                    Token::Ident(ident),
-                                    Span {
-                                        start,
-                                        end: self.pos,
-                                    },
-                                )));
+                            if !ch.is_ascii_alphanumeric() && ch != b'_' && ch != b'.' {
+                                break;
                             }
 
                             self.pos += 1;
                         }
 
-                        return Some(Err(Error::Unknown));
+                        let ident =
+                            unsafe { std::str::from_utf8_unchecked(&self.source[start..self.pos]) };
+
+                        return Some(Ok((
+                            Token::Ident(ident),
+                            Span {
+                                start,
+                                end: self.pos,
+                            },
+                        )));
                     }
                     // tag is u32, but option's value field might be negative
                     b'-' | b'0'..=b'9' => {
@@ -391,80 +568,97 @@ impl<'a> Iterator for Lexer<'a> {
                         } else {
                             false
                         };
+                        let span = |end| Span { start, end };
 
-                        let mut dots = 0;
-                        while self.pos < self.source.len() {
-                            let ch = self.source[self.pos];
-                            if ch == b'.' {
-                                self.pos += 1;
-                                dots += 1;
-                                continue;
-                            }
+                        let rest = &self.source[self.pos..];
+                        if rest.starts_with(b"inf") || rest.starts_with(b"nan") {
+                            self.pos += 3;
+
+                            let value = match (rest[0], negative) {
+                                (b'n', true) => return Some(Err(Error::Unknown)),
+                                (b'n', false) => f64::NAN,
+                                (_, true) => f64::NEG_INFINITY,
+                                (_, false) => f64::INFINITY,
+                            };
 
-                            if ch.is_ascii_digit() || b"afin".contains(&ch) {
+                            return Some(Ok((Token::Float(value), span(self.pos))));
+                        }
+
+                        // hex, e.g. 0x1F
+                        if rest.starts_with(b"0x") || rest.starts_with(b"0X") {
+                            self.pos += 2;
+                            let digits = self.pos;
+                            while self.pos < self.source.len()
+                                && self.source[self.pos].is_ascii_hexdigit()
+                            {
                                 self.pos += 1;
-                                continue;
-                            } else {
-                                break;
                             }
-                        }
 
-                        if dots >= 2 {
-                            return Some(Err(Error::Unknown));
+                            let s = unsafe {
+                                std::str::from_utf8_unchecked(&self.source[digits..self.pos])
+                            };
+                            return match i64::from_str_radix(s, 16) {
+                                Ok(value) => Some(Ok((
+                                    Token::Integer(if negative { value.neg() } else { value }),
+                                    span(self.pos),
+                                ))),
+                                Err(_err) => Some(Err(Error::InvalidInteger)),
+                            };
                         }
 
-                        let n = &self.source[start + negative as usize..self.pos];
-                        let s = unsafe { std::str::from_utf8_unchecked(n) };
-                        return if dots == 0 {
-                            if s == "nan" {
-                                if negative {
+                        let digits = self.pos;
+                        let mut float = false;
+                        while self.pos < self.source.len() {
+                            let ch = self.source[self.pos];
+                            if ch.is_ascii_digit() {
+                                self.pos += 1;
+                            } else if ch == b'.' {
+                                if float {
                                     return Some(Err(Error::Unknown));
                                 }
 
-                                return Some(Ok((
-                                    Token::Float(f64::NAN),
-                                    Span {
-                                        start,
-                                        end: self.pos,
-                                    },
-                                )));
-                            }
-                            if s == "inf" {
-                                let v = if negative {
-                                    f64::NEG_INFINITY
-                                } else {
-                                    f64::INFINITY
-                                };
-                                return Some(Ok((
-                                    Token::Float(v),
-                                    Span {
-                                        start,
-                                        end: self.pos,
-                                    },
-                                )));
+                                float = true;
+                                self.pos += 1;
+                            } else if ch == b'e' || ch == b'E' {
+                                // exponent, e.g. 1e10, 1.5E-3
+                                float = true;
+                                self.pos += 1;
+                                if self.pos < self.source.len()
+                                    && (self.source[self.pos] == b'-'
+                                        || self.source[self.pos] == b'+')
+                                {
+                                    self.pos += 1;
+                                }
+                            } else {
+                                break;
                             }
-                            // integer
-                            match s.parse::<i64>().map_err(|_err| Error::InvalidInteger) {
+                        }
+
+                        let s = unsafe {
+                            std::str::from_utf8_unchecked(&self.source[digits..self.pos])
+                        };
+                        return if float {
+                            match s.parse::<f64>() {
                                 Ok(value) => Some(Ok((
-                                    Token::Integer(if negative { value.neg() } else { value }),
-                                    Span {
-                                        start,
-                                        end: self.pos,
-                                    },
+                                    Token::Float(if negative { value.neg() } else { value }),
+                                    span(self.pos),
                                 ))),
-                                Err(_err) => Some(Err(Error::InvalidInteger)),
+                                Err(_err) => Some(Err(Error::InvalidFloat)),
                             }
                         } else {
-                            // float
-                            match s.parse::<f64>().map_err(|_err| Error::InvalidInteger) {
+                            // a leading zero means octal, e.g. 017
+                            let radix = if s.len() > 1 && s.starts_with('0') {
+                                8
+                            } else {
+                                10
+                            };
+
+                            match i64::from_str_radix(s, radix) {
                                 Ok(value) => Some(Ok((
-                                    Token::Float(if negative { value.neg() } else { value }),
-                                    Span {
-                                        start,
-                                        end: self.pos,
-                                    },
+                                    Token::Integer(if negative { value.neg() } else { value }),
+                                    span(self.pos),
                                 ))),
-                                Err(_err) => Some(Err(Error::InvalidFlota)),
+                                Err(_err) => Some(Err(Error::InvalidInteger)),
                             }
                         };
                     }
@@ -2,4 +2,8 @@ mod lex;
 mod parse;
 mod unescape;
 
-pub use parse::{Error, parse};
+pub use lex::Error as LexError;
+pub(crate) use parse::parse_recovering;
+pub use parse::{Error, ErrorKind, parse};
+pub(crate) use parse::{comments, parse_aggregate_value, parse_text_format};
+pub(crate) use unescape::unescape_c_escape_string;
@@ -1,230 +1,504 @@
-use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 
 use super::lex::{Error as LexerError, Lexer, Span, Token};
 use crate::ast::{
-    Enum, Extension, Field, FieldType, FileDescriptor, Function, Label, Message, Method, OneOf,
-    OneOfVarint, Reserved, Service, Syntax,
+    Comments, Enum, EnumVariant, Extend, Extension, Field, FieldType, FileDescriptor, Function,
+    Import, ImportKind, Label, Location, Message, Method, NamePart, OneOf, OneOfVarint, OptionName,
+    OptionValue, Options, ProtoOption, Reserved, Service, Syntax,
 };
 use crate::parse::unescape::unescape_c_escape_string;
 
 const PROTOBUF_RESERVED: Reserved = Reserved::Range(19000, 19999);
+const MAX_FIELD_NUMBER: i64 = (1 << 29) - 1;
 
 /*
 1 to 536,870,911
 19,000 to 19,999
 */
 #[derive(Debug)]
-pub enum Error {
+pub enum ErrorKind {
     Lex(LexerError),
 
-    Unknown(String),
-
-    Unexpected {
-        token: String,
-        span: Span,
-        expected: String,
-    },
+    Unexpected { token: String, expected: String },
 
     Eof,
 
-    InvalidFieldNumber {
-        number: i64,
-        reason: String,
-        span: Span,
-    },
+    InvalidFieldNumber { number: i64, reason: String },
 
     InvalidMapKey(String),
 
+    // bad escapes in string literals
+    InvalidString(String),
+
     Duplicate(String),
 
     Unsupported(String),
 
     NotAllowed(String),
+
+    // the first value of a proto3 enum is not zero, which the linter
+    // recovers from
+    EnumZeroValue,
 }
 
-impl<'a> From<LexerError> for Error {
+impl From<LexerError> for ErrorKind {
     fn from(err: LexerError) -> Self {
         match err {
-            LexerError::Eof => Error::Eof,
-            err => Error::Lex(err),
+            LexerError::Eof => ErrorKind::Eof,
+            err => ErrorKind::Lex(err),
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Lex(err) => err.fmt(f),
+            ErrorKind::Unexpected { token, expected } => {
+                write!(f, "expected {expected}, found `{token}`")
+            }
+            ErrorKind::Eof => f.write_str("unexpected end of file"),
+            ErrorKind::InvalidFieldNumber { number, reason } => {
+                write!(f, "invalid field number {number}, {reason}")
+            }
+            ErrorKind::InvalidMapKey(typ) => write!(f, "`{typ}` can not be used as a map key"),
+            ErrorKind::InvalidString(msg)
+            | ErrorKind::Duplicate(msg)
+            | ErrorKind::Unsupported(msg)
+            | ErrorKind::NotAllowed(msg) => f.write_str(msg),
+            ErrorKind::EnumZeroValue => f.write_str("first variant of enum must be zero in proto3"),
         }
     }
 }
 
+/// A parse failure and where it happens
+#[derive(Debug)]
+pub struct Error(Box<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    kind: ErrorKind,
+    file: Option<PathBuf>,
+    line: usize,
+    column: usize,
+
+    span: Span,
+    // the whole line, for rendering
+    text: String,
+}
+
+impl Error {
+    fn new(kind: ErrorKind, span: Span) -> Self {
+        Self(Box::new(Inner {
+            kind,
+            file: None,
+            line: 0,
+            column: 0,
+            span,
+            text: String::new(),
+        }))
+    }
+
+    fn locate(mut self, lexer: &Lexer) -> Self {
+        let (line, column, text) = lexer.locate(self.0.span.start);
+
+        self.0.line = line;
+        self.0.column = column;
+        self.0.text = text.to_string();
+        self
+    }
+
+    #[inline]
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0.kind
+    }
+
+    /// The proto file, it is set when the file is loaded by `Config`
+    #[inline]
+    pub fn file(&self) -> Option<&Path> {
+        self.0.file.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn set_file(&mut self, path: impl Into<PathBuf>) {
+        self.0.file = Some(path.into());
+    }
+
+    /// Line number, starts from 1
+    #[inline]
+    pub fn line(&self) -> usize {
+        self.0.line
+    }
+
+    /// Column number in characters, starts from 1
+    #[inline]
+    pub fn column(&self) -> usize {
+        self.0.column
+    }
+}
+
+/// Renders the error like rustc does
+///
+/// ```text
+/// error: expected `;`, found `int32`
+///  --> foo.proto:3:5
+///   |
+/// 3 |     int32 bar = 1
+///   |     ^^^^^
+/// ```
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Inner {
+            kind,
+            file,
+            line,
+            column,
+            span,
+            text,
+        } = &*self.0;
+
+        let line = line.to_string();
+        let pad = " ".repeat(line.len());
+        let file = match file {
+            Some(path) => path.display().to_string(),
+            None => "<input>".to_string(),
+        };
+        let indent: String = text
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = text
+            .chars()
+            .skip(column.saturating_sub(1))
+            .take(span.end.saturating_sub(span.start))
+            .count()
+            .max(1);
+
+        writeln!(f, "error: {kind}")?;
+        writeln!(f, "{pad}--> {file}:{line}:{column}")?;
+        writeln!(f, "{pad} |")?;
+        writeln!(f, "{line} | {text}")?;
+        write!(f, "{pad} | {indent}{}", "^".repeat(width))
+    }
+}
+
+impl std::error::Error for Error {}
+
+// comments of the definition starts at `start`, the trailing one is after
+// `end`, which is the semicolon or the left brace
+fn lookup_comments(lexer: &mut Lexer, start: usize, end: usize) -> Comments {
+    let (detached, leading) = lexer.comment_blocks(start);
+
+    Comments {
+        leading,
+        trailing: lexer.trailing_comment(end),
+        detached,
+        closing: vec![],
+    }
+}
+
+// `reserved` statements are merged, so are their comments
+fn merge_comments(into: &mut Comments, comments: Comments) {
+    into.detached.extend(comments.detached);
+    for (into, text, separator) in [
+        (&mut into.leading, comments.leading, "\n"),
+        (&mut into.trailing, comments.trailing, " "),
+    ] {
+        *into = match (into.take(), text) {
+            (Some(into), Some(text)) => Some(format!("{into}{separator}{text}")),
+            (into, text) => into.or(text),
+        };
+    }
+}
+
+// comments before the right brace of a block, or the end of file at `end`
+fn closing_comments(lexer: &Lexer, end: usize) -> Vec<String> {
+    let (mut blocks, last) = lexer.comment_blocks(end);
+    blocks.extend(last);
+    blocks
+}
+
+/// Texts of all comments in `input`, with the lines they start at
+pub(crate) fn comments(input: &[u8]) -> Vec<(usize, String)> {
+    let mut lexer = Lexer::new(input);
+    while lexer.next().is_some() {}
+
+    lexer
+        .comments()
+        .map(|(span, text)| (lexer.locate(span.start).0, text))
+        .collect()
+}
+
+// lines and columns are zero based in descriptors
+fn locate(lexer: &Lexer, start: usize, end: usize) -> Location {
+    let (start_line, start_column, _) = lexer.locate(start);
+    let (end_line, end_column, _) = lexer.locate(end);
+
+    Location {
+        start_line: start_line as u32 - 1,
+        start_column: start_column as u32 - 1,
+        end_line: end_line as u32 - 1,
+        end_column: end_column as u32 - 1,
+    }
+}
+
+fn unexpected(token: Token, expected: impl Into<String>, span: Span) -> Error {
+    Error::new(
+        ErrorKind::Unexpected {
+            token: token.to_string(),
+            expected: expected.into(),
+        },
+        span,
+    )
+}
+
 struct Context {
     syntax: Syntax,
+
+    errors: Vec<Error>,
 }
 
-pub fn parse(input: &[u8]) -> Result<FileDescriptor, Error> {
-    let mut lexer = Lexer::new(input);
+impl Context {
+    // errors which don't break the syntax, parsing goes on
+    fn report(&mut self, kind: ErrorKind, span: Span) {
+        self.errors.push(Error::new(kind, span));
+    }
+
+    // record the error and skip the rest of the broken statement, so one run
+    // reports as many problems as possible. Nothing can be recovered from eof.
+    fn recover(&mut self, lexer: &mut Lexer, err: Error) -> Result<(), Error> {
+        if matches!(err.kind(), ErrorKind::Eof) {
+            return Err(err);
+        }
 
-    let mut package = None;
-    let mut imports = Vec::<String>::new();
-    let mut options = HashMap::new();
-    let mut messages = vec![];
-    let mut enums = vec![];
-    let mut services = vec![];
+        // the unexpected right brace closes the enclosing block
+        if let ErrorKind::Unexpected { token, .. } = err.kind()
+            && token == "}"
+        {
+            lexer.unread();
+        }
+
+        self.errors.push(err);
+        skip_statement(lexer);
+
+        Ok(())
+    }
+}
+
+// skip tokens to the end of current statement, which is a semicolon or a
+// block. The right brace closes the enclosing block is left to the caller.
+fn skip_statement(lexer: &mut Lexer) {
+    let mut depth = 0;
+
+    loop {
+        match lexer.peek() {
+            None => return,
+            Some(Ok((Token::RightBrace, _))) if depth == 0 => return,
+            _ => {}
+        }
+
+        match lexer.next() {
+            Some(Ok((Token::LeftBrace, _))) => depth += 1,
+            Some(Ok((Token::RightBrace, _))) => {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+            Some(Ok((Token::Semicolon, _))) if depth == 0 => return,
+            _ => {}
+        }
+    }
+}
+
+/// Parse a proto file, all errors are returned if there is any
+pub fn parse(input: &[u8]) -> Result<FileDescriptor, Vec<Error>> {
+    parse_recovering(input, |_| false)
+}
+
+// like `parse`, but errors `recoverable` accepts don't fail the file, e.g.
+// the linter reports some of them as rules
+pub(crate) fn parse_recovering(
+    input: &[u8],
+    recoverable: impl Fn(&ErrorKind) -> bool,
+) -> Result<FileDescriptor, Vec<Error>> {
+    let mut lexer = Lexer::new(input);
+    let mut fd = FileDescriptor::default();
 
     let mut cx = Context {
         syntax: Default::default(),
+        errors: vec![],
     };
 
-    while let Some(result) = lexer.next() {
-        let (token, span) = result?;
+    loop {
+        let result = match take_next(&mut lexer) {
+            Ok((token, span)) => parse_statement(&mut lexer, &mut cx, &mut fd, token, span),
+            Err(err) if matches!(err.kind(), ErrorKind::Eof) => {
+                fd.comments.closing = closing_comments(&lexer, input.len());
+                break;
+            }
+            Err(err) => Err(err),
+        };
 
-        match token {
-            Token::Ident(ident) => match ident {
-                "syntax" => {
-                    let syntax = parse_syntax(&mut lexer)?;
-                    cx.syntax = syntax;
-                }
-                "package" => {
-                    let ident = take_ident(&mut lexer)?;
-                    package = Some(ident.to_string());
-                    assert_next(&mut lexer, Token::Semicolon)?;
-                }
-                "import" => {
-                    let (token, span) = take_next(&mut lexer)?;
-                    match token {
-                        Token::String(s) => {
-                            if imports.iter().any(|item| item == s) {
-                                return Err(Error::Duplicate(format!("duplicate import `{}`", s)));
-                            }
+        if let Err(err) = result {
+            let eof = matches!(err.kind(), ErrorKind::Eof);
+            cx.errors.push(err);
+            if eof {
+                break;
+            }
 
-                            imports.push(s.to_string());
+            skip_statement(&mut lexer);
+        }
+    }
 
-                            assert_next(&mut lexer, Token::Semicolon)?;
+    cx.errors.retain(|err| !recoverable(err.kind()));
+    if !cx.errors.is_empty() {
+        return Err(cx
+            .errors
+            .into_iter()
+            .map(|err| err.locate(&lexer))
+            .collect());
+    }
 
-                            continue;
-                        }
-                        _ => {
-                            return Err(Error::Unexpected {
-                                token: token.to_string(),
-                                expected: "string".to_string(),
-                                span,
-                            });
-                        }
-                    }
-                }
-                "option" => {
-                    let key = take_ident(&mut lexer)?;
-                    assert_next(&mut lexer, Token::Equals)?;
-                    let value = match lexer.next() {
-                        Some(Ok((token, _span))) => match token {
-                            Token::Ident(ident) => ident.to_string(),
-                            Token::String(s) => s.to_string(),
-                            Token::Integer(i) => i.to_string(),
-                            _ => {
-                                return Err(Error::Unexpected {
-                                    token: token.to_string(),
-                                    expected: "identity, integer, bool or string".to_string(),
-                                    span,
-                                });
-                            }
-                        },
-                        Some(Err(err)) => return Err(err.into()),
-                        None => return Err(Error::Eof),
-                    };
+    fd.syntax = cx.syntax;
 
-                    assert_next(&mut lexer, Token::Semicolon)?;
+    Ok(fd)
+}
 
-                    options.insert(key.to_string(), value);
-                }
-                "message" => {
-                    let msg = parse_message(&mut lexer, &mut cx)?;
-                    // todo: check msg name, return err is it is duplicate
+fn parse_statement(
+    lexer: &mut Lexer,
+    cx: &mut Context,
+    fd: &mut FileDescriptor,
+    token: Token,
+    span: Span,
+) -> Result<(), Error> {
+    match token {
+        Token::Ident("syntax") => {
+            cx.syntax = parse_syntax(lexer)?;
+            fd.comments = lookup_comments(lexer, span.start, lexer.last_span().end);
+        }
+        Token::Ident("package") => {
+            let ident = take_ident(lexer)?;
+            fd.package = Some(ident.to_string());
+            assert_next(lexer, Token::Semicolon)?;
+            fd.package_comments = lookup_comments(lexer, span.start, lexer.last_span().end);
+        }
+        Token::Ident("import") => {
+            let start = span.start;
+            let (mut token, mut span) = take_next(lexer)?;
+            let kind = match token {
+                Token::Ident("public") => ImportKind::Public,
+                Token::Ident("weak") => ImportKind::Weak,
+                _ => ImportKind::Normal,
+            };
+            if kind != ImportKind::Normal {
+                (token, span) = take_next(lexer)?;
+            }
 
-                    messages.push(msg);
-                    continue;
-                }
-                "enum" => {
-                    let enumerator = parse_enum(&mut lexer, &mut cx)?;
-                    enums.push(enumerator);
-                    continue;
-                }
-                "service" => {
-                    let service = parse_service(&mut lexer)?;
-                    services.push(service);
-                    continue;
-                }
-                _ => {
-                    panic!("{}", lexer.diagnostic(span, "unexpected token"));
-                }
-            },
-            Token::Semicolon => {}
-            token => {
-                panic!(
-                    "{}",
-                    lexer.diagnostic(span, format!("unexpected token `{token:?}`"))
+            let Token::String(s) = token else {
+                return Err(unexpected(token, "string", span));
+            };
+
+            assert_next(lexer, Token::Semicolon)?;
+
+            if fd.imports.iter().any(|item| item.path == s) {
+                cx.report(
+                    ErrorKind::Duplicate(format!("duplicate import `{}`", s)),
+                    span,
                 );
+            } else {
+                fd.imports.push(Import {
+                    path: s.to_string(),
+                    kind,
+                    comments: lookup_comments(lexer, start, lexer.last_span().end),
+                });
             }
         }
+        Token::Ident("option") => {
+            let option = parse_option(lexer)?;
+            fd.options.push(option);
+        }
+        Token::Ident("message") => {
+            let msg = parse_message(lexer, cx)?;
+            // todo: check msg name, return err is it is duplicate
+
+            fd.messages.push(msg);
+        }
+        Token::Ident("enum") => {
+            let enumerator = parse_enum(lexer, cx)?;
+            fd.enums.push(enumerator);
+        }
+        Token::Ident("service") => {
+            let service = parse_service(lexer, cx)?;
+            fd.services.push(service);
+        }
+        Token::Ident("extend") => {
+            let extend = parse_extend(lexer, cx)?;
+            fd.extends.push(extend);
+        }
+        Token::Semicolon => {}
+        token => {
+            return Err(unexpected(
+                token,
+                "`syntax`, `package`, `import`, `option`, `message`, `enum`, `service` or `extend`",
+                span,
+            ));
+        }
     }
 
-    Ok(FileDescriptor {
-        syntax: cx.syntax,
-        package,
-        options,
-        imports,
-        messages,
-        enums,
-        services,
-    })
+    Ok(())
 }
 
 fn parse_syntax(lexer: &mut Lexer) -> Result<Syntax, Error> {
     assert_next(lexer, Token::Equals)?;
 
-    match lexer.next() {
-        Some(Ok((token, _span))) => {
-            let syntax = if token == Token::String("proto2") {
-                Syntax::Proto2
-            } else if token == Token::String("proto3") {
-                Syntax::Proto3
-            } else {
-                return Err(Error::Unknown(token.to_string()));
-            };
+    let (token, span) = take_next(lexer)?;
+    let syntax = match token {
+        Token::String("proto2") => Syntax::Proto2,
+        Token::String("proto3") => Syntax::Proto3,
+        _ => return Err(unexpected(token, "\"proto2\" or \"proto3\"", span)),
+    };
 
-            assert_next(lexer, Token::Semicolon)?;
+    assert_next(lexer, Token::Semicolon)?;
 
-            Ok(syntax)
-        }
-        Some(Err(err)) => Err(err.into()),
-        None => Err(Error::Eof),
-    }
+    Ok(syntax)
 }
 
 fn take_next<'a>(lexer: &mut Lexer<'a>) -> Result<(Token<'a>, Span), Error> {
     match lexer.next() {
         Some(Ok((token, span))) => Ok((token, span)),
-        Some(Err(err)) => Err(err.into()),
-        None => Err(Error::Eof),
+        Some(Err(err)) => Err(Error::new(err.into(), lexer.last_span())),
+        None => Err(Error::new(ErrorKind::Eof, lexer.last_span())),
+    }
+}
+
+// like `take_next`, but lexer errors are recorded and skipped, it is used
+// at the beginning of statements
+fn next_statement<'a>(lexer: &mut Lexer<'a>, cx: &mut Context) -> Result<(Token<'a>, Span), Error> {
+    loop {
+        match take_next(lexer) {
+            Ok(next) => return Ok(next),
+            Err(err) => cx.recover(lexer, err)?,
+        }
     }
 }
 
 fn assert_next(lexer: &mut Lexer, expected: Token) -> Result<(), Error> {
     let (token, span) = take_next(lexer)?;
     if token != expected {
-        panic!(
-            "{}",
-            lexer.diagnostic(span, format!("expected token {:?}", expected))
-        );
+        return Err(unexpected(token, format!("`{expected}`"), span));
     }
 
     Ok(())
 }
 
 fn take_ident<'a>(lexer: &mut Lexer<'a>) -> Result<&'a str, Error> {
-    match lexer.next() {
-        Some(Ok((token, span))) => match token {
-            Token::Ident(ident) => Ok(ident),
-            _ => panic!(
-                "{}",
-                lexer.diagnostic(span, format!("unexpected token {:?}", token))
-            ),
-        },
-        Some(Err(err)) => Err(err.into()),
-        None => Err(Error::Eof),
+    let (token, span) = take_next(lexer)?;
+    match token {
+        Token::Ident(ident) => Ok(ident),
+        _ => Err(unexpected(token, "identifier", span)),
     }
 }
 
@@ -250,7 +524,12 @@ fn parse_field_type(lexer: &mut Lexer) -> Result<FieldType, Error> {
                 | FieldType::Sfixed64
                 | FieldType::Bool
                 | FieldType::String => {}
-                _ => return Err(Error::InvalidMapKey(key_typ.to_string())),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidMapKey(key_typ.to_string()),
+                        lexer.last_span(),
+                    ));
+                }
             }
 
             assert_next(lexer, Token::Comma)?;
@@ -264,314 +543,469 @@ fn parse_field_type(lexer: &mut Lexer) -> Result<FieldType, Error> {
             Ok(FieldType::Map(Box::new(key), Box::new(value)))
         }
         Token::Ident(ident) => Ok(FieldType::from(ident)),
-        _ => Err(Error::Unexpected {
-            expected: "a valid field type".to_string(),
-            token: token.to_string(),
-            span,
-        }),
+        _ => Err(unexpected(token, "a valid field type", span)),
     }
 }
 
 fn parse_field_and_next(
     lexer: &mut Lexer,
-    cx: &Context,
-) -> Result<(String, u32, HashMap<String, String>), Error> {
+    cx: &mut Context,
+) -> Result<(String, u32, Options), Error> {
     let name = take_ident(lexer)?.to_string();
 
     assert_next(lexer, Token::Equals)?;
 
-    let number = match lexer.next() {
-        Some(Ok((token, span))) => match token {
-            Token::Integer(value) => {
-                u32::try_from(value).map_err(|err| Error::InvalidFieldNumber {
-                    number: value,
-                    reason: err.to_string(),
-                    span,
-                })?
-            }
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "unsigned integer".to_string(),
-                    span,
-                });
-            }
-        },
-        Some(Err(err)) => return Err(err.into()),
-        None => return Err(Error::Eof),
+    let (token, span) = take_next(lexer)?;
+    let number = match token {
+        Token::Integer(value) => field_number(value, span)?,
+        _ => return Err(unexpected(token, "unsigned integer", span)),
     };
 
-    let extensions = match lexer.next() {
-        Some(Ok((token, span))) => match token {
-            Token::Semicolon => {
-                // all done
-                Default::default()
-            }
-            Token::LeftBracket => parse_field_options(lexer, cx)?,
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "semicolon or left bracket".to_string(),
-                    span,
-                });
-            }
-        },
-        Some(Err(err)) => return Err(err.into()),
-        None => return Err(Error::Eof),
+    let (token, span) = take_next(lexer)?;
+    let options = match token {
+        // all done
+        Token::Semicolon => Default::default(),
+        Token::LeftBracket => parse_field_options(lexer, cx)?,
+        _ => return Err(unexpected(token, "semicolon or left bracket", span)),
     };
 
-    Ok((name, number, extensions))
+    Ok((name, number, options))
 }
 
 fn parse_field(
     lexer: &mut Lexer,
-    cx: &Context,
-) -> Result<(FieldType, String, u32, HashMap<String, String>), Error> {
+    cx: &mut Context,
+) -> Result<(FieldType, String, u32, Options), Error> {
     let typ = FieldType::from(take_ident(lexer)?);
-    let name = take_ident(lexer)?.to_string();
-
-    assert_next(lexer, Token::Equals)?;
-
-    let number = match lexer.next() {
-        Some(Ok((token, span))) => match token {
-            Token::Integer(value) => {
-                if value < 1 || value > (1 << 29) - 1 {
-                    return Err(Error::InvalidFieldNumber {
-                        number: value,
-                        reason: "field number must in [1, 536,870,911]".to_string(),
-                        span,
-                    });
-                }
-
-                value as u32
-            }
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "unsigned integer".to_string(),
-                    span,
-                });
-            }
-        },
-        Some(Err(err)) => return Err(err.into()),
-        None => return Err(Error::Eof),
-    };
-
-    let options = match lexer.next() {
-        Some(Ok((token, span))) => match token {
-            Token::Semicolon => {
-                // all done
-                Default::default()
-            }
-            Token::LeftBracket => parse_field_options(lexer, cx)?,
-            _ => {
-                panic!(
-                    "{}",
-                    lexer.diagnostic(span, "expected token semicolon or left bracket")
-                );
-            }
-        },
-        Some(Err(err)) => return Err(err.into()),
-        None => return Err(Error::Eof),
-    };
+    let (name, number, options) = parse_field_and_next(lexer, cx)?;
 
     Ok((typ, name, number, options))
 }
 
-fn parse_field_options<'a>(
-    lexer: &mut Lexer,
-    cx: &Context,
-) -> Result<HashMap<String, String>, Error> {
-    let mut extensions = HashMap::new();
+// parse options between brackets, e.g. `[packed = true, (foo.bar).baz = 1]`,
+// the left bracket is consumed already and the trailing semicolon will be
+// consumed too
+fn parse_field_options(lexer: &mut Lexer, cx: &mut Context) -> Result<Options, Error> {
+    let mut options = Options::default();
 
     loop {
-        let (token, span) = take_next(lexer)?;
-        let key = match token {
-            Token::Ident(ident) => ident,
-            Token::LeftParentheses => {
-                // something like `[(gogoproto.nullable) = false];`
-                let key = take_ident(lexer)?;
-                assert_next(lexer, Token::RightParentheses)?;
-                key
-            }
-            _ => {
-                panic!("{}", lexer.diagnostic(span, "unknown token"));
-            }
-        };
+        let name = parse_option_name(lexer)?;
+        let key = name.to_string();
 
         if cx.syntax == Syntax::Proto3 && key == "default" {
-            return Err(Error::NotAllowed(
-                "explicit default values are not allowed in proto3".to_string(),
-            ));
+            cx.report(
+                ErrorKind::NotAllowed(
+                    "explicit default values are not allowed in proto3".to_string(),
+                ),
+                lexer.last_span(),
+            );
         }
 
-        if extensions.contains_key(key) {
-            return Err(Error::Duplicate(key.to_string()));
+        if options.contains(&key) {
+            cx.report(
+                ErrorKind::Duplicate(format!("duplicate option `{key}`")),
+                lexer.last_span(),
+            );
         }
 
         assert_next(lexer, Token::Equals)?;
 
-        // value part
-        let (token, span) = take_next(lexer)?;
-        let value = match token {
-            Token::Ident(ident) => ident.to_string(),
-            Token::Integer(i) => i.to_string(),
-            Token::Float(f) => f.to_string(),
-            Token::String(s) => {
-                let mut buf = String::new();
-                for b in unescape_c_escape_string(s) {
-                    buf.extend((b as char).escape_default());
-                }
-
-                buf
-            }
-            _ => {
-                panic!(
-                    "{}",
-                    lexer.diagnostic(span, "identity, integer, float, bool or string")
-                );
-            }
-        };
-
-        extensions.insert(key.to_string(), value);
+        let value = parse_option_value(lexer)?;
+        options.push(ProtoOption { name, value });
 
         let (token, span) = take_next(lexer)?;
         match token {
             Token::Comma => continue,
             Token::RightBracket => break,
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "comma or bracket".to_string(),
-                    span,
-                });
-            }
+            _ => return Err(unexpected(token, "comma or bracket", span)),
         }
     }
 
     assert_next(lexer, Token::Semicolon)?;
 
-    Ok(extensions)
+    Ok(options)
 }
 
-fn parse_enum(lexer: &mut Lexer, cx: &mut Context) -> Result<Enum, Error> {
-    let name = take_ident(lexer)?;
+// parse `option foo = "bar";`, the `option` keyword is consumed already
+fn parse_option(lexer: &mut Lexer) -> Result<ProtoOption, Error> {
+    let name = parse_option_name(lexer)?;
+    assert_next(lexer, Token::Equals)?;
+    let value = parse_option_value(lexer)?;
+    assert_next(lexer, Token::Semicolon)?;
 
-    assert_next(lexer, Token::LeftBrace)?;
+    Ok(ProtoOption { name, value })
+}
 
-    let mut variants = Vec::new();
+// parse option names
+//
+// - deprecated
+// - (gogoproto.nullable)
+// - (google.api.http).additional_bindings
+// - foo.(bar.baz).qux
+//
+// the lexer keeps dots in idents, so sub paths come as `.additional_bindings`
+fn parse_option_name(lexer: &mut Lexer) -> Result<OptionName, Error> {
+    let mut parts = vec![];
 
     loop {
         let (token, span) = take_next(lexer)?;
-        let variant = match token {
-            Token::RightBrace => break,
-            Token::Ident(ident) => {
-                if variants.iter().any(|(v, _)| v == ident) {
-                    return Err(Error::Duplicate(format!("duplicate variant {ident}")));
-                }
+        let dangling = match token {
+            Token::LeftParentheses => {
+                let name = take_ident(lexer)?;
+                assert_next(lexer, Token::RightParentheses)?;
 
-                ident
-            }
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "enum variant or right brace".to_string(),
-                    span,
+                parts.push(NamePart {
+                    name: name.trim_start_matches('.').to_string(),
+                    extension: true,
                 });
-            }
-        };
 
-        assert_next(lexer, Token::Equals)?;
+                false
+            }
+            Token::Ident(ident) => {
+                parts.extend(
+                    ident
+                        .split('.')
+                        .filter(|name| !name.is_empty())
+                        .map(|name| NamePart {
+                            name: name.to_string(),
+                            extension: false,
+                        }),
+                );
 
-        let (token, span) = take_next(lexer)?;
-        let value = match token {
-            // enum variant value could be negative
-            Token::Integer(value) => match i32::try_from(value) {
-                Ok(value) => value,
-                Err(err) => panic!("{}", lexer.diagnostic(span, &err.to_string())),
-            },
-            _ => {
-                panic!("{}", lexer.diagnostic(span, "expect an int32"));
+                ident.ends_with('.')
             }
+            _ => return Err(unexpected(token, "option name", span)),
         };
 
-        if cx.syntax == Syntax::Proto3 && variants.is_empty() && value != 0 {
-            panic!(
-                "{}",
-                lexer.diagnostic(span, "first variant of enum must be zero in proto3")
-            );
-        }
-
-        if variants.iter().any(|(n, v)| variant == n || value == *v) {
-            return Err(Error::Duplicate(format!("duplicate tag {value}")));
+        // `foo.(bar)` or `(foo).bar`
+        match lexer.peek() {
+            Some(Ok((Token::LeftParentheses, _))) if dangling => continue,
+            Some(Ok((Token::Ident(ident), _))) if ident.starts_with('.') => continue,
+            _ => break,
         }
+    }
 
-        assert_next(lexer, Token::Semicolon)?;
+    Ok(OptionName(parts))
+}
 
-        variants.push((variant.to_string(), value));
-    }
+fn unescape(s: &str, span: Span) -> Result<Vec<u8>, Error> {
+    unescape_c_escape_string(s).map_err(|err| Error::new(ErrorKind::InvalidString(err), span))
+}
 
-    Ok(Enum {
-        name: name.to_string(),
-        variants,
-    })
-}
+// parse option value, aggregate values are in text format, e.g.
+// `{ get: "/v1/messages/{id}" body: "*" }`
+fn parse_option_value(lexer: &mut Lexer) -> Result<OptionValue, Error> {
+    let (token, span) = take_next(lexer)?;
+    let value = match token {
+        Token::Ident(ident) => OptionValue::Ident(ident.to_string()),
+        Token::Integer(value) => OptionValue::Integer(value),
+        Token::Float(value) => OptionValue::Float(value),
+        Token::String(s) => {
+            let mut buf = unescape(s, span)?;
+
+            // adjacent strings are concatenated, e.g. `"foo" "bar"`
+            while let Some(Ok((Token::String(s), span))) = lexer.peek() {
+                take_next(lexer)?;
+                buf.extend(unescape(s, span)?);
+            }
 
-fn parse_oneof(lexer: &mut Lexer, cx: &Context) -> Result<OneOf, Error> {
-    let name = take_ident(lexer)?;
+            OptionValue::String(buf)
+        }
+        Token::LeftBrace => OptionValue::Aggregate(parse_aggregate(lexer, Token::RightBrace)?),
+        _ => {
+            return Err(unexpected(
+                token,
+                "identity, integer, float, bool, string or aggregate",
+                span,
+            ));
+        }
+    };
 
-    assert_next(lexer, Token::LeftBrace)?;
+    Ok(value)
+}
 
-    let mut variants = vec![];
+// parse text format message until `end`, which is `}` or `>`
+fn parse_aggregate(lexer: &mut Lexer, end: Token) -> Result<Vec<(String, OptionValue)>, Error> {
+    let mut fields = vec![];
 
     loop {
         let (token, span) = take_next(lexer)?;
-        let typ = match token {
-            Token::RightBrace => break,
-            Token::Ident(typ) => FieldType::from(typ),
+        let name = match token {
+            token if token == end => break,
+            // separators are optional
+            Token::Comma | Token::Semicolon => continue,
+            Token::Ident(ident) => ident.to_string(),
+            // extension fields, e.g. `[foo.bar]: 1`
+            Token::LeftBracket => {
+                let name = take_ident(lexer)?;
+                assert_next(lexer, Token::RightBracket)?;
+                format!("[{name}]")
+            }
+            _ => return Err(unexpected(token, format!("field name or `{end}`"), span)),
+        };
+
+        let (token, span) = take_next(lexer)?;
+        let value = match token {
+            Token::Colon => parse_text_value(lexer)?,
+            // the colon is optional for messages
+            Token::LeftBrace => OptionValue::Aggregate(parse_aggregate(lexer, Token::RightBrace)?),
+            Token::LeftAngleBracket => {
+                OptionValue::Aggregate(parse_aggregate(lexer, Token::RightAngleBracket)?)
+            }
             _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "enum variant or right brace".to_string(),
+                return Err(unexpected(
+                    token,
+                    "colon, left brace or left angle bracket",
                     span,
-                });
+                ));
             }
         };
 
-        let name = take_ident(lexer)?;
+        fields.push((name, value));
+    }
+
+    Ok(fields)
+}
 
-        assert_next(lexer, Token::Equals)?;
+/// Parse aggregate option value in text format without the braces, the form
+/// `UninterpretedOption.aggregate_value` of descriptors keeps it in
+pub(crate) fn parse_aggregate_value(text: &str) -> Option<Vec<(String, OptionValue)>> {
+    let source = format!("{text} }}");
+    let mut lexer = Lexer::new(source.as_bytes());
+    parse_aggregate(&mut lexer, Token::RightBrace).ok()
+}
 
-        let (token, span) = take_next(lexer)?;
-        let number = match token {
-            // enum variant value could be negative
-            Token::Integer(value) => match u32::try_from(value) {
-                Ok(value) => value,
-                Err(err) => panic!("{}", lexer.diagnostic(span, &err.to_string())),
-            },
-            _ => {
-                panic!("{}", lexer.diagnostic(span, "expect an uint32"));
+/// Parse a whole message in text format, e.g. the input of `prust-build encode`
+pub(crate) fn parse_text_format(input: &[u8]) -> Result<Vec<(String, OptionValue)>, Error> {
+    let mut source = input.to_vec();
+    source.extend_from_slice(b"\n}");
+    let mut lexer = Lexer::new(&source);
+
+    let fields =
+        parse_aggregate(&mut lexer, Token::RightBrace).map_err(|err| err.locate(&lexer))?;
+    match lexer.next() {
+        // a `}` without the opening one ends the message too early
+        Some(Ok((token, span))) => Err(unexpected(token, "field name", span).locate(&lexer)),
+        _ => Ok(fields),
+    }
+}
+
+// field value of text format, lists are allowed, e.g. `[1, 2, 3]`
+fn parse_text_value(lexer: &mut Lexer) -> Result<OptionValue, Error> {
+    match lexer.peek() {
+        Some(Ok((Token::LeftBracket, _))) => {
+            take_next(lexer)?;
+
+            let mut values = vec![];
+            loop {
+                if let Some(Ok((Token::RightBracket, _))) = lexer.peek() {
+                    take_next(lexer)?;
+                    break;
+                }
+
+                values.push(parse_text_value(lexer)?);
+
+                let (token, span) = take_next(lexer)?;
+                match token {
+                    Token::Comma => continue,
+                    Token::RightBracket => break,
+                    _ => return Err(unexpected(token, "comma or right bracket", span)),
+                }
             }
+
+            Ok(OptionValue::List(values))
+        }
+        Some(Ok((Token::LeftAngleBracket, _))) => {
+            take_next(lexer)?;
+            Ok(OptionValue::Aggregate(parse_aggregate(
+                lexer,
+                Token::RightAngleBracket,
+            )?))
+        }
+        _ => parse_option_value(lexer),
+    }
+}
+
+fn parse_enum(lexer: &mut Lexer, cx: &mut Context) -> Result<Enum, Error> {
+    // the `enum` keyword
+    let start = lexer.last_span().start;
+    let name = take_ident(lexer)?;
+
+    assert_next(lexer, Token::LeftBrace)?;
+    let mut comments = lookup_comments(lexer, start, lexer.last_span().end);
+
+    let mut variants = Vec::<EnumVariant>::new();
+    // where variants are declared, for reporting conflicts
+    let mut spans = vec![];
+    let mut reserved = vec![];
+    let mut reserved_comments = Comments::default();
+    let mut options = Options::default();
+
+    loop {
+        let (token, span) = next_statement(lexer, cx)?;
+        let result = match token {
+            Token::RightBrace => {
+                comments.closing = closing_comments(lexer, span.start);
+                break;
+            }
+            Token::Semicolon => continue,
+            Token::Ident("option") => parse_option(lexer).map(|option| options.push(option)),
+            Token::Ident("reserved") => parse_reserved(lexer, i32::MAX as i64).map(|partial| {
+                reserved.extend(partial);
+                let comments = lookup_comments(lexer, span.start, lexer.last_span().end);
+                merge_comments(&mut reserved_comments, comments);
+            }),
+            Token::Ident(ident) => parse_enum_variant(lexer, cx, ident).map(|variant| {
+                if variants.iter().any(|v| v.name == ident) {
+                    cx.report(
+                        ErrorKind::Duplicate(format!("duplicate variant `{ident}`")),
+                        span,
+                    );
+                    return;
+                }
+
+                if cx.syntax == Syntax::Proto3 && variants.is_empty() && variant.value != 0 {
+                    cx.report(ErrorKind::EnumZeroValue, span);
+                }
+
+                variants.push(variant);
+                spans.push(span);
+            }),
+            _ => Err(unexpected(token, "enum variant or right brace", span)),
         };
 
-        let (token, span) = take_next(lexer)?;
-        let options = match token {
-            Token::Semicolon => HashMap::new(),
-            Token::LeftBracket => parse_field_options(lexer, cx)?,
-            _ => panic!("{}", lexer.diagnostic(span, "unexpected token")),
+        if let Err(err) = result {
+            cx.recover(lexer, err)?;
+        }
+    }
+
+    let en = Enum {
+        name: name.to_string(),
+        variants,
+        reserved,
+        reserved_comments,
+        options,
+        comments,
+        location: locate(lexer, start, lexer.last_span().end),
+        closed: cx.syntax == Syntax::Proto2,
+    };
+
+    // `allow_alias` could be set after the variants, so check values at last
+    for (index, variant) in en.variants.iter().enumerate() {
+        if !en.allow_alias()
+            && en.variants[..index]
+                .iter()
+                .any(|other| other.value == variant.value)
+        {
+            cx.report(
+                ErrorKind::Duplicate(format!(
+                    "duplicate tag {}, set `option allow_alias = true;` to allow aliases",
+                    variant.value
+                )),
+                spans[index],
+            );
+        }
+
+        for reserved in &en.reserved {
+            let conflict =
+                reserved.contains(variant.value as i64) || reserved.contains_name(&variant.name);
+
+            if conflict {
+                cx.report(
+                    ErrorKind::NotAllowed(format!(
+                        "enum variant {} = {} of {} is reserved",
+                        variant.name, variant.value, en.name
+                    )),
+                    spans[index],
+                );
+            }
+        }
+    }
+
+    Ok(en)
+}
+
+// parse `FOO = 1 [deprecated = true];`, the name is consumed already
+fn parse_enum_variant(
+    lexer: &mut Lexer,
+    cx: &mut Context,
+    name: &str,
+) -> Result<EnumVariant, Error> {
+    let start = lexer.last_span().start;
+    assert_next(lexer, Token::Equals)?;
+
+    let (token, span) = take_next(lexer)?;
+    let value = match token {
+        // enum variant value could be negative
+        Token::Integer(value) => i32::try_from(value).map_err(|_err| {
+            Error::new(
+                ErrorKind::NotAllowed(format!("enum value {value} is out of the int32 range")),
+                span,
+            )
+        })?,
+        _ => return Err(unexpected(token, "an int32", span)),
+    };
+
+    let (token, span) = take_next(lexer)?;
+    let options = match token {
+        Token::Semicolon => Options::default(),
+        Token::LeftBracket => parse_field_options(lexer, cx)?,
+        _ => return Err(unexpected(token, "semicolon or left bracket", span)),
+    };
+
+    Ok(EnumVariant {
+        name: name.to_string(),
+        value,
+        options,
+        comments: lookup_comments(lexer, start, lexer.last_span().end),
+        location: locate(lexer, start, lexer.last_span().end),
+    })
+}
+
+fn parse_oneof(lexer: &mut Lexer, cx: &mut Context) -> Result<OneOf, Error> {
+    // the `oneof` keyword
+    let start = lexer.last_span().start;
+    let name = take_ident(lexer)?;
+
+    assert_next(lexer, Token::LeftBrace)?;
+    let mut comments = lookup_comments(lexer, start, lexer.last_span().end);
+
+    let mut variants = vec![];
+    let mut options = Options::default();
+
+    loop {
+        let (token, span) = next_statement(lexer, cx)?;
+        let result = match token {
+            Token::RightBrace => {
+                comments.closing = closing_comments(lexer, span.start);
+                break;
+            }
+            Token::Semicolon => continue,
+            Token::Ident("option") => parse_option(lexer).map(|option| options.push(option)),
+            Token::Ident(typ) => parse_field_and_next(lexer, cx).map(|(name, number, options)| {
+                variants.push(OneOfVarint {
+                    name,
+                    number,
+                    typ: FieldType::from(typ),
+                    options,
+                    comments: lookup_comments(lexer, span.start, lexer.last_span().end),
+                    location: locate(lexer, span.start, lexer.last_span().end),
+                })
+            }),
+            _ => Err(unexpected(token, "oneof field or right brace", span)),
         };
 
-        variants.push(OneOfVarint {
-            name: name.to_string(),
-            number,
-            typ,
-            options,
-        })
+        if let Err(err) = result {
+            cx.recover(lexer, err)?;
+        }
     }
 
     Ok(OneOf {
         name: name.to_string(),
         variants,
+        options,
+        comments,
+        location: locate(lexer, start, lexer.last_span().end),
     })
 }
 
@@ -581,36 +1015,28 @@ fn parse_oneof(lexer: &mut Lexer, cx: &Context) -> Result<OneOf, Error> {
 // - reserved 1, 2;
 // - reserved 1 to 2;
 // - reserved 1, 2 to 3, 4 to max;
-fn parse_reserved(lexer: &mut Lexer) -> Result<Vec<Reserved>, Error> {
+// - reserved "foo", "bar";
+//
+// `max` is different for field numbers and enum values
+fn parse_reserved(lexer: &mut Lexer, max: i64) -> Result<Vec<Reserved>, Error> {
     let mut reserved = Vec::new();
 
     loop {
         let (token, span) = take_next(lexer)?;
         let value = match token {
-            Token::Integer(value) => {
-                u32::try_from(value).map_err(|err| Error::InvalidFieldNumber {
-                    number: value,
-                    reason: err.to_string(),
-                    span,
-                })?
-            }
-            Token::Semicolon => {
-                if reserved.is_empty() {
-                    return Err(Error::Unexpected {
-                        token: token.to_string(),
-                        expected: "valid reserved values".to_string(),
-                        span,
-                    });
+            Token::Integer(value) => value,
+            Token::String(name) => {
+                reserved.push(Reserved::Field(name.to_string()));
+
+                let (token, span) = take_next(lexer)?;
+                match token {
+                    Token::Comma => continue,
+                    Token::Semicolon => break,
+                    _ => return Err(unexpected(token, "comma or semicolon", span)),
                 }
-                break;
-            }
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "".to_string(),
-                    span,
-                });
             }
+            Token::Semicolon if !reserved.is_empty() => break,
+            _ => return Err(unexpected(token, "reserved numbers or names", span)),
         };
 
         let (token, span) = take_next(lexer)?;
@@ -623,44 +1049,38 @@ fn parse_reserved(lexer: &mut Lexer) -> Result<Vec<Reserved>, Error> {
                 reserved.push(Reserved::Single(value));
                 break;
             }
-            Token::Ident(ident) => {
-                if ident != "to" {
-                    return Err(Error::Unexpected {
-                        token: token.to_string(),
-                        expected: "comma, semicolon or `to`".to_string(),
-                        span,
-                    });
-                }
-
+            Token::Ident("to") => {
                 let (token, span) = take_next(lexer)?;
                 match token {
-                    Token::Integer(v) => {
-                        let end = u32::try_from(v).map_err(|err| Error::InvalidFieldNumber {
-                            number: v,
-                            reason: err.to_string(),
-                            span,
-                        })?;
+                    Token::Integer(end) => {
+                        if end < value {
+                            return Err(Error::new(
+                                ErrorKind::InvalidFieldNumber {
+                                    number: end,
+                                    reason: format!(
+                                        "reserved range end must not be less than {value}"
+                                    ),
+                                },
+                                span,
+                            ));
+                        }
+
                         reserved.push(Reserved::Range(value, end));
                     }
-                    Token::Ident(ident) if ident == "max" => {
-                        reserved.push(Reserved::Range(value, u32::MAX));
-                    }
-                    _ => {
-                        return Err(Error::Unexpected {
-                            token: token.to_string(),
-                            expected: "comma, semicolon or `to`".to_string(),
-                            span,
-                        });
+                    Token::Ident("max") => {
+                        reserved.push(Reserved::Range(value, max));
                     }
+                    _ => return Err(unexpected(token, "integer or `max`", span)),
+                }
+
+                let (token, span) = take_next(lexer)?;
+                match token {
+                    Token::Comma => continue,
+                    Token::Semicolon => break,
+                    _ => return Err(unexpected(token, "comma or semicolon", span)),
                 }
             }
-            _ => {
-                return Err(Error::Unexpected {
-                    token: token.to_string(),
-                    expected: "comma, semicolon or `to`".to_string(),
-                    span,
-                });
-            }
+            _ => return Err(unexpected(token, "comma, semicolon or `to`", span)),
         }
     }
 
@@ -668,375 +1088,488 @@ fn parse_reserved(lexer: &mut Lexer) -> Result<Vec<Reserved>, Error> {
 }
 
 fn parse_message(lexer: &mut Lexer, cx: &mut Context) -> Result<Message, Error> {
+    // the `message` keyword
+    let start = lexer.last_span().start;
     let name = take_ident(lexer)?;
 
     assert_next(lexer, Token::LeftBrace)?;
 
-    let mut fields: Vec<Field> = vec![];
-    let mut messages = vec![];
-    let mut options = HashMap::new();
-    let mut enums = vec![];
-    let mut oneofs = Vec::<OneOf>::new();
-    let mut reserved = vec![];
-    let mut extensions = vec![];
+    let mut msg = Message {
+        name: name.to_string(),
+        comments: lookup_comments(lexer, start, lexer.last_span().end),
+        ..Default::default()
+    };
 
     loop {
-        let (ident, span) = match lexer.next() {
-            Some(Ok((token, span))) => match token {
-                Token::Ident(ident) => (ident, span),
-                Token::RightBrace => break,
-                Token::Semicolon => continue,
-                _ => {
-                    panic!("{}", lexer.diagnostic(span, "unexpected token"));
-                }
-            },
-            Some(Err(err)) => return Err(err.into()),
-            None => return Err(Error::Eof),
+        let (token, span) = next_statement(lexer, cx)?;
+        let result = match token {
+            Token::Ident(ident) => parse_message_item(lexer, cx, &mut msg, ident, span),
+            Token::RightBrace => {
+                msg.comments.closing = closing_comments(lexer, span.start);
+                break;
+            }
+            Token::Semicolon => continue,
+            _ => Err(unexpected(token, "message item or right brace", span)),
         };
 
-        if ident == "message" {
-            let msg = parse_message(lexer, cx)?;
-            messages.push(msg);
-            continue;
+        if let Err(err) = result {
+            cx.recover(lexer, err)?;
         }
+    }
+    msg.location = locate(lexer, start, lexer.last_span().end);
 
-        if ident == "enum" {
+    Ok(msg)
+}
+
+// parse one statement of message body, `ident` is the first token of it
+fn parse_message_item(
+    lexer: &mut Lexer,
+    cx: &mut Context,
+    msg: &mut Message,
+    ident: &str,
+    span: Span,
+) -> Result<(), Error> {
+    match ident {
+        "message" => {
+            let nested = parse_message(lexer, cx)?;
+            msg.messages.push(nested);
+            return Ok(());
+        }
+        "enum" => {
             let enumerator = parse_enum(lexer, cx)?;
-            enums.push(enumerator);
-            continue;
+            msg.enums.push(enumerator);
+            return Ok(());
         }
-
-        if ident == "group" {
-            return Err(Error::Unsupported(
-                "group is not supported, because it is deprecated already".to_string(),
+        "group" => {
+            return Err(Error::new(
+                ErrorKind::Unsupported(
+                    "group is not supported, because it is deprecated already".to_string(),
+                ),
+                span,
             ));
         }
-
-        if ident == "option" {
-            let key = take_ident(lexer)?;
-            assert_next(lexer, Token::Equals)?;
-            let (token, span) = take_next(lexer)?;
-            let value = match token {
-                Token::Ident(ident) => ident.to_string(),
-                Token::String(s) => s.to_string(),
-                _ => {
-                    panic!(
-                        "{}",
-                        lexer.diagnostic(span, "unknown token for option value")
-                    )
-                }
-            };
-
-            options.insert(key.to_string(), value);
-
-            continue;
+        "option" => {
+            let option = parse_option(lexer)?;
+            msg.options.push(option);
+            return Ok(());
         }
+        "reserved" => {
+            let partial = parse_reserved(lexer, MAX_FIELD_NUMBER)?;
 
-        if ident == "reserved" {
-            let partial = parse_reserved(lexer)?;
-
-            for field in &fields {
-                if !validate_number(field.number, &reserved) {
-                    return Err(Error::InvalidFieldNumber {
-                        number: field.number as i64,
-                        reason: "reserved a predefined field".to_string(),
+            let numbers = msg.fields.iter().map(|field| field.number).chain(
+                msg.oneofs
+                    .iter()
+                    .flat_map(|oneof| oneof.variants.iter().map(|variant| variant.number)),
+            );
+            for number in numbers {
+                if !validate_number(number, &partial) {
+                    cx.report(
+                        ErrorKind::InvalidFieldNumber {
+                            number: number as i64,
+                            reason: "reserved a predefined field".to_string(),
+                        },
                         span,
-                    });
-                }
-            }
-
-            for oneof in &oneofs {
-                for variant in &oneof.variants {
-                    if !validate_number(variant.number, &reserved) {
-                        return Err(Error::InvalidFieldNumber {
-                            number: variant.number as i64,
-                            reason: "reserved a predefined file".to_string(),
-                            span,
-                        });
-                    }
+                    );
                 }
             }
 
-            reserved.extend(partial);
-
-            continue;
+            msg.reserved.extend(partial);
+            let comments = lookup_comments(lexer, span.start, lexer.last_span().end);
+            merge_comments(&mut msg.reserved_comments, comments);
+            return Ok(());
         }
-
-        if ident == "map" {
+        "map" => {
             let typ = parse_field_type(lexer)?;
             let (name, number, options) = parse_field_and_next(lexer, cx)?;
-            if PROTOBUF_RESERVED.contains(number) {
-                return Err(Error::InvalidFieldNumber {
-                    number: number as i64,
-                    reason: format!(
-                        "field number {number} is reserved for Protobuf implementation"
-                    ),
+            let comments = lookup_comments(lexer, span.start, lexer.last_span().end);
+            if PROTOBUF_RESERVED.contains(number as i64) {
+                cx.report(
+                    ErrorKind::InvalidFieldNumber {
+                        number: number as i64,
+                        reason: format!(
+                            "field number {number} is reserved for Protobuf implementation"
+                        ),
+                    },
                     span,
-                });
-            }
-
-            if options.get("deprecated").map(|v| v.as_str()) == Some("true") {
-                continue;
+                );
             }
 
-            fields.push(Field {
+            msg.fields.push(Field {
                 // todo: handle label by syntax
                 label: Label::Required,
                 typ,
                 name,
                 number,
                 options,
+                comments,
+                location: locate(lexer, span.start, lexer.last_span().end),
             });
-            continue;
+            return Ok(());
         }
-
-        if ident == "extensions" {
-            let ext = parse_message_extensions(lexer, cx)?;
-            extensions.push(ext);
-            continue;
+        "extensions" => {
+            let mut ranges = parse_message_extensions(lexer, cx)?;
+            if let Some(first) = ranges.first_mut() {
+                first.comments = lookup_comments(lexer, span.start, lexer.last_span().end);
+            }
+            msg.extensions.extend(ranges);
+            return Ok(());
         }
-
-        if ident == "oneof" {
+        "extend" => {
+            let extend = parse_extend(lexer, cx)?;
+            msg.extends.push(extend);
+            return Ok(());
+        }
+        "oneof" => {
             let oneof = parse_oneof(lexer, cx)?;
-            oneofs.push(oneof);
-            continue;
+            msg.oneofs.push(oneof);
+            return Ok(());
         }
+        _ => {}
+    }
 
-        // parse fields
-        let label = match cx.syntax {
-            Syntax::Proto2 => match ident {
-                "required" => Label::Required,
-                "optional" => Label::Optional,
-                "repeated" => Label::Repeated,
-                _ => {
-                    panic!("{}", lexer.diagnostic(span, "unexpected token"))
-                }
-            },
-            Syntax::Proto3 => match ident {
-                "optional" => Label::Optional,
-                "repeated" => Label::Repeated,
-                "required" => {
-                    panic!(
-                        "{}",
-                        lexer.diagnostic(span, "`required` is not allowed in proto3")
-                    )
-                }
-                ident => {
-                    let typ = FieldType::from(ident);
-                    let (name, number, options) = parse_field_and_next(lexer, cx)?;
-                    if options.get("deprecated").map(|x| x.as_str()) != Some("true") {
-                        fields.push(Field {
-                            label: Label::Required,
-                            typ,
-                            name,
-                            number,
-                            options,
-                        });
-                    }
-
-                    continue;
-                }
-            },
-            _ => panic!("{}", lexer.diagnostic(span, "unknown field")),
-        };
+    // parse fields
+    let label = match (cx.syntax, ident) {
+        (_, "optional") => Label::Optional,
+        (_, "repeated") => Label::Repeated,
+        (Syntax::Proto3, "required") => {
+            return Err(Error::new(
+                ErrorKind::NotAllowed("`required` is not allowed in proto3".to_string()),
+                span,
+            ));
+        }
+        (Syntax::Proto3, ident) => {
+            let typ = FieldType::from(ident);
+            let (name, number, options) = parse_field_and_next(lexer, cx)?;
+            msg.fields.push(Field {
+                label: Label::Required,
+                typ,
+                name,
+                number,
+                options,
+                comments: lookup_comments(lexer, span.start, lexer.last_span().end),
+                location: locate(lexer, span.start, lexer.last_span().end),
+            });
 
-        let (typ, name, number, options) = parse_field(lexer, cx)?;
-        if options.get("deprecated").map(|v| v.as_str()) == Some("true") {
-            continue;
+            return Ok(());
+        }
+        (_, "required") => Label::Required,
+        (_, ident) => {
+            return Err(Error::new(
+                ErrorKind::Unexpected {
+                    token: ident.to_string(),
+                    expected: "`required`, `optional` or `repeated`".to_string(),
+                },
+                span,
+            ));
         }
+    };
 
-        fields.push(Field {
-            label,
-            typ,
-            name,
-            number,
-            options,
-        });
-    }
+    let (typ, name, number, options) = parse_field(lexer, cx)?;
 
-    Ok(Message {
-        name: name.to_string(),
-        fields,
-        reserved,
+    msg.fields.push(Field {
+        label,
+        typ,
+        name,
+        number,
         options,
-        messages,
-        enums,
-        oneofs,
-    })
+        comments: lookup_comments(lexer, span.start, lexer.last_span().end),
+        location: locate(lexer, span.start, lexer.last_span().end),
+    });
+
+    Ok(())
 }
 
-fn parse_message_extensions(lexer: &mut Lexer, _cx: &Context) -> Result<Extension, Error> {
-    let (token, span) = take_next(lexer)?;
-    let start = match token {
-        Token::Integer(value) => match u32::try_from(value) {
-            Ok(value) => value,
-            Err(err) => panic!("{}", lexer.diagnostic(span, err.to_string())),
-        },
-        _ => panic!("{}", lexer.diagnostic(span, "unknown token")),
-    };
+// parse extension ranges
+//
+// - extensions 100 to 199;
+// - extensions 4, 20 to max;
+// - extensions 1000 to max [verification = UNVERIFIED];
+fn parse_message_extensions(lexer: &mut Lexer, cx: &mut Context) -> Result<Vec<Extension>, Error> {
+    let mut ranges = vec![];
 
-    assert_next(lexer, Token::Ident("to"))?;
+    let properties = loop {
+        let (token, span) = take_next(lexer)?;
+        let start = match token {
+            Token::Integer(value) => field_number(value, span)?,
+            _ => return Err(unexpected(token, "field number", span)),
+        };
 
-    let (token, span) = take_next(lexer)?;
-    let end = match token {
-        Token::Ident(ident) if ident == "max" => u32::MAX,
-        Token::Integer(value) => match u32::try_from(value) {
-            Ok(value) => value,
-            Err(err) => panic!("{}", lexer.diagnostic(span, err.to_string())),
-        },
-        _ => panic!("{}", lexer.diagnostic(span, "unknown token")),
-    };
+        let (mut token, mut span) = take_next(lexer)?;
+        let end = if token == Token::Ident("to") {
+            let (next, next_span) = take_next(lexer)?;
+            let end = match next {
+                Token::Integer(value) => field_number(value, next_span)?,
+                Token::Ident("max") => MAX_FIELD_NUMBER as u32,
+                _ => return Err(unexpected(next, "field number or `max`", next_span)),
+            };
 
-    let (token, _span) = take_next(lexer)?;
-    match token {
-        Token::LeftBracket => {}
-        Token::Semicolon => {
-            return Ok(Extension {
-                start,
-                end,
-                properties: BTreeMap::new(),
-            });
-        }
-        _ => {
-            panic!(
-                "{}",
-                lexer.diagnostic(span, format!("unexpected token {:?}", token.to_string()))
-            )
+            if end < start {
+                return Err(Error::new(
+                    ErrorKind::InvalidFieldNumber {
+                        number: end as i64,
+                        reason: format!("extension range end must not be less than {start}"),
+                    },
+                    next_span,
+                ));
+            }
+
+            (token, span) = take_next(lexer)?;
+            end
+        } else {
+            start
+        };
+
+        ranges.push((start, end));
+
+        match token {
+            Token::Comma => continue,
+            Token::Semicolon => break Options::default(),
+            // `parse_field_options` consumes the trailing semicolon
+            Token::LeftBracket => break parse_field_options(lexer, cx)?,
+            _ => {
+                return Err(unexpected(token, "comma, semicolon or left bracket", span));
+            }
         }
+    };
+
+    Ok(ranges
+        .into_iter()
+        .map(|(start, end)| Extension {
+            start,
+            end,
+            properties: properties.clone(),
+            comments: Comments::default(),
+        })
+        .collect())
+}
+
+fn field_number(value: i64, span: Span) -> Result<u32, Error> {
+    if !(1..=MAX_FIELD_NUMBER).contains(&value) {
+        return Err(Error::new(
+            ErrorKind::InvalidFieldNumber {
+                number: value,
+                reason: "field number must in [1, 536,870,911]".to_string(),
+            },
+            span,
+        ));
     }
 
-    assert_next(lexer, Token::LeftBracket)?;
+    Ok(value as u32)
+}
 
-    let mut properties = BTreeMap::new();
-    loop {
-        let (token, span) = take_next(lexer)?;
-        let key = match token {
-            Token::Ident(key) => key.to_string(),
-            Token::RightBracket => break,
-            _ => panic!("{}", lexer.diagnostic(span, "unknown token")),
-        };
+// parse `extend Foo { ... }`, the `extend` keyword is consumed already
+fn parse_extend(lexer: &mut Lexer, cx: &mut Context) -> Result<Extend, Error> {
+    // the `extend` keyword
+    let start = lexer.last_span().start;
+    let extendee = take_ident(lexer)?.to_string();
 
-        assert_next(lexer, Token::Equals)?;
+    assert_next(lexer, Token::LeftBrace)?;
+    let mut comments = lookup_comments(lexer, start, lexer.last_span().end);
 
-        let (token, span) = take_next(lexer)?;
-        let value = match token {
-            Token::Ident(value) => value.to_string(),
-            Token::Integer(value) => value.to_string(),
-            Token::Float(value) => value.to_string(),
-            Token::String(value) => value.to_string(),
-            _ => panic!("{}", lexer.diagnostic(span, "unknown token")),
+    let mut fields = vec![];
+    loop {
+        let (token, span) = next_statement(lexer, cx)?;
+        let result = match token {
+            Token::RightBrace => {
+                comments.closing = closing_comments(lexer, span.start);
+                break;
+            }
+            Token::Semicolon => continue,
+            Token::Ident(ident) => {
+                parse_extend_field(lexer, cx, ident, span).map(|field| fields.push(field))
+            }
+            _ => Err(unexpected(token, "extension field or right brace", span)),
         };
 
-        properties.insert(key, value);
+        if let Err(err) = result {
+            cx.recover(lexer, err)?;
+        }
     }
 
-    Ok(Extension {
-        start,
-        end,
-        properties,
+    Ok(Extend {
+        extendee,
+        fields,
+        comments,
+        location: locate(lexer, start, lexer.last_span().end),
     })
 }
 
-fn parse_service(lexer: &mut Lexer) -> Result<Service, Error> {
+fn parse_extend_field(
+    lexer: &mut Lexer,
+    cx: &mut Context,
+    ident: &str,
+    span: Span,
+) -> Result<Field, Error> {
+    let label = match ident {
+        "optional" => Label::Optional,
+        "repeated" => Label::Repeated,
+        "required" => {
+            return Err(Error::new(
+                ErrorKind::NotAllowed("extension fields can not be `required`".to_string()),
+                span,
+            ));
+        }
+        "group" => {
+            return Err(Error::new(
+                ErrorKind::Unsupported(
+                    "group is not supported, because it is deprecated already".to_string(),
+                ),
+                span,
+            ));
+        }
+        // proto3 only allows extending options, which fields are
+        // optional without the label
+        typ if cx.syntax == Syntax::Proto3 => {
+            let (name, number, options) = parse_field_and_next(lexer, cx)?;
+            return Ok(Field {
+                label: Label::Optional,
+                typ: FieldType::from(typ),
+                name,
+                number,
+                options,
+                comments: lookup_comments(lexer, span.start, lexer.last_span().end),
+                location: locate(lexer, span.start, lexer.last_span().end),
+            });
+        }
+        _ => {
+            return Err(Error::new(
+                ErrorKind::Unexpected {
+                    token: ident.to_string(),
+                    expected: "`optional` or `repeated`".to_string(),
+                },
+                span,
+            ));
+        }
+    };
+
+    let (typ, name, number, options) = parse_field(lexer, cx)?;
+
+    Ok(Field {
+        label,
+        typ,
+        name,
+        number,
+        options,
+        comments: lookup_comments(lexer, span.start, lexer.last_span().end),
+        location: locate(lexer, span.start, lexer.last_span().end),
+    })
+}
+
+fn parse_service(lexer: &mut Lexer, cx: &mut Context) -> Result<Service, Error> {
+    // the `service` keyword
+    let start = lexer.last_span().start;
     let name = take_ident(lexer)?;
 
     assert_next(lexer, Token::LeftBrace)?;
+    let mut comments = lookup_comments(lexer, start, lexer.last_span().end);
 
     let mut functions = Vec::new();
+    let mut options = Options::default();
     loop {
-        let (token, span) = take_next(lexer)?;
-        match token {
+        let (token, span) = next_statement(lexer, cx)?;
+        let result = match token {
             Token::RightBrace => {
+                comments.closing = closing_comments(lexer, span.start);
                 break;
             }
-            Token::Ident(ident) if ident == "rpc" => {}
-            _ => {
-                panic!("{}", lexer.diagnostic(span, "unexpected token"))
+            Token::Semicolon => continue,
+            Token::Ident("option") => parse_option(lexer).map(|option| options.push(option)),
+            Token::Ident("rpc") => {
+                parse_function(lexer, cx).map(|function| functions.push(function))
             }
+            _ => Err(unexpected(token, "`rpc`, `option` or right brace", span)),
+        };
+
+        if let Err(err) = result {
+            cx.recover(lexer, err)?;
         }
+    }
 
-        let name = take_ident(lexer)?;
+    Ok(Service {
+        name: name.to_string(),
+        functions,
+        options,
+        comments,
+        location: locate(lexer, start, lexer.last_span().end),
+    })
+}
 
-        //
-        assert_next(lexer, Token::LeftParentheses)?;
+// parse `Foo(stream Req) returns (Resp) { option ...; }`, the `rpc` keyword
+// is consumed already
+fn parse_function(lexer: &mut Lexer, cx: &mut Context) -> Result<Function, Error> {
+    // the `rpc` keyword
+    let start = lexer.last_span().start;
+    let name = take_ident(lexer)?;
 
+    //
+    assert_next(lexer, Token::LeftParentheses)?;
+
+    let ident = take_ident(lexer)?;
+    let (client_stream, request) = if ident == "stream" {
         let ident = take_ident(lexer)?;
-        let (client_stream, request) = if ident == "stream" {
-            let ident = take_ident(lexer)?;
-            (true, ident)
-        } else {
-            (false, ident)
-        };
+        (true, ident)
+    } else {
+        (false, ident)
+    };
 
-        assert_next(lexer, Token::RightParentheses)?;
+    assert_next(lexer, Token::RightParentheses)?;
 
-        assert_next(lexer, Token::Ident("returns"))?;
+    assert_next(lexer, Token::Ident("returns"))?;
 
-        assert_next(lexer, Token::LeftParentheses)?;
+    assert_next(lexer, Token::LeftParentheses)?;
 
+    let ident = take_ident(lexer)?;
+    let (server_stream, response) = if ident == "stream" {
         let ident = take_ident(lexer)?;
-        let (server_stream, response) = if ident == "stream" {
-            let ident = take_ident(lexer)?;
-            (true, ident)
-        } else {
-            (false, ident)
-        };
+        (true, ident)
+    } else {
+        (false, ident)
+    };
 
-        assert_next(lexer, Token::RightParentheses)?;
+    assert_next(lexer, Token::RightParentheses)?;
 
-        match take_next(lexer)? {
-            (Token::LeftBrace, _) => {
-                assert_next(lexer, Token::RightBrace)?;
-            }
-            (Token::Semicolon, _) => {
-                // do nothing
-            }
-            (_, span) => {
-                panic!("{}", lexer.diagnostic(span, "unexpected token"))
+    let mut options = Options::default();
+    let (token, span) = take_next(lexer)?;
+    match token {
+        Token::LeftBrace => loop {
+            let (token, span) = next_statement(lexer, cx)?;
+            let result = match token {
+                Token::RightBrace => break,
+                Token::Semicolon => continue,
+                Token::Ident("option") => parse_option(lexer).map(|option| options.push(option)),
+                _ => Err(unexpected(token, "option or right brace", span)),
+            };
+
+            if let Err(err) = result {
+                cx.recover(lexer, err)?;
             }
+        },
+        Token::Semicolon => {
+            // do nothing
         }
-
-        let method = match (client_stream, server_stream) {
-            (false, false) => Method::Unary,
-            (true, false) => Method::ClientStreaming,
-            (false, true) => Method::ServerStreaming,
-            (true, true) => Method::BidiStreaming,
-        };
-
-        functions.push(Function {
-            name: name.to_string(),
-            method,
-            request: request.to_string(),
-            response: response.to_string(),
-        })
+        _ => return Err(unexpected(token, "left brace or semicolon", span)),
     }
 
-    Ok(Service {
+    let method = match (client_stream, server_stream) {
+        (false, false) => Method::Unary,
+        (true, false) => Method::ClientStreaming,
+        (false, true) => Method::ServerStreaming,
+        (true, true) => Method::BidiStreaming,
+    };
+
+    Ok(Function {
         name: name.to_string(),
-        functions,
+        method,
+        request: request.to_string(),
+        response: response.to_string(),
+        options,
+        comments: lookup_comments(lexer, start, lexer.last_span().end),
+        location: locate(lexer, start, lexer.last_span().end),
     })
 }
 
 fn validate_number(tag: u32, all: &[Reserved]) -> bool {
-    for reserved in all {
-        match reserved {
-            Reserved::Single(value) => {
-                if tag == *value {
-                    return false;
-                }
-            }
-            Reserved::Range(start, end) => {
-                if tag >= *start && tag < *end {
-                    return false;
-                }
-            }
-            Reserved::Field(_) => {
-                unimplemented!()
-            }
-        }
-    }
-
-    true
+    !all.iter().any(|reserved| reserved.contains(tag as i64))
 }
 
 #[cfg(test)]
@@ -1058,8 +1591,12 @@ mod tests {
         }
         "#;
 
+        let mut cx = Context {
+            syntax: Syntax::Proto3,
+            errors: vec![],
+        };
         let mut lexer = Lexer::new(input.as_bytes());
-        let service = parse_service(&mut lexer).unwrap();
+        let service = parse_service(&mut lexer, &mut cx).unwrap();
 
         assert_eq!(
             service,
@@ -1071,33 +1608,518 @@ mod tests {
                         method: Method::Unary,
                         request: "Point".to_string(),
                         response: "Feature".to_string(),
+                        options: Default::default(),
+                        comments: Default::default(),
+                        location: Location {
+                            start_line: 2,
+                            start_column: 12,
+                            end_line: 2,
+                            end_column: 54,
+                        },
                     },
                     Function {
                         name: "Get".to_string(),
                         method: Method::Unary,
                         request: "Point".to_string(),
                         response: "Feature".to_string(),
+                        options: Default::default(),
+                        comments: Default::default(),
+                        location: Location {
+                            start_line: 4,
+                            start_column: 12,
+                            end_line: 4,
+                            end_column: 45,
+                        },
                     },
                     Function {
                         name: "ListFeatures".to_string(),
                         method: Method::ServerStreaming,
                         request: "Rectangle".to_string(),
                         response: "Feature".to_string(),
+                        options: Default::default(),
+                        comments: Default::default(),
+                        location: Location {
+                            start_line: 6,
+                            start_column: 12,
+                            end_line: 6,
+                            end_column: 67,
+                        },
                     },
                     Function {
                         name: "RecordRoute".to_string(),
                         method: Method::ClientStreaming,
                         request: "Point".to_string(),
                         response: "RouteSummary".to_string(),
+                        options: Default::default(),
+                        comments: Default::default(),
+                        location: Location {
+                            start_line: 7,
+                            start_column: 12,
+                            end_line: 7,
+                            end_column: 67,
+                        },
                     },
                     Function {
                         name: "RouteChat".to_string(),
                         method: Method::BidiStreaming,
                         request: "RouteNote".to_string(),
                         response: "RouteNote".to_string(),
+                        options: Default::default(),
+                        comments: Default::default(),
+                        location: Location {
+                            start_line: 8,
+                            start_column: 12,
+                            end_line: 8,
+                            end_column: 73,
+                        },
                     },
-                ]
+                ],
+                options: Default::default(),
+                comments: Default::default(),
+                location: Location {
+                    start_line: 0,
+                    start_column: 0,
+                    end_line: 9,
+                    end_column: 9,
+                },
             }
         )
     }
+
+    #[test]
+    fn enum_alias() {
+        // enum is consumed already
+        let input = r#"
+        Status {
+            option allow_alias = true;
+            reserved 3, 5 to 7, "GONE";
+
+            UNKNOWN = 0;
+            STARTED = 1;
+            RUNNING = 1 [deprecated = true];
+            STOPPED = -2;
+        }
+        "#;
+
+        let mut cx = Context {
+            syntax: Syntax::Proto3,
+            errors: vec![],
+        };
+        let mut lexer = Lexer::new(input.as_bytes());
+        let en = parse_enum(&mut lexer, &mut cx).unwrap();
+
+        assert!(en.allow_alias());
+        assert_eq!(en.reserved.len(), 3);
+        assert_eq!(en.variants.len(), 4);
+        assert!(!en.is_alias(&en.variants[1]));
+        assert!(en.is_alias(&en.variants[2]));
+        assert!(en.variants[2].deprecated());
+        assert_eq!(en.canonical(&en.variants[2]).name, "STARTED");
+        assert_eq!(en.variants[3].value, -2);
+
+        for (input, err) in [
+            ("Foo { A = 0; B = 0; }", "duplicate tag 0"),
+            ("Foo { reserved 1 to 3; A = 0; B = 3; }", "is reserved"),
+            ("Foo { reserved \"B\"; A = 0; B = 1; }", "is reserved"),
+        ] {
+            let mut lexer = Lexer::new(input.as_bytes());
+            parse_enum(&mut lexer, &mut cx).unwrap();
+            match cx.errors.pop().as_ref().map(Error::kind) {
+                Some(ErrorKind::Duplicate(text)) | Some(ErrorKind::NotAllowed(text)) => {
+                    assert!(text.contains(err), "{text}")
+                }
+                other => panic!("unexpected result {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn extensions() {
+        let input = r#"
+        syntax = "proto2";
+
+        message Foo {
+            extensions 4, 20 to max [verification = UNVERIFIED];
+
+            extend Foo {
+                repeated string names = 21;
+            }
+        }
+
+        extend Foo {
+            optional int32 bar = 4;
+        }
+        "#;
+
+        let fd = parse(input.as_bytes()).unwrap();
+
+        let foo = &fd.messages[0];
+        assert_eq!(foo.extensions.len(), 2);
+        assert!(foo.extensions[0].contains(4));
+        assert!(!foo.extensions[0].contains(5));
+        assert!(foo.extensions[1].contains((1 << 29) - 1));
+        assert_eq!(
+            foo.extensions[1].properties.get("verification"),
+            Some(&OptionValue::Ident("UNVERIFIED".to_string()))
+        );
+
+        assert_eq!(foo.extends[0].extendee, "Foo");
+        assert_eq!(foo.extends[0].fields[0].label, Label::Repeated);
+        assert_eq!(fd.extends[0].fields[0].name, "bar");
+        assert_eq!(fd.extends[0].fields[0].number, 4);
+    }
+
+    #[test]
+    fn imports() {
+        let input = r#"
+        syntax = "proto3";
+
+        import "a.proto";
+        import public "b.proto";
+        import weak "c.proto";
+        "#;
+
+        let fd = parse(input.as_bytes()).unwrap();
+        let imports = fd
+            .imports
+            .iter()
+            .map(|import| (import.path.as_str(), import.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            imports,
+            [
+                ("a.proto", ImportKind::Normal),
+                ("b.proto", ImportKind::Public),
+                ("c.proto", ImportKind::Weak),
+            ]
+        );
+    }
+
+    #[test]
+    fn options() {
+        let input = r#"
+        syntax = "proto3";
+
+        option java_package = "com.example" ".foo";
+        option (my.file_opt).nested.(my.ext) = -1.5e3;
+
+        message Foo {
+            option (my.msg_opt) = { name: "foo" [my.ext]: 0x10 tags: [1, 2] inner < a: true > };
+
+            int32 bar = 1 [(my.field_opt).min = -5, json_name = "Bar"];
+
+            oneof choice {
+                option (my.oneof_opt) = inf;
+
+                string a = 2;
+            }
+        }
+
+        enum Kind {
+            option (my.enum_opt) = 017;
+
+            UNKNOWN = 0 [(my.value_opt) = "unknown"];
+        }
+
+        service Messaging {
+            option (google.api.default_host) = "example.com";
+
+            rpc GetMessage(GetMessageRequest) returns (Message) {
+                option (google.api.http) = {
+                    get: "/v1/{name=messages/*}"
+                    additional_bindings {
+                        post: "/v1/messages"; body: "*"
+                    }
+                };
+            }
+        }
+        "#;
+
+        let fd = parse(input.as_bytes()).unwrap();
+
+        assert_eq!(
+            fd.options.get("java_package").and_then(OptionValue::as_str),
+            Some("com.example.foo")
+        );
+        assert_eq!(
+            fd.options
+                .get("(my.file_opt).nested.(my.ext)")
+                .and_then(OptionValue::as_f64),
+            Some(-1500.0)
+        );
+        assert_eq!(fd.options.extension("my.file_opt").count(), 1);
+
+        let foo = &fd.messages[0];
+        let msg_opt = foo.options.get("(my.msg_opt)").unwrap();
+        assert_eq!(
+            msg_opt.get("name").and_then(OptionValue::as_str),
+            Some("foo")
+        );
+        assert_eq!(
+            msg_opt.get("[my.ext]").and_then(OptionValue::as_i64),
+            Some(16)
+        );
+        assert_eq!(
+            msg_opt.get("tags"),
+            Some(&OptionValue::List(vec![
+                OptionValue::Integer(1),
+                OptionValue::Integer(2)
+            ]))
+        );
+        assert_eq!(
+            msg_opt
+                .get("inner")
+                .and_then(|inner| inner.get("a"))
+                .and_then(OptionValue::as_bool),
+            Some(true)
+        );
+        assert_eq!(
+            foo.fields[0]
+                .options
+                .get("(my.field_opt).min")
+                .and_then(OptionValue::as_i64),
+            Some(-5)
+        );
+        assert_eq!(
+            foo.oneofs[0]
+                .options
+                .get("(my.oneof_opt)")
+                .and_then(OptionValue::as_f64),
+            Some(f64::INFINITY)
+        );
+
+        let kind = &fd.enums[0];
+        assert_eq!(
+            kind.options
+                .get("(my.enum_opt)")
+                .and_then(OptionValue::as_i64),
+            Some(15)
+        );
+        assert_eq!(
+            kind.variants[0]
+                .options
+                .get("(my.value_opt)")
+                .and_then(OptionValue::as_str),
+            Some("unknown")
+        );
+
+        let service = &fd.services[0];
+        assert!(service.options.contains("(google.api.default_host)"));
+        let http = service.functions[0]
+            .options
+            .get("(google.api.http)")
+            .unwrap();
+        assert_eq!(
+            http.get("get").and_then(OptionValue::as_str),
+            Some("/v1/{name=messages/*}")
+        );
+        let binding = http.get("additional_bindings").unwrap();
+        assert_eq!(
+            binding.get("post").and_then(OptionValue::as_str),
+            Some("/v1/messages")
+        );
+        assert_eq!(binding.get("body").and_then(OptionValue::as_str), Some("*"));
+        assert_eq!(
+            http.to_string(),
+            r#"{ get: "/v1/{name=messages/*}" additional_bindings { post: "/v1/messages" body: "*" } }"#
+        );
+    }
+
+    #[test]
+    fn recovery() {
+        let input = r#"syntax = "proto3";
+
+message Foo {
+    int32 a = 1
+    string b = 0;
+    oneof c {
+        int32 d = 3 [default = 1];
+    }
+}
+
+enum Bar {
+    A = 0;
+    B = 4294967296;
+}
+
+enum Qux {
+    C = 1;
+}
+
+message Ok {
+    int32 a = 1;
+}
+
+message Baz {
+    int32 e = 5;
+"#;
+
+        let errors = parse(input.as_bytes()).unwrap_err();
+        let found = errors
+            .iter()
+            .map(|err| (err.line(), err.column(), err.kind().to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            found,
+            [
+                (
+                    5,
+                    5,
+                    "expected semicolon or left bracket, found `string`".to_string()
+                ),
+                (
+                    7,
+                    22,
+                    "explicit default values are not allowed in proto3".to_string()
+                ),
+                (
+                    13,
+                    9,
+                    "enum value 4294967296 is out of the int32 range".to_string()
+                ),
+                (
+                    17,
+                    5,
+                    "first variant of enum must be zero in proto3".to_string()
+                ),
+                (26, 1, "unexpected end of file".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            errors[0].to_string(),
+            r#"error: expected semicolon or left bracket, found `string`
+ --> <input>:5:5
+  |
+5 |     string b = 0;
+  |     ^^^^^^"#
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let input = r#"
+        syntax = "proto3";
+
+        // detached, because of the blank line
+
+        // A message.
+        //
+        // With two paragraphs.
+        message Foo { // trailing of Foo
+            /* The bar,
+             * block comment */
+            int32 bar = 1; // trailing of bar
+
+            int32 baz = 2;
+            // leading of qux
+            oneof qux {
+                string a = 3; /* trailing of a */
+            }
+        }
+
+        /** Kinds */
+        enum Kind {
+            UNKNOWN = 0; // unknown
+        }
+
+        service Search {
+            // Search everything
+            rpc Find(Foo) returns (Foo);
+        }
+        "#;
+
+        let fd = parse(input.as_bytes()).unwrap();
+
+        let foo = &fd.messages[0];
+        assert_eq!(
+            foo.comments,
+            Comments {
+                leading: Some("A message.\n\nWith two paragraphs.".to_string()),
+                trailing: Some("trailing of Foo".to_string()),
+                detached: vec!["detached, because of the blank line".to_string()],
+                closing: vec![],
+            }
+        );
+        assert_eq!(
+            foo.fields[0].comments,
+            Comments {
+                leading: Some("The bar,\nblock comment".to_string()),
+                trailing: Some("trailing of bar".to_string()),
+                ..Default::default()
+            }
+        );
+        assert!(foo.fields[1].comments.is_empty());
+        assert_eq!(
+            foo.oneofs[0].comments.leading.as_deref(),
+            Some("leading of qux")
+        );
+        assert_eq!(
+            foo.oneofs[0].variants[0].comments.trailing.as_deref(),
+            Some("trailing of a")
+        );
+
+        let kind = &fd.enums[0];
+        assert_eq!(kind.comments.leading.as_deref(), Some("Kinds"));
+        assert_eq!(
+            kind.variants[0].comments.trailing.as_deref(),
+            Some("unknown")
+        );
+
+        let service = &fd.services[0];
+        assert!(service.comments.is_empty());
+        assert_eq!(
+            service.functions[0].comments.leading.as_deref(),
+            Some("Search everything")
+        );
+    }
+
+    #[test]
+    fn closing_comments() {
+        let input = r#"
+        // Copyright
+
+        // The file
+        syntax = "proto3"; // trailing of syntax
+
+        // The package
+        package foo;
+
+        import "bar.proto"; // trailing of import
+
+        message Foo {
+            int32 a = 1;
+
+            // nothing follows
+        }
+
+        enum Kind {
+            UNKNOWN = 0;
+            // first
+
+            // second
+        }
+
+        // the end
+        "#;
+
+        let fd = parse(input.as_bytes()).unwrap();
+        assert_eq!(
+            fd.comments,
+            Comments {
+                leading: Some("The file".to_string()),
+                trailing: Some("trailing of syntax".to_string()),
+                detached: vec!["Copyright".to_string()],
+                closing: vec!["the end".to_string()],
+            }
+        );
+        assert_eq!(fd.package_comments.leading.as_deref(), Some("The package"));
+        assert_eq!(
+            fd.imports[0].comments.trailing.as_deref(),
+            Some("trailing of import")
+        );
+        assert_eq!(fd.messages[0].comments.closing, ["nothing follows"]);
+        assert_eq!(fd.enums[0].comments.closing, ["first", "second"]);
+    }
 }
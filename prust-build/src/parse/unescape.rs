@@ -1,7 +1,7 @@
 /// Based on [`google::protobuf::UnescapeCEscapeString`][1]
 ///
 /// [1]: https://github.com/google/protobuf/blob/3.3.x/src/google/protobuf/stubs/strutil.cc#L312-L322
-pub(super) fn unescape_c_escape_string(s: &str) -> Vec<u8> {
+pub(crate) fn unescape_c_escape_string(s: &str) -> Result<Vec<u8>, String> {
     let src = s.as_bytes();
     let len = src.len();
     let mut dst = Vec::new();
@@ -15,10 +15,10 @@ pub(super) fn unescape_c_escape_string(s: &str) -> Vec<u8> {
         } else {
             p += 1;
             if p == len {
-                panic!(
+                return Err(format!(
                     "invalid c-escaped default binary value ({}): ends with '\'",
                     s
-                )
+                ));
             }
             match src[p] {
                 b'a' => {
@@ -81,28 +81,33 @@ pub(super) fn unescape_c_escape_string(s: &str) -> Vec<u8> {
                 }
                 b'x' | b'X' => {
                     if p + 3 > len {
-                        panic!(
+                        return Err(format!(
                             "invalid c-escaped default binary value ({}): incomplete hex value",
                             s
-                        )
+                        ));
                     }
-                    match u8::from_str_radix(&s[p + 1..p + 3], 16) {
+                    let hex = std::str::from_utf8(&src[p + 1..p + 3]).unwrap_or_default();
+                    match u8::from_str_radix(hex, 16) {
                         Ok(b) => dst.push(b),
-                        _ => panic!(
-                            "invalid c-escaped default binary value ({}): invalid hex value",
-                            &s[p..p + 2]
-                        ),
+                        _ => {
+                            return Err(format!(
+                                "invalid c-escaped default binary value ({}): invalid hex value",
+                                String::from_utf8_lossy(&src[p..p + 3])
+                            ));
+                        }
                     }
                     p += 3;
                 }
-                _ => panic!(
-                    "invalid c-escaped default binary value ({}): invalid escape",
-                    s
-                ),
+                _ => {
+                    return Err(format!(
+                        "invalid c-escaped default binary value ({}): invalid escape",
+                        s
+                    ));
+                }
             }
         }
     }
-    dst
+    Ok(dst)
 }
 
 #[cfg(test)]
@@ -113,26 +118,29 @@ mod tests {
     fn test_unescape_c_escape_string() {
         assert_eq!(
             &b"hello world"[..],
-            &unescape_c_escape_string("hello world")[..]
+            &unescape_c_escape_string("hello world").unwrap()[..]
         );
 
-        assert_eq!(&b"\0"[..], &unescape_c_escape_string(r#"\0"#)[..]);
+        assert_eq!(&b"\0"[..], &unescape_c_escape_string(r#"\0"#).unwrap()[..]);
 
         assert_eq!(
             &[0o012, 0o156],
-            &unescape_c_escape_string(r#"\012\156"#)[..]
+            &unescape_c_escape_string(r#"\012\156"#).unwrap()[..]
+        );
+        assert_eq!(
+            &[0x01, 0x02],
+            &unescape_c_escape_string(r#"\x01\x02"#).unwrap()[..]
         );
-        assert_eq!(&[0x01, 0x02], &unescape_c_escape_string(r#"\x01\x02"#)[..]);
 
         assert_eq!(
             &b"\0\x01\x07\x08\x0C\n\r\t\x0B\\\'\"\xFE"[..],
-            &unescape_c_escape_string(r#"\0\001\a\b\f\n\r\t\v\\\'\"\xfe"#)[..]
+            &unescape_c_escape_string(r#"\0\001\a\b\f\n\r\t\v\\\'\"\xfe"#).unwrap()[..]
         );
     }
 
     #[test]
-    #[should_panic(expected = "incomplete hex value")]
     fn test_unescape_c_escape_string_incomplete_hex_value() {
-        unescape_c_escape_string(r#"\x1"#);
+        let err = unescape_c_escape_string(r#"\x1"#).unwrap_err();
+        assert!(err.contains("incomplete hex value"), "{err}");
     }
 }
@@ -0,0 +1,35 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn prust_build(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_prust-build"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn invalid_extend() {
+    let dir = std::env::temp_dir().join(format!("prust-build-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("extend.proto"),
+        "syntax = \"proto2\";\npackage pkg;\nmessage Foo { extensions 100 to 199; }\nextend Foo { optional int32 bar = 9; }\n",
+    )
+    .unwrap();
+
+    // errors are reported, not panics
+    for args in [["generate", "extend.proto"], ["check", "extend.proto"]] {
+        let output = prust_build(&args, &dir);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "{stderr}");
+        assert_eq!(
+            stderr,
+            "invalid extension: `9` of `pkg.bar` is not in the extension ranges of `pkg.Foo`\n"
+        );
+    }
+    assert!(!dir.join("pkg.rs").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
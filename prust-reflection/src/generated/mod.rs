@@ -0,0 +1,9 @@
+//! `v1.rs` and `v1alpha.rs` are generated by `prust-build` from
+//! `proto/grpc/reflection`, with `--embed-descriptors` and `-o` naming them
+//! after the version. The features gating the client and the server are
+//! added by hand.
+
+#[rustfmt::skip]
+pub mod v1;
+#[rustfmt::skip]
+pub mod v1alpha;
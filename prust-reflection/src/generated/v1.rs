@@ -0,0 +1,643 @@
+use prust::*;
+/// The message sent by the client when calling ServerReflectionInfo method.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ServerReflectionRequest {
+    pub host: String,
+    /// To use reflection service, the client should set one of the following
+    /// fields in message_request. The server distinguishes requests by their
+    /// defined field and then handles them using corresponding methods.
+    pub message_request: Option<server_reflection_request::MessageRequest>,
+}
+impl Deserialize for ServerReflectionRequest {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.host = buf.read_string()?,
+                26 => msg.message_request = Some(server_reflection_request::MessageRequest::FileByFilename(buf.read_string()?)),
+                34 => msg.message_request = Some(server_reflection_request::MessageRequest::FileContainingSymbol(buf.read_string()?)),
+                42 => msg.message_request = Some(server_reflection_request::MessageRequest::FileContainingExtension(buf.read_msg()?)),
+                50 => msg.message_request = Some(server_reflection_request::MessageRequest::AllExtensionNumbersOfType(buf.read_string()?)),
+                58 => msg.message_request = Some(server_reflection_request::MessageRequest::ListServices(buf.read_string()?)),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ServerReflectionRequest {
+    fn encoded_len(&self) -> usize {
+        (if !self.host.is_empty() { 1 + sizeof_len(self.host.len()) } else { 0 })
+            + match &self.message_request {
+                Some(server_reflection_request::MessageRequest::FileByFilename(v)) => 1 + sizeof_len(v.len()),
+                Some(server_reflection_request::MessageRequest::FileContainingSymbol(v)) => 1 + sizeof_len(v.len()),
+                Some(server_reflection_request::MessageRequest::FileContainingExtension(v)) => 1 + sizeof_len(v.encoded_len()),
+                Some(server_reflection_request::MessageRequest::AllExtensionNumbersOfType(v)) => 1 + sizeof_len(v.len()),
+                Some(server_reflection_request::MessageRequest::ListServices(v)) => 1 + sizeof_len(v.len()),
+                None => 0,
+            }
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if !self.host.is_empty() { buf.write_string(10, self.host.as_str())? }
+        match &self.message_request {
+            Some(server_reflection_request::MessageRequest::FileByFilename(v)) => buf.write_string(26, v.as_str())?,
+            Some(server_reflection_request::MessageRequest::FileContainingSymbol(v)) => buf.write_string(34, v.as_str())?,
+            Some(server_reflection_request::MessageRequest::FileContainingExtension(v)) => buf.write_msg(42, v)?,
+            Some(server_reflection_request::MessageRequest::AllExtensionNumbersOfType(v)) => buf.write_string(50, v.as_str())?,
+            Some(server_reflection_request::MessageRequest::ListServices(v)) => buf.write_string(58, v.as_str())?,
+            None => {}
+        }
+        Ok(buf.pos)
+    }
+}
+pub mod server_reflection_request {
+    #[allow(unused_imports)]
+    use super::*;
+    /// To use reflection service, the client should set one of the following
+    /// fields in message_request. The server distinguishes requests by their
+    /// defined field and then handles them using corresponding methods.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum MessageRequest {
+        /// Find a proto file by the file name.
+        FileByFilename(String),
+        /// Find the proto file that declares the given fully-qualified symbol name.
+        /// This field should be a fully-qualified symbol name
+        /// (e.g. \<package\>.\<service\>\[.\<method\>\] or \<package\>.\<type\>).
+        FileContainingSymbol(String),
+        /// Find the proto file which defines an extension extending the given
+        /// message type with the given field number.
+        FileContainingExtension(ExtensionRequest),
+        /// Finds the tag numbers used by all known extensions of the given message
+        /// type, and appends them to ExtensionNumberResponse in an undefined order.
+        /// Its corresponding method is best-effort: it's not guaranteed that the
+        /// reflection service will implement this method, and it's not guaranteed
+        /// that this method will provide all extensions. Returns
+        /// StatusCode::UNIMPLEMENTED if it's not implemented.
+        /// This field should be a fully-qualified type name. The format is
+        /// \<package\>.\<type\>
+        AllExtensionNumbersOfType(String),
+        /// List the full names of registered services. The content will not be
+        /// checked.
+        ListServices(String),
+    }
+}
+/// The type name and extension number sent by the client when requesting
+/// file_containing_extension.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExtensionRequest {
+    /// Fully-qualified type name. The format should be \<package\>.\<type\>
+    pub containing_type: String,
+    pub extension_number: i32,
+}
+impl Deserialize for ExtensionRequest {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.containing_type = buf.read_string()?,
+                16 => msg.extension_number = buf.read_int32()?,
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ExtensionRequest {
+    fn encoded_len(&self) -> usize {
+        (if !self.containing_type.is_empty() { 1 + sizeof_len(self.containing_type.len()) } else { 0 })
+            + if self.extension_number != 0 { 1 + sizeof_int32(self.extension_number) } else { 0 }
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if !self.containing_type.is_empty() { buf.write_string(10, self.containing_type.as_str())? }
+        if self.extension_number != 0 { buf.write_int32(16, self.extension_number)? }
+        Ok(buf.pos)
+    }
+}
+/// The message sent by the server to answer ServerReflectionInfo method.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ServerReflectionResponse {
+    pub valid_host: String,
+    pub original_request: Option<ServerReflectionRequest>,
+    /// The server sets one of the following fields according to the message_request
+    /// in the request.
+    pub message_response: Option<server_reflection_response::MessageResponse>,
+}
+impl Deserialize for ServerReflectionResponse {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.valid_host = buf.read_string()?,
+                18 => msg.original_request = Some(buf.read_msg()?),
+                34 => msg.message_response = Some(server_reflection_response::MessageResponse::FileDescriptorResponse(buf.read_msg()?)),
+                42 => msg.message_response = Some(server_reflection_response::MessageResponse::AllExtensionNumbersResponse(buf.read_msg()?)),
+                50 => msg.message_response = Some(server_reflection_response::MessageResponse::ListServicesResponse(buf.read_msg()?)),
+                58 => msg.message_response = Some(server_reflection_response::MessageResponse::ErrorResponse(buf.read_msg()?)),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ServerReflectionResponse {
+    fn encoded_len(&self) -> usize {
+        (if !self.valid_host.is_empty() { 1 + sizeof_len(self.valid_host.len()) } else { 0 })
+            + self.original_request.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + match &self.message_response {
+                Some(server_reflection_response::MessageResponse::FileDescriptorResponse(v)) => 1 + sizeof_len(v.encoded_len()),
+                Some(server_reflection_response::MessageResponse::AllExtensionNumbersResponse(v)) => 1 + sizeof_len(v.encoded_len()),
+                Some(server_reflection_response::MessageResponse::ListServicesResponse(v)) => 1 + sizeof_len(v.encoded_len()),
+                Some(server_reflection_response::MessageResponse::ErrorResponse(v)) => 1 + sizeof_len(v.encoded_len()),
+                None => 0,
+            }
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if !self.valid_host.is_empty() { buf.write_string(10, self.valid_host.as_str())? }
+        if let Some(v) = &self.original_request { buf.write_msg(18, v)? }
+        match &self.message_response {
+            Some(server_reflection_response::MessageResponse::FileDescriptorResponse(v)) => buf.write_msg(34, v)?,
+            Some(server_reflection_response::MessageResponse::AllExtensionNumbersResponse(v)) => buf.write_msg(42, v)?,
+            Some(server_reflection_response::MessageResponse::ListServicesResponse(v)) => buf.write_msg(50, v)?,
+            Some(server_reflection_response::MessageResponse::ErrorResponse(v)) => buf.write_msg(58, v)?,
+            None => {}
+        }
+        Ok(buf.pos)
+    }
+}
+pub mod server_reflection_response {
+    #[allow(unused_imports)]
+    use super::*;
+    /// The server sets one of the following fields according to the message_request
+    /// in the request.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum MessageResponse {
+        /// This message is used to answer file_by_filename, file_containing_symbol,
+        /// file_containing_extension requests with transitive dependencies.
+        /// As the repeated label is not allowed in oneof fields, we use a
+        /// FileDescriptorResponse message to encapsulate the repeated fields.
+        /// The reflection service is allowed to avoid sending FileDescriptorProtos
+        /// that were previously sent in response to earlier requests in the stream.
+        FileDescriptorResponse(FileDescriptorResponse),
+        /// This message is used to answer all_extension_numbers_of_type requests.
+        AllExtensionNumbersResponse(ExtensionNumberResponse),
+        /// This message is used to answer list_services requests.
+        ListServicesResponse(ListServiceResponse),
+        /// This message is used when an error occurs.
+        ErrorResponse(ErrorResponse),
+    }
+}
+/// Serialized FileDescriptorProto messages sent by the server answering
+/// a file_by_filename, file_containing_symbol, or file_containing_extension
+/// request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileDescriptorResponse {
+    /// Serialized FileDescriptorProto messages. We avoid taking a dependency on
+    /// descriptor.proto, which uses proto2 only features, by making them opaque
+    /// bytes instead.
+    pub file_descriptor_proto: Vec<Vec<u8>>,
+}
+impl Deserialize for FileDescriptorResponse {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.file_descriptor_proto.push(buf.read_bytes()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for FileDescriptorResponse {
+    fn encoded_len(&self) -> usize {
+        self.file_descriptor_proto.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.file_descriptor_proto { buf.write_bytes(10, v.as_slice())? }
+        Ok(buf.pos)
+    }
+}
+/// A list of extension numbers sent by the server answering
+/// all_extension_numbers_of_type request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExtensionNumberResponse {
+    /// Full name of the base type, including the package name. The format
+    /// is \<package\>.\<type\>
+    pub base_type_name: String,
+    pub extension_number: Vec<i32>,
+}
+impl Deserialize for ExtensionNumberResponse {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.base_type_name = buf.read_string()?,
+                18 => msg.extension_number = buf.read_packed(Reader::read_int32)?,
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ExtensionNumberResponse {
+    fn encoded_len(&self) -> usize {
+        (if !self.base_type_name.is_empty() { 1 + sizeof_len(self.base_type_name.len()) } else { 0 })
+            + if self.extension_number.is_empty() { 0 } else { 1 + sizeof_len(self.extension_number.iter().fold(0, |acc, v| acc + sizeof_int32(*v))) }
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if !self.base_type_name.is_empty() { buf.write_string(10, self.base_type_name.as_str())? }
+        buf.write_packed_int32(18, &self.extension_number)?;
+        Ok(buf.pos)
+    }
+}
+/// A list of ServiceResponse sent by the server answering list_services request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ListServiceResponse {
+    /// The information of each service may be expanded in the future, so we use
+    /// ServiceResponse message to encapsulate it.
+    pub service: Vec<ServiceResponse>,
+}
+impl Deserialize for ListServiceResponse {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.service.push(buf.read_msg()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ListServiceResponse {
+    fn encoded_len(&self) -> usize {
+        self.service.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.service { buf.write_msg(10, v)? }
+        Ok(buf.pos)
+    }
+}
+/// The information of a single service used by ListServiceResponse to answer
+/// list_services request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ServiceResponse {
+    /// Full name of a registered service, including its package name. The format
+    /// is \<package\>.\<service\>
+    pub name: String,
+}
+impl Deserialize for ServiceResponse {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = buf.read_string()?,
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ServiceResponse {
+    fn encoded_len(&self) -> usize {
+        if !self.name.is_empty() { 1 + sizeof_len(self.name.len()) } else { 0 }
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if !self.name.is_empty() { buf.write_string(10, self.name.as_str())? }
+        Ok(buf.pos)
+    }
+}
+/// The error code and error message sent by the server when an error occurs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ErrorResponse {
+    /// This field uses the error codes defined in grpc::StatusCode.
+    pub error_code: i32,
+    pub error_message: String,
+}
+impl Deserialize for ErrorResponse {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                8 => msg.error_code = buf.read_int32()?,
+                18 => msg.error_message = buf.read_string()?,
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ErrorResponse {
+    fn encoded_len(&self) -> usize {
+        (if self.error_code != 0 { 1 + sizeof_int32(self.error_code) } else { 0 })
+            + if !self.error_message.is_empty() { 1 + sizeof_len(self.error_message.len()) } else { 0 }
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if self.error_code != 0 { buf.write_int32(8, self.error_code)? }
+        if !self.error_message.is_empty() { buf.write_string(18, self.error_message.as_str())? }
+        Ok(buf.pos)
+    }
+}
+#[cfg(feature = "client")]
+pub mod server_reflection_client {
+    #![allow(dead_code, unused_imports, unused_variables)]
+    use super::*;
+    use tonic::codegen::*;
+    #[derive(Clone, Debug)]
+    pub struct ServerReflectionClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl ServerReflectionClient<tonic::transport::Channel> {
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> ServerReflectionClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::Body>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: http::Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> ServerReflectionClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: Service<
+                http::Request<tonic::body::Body>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::Body>>::ResponseBody,
+                >,
+            >,
+            <T as Service<
+                http::Request<tonic::body::Body>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            ServerReflectionClient::new(InterceptedService::new(inner, interceptor))
+        }
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// The reflection service is structured as a bidirectional stream, ensuring
+        /// all related requests go to a single server.
+        pub async fn server_reflection_info(
+            &mut self,
+            req: impl tonic::IntoStreamingRequest<Message = ServerReflectionRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<ServerReflectionResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|err| tonic::Status::unknown(
+                    format!("Service was not ready: {}", err.into())
+                ))?;
+            let codec = prust::tonic_codec::Codec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+            );
+            let mut req = req.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.reflection.v1.ServerReflection", "ServerReflectionInfo"));
+            self.inner.streaming(req, path, codec).await
+        }
+    }
+}
+#[cfg(feature = "server")]
+pub mod server_reflection_server {
+    #![allow(dead_code, unused_imports)]
+    use super::*;
+    use tonic::codegen::*;
+    #[async_trait]
+    pub trait ServerReflection: Send + Sync + 'static {
+        type ServerReflectionInfoStream: tokio_stream::Stream<
+            Item = Result<ServerReflectionResponse, tonic::Status>,
+        > + Send + 'static;
+        /// The reflection service is structured as a bidirectional stream, ensuring
+        /// all related requests go to a single server.
+        async fn server_reflection_info(
+            &self,
+            req: tonic::Request<tonic::Streaming<ServerReflectionRequest>>,
+        ) -> Result<tonic::Response<Self::ServerReflectionInfoStream>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct ServerReflectionServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> ServerReflectionServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self {
+                inner: Arc::new(inner),
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T> Clone for ServerReflectionServer<T> {
+        fn clone(&self) -> Self {
+            Self {
+                inner: self.inner.clone(),
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T> tonic::server::NamedService for ServerReflectionServer<T> {
+        const NAME: &'static str = "grpc.reflection.v1.ServerReflection";
+    }
+    impl<T, B> Service<http::Request<B>> for ServerReflectionServer<T>
+    where
+        T: ServerReflection,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo" => {
+                    struct Wrapper<T: ServerReflection>(Arc<T>);
+                    impl<T: ServerReflection> tonic::server::StreamingService<ServerReflectionRequest> for Wrapper<T> {
+                        type Response = ServerReflectionResponse;
+                        type ResponseStream = T::ServerReflectionInfoStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            req: tonic::Request<tonic::Streaming<ServerReflectionRequest>>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            Box::pin(async move {
+                                <T as ServerReflection>::server_reflection_info(&inner, req).await
+                            })
+                        }
+                    }
+                    let method = Wrapper(self.inner.clone());
+                    let codec = prust::tonic_codec::Codec::default();
+                    let mut grpc = tonic::server::Grpc::new(codec)
+                        .apply_compression_config(
+                            self.accept_compression_encodings,
+                            self.send_compression_encodings,
+                        )
+                        .apply_max_message_size_config(
+                             self.max_decoding_message_size,
+                             self.max_encoding_message_size,
+                         );
+                    Box::pin(async move {
+                        Ok(grpc.streaming(method, req).await)
+                    })
+                }
+                _ => Box::pin(async move {
+                    let mut resp = http::Response::new(
+                        tonic::body::Body::default(),
+                    );
+                    let headers = resp.headers_mut();
+                    headers.insert(
+                        tonic::Status::GRPC_STATUS,
+                        (tonic::Code::Unimplemented as i32).into(),
+                    );
+                    headers.insert(
+                        http::header::CONTENT_TYPE,
+                        tonic::metadata::GRPC_CONTENT_TYPE,
+                    );
+                    Ok(resp)
+                })
+            }
+        }
+    }
+}
+/// Encoded `FileDescriptorSet` of this file and its imports
+pub const FILE_DESCRIPTOR_SET: &[u8] = b"\n\xd0\r\n#grpc/reflection/v1/reflection.proto\x12\x12grpc.reflection.v1\"\xf3\x02\n\x17ServerReflectionRequest\x12\x12\n\x04host\x18\x01 \x01(\tR\x04host\x12*\n\x10file_by_filename\x18\x03 \x01(\tH\x00R\x0efileByFilename\x126\n\x16file_containing_symbol\x18\x04 \x01(\tH\x00R\x14fileContainingSymbol\x12b\n\x19file_containing_extension\x18\x05 \x01(\x0b2$.grpc.reflection.v1.ExtensionRequestH\x00R\x17fileContainingExtension\x12B\n\x1dall_extension_numbers_of_type\x18\x06 \x01(\tH\x00R\x19allExtensionNumbersOfType\x12%\n\rlist_services\x18\x07 \x01(\tH\x00R\x0clistServicesB\x11\n\x0fmessage_request\"f\n\x10ExtensionRequest\x12\'\n\x0fcontaining_type\x18\x01 \x01(\tR\x0econtainingType\x12)\n\x10extension_number\x18\x02 \x01(\x05R\x0fextensionNumber\"\xae\x04\n\x18ServerReflectionResponse\x12\x1d\n\nvalid_host\x18\x01 \x01(\tR\tvalidHost\x12V\n\x10original_request\x18\x02 \x01(\x0b2+.grpc.reflection.v1.ServerReflectionRequestR\x0foriginalRequest\x12f\n\x18file_descriptor_response\x18\x04 \x01(\x0b2*.grpc.reflection.v1.FileDescriptorResponseH\x00R\x16fileDescriptorResponse\x12r\n\x1eall_extension_numbers_response\x18\x05 \x01(\x0b2+.grpc.reflection.v1.ExtensionNumberResponseH\x00R\x1ballExtensionNumbersResponse\x12_\n\x16list_services_response\x18\x06 \x01(\x0b2\'.grpc.reflection.v1.ListServiceResponseH\x00R\x14listServicesResponse\x12J\n\x0eerror_response\x18\x07 \x01(\x0b2!.grpc.reflection.v1.ErrorResponseH\x00R\rerrorResponseB\x12\n\x10message_response\"L\n\x16FileDescriptorResponse\x122\n\x15file_descriptor_proto\x18\x01 \x03(\x0cR\x13fileDescriptorProto\"j\n\x17ExtensionNumberResponse\x12$\n\x0ebase_type_name\x18\x01 \x01(\tR\x0cbaseTypeName\x12)\n\x10extension_number\x18\x02 \x03(\x05R\x0fextensionNumber\"T\n\x13ListServiceResponse\x12=\n\x07service\x18\x01 \x03(\x0b2#.grpc.reflection.v1.ServiceResponseR\x07service\"%\n\x0fServiceResponse\x12\x12\n\x04name\x18\x01 \x01(\tR\x04name\"S\n\rErrorResponse\x12\x1d\n\nerror_code\x18\x01 \x01(\x05R\terrorCode\x12#\n\rerror_message\x18\x02 \x01(\tR\x0cerrorMessage2\x89\x01\n\x10ServerReflection\x12u\n\x14ServerReflectionInfo\x12+.grpc.reflection.v1.ServerReflectionRequest\x1a,.grpc.reflection.v1.ServerReflectionResponse(\x010\x01Bf\n\x15io.grpc.reflection.v1B\x15ServerReflectionProtoP\x01Z4google.golang.org/grpc/reflection/grpc_reflection_v1b\x06proto3";
@@ -0,0 +1,8 @@
+mod generated;
+#[cfg(feature = "server")]
+pub mod server;
+
+pub use generated::{v1, v1alpha};
+
+#[cfg(feature = "server")]
+pub use server::{Builder, Error, ReflectionService};
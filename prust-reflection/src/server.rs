@@ -0,0 +1,652 @@
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use prust::descriptor::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    FileDescriptorSet,
+};
+use prust::tonic::async_trait;
+use prust::tonic::{Code, Request, Response, Status, Streaming};
+use prust::{DecodeError, Deserialize, Serialize};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::v1::server_reflection_request::MessageRequest;
+use crate::v1::server_reflection_response::MessageResponse;
+use crate::v1::{
+    ErrorResponse, ExtensionNumberResponse, FileDescriptorResponse, ListServiceResponse,
+    ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+};
+use crate::{v1, v1alpha};
+
+/// Errors building a reflection service from descriptor sets.
+#[derive(Debug)]
+pub enum Error {
+    /// A registered descriptor set could not be decoded
+    DecodeError(DecodeError),
+    /// A registered file is unnamed or imports a file that is not registered
+    InvalidFileDescriptor(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DecodeError(err) => write!(f, "failed to decode descriptor set: {err}"),
+            Error::InvalidFileDescriptor(reason) => write!(f, "invalid file descriptor: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Builds a gRPC server reflection service, serving the files of registered
+/// `FileDescriptorSet`s over `grpc.reflection.v1` or `grpc.reflection.v1alpha`.
+///
+/// Descriptor sets of generated services are embedded as `FILE_DESCRIPTOR_SET`
+/// when `prust_build::Config::embed_descriptors` is enabled.
+///
+/// ```ignore
+/// let reflection = prust_reflection::Builder::configure()
+///     .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+///     .build_v1()?;
+///
+/// Server::builder().add_service(reflection).serve(addr).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Builder {
+    sets: Vec<Vec<u8>>,
+    include_reflection_service: bool,
+}
+
+impl Builder {
+    /// Create a builder, the reflection service itself is listed by default
+    pub fn configure() -> Self {
+        Builder {
+            sets: Vec::new(),
+            include_reflection_service: true,
+        }
+    }
+
+    /// Register an encoded `FileDescriptorSet`. Files registered already, e.g.
+    /// imports shared by several sets, are skipped.
+    pub fn register_encoded_file_descriptor_set(mut self, bytes: &[u8]) -> Self {
+        self.sets.push(bytes.to_vec());
+        self
+    }
+
+    /// Register a `FileDescriptorSet`
+    pub fn register_file_descriptor_set(mut self, set: &FileDescriptorSet) -> Self {
+        let mut buf = vec![0u8; set.encoded_len()];
+        set.encode(&mut buf)
+            .expect("buffer is large enough for the descriptor set");
+        self.sets.push(buf);
+        self
+    }
+
+    /// Whether the reflection service describes and lists itself
+    pub fn include_reflection_service(mut self, include: bool) -> Self {
+        self.include_reflection_service = include;
+        self
+    }
+
+    /// Build a `grpc.reflection.v1.ServerReflection` server
+    pub fn build_v1(
+        self,
+    ) -> Result<v1::server_reflection_server::ServerReflectionServer<ReflectionService>, Error>
+    {
+        let service = self.build(v1::FILE_DESCRIPTOR_SET)?;
+        Ok(v1::server_reflection_server::ServerReflectionServer::new(
+            service,
+        ))
+    }
+
+    /// Build a `grpc.reflection.v1alpha.ServerReflection` server, for clients
+    /// predating `v1`
+    pub fn build_v1alpha(
+        self,
+    ) -> Result<v1alpha::server_reflection_server::ServerReflectionServer<ReflectionService>, Error>
+    {
+        let service = self.build(v1alpha::FILE_DESCRIPTOR_SET)?;
+        Ok(v1alpha::server_reflection_server::ServerReflectionServer::new(service))
+    }
+
+    fn build(mut self, reflection: &[u8]) -> Result<ReflectionService, Error> {
+        if self.include_reflection_service {
+            self.sets.push(reflection.to_vec());
+        }
+
+        let mut index = Index::default();
+        for bytes in &self.sets {
+            let set = FileDescriptorSet::decode(bytes).map_err(Error::DecodeError)?;
+            for file in set.file {
+                index.add_file(file)?;
+            }
+        }
+
+        // imports might be registered by a later set
+        for (name, deps) in &index.dependencies {
+            if let Some(missing) = deps.iter().find(|dep| !index.files.contains_key(*dep)) {
+                return Err(Error::InvalidFileDescriptor(format!(
+                    "{name:?} imports {missing:?}, which is not registered"
+                )));
+            }
+        }
+
+        Ok(ReflectionService {
+            index: Arc::new(index),
+        })
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::configure()
+    }
+}
+
+/// Registered files, indexed by everything clients look them up with. Names
+/// are fully qualified, without the leading dot.
+#[derive(Debug, Default)]
+struct Index {
+    // file name -> encoded FileDescriptorProto
+    files: HashMap<String, Vec<u8>>,
+    // file name -> imported file names
+    dependencies: HashMap<String, Vec<String>>,
+    // symbol -> file name
+    symbols: HashMap<String, String>,
+    // (extendee, number) -> file name
+    extensions: HashMap<(String, i32), String>,
+    services: Vec<String>,
+}
+
+impl Index {
+    fn add_file(&mut self, file: FileDescriptorProto) -> Result<(), Error> {
+        let Some(name) = file.name.clone() else {
+            return Err(Error::InvalidFileDescriptor(
+                "file without name".to_string(),
+            ));
+        };
+
+        if self.files.contains_key(&name) {
+            return Ok(());
+        }
+
+        let prefix = match file.package.as_deref() {
+            Some(package) if !package.is_empty() => format!("{package}."),
+            _ => String::new(),
+        };
+
+        for msg in &file.message_type {
+            self.add_message(&prefix, msg, &name);
+        }
+        for en in &file.enum_type {
+            self.add_enum(&prefix, en, &name);
+        }
+        for ext in &file.extension {
+            self.add_extension(&prefix, ext, &name);
+        }
+        for svc in &file.service {
+            let svc_name = format!("{prefix}{}", svc.name.as_deref().unwrap_or_default());
+            for method in &svc.method {
+                self.add_symbol(
+                    format!("{svc_name}.{}", method.name.as_deref().unwrap_or_default()),
+                    &name,
+                );
+            }
+            self.add_symbol(svc_name.clone(), &name);
+            self.services.push(svc_name);
+        }
+
+        // weak imports are allowed to be missing
+        let deps = file
+            .dependency
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !file.weak_dependency.contains(&(*i as i32)))
+            .map(|(_, dep)| dep.clone())
+            .collect();
+        self.dependencies.insert(name.clone(), deps);
+
+        let mut buf = vec![0u8; file.encoded_len()];
+        file.encode(&mut buf)
+            .expect("buffer is large enough for the file descriptor");
+        self.files.insert(name, buf);
+
+        Ok(())
+    }
+
+    fn add_message(&mut self, prefix: &str, msg: &DescriptorProto, file: &str) {
+        let full_name = format!("{prefix}{}", msg.name.as_deref().unwrap_or_default());
+        let nested = format!("{full_name}.");
+
+        for field in &msg.field {
+            self.add_symbol(
+                format!("{nested}{}", field.name.as_deref().unwrap_or_default()),
+                file,
+            );
+        }
+        for oneof in &msg.oneof_decl {
+            self.add_symbol(
+                format!("{nested}{}", oneof.name.as_deref().unwrap_or_default()),
+                file,
+            );
+        }
+        for msg in &msg.nested_type {
+            self.add_message(&nested, msg, file);
+        }
+        for en in &msg.enum_type {
+            self.add_enum(&nested, en, file);
+        }
+        for ext in &msg.extension {
+            self.add_extension(&nested, ext, file);
+        }
+
+        self.add_symbol(full_name, file);
+    }
+
+    fn add_enum(&mut self, prefix: &str, en: &EnumDescriptorProto, file: &str) {
+        // enum values are siblings of their enum, like in C++
+        for value in &en.value {
+            self.add_symbol(
+                format!("{prefix}{}", value.name.as_deref().unwrap_or_default()),
+                file,
+            );
+        }
+        self.add_symbol(
+            format!("{prefix}{}", en.name.as_deref().unwrap_or_default()),
+            file,
+        );
+    }
+
+    fn add_extension(&mut self, prefix: &str, ext: &FieldDescriptorProto, file: &str) {
+        self.add_symbol(
+            format!("{prefix}{}", ext.name.as_deref().unwrap_or_default()),
+            file,
+        );
+
+        let extendee = ext
+            .extendee
+            .as_deref()
+            .unwrap_or_default()
+            .trim_start_matches('.')
+            .to_string();
+        self.extensions
+            .entry((extendee, ext.number.unwrap_or_default()))
+            .or_insert_with(|| file.to_string());
+    }
+
+    // the first file defining a symbol wins
+    fn add_symbol(&mut self, symbol: String, file: &str) {
+        self.symbols
+            .entry(symbol)
+            .or_insert_with(|| file.to_string());
+    }
+
+    fn respond(&self, req: ServerReflectionRequest) -> ServerReflectionResponse {
+        let message_response = match &req.message_request {
+            Some(MessageRequest::FileByFilename(name)) => self
+                .file_response(name)
+                .ok_or_else(|| (Code::NotFound, format!("file {name:?} not found"))),
+            Some(MessageRequest::FileContainingSymbol(symbol)) => self
+                .symbols
+                .get(symbol.trim_start_matches('.'))
+                .and_then(|name| self.file_response(name))
+                .ok_or_else(|| (Code::NotFound, format!("symbol {symbol:?} not found"))),
+            Some(MessageRequest::FileContainingExtension(ext)) => {
+                let key = (
+                    ext.containing_type.trim_start_matches('.').to_string(),
+                    ext.extension_number,
+                );
+                self.extensions
+                    .get(&key)
+                    .and_then(|name| self.file_response(name))
+                    .ok_or_else(|| {
+                        let message = format!(
+                            "extension {} of {:?} not found",
+                            ext.extension_number, ext.containing_type
+                        );
+                        (Code::NotFound, message)
+                    })
+            }
+            Some(MessageRequest::AllExtensionNumbersOfType(typ)) => {
+                let base_type_name = typ.trim_start_matches('.').to_string();
+                if self.symbols.contains_key(&base_type_name) {
+                    let mut extension_number = self
+                        .extensions
+                        .keys()
+                        .filter(|(extendee, _)| *extendee == base_type_name)
+                        .map(|(_, number)| *number)
+                        .collect::<Vec<_>>();
+                    extension_number.sort_unstable();
+
+                    Ok(MessageResponse::AllExtensionNumbersResponse(
+                        ExtensionNumberResponse {
+                            base_type_name,
+                            extension_number,
+                        },
+                    ))
+                } else {
+                    Err((Code::NotFound, format!("type {typ:?} not found")))
+                }
+            }
+            Some(MessageRequest::ListServices(_)) => {
+                Ok(MessageResponse::ListServicesResponse(ListServiceResponse {
+                    service: self
+                        .services
+                        .iter()
+                        .map(|name| ServiceResponse { name: name.clone() })
+                        .collect(),
+                }))
+            }
+            None => Err((Code::InvalidArgument, "empty request".to_string())),
+        }
+        .unwrap_or_else(|(code, error_message)| {
+            MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: code as i32,
+                error_message,
+            })
+        });
+
+        ServerReflectionResponse {
+            valid_host: req.host.clone(),
+            original_request: Some(req),
+            message_response: Some(message_response),
+        }
+    }
+
+    // the file first, then everything it imports transitively
+    fn file_response(&self, name: &str) -> Option<MessageResponse> {
+        self.files.get(name)?;
+
+        let mut visited = HashSet::new();
+        let mut pending = vec![name];
+        let mut file_descriptor_proto = Vec::new();
+        while let Some(name) = pending.pop() {
+            if !visited.insert(name) {
+                continue;
+            }
+
+            file_descriptor_proto.push(self.files[name].clone());
+            if let Some(deps) = self.dependencies.get(name) {
+                pending.extend(deps.iter().rev().map(String::as_str));
+            }
+        }
+
+        Some(MessageResponse::FileDescriptorResponse(
+            FileDescriptorResponse {
+                file_descriptor_proto,
+            },
+        ))
+    }
+}
+
+/// A service answering `ServerReflectionInfo` streams from registered
+/// descriptor sets, see [`Builder`].
+#[derive(Clone, Debug)]
+pub struct ReflectionService {
+    index: Arc<Index>,
+}
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+#[async_trait]
+impl v1::server_reflection_server::ServerReflection for ReflectionService {
+    type ServerReflectionInfoStream = ResponseStream<ServerReflectionResponse>;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let index = Arc::clone(&self.index);
+        let stream = request
+            .into_inner()
+            .map(move |req| req.map(|req| index.respond(req)));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+#[async_trait]
+impl v1alpha::server_reflection_server::ServerReflection for ReflectionService {
+    type ServerReflectionInfoStream = ResponseStream<v1alpha::ServerReflectionResponse>;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<v1alpha::ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        // both versions share the same wire format
+        let index = Arc::clone(&self.index);
+        let stream = request
+            .into_inner()
+            .map(move |req| req.and_then(|req| transcode(&index.respond(transcode(&req)?))));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+fn transcode<A: Serialize, B: Deserialize>(msg: &A) -> Result<B, Status> {
+    let mut buf = vec![0u8; msg.encoded_len()];
+    msg.encode(&mut buf)
+        .map_err(|err| Status::internal(format!("failed to transcode message: {err}")))?;
+    B::decode(&buf).map_err(|err| Status::internal(format!("failed to transcode message: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use prust::Deserialize;
+    use prust::descriptor::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MethodDescriptorProto, ServiceDescriptorProto, descriptor_proto,
+    };
+    use prust::tonic::Code;
+
+    use super::{Builder, Error, Index, transcode};
+    use crate::v1::server_reflection_request::MessageRequest;
+    use crate::v1::server_reflection_response::MessageResponse;
+    use crate::v1::{ExtensionRequest, ServerReflectionRequest};
+    use crate::v1alpha;
+
+    // package example; message Foo { extensions 100 to 200; }
+    // extend Foo { Foo bar = 100; } service Echo { rpc Echo(Foo) returns (Foo); }
+    fn example() -> FileDescriptorSet {
+        let file = FileDescriptorProto {
+            name: Some("example.proto".to_string()),
+            package: Some("example".to_string()),
+            dependency: vec!["grpc/reflection/v1/reflection.proto".to_string()],
+            message_type: vec![DescriptorProto {
+                name: Some("Foo".to_string()),
+                extension_range: vec![descriptor_proto::ExtensionRange {
+                    start: Some(100),
+                    end: Some(201),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            extension: vec![FieldDescriptorProto {
+                name: Some("bar".to_string()),
+                number: Some(100),
+                extendee: Some(".example.Foo".to_string()),
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Echo".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("Echo".to_string()),
+                    input_type: Some(".example.Foo".to_string()),
+                    output_type: Some(".example.Foo".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        FileDescriptorSet { file: vec![file] }
+    }
+
+    fn index() -> Index {
+        let service = Builder::configure()
+            .register_file_descriptor_set(&example())
+            .build(crate::v1::FILE_DESCRIPTOR_SET)
+            .unwrap();
+        std::sync::Arc::into_inner(service.index).unwrap()
+    }
+
+    fn request(req: MessageRequest) -> ServerReflectionRequest {
+        ServerReflectionRequest {
+            host: "localhost".to_string(),
+            message_request: Some(req),
+        }
+    }
+
+    // names of the files in a file descriptor response
+    fn file_names(resp: Option<MessageResponse>) -> Vec<String> {
+        let Some(MessageResponse::FileDescriptorResponse(resp)) = resp else {
+            panic!("not a file descriptor response: {resp:?}");
+        };
+
+        resp.file_descriptor_proto
+            .iter()
+            .map(|bytes| FileDescriptorProto::decode(bytes).unwrap().name.unwrap())
+            .collect()
+    }
+
+    fn error_code(resp: Option<MessageResponse>) -> i32 {
+        let Some(MessageResponse::ErrorResponse(resp)) = resp else {
+            panic!("not an error response: {resp:?}");
+        };
+
+        resp.error_code
+    }
+
+    #[test]
+    fn list_services() {
+        let resp = index().respond(request(MessageRequest::ListServices(String::new())));
+        assert_eq!(resp.valid_host, "localhost");
+        assert!(resp.original_request.is_some());
+
+        let Some(MessageResponse::ListServicesResponse(resp)) = resp.message_response else {
+            panic!("not a list services response");
+        };
+        let mut names = resp
+            .service
+            .into_iter()
+            .map(|svc| svc.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            ["example.Echo", "grpc.reflection.v1.ServerReflection"]
+        );
+    }
+
+    #[test]
+    fn files() {
+        let index = index();
+        let expected = ["example.proto", "grpc/reflection/v1/reflection.proto"];
+
+        for symbol in [
+            "example.Echo",
+            ".example.Echo.Echo",
+            "example.Foo",
+            "example.bar",
+        ] {
+            let req = request(MessageRequest::FileContainingSymbol(symbol.to_string()));
+            assert_eq!(file_names(index.respond(req).message_response), expected);
+        }
+
+        let req = request(MessageRequest::FileByFilename("example.proto".to_string()));
+        assert_eq!(file_names(index.respond(req).message_response), expected);
+
+        let req = request(MessageRequest::FileContainingExtension(ExtensionRequest {
+            containing_type: "example.Foo".to_string(),
+            extension_number: 100,
+        }));
+        assert_eq!(file_names(index.respond(req).message_response), expected);
+
+        let req = request(MessageRequest::FileContainingSymbol(
+            "grpc.reflection.v1.ServerReflectionRequest.file_by_filename".to_string(),
+        ));
+        assert_eq!(
+            file_names(index.respond(req).message_response),
+            ["grpc/reflection/v1/reflection.proto"]
+        );
+    }
+
+    #[test]
+    fn extension_numbers() {
+        let req = request(MessageRequest::AllExtensionNumbersOfType(
+            "example.Foo".to_string(),
+        ));
+        let Some(MessageResponse::AllExtensionNumbersResponse(resp)) =
+            index().respond(req).message_response
+        else {
+            panic!("not an extension numbers response");
+        };
+
+        assert_eq!(resp.base_type_name, "example.Foo");
+        assert_eq!(resp.extension_number, [100]);
+    }
+
+    #[test]
+    fn error_codes() {
+        let index = index();
+        for req in [
+            MessageRequest::FileByFilename("missing.proto".to_string()),
+            MessageRequest::FileContainingSymbol("example.Missing".to_string()),
+            MessageRequest::FileContainingExtension(ExtensionRequest {
+                containing_type: "example.Foo".to_string(),
+                extension_number: 101,
+            }),
+            MessageRequest::AllExtensionNumbersOfType("example.Missing".to_string()),
+        ] {
+            let resp = index.respond(request(req));
+            assert_eq!(error_code(resp.message_response), Code::NotFound as i32);
+        }
+
+        let resp = index.respond(ServerReflectionRequest::default());
+        assert_eq!(
+            error_code(resp.message_response),
+            Code::InvalidArgument as i32
+        );
+    }
+
+    #[test]
+    fn invalid_sets() {
+        let err = Builder::configure()
+            .register_encoded_file_descriptor_set(&[0xff])
+            .build_v1()
+            .unwrap_err();
+        assert!(matches!(err, Error::DecodeError(_)), "{err}");
+
+        // the reflection service is the import of the example
+        let err = Builder::configure()
+            .register_file_descriptor_set(&example())
+            .include_reflection_service(false)
+            .build_v1()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidFileDescriptor(_)), "{err}");
+    }
+
+    #[test]
+    fn v1alpha() {
+        let req = v1alpha::ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(
+                v1alpha::server_reflection_request::MessageRequest::FileContainingSymbol(
+                    "example.Foo".to_string(),
+                ),
+            ),
+        };
+
+        let resp: v1alpha::ServerReflectionResponse =
+            transcode(&index().respond(transcode(&req).unwrap())).unwrap();
+        let Some(v1alpha::server_reflection_response::MessageResponse::FileDescriptorResponse(
+            resp,
+        )) = resp.message_response
+        else {
+            panic!("not a file descriptor response");
+        };
+        assert_eq!(resp.file_descriptor_proto.len(), 2);
+    }
+}
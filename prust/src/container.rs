@@ -0,0 +1,139 @@
+//! Rust types of `string`, `bytes` and repeated fields.
+//!
+//! `prust_build::Config::string_type`, `bytes_type` and `repeated_type`
+//! replace `String`, `Vec<u8>` and `Vec<T>` in the generated code, e.g. with
+//! `Arc<str>` for strings shared after decoding or a `SmallVec` for short
+//! lists. The generated code reads the values through `Deref` and builds
+//! them with the traits here.
+//!
+//! Implementations for `bytes::Bytes`, `smallvec::SmallVec` and
+//! `smol_str::SmolStr` are behind the features of the same names. Other
+//! foreign types need a newtype.
+
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A rust type of `string` fields.
+pub trait ProtoString: Default + Deref<Target = str> {
+    fn from_string(value: String) -> Self;
+
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+/// A rust type of `bytes` fields.
+pub trait ProtoBytes: Default + Deref<Target = [u8]> {
+    fn from_vec(value: Vec<u8>) -> Self;
+
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A rust type of repeated fields, values decoded are pushed one by one.
+pub trait ProtoRepeated<T>: Default + Deref<Target = [T]> {
+    fn push(&mut self, value: T);
+}
+
+impl ProtoString for String {
+    fn from_string(value: String) -> Self {
+        value
+    }
+}
+
+impl ProtoString for Box<str> {
+    fn from_string(value: String) -> Self {
+        value.into_boxed_str()
+    }
+}
+
+impl ProtoString for Arc<str> {
+    fn from_string(value: String) -> Self {
+        Arc::from(value)
+    }
+}
+
+impl ProtoString for Rc<str> {
+    fn from_string(value: String) -> Self {
+        Rc::from(value)
+    }
+}
+
+impl ProtoBytes for Vec<u8> {
+    fn from_vec(value: Vec<u8>) -> Self {
+        value
+    }
+}
+
+impl ProtoBytes for Box<[u8]> {
+    fn from_vec(value: Vec<u8>) -> Self {
+        value.into_boxed_slice()
+    }
+}
+
+impl ProtoBytes for Arc<[u8]> {
+    fn from_vec(value: Vec<u8>) -> Self {
+        Arc::from(value)
+    }
+}
+
+impl ProtoBytes for Rc<[u8]> {
+    fn from_vec(value: Vec<u8>) -> Self {
+        Rc::from(value)
+    }
+}
+
+impl<T> ProtoRepeated<T> for Vec<T> {
+    fn push(&mut self, value: T) {
+        Vec::push(self, value)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl ProtoBytes for bytes::Bytes {
+    fn from_vec(value: Vec<u8>) -> Self {
+        bytes::Bytes::from(value)
+    }
+}
+
+#[cfg(feature = "smol_str")]
+impl ProtoString for smol_str::SmolStr {
+    fn from_string(value: String) -> Self {
+        smol_str::SmolStr::from(value)
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<A: smallvec::Array> ProtoRepeated<A::Item> for smallvec::SmallVec<A> {
+    fn push(&mut self, value: A::Item) {
+        smallvec::SmallVec::push(self, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_all<C: ProtoRepeated<T>, T>(values: impl IntoIterator<Item = T>) -> C {
+        let mut container = C::default();
+        for value in values {
+            container.push(value);
+        }
+        container
+    }
+
+    #[test]
+    fn containers() {
+        let s = <Arc<str>>::from_string("hello".to_string());
+        assert_eq!(s.as_str(), "hello");
+        assert_eq!(<Box<str>>::default().len(), 0);
+
+        let b = <Box<[u8]>>::from_vec(vec![1, 2, 3]);
+        assert_eq!(ProtoBytes::as_slice(&b), [1, 2, 3]);
+
+        let v: Vec<u32> = push_all([1, 2]);
+        assert_eq!(v, [1, 2]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Custom rust types of fields.
+//!
+//! `prust_build::Config::field_type` replaces the type generated for a field,
+//! e.g. `Ipv4Addr` for a `fixed32` or a `Money` newtype for a message. The
+//! generated code converts the value to the proto type when encoding, and
+//! back when decoding, through the traits here.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::encoding::DecodeError;
+
+/// A rust type of a scalar or enum field, `P` is the type generated for the
+/// proto type otherwise, e.g. `u32` for `fixed32`, `Vec<u8>` for `bytes` or
+/// the enum.
+///
+/// Values are converted for both computing the size and writing, so the
+/// conversion should be cheap.
+pub trait ProtoScalar<P>: Sized {
+    fn to_proto(&self) -> P;
+
+    /// Errors are returned as [`DecodeError::Conversion`] by decoding
+    fn from_proto(value: P) -> Result<Self, DecodeError>;
+}
+
+/// A rust type of a message field, `M` is the message generated.
+pub trait ProtoMessage<M>: Sized {
+    fn to_message(&self) -> M;
+
+    /// Errors are returned as [`DecodeError::Conversion`] by decoding
+    fn from_message(msg: M) -> Result<Self, DecodeError>;
+}
+
+impl ProtoScalar<u32> for Ipv4Addr {
+    fn to_proto(&self) -> u32 {
+        u32::from(*self)
+    }
+
+    fn from_proto(value: u32) -> Result<Self, DecodeError> {
+        Ok(Ipv4Addr::from(value))
+    }
+}
+
+impl ProtoScalar<Vec<u8>> for Ipv4Addr {
+    fn to_proto(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn from_proto(value: Vec<u8>) -> Result<Self, DecodeError> {
+        let octets = <[u8; 4]>::try_from(value).map_err(|value| {
+            DecodeError::Conversion(format!("{} bytes is not an IPv4 address", value.len()))
+        })?;
+
+        Ok(Ipv4Addr::from(octets))
+    }
+}
+
+impl ProtoScalar<Vec<u8>> for Ipv6Addr {
+    fn to_proto(&self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn from_proto(value: Vec<u8>) -> Result<Self, DecodeError> {
+        let octets = <[u8; 16]>::try_from(value).map_err(|value| {
+            DecodeError::Conversion(format!("{} bytes is not an IPv6 address", value.len()))
+        })?;
+
+        Ok(Ipv6Addr::from(octets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_addresses() {
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        assert_eq!(ProtoScalar::<u32>::to_proto(&ip), 0x0A00_0001);
+        assert_eq!(<Ipv4Addr as ProtoScalar<u32>>::from_proto(0x0A00_0001), Ok(ip));
+        assert_eq!(
+            <Ipv4Addr as ProtoScalar<Vec<u8>>>::from_proto(vec![10, 0, 0, 1]),
+            Ok(ip)
+        );
+        assert_eq!(
+            <Ipv4Addr as ProtoScalar<Vec<u8>>>::from_proto(vec![10, 0, 1]),
+            Err(DecodeError::Conversion(
+                "3 bytes is not an IPv4 address".to_string()
+            ))
+        );
+
+        let ip = Ipv6Addr::LOCALHOST;
+        let bytes = ProtoScalar::<Vec<u8>>::to_proto(&ip);
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(<Ipv6Addr as ProtoScalar<Vec<u8>>>::from_proto(bytes), Ok(ip));
+    }
+}
@@ -0,0 +1,1949 @@
+use prust::*;
+/// The protocol compiler can output a FileDescriptorSet containing the .proto
+/// files it parses.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileDescriptorSet {
+    pub file: Vec<FileDescriptorProto>,
+}
+impl Deserialize for FileDescriptorSet {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.file.push(buf.read_msg()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for FileDescriptorSet {
+    fn encoded_len(&self) -> usize {
+        self.file.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.file { buf.write_msg(10, v)? }
+        Ok(buf.pos)
+    }
+}
+/// Describes a complete .proto file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileDescriptorProto {
+    /// file name, relative to root of source tree
+    pub name: Option<String>,
+    /// e.g. "foo", "foo.bar", etc.
+    pub package: Option<String>,
+    /// Names of files imported by this file.
+    pub dependency: Vec<String>,
+    /// Indexes of the public imported files in the dependency list above.
+    pub public_dependency: Vec<i32>,
+    /// Indexes of the weak imported files in the dependency list.
+    pub weak_dependency: Vec<i32>,
+    /// All top-level definitions in this file.
+    pub message_type: Vec<DescriptorProto>,
+    pub enum_type: Vec<EnumDescriptorProto>,
+    pub service: Vec<ServiceDescriptorProto>,
+    pub extension: Vec<FieldDescriptorProto>,
+    pub options: Option<FileOptions>,
+    /// This field contains optional information about the original source code.
+    pub source_code_info: Option<SourceCodeInfo>,
+    /// The syntax of the proto file, "proto2" or "proto3".
+    pub syntax: Option<String>,
+}
+#[allow(dead_code)]
+impl FileDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+    pub fn package(&self) -> &str { self.package.as_deref().unwrap_or("") }
+    pub fn has_package(&self) -> bool { self.package.is_some() }
+    pub fn set_package(&mut self, value: String) { self.package = Some(value) }
+    pub fn clear_package(&mut self) { self.package = None }
+    pub fn syntax(&self) -> &str { self.syntax.as_deref().unwrap_or("") }
+    pub fn has_syntax(&self) -> bool { self.syntax.is_some() }
+    pub fn set_syntax(&mut self, value: String) { self.syntax = Some(value) }
+    pub fn clear_syntax(&mut self) { self.syntax = None }
+}
+impl Deserialize for FileDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                18 => msg.package = Some(buf.read_string()?),
+                26 => msg.dependency.push(buf.read_string()?),
+                80 => msg.public_dependency.push(buf.read_int32()?),
+                88 => msg.weak_dependency.push(buf.read_int32()?),
+                34 => msg.message_type.push(buf.read_msg()?),
+                42 => msg.enum_type.push(buf.read_msg()?),
+                50 => msg.service.push(buf.read_msg()?),
+                58 => msg.extension.push(buf.read_msg()?),
+                66 => msg.options = Some(buf.read_msg()?),
+                74 => msg.source_code_info = Some(buf.read_msg()?),
+                98 => msg.syntax = Some(buf.read_string()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for FileDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.package.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.dependency.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.len()))
+            + self.public_dependency.iter().fold(0, |acc, v| acc + 1 + sizeof_int32(*v))
+            + self.weak_dependency.iter().fold(0, |acc, v| acc + 1 + sizeof_int32(*v))
+            + self.message_type.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.enum_type.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.service.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.extension.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + self.source_code_info.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + self.syntax.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        if let Some(v) = &self.package { buf.write_string(18, v.as_str())? }
+        for v in &self.dependency { buf.write_string(26, v.as_str())? }
+        for v in &self.public_dependency { buf.write_int32(80, *v)? }
+        for v in &self.weak_dependency { buf.write_int32(88, *v)? }
+        for v in &self.message_type { buf.write_msg(34, v)? }
+        for v in &self.enum_type { buf.write_msg(42, v)? }
+        for v in &self.service { buf.write_msg(50, v)? }
+        for v in &self.extension { buf.write_msg(58, v)? }
+        if let Some(v) = &self.options { buf.write_msg(66, v)? }
+        if let Some(v) = &self.source_code_info { buf.write_msg(74, v)? }
+        if let Some(v) = &self.syntax { buf.write_string(98, v.as_str())? }
+        Ok(buf.pos)
+    }
+}
+/// Describes a message type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DescriptorProto {
+    pub name: Option<String>,
+    pub field: Vec<FieldDescriptorProto>,
+    pub extension: Vec<FieldDescriptorProto>,
+    pub nested_type: Vec<DescriptorProto>,
+    pub enum_type: Vec<EnumDescriptorProto>,
+    pub extension_range: Vec<descriptor_proto::ExtensionRange>,
+    pub oneof_decl: Vec<OneofDescriptorProto>,
+    pub options: Option<MessageOptions>,
+    pub reserved_range: Vec<descriptor_proto::ReservedRange>,
+    /// Reserved field names, which may not be used by fields in the same message.
+    /// A given name may only be reserved once.
+    pub reserved_name: Vec<String>,
+}
+#[allow(dead_code)]
+impl DescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+}
+impl Deserialize for DescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                18 => msg.field.push(buf.read_msg()?),
+                50 => msg.extension.push(buf.read_msg()?),
+                26 => msg.nested_type.push(buf.read_msg()?),
+                34 => msg.enum_type.push(buf.read_msg()?),
+                42 => msg.extension_range.push(buf.read_msg()?),
+                66 => msg.oneof_decl.push(buf.read_msg()?),
+                58 => msg.options = Some(buf.read_msg()?),
+                74 => msg.reserved_range.push(buf.read_msg()?),
+                82 => msg.reserved_name.push(buf.read_string()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for DescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.field.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.extension.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.nested_type.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.enum_type.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.extension_range.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.oneof_decl.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + self.reserved_range.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.reserved_name.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        for v in &self.field { buf.write_msg(18, v)? }
+        for v in &self.extension { buf.write_msg(50, v)? }
+        for v in &self.nested_type { buf.write_msg(26, v)? }
+        for v in &self.enum_type { buf.write_msg(34, v)? }
+        for v in &self.extension_range { buf.write_msg(42, v)? }
+        for v in &self.oneof_decl { buf.write_msg(66, v)? }
+        if let Some(v) = &self.options { buf.write_msg(58, v)? }
+        for v in &self.reserved_range { buf.write_msg(74, v)? }
+        for v in &self.reserved_name { buf.write_string(82, v.as_str())? }
+        Ok(buf.pos)
+    }
+}
+pub mod descriptor_proto {
+    #[allow(unused_imports)]
+    use super::*;
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct ExtensionRange {
+        /// Inclusive.
+        pub start: Option<i32>,
+        /// Exclusive.
+        pub end: Option<i32>,
+        pub options: Option<ExtensionRangeOptions>,
+    }
+    #[allow(dead_code)]
+    impl ExtensionRange {
+        pub fn start(&self) -> i32 { self.start.unwrap_or_default() }
+        pub fn has_start(&self) -> bool { self.start.is_some() }
+        pub fn set_start(&mut self, value: i32) { self.start = Some(value) }
+        pub fn clear_start(&mut self) { self.start = None }
+        pub fn end(&self) -> i32 { self.end.unwrap_or_default() }
+        pub fn has_end(&self) -> bool { self.end.is_some() }
+        pub fn set_end(&mut self, value: i32) { self.end = Some(value) }
+        pub fn clear_end(&mut self) { self.end = None }
+    }
+    impl Deserialize for ExtensionRange {
+        fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+            let mut buf = Reader::new(src);
+            let mut msg: Self = Default::default();
+            while buf.pos < buf.src.len() {
+                let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+                match tag {
+                    8 => msg.start = Some(buf.read_int32()?),
+                    16 => msg.end = Some(buf.read_int32()?),
+                    26 => msg.options = Some(buf.read_msg()?),
+                    _ => {
+                        buf.pos -= 1;
+                        let tag = buf.read_uint32()?;
+                        buf.read_unknown(tag)?;
+                    }
+                }
+            }
+            Ok(msg)
+        }
+    }
+    impl Serialize for ExtensionRange {
+        fn encoded_len(&self) -> usize {
+            self.start.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+                + self.end.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+                + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+        }
+        fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+            let mut buf = Writer::new(buf);
+            if let Some(v) = &self.start { buf.write_int32(8, *v)? }
+            if let Some(v) = &self.end { buf.write_int32(16, *v)? }
+            if let Some(v) = &self.options { buf.write_msg(26, v)? }
+            Ok(buf.pos)
+        }
+    }
+    /// Range of reserved tag numbers. Reserved tag numbers may not be used by
+    /// fields or extension ranges in the same message. Reserved ranges may
+    /// not overlap.
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct ReservedRange {
+        /// Inclusive.
+        pub start: Option<i32>,
+        /// Exclusive.
+        pub end: Option<i32>,
+    }
+    #[allow(dead_code)]
+    impl ReservedRange {
+        pub fn start(&self) -> i32 { self.start.unwrap_or_default() }
+        pub fn has_start(&self) -> bool { self.start.is_some() }
+        pub fn set_start(&mut self, value: i32) { self.start = Some(value) }
+        pub fn clear_start(&mut self) { self.start = None }
+        pub fn end(&self) -> i32 { self.end.unwrap_or_default() }
+        pub fn has_end(&self) -> bool { self.end.is_some() }
+        pub fn set_end(&mut self, value: i32) { self.end = Some(value) }
+        pub fn clear_end(&mut self) { self.end = None }
+    }
+    impl Deserialize for ReservedRange {
+        fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+            let mut buf = Reader::new(src);
+            let mut msg: Self = Default::default();
+            while buf.pos < buf.src.len() {
+                let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+                match tag {
+                    8 => msg.start = Some(buf.read_int32()?),
+                    16 => msg.end = Some(buf.read_int32()?),
+                    _ => {
+                        buf.pos -= 1;
+                        let tag = buf.read_uint32()?;
+                        buf.read_unknown(tag)?;
+                    }
+                }
+            }
+            Ok(msg)
+        }
+    }
+    impl Serialize for ReservedRange {
+        fn encoded_len(&self) -> usize {
+            self.start.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+                + self.end.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+        }
+        fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+            let mut buf = Writer::new(buf);
+            if let Some(v) = &self.start { buf.write_int32(8, *v)? }
+            if let Some(v) = &self.end { buf.write_int32(16, *v)? }
+            Ok(buf.pos)
+        }
+    }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExtensionRangeOptions {
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+impl Deserialize for ExtensionRangeOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ExtensionRangeOptions {
+    fn encoded_len(&self) -> usize {
+        self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for ExtensionRangeOptions {
+    const NAME: &'static str = "google.protobuf.ExtensionRangeOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+/// Describes a field within a message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldDescriptorProto {
+    pub name: Option<String>,
+    pub number: Option<i32>,
+    pub label: Option<field_descriptor_proto::Label>,
+    /// If type_name is set, this need not be set.  If both this and type_name
+    /// are set, this must be one of TYPE_ENUM, TYPE_MESSAGE or TYPE_GROUP.
+    pub r#type: Option<field_descriptor_proto::Type>,
+    /// For message and enum types, this is the name of the type.  If the name
+    /// starts with a '.', it is fully-qualified.
+    pub type_name: Option<String>,
+    /// For extensions, this is the name of the type being extended.  It is
+    /// resolved in the same manner as type_name.
+    pub extendee: Option<String>,
+    /// For numeric types, contains the original text representation of the value.
+    /// For booleans, "true" or "false".
+    /// For strings, contains the default text contents (not escaped in any way).
+    /// For bytes, contains the C escaped value.  All bytes \>= 128 are escaped.
+    pub default_value: Option<String>,
+    /// If set, gives the index of a oneof in the containing type's oneof_decl
+    /// list.  This field is a member of that oneof.
+    pub oneof_index: Option<i32>,
+    /// JSON name of this field. The value is set by protocol compiler. If the
+    /// user has set a "json_name" option on this field, that option's value
+    /// will be used. Otherwise, it's deduced from the field's name by converting
+    /// it to camelCase.
+    pub json_name: Option<String>,
+    pub options: Option<FieldOptions>,
+    /// If true, this is a proto3 "optional". When a proto3 field is optional, it
+    /// tracks presence regardless of field type.
+    pub proto3_optional: Option<bool>,
+}
+#[allow(dead_code)]
+impl FieldDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+    pub fn number(&self) -> i32 { self.number.unwrap_or_default() }
+    pub fn has_number(&self) -> bool { self.number.is_some() }
+    pub fn set_number(&mut self, value: i32) { self.number = Some(value) }
+    pub fn clear_number(&mut self) { self.number = None }
+    pub fn label(&self) -> field_descriptor_proto::Label { self.label.unwrap_or(field_descriptor_proto::Label::Optional) }
+    pub fn has_label(&self) -> bool { self.label.is_some() }
+    pub fn set_label(&mut self, value: field_descriptor_proto::Label) { self.label = Some(value) }
+    pub fn clear_label(&mut self) { self.label = None }
+    pub fn r#type(&self) -> field_descriptor_proto::Type { self.r#type.unwrap_or(field_descriptor_proto::Type::Double) }
+    pub fn has_type(&self) -> bool { self.r#type.is_some() }
+    pub fn set_type(&mut self, value: field_descriptor_proto::Type) { self.r#type = Some(value) }
+    pub fn clear_type(&mut self) { self.r#type = None }
+    pub fn type_name(&self) -> &str { self.type_name.as_deref().unwrap_or("") }
+    pub fn has_type_name(&self) -> bool { self.type_name.is_some() }
+    pub fn set_type_name(&mut self, value: String) { self.type_name = Some(value) }
+    pub fn clear_type_name(&mut self) { self.type_name = None }
+    pub fn extendee(&self) -> &str { self.extendee.as_deref().unwrap_or("") }
+    pub fn has_extendee(&self) -> bool { self.extendee.is_some() }
+    pub fn set_extendee(&mut self, value: String) { self.extendee = Some(value) }
+    pub fn clear_extendee(&mut self) { self.extendee = None }
+    pub fn default_value(&self) -> &str { self.default_value.as_deref().unwrap_or("") }
+    pub fn has_default_value(&self) -> bool { self.default_value.is_some() }
+    pub fn set_default_value(&mut self, value: String) { self.default_value = Some(value) }
+    pub fn clear_default_value(&mut self) { self.default_value = None }
+    pub fn oneof_index(&self) -> i32 { self.oneof_index.unwrap_or_default() }
+    pub fn has_oneof_index(&self) -> bool { self.oneof_index.is_some() }
+    pub fn set_oneof_index(&mut self, value: i32) { self.oneof_index = Some(value) }
+    pub fn clear_oneof_index(&mut self) { self.oneof_index = None }
+    pub fn json_name(&self) -> &str { self.json_name.as_deref().unwrap_or("") }
+    pub fn has_json_name(&self) -> bool { self.json_name.is_some() }
+    pub fn set_json_name(&mut self, value: String) { self.json_name = Some(value) }
+    pub fn clear_json_name(&mut self) { self.json_name = None }
+    pub fn proto3_optional(&self) -> bool { self.proto3_optional.unwrap_or_default() }
+    pub fn has_proto3_optional(&self) -> bool { self.proto3_optional.is_some() }
+    pub fn set_proto3_optional(&mut self, value: bool) { self.proto3_optional = Some(value) }
+    pub fn clear_proto3_optional(&mut self) { self.proto3_optional = None }
+}
+impl Deserialize for FieldDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                10 => msg.name = Some(buf.read_string()?),
+                24 => msg.number = Some(buf.read_int32()?),
+                32 => msg.label = Some(buf.read_enum()?),
+                40 => msg.r#type = Some(buf.read_enum()?),
+                50 => msg.type_name = Some(buf.read_string()?),
+                18 => msg.extendee = Some(buf.read_string()?),
+                58 => msg.default_value = Some(buf.read_string()?),
+                72 => msg.oneof_index = Some(buf.read_int32()?),
+                82 => msg.json_name = Some(buf.read_string()?),
+                66 => msg.options = Some(buf.read_msg()?),
+                136 => msg.proto3_optional = Some(buf.read_bool()?),
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for FieldDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.number.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+            + self.label.as_ref().map_or(0, |_| 1 + 1)
+            + self.r#type.as_ref().map_or(0, |_| 1 + 1)
+            + self.type_name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.extendee.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.default_value.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.oneof_index.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+            + self.json_name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + self.proto3_optional.as_ref().map_or(0, |_| 2 + 1)
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        if let Some(v) = &self.number { buf.write_int32(24, *v)? }
+        if let Some(v) = &self.label { buf.write_int32(32, i32::from(*v))? }
+        if let Some(v) = &self.r#type { buf.write_int32(40, i32::from(*v))? }
+        if let Some(v) = &self.type_name { buf.write_string(50, v.as_str())? }
+        if let Some(v) = &self.extendee { buf.write_string(18, v.as_str())? }
+        if let Some(v) = &self.default_value { buf.write_string(58, v.as_str())? }
+        if let Some(v) = &self.oneof_index { buf.write_int32(72, *v)? }
+        if let Some(v) = &self.json_name { buf.write_string(82, v.as_str())? }
+        if let Some(v) = &self.options { buf.write_msg(66, v)? }
+        if let Some(v) = &self.proto3_optional { buf.write_bool(136, *v)? }
+        Ok(buf.pos)
+    }
+}
+pub mod field_descriptor_proto {
+    #[allow(unused_imports)]
+    use super::*;
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    #[repr(i32)]
+    pub enum Type {
+        /// 0 is reserved for errors.
+        /// Order is weird for historical reasons.
+        #[default]
+        Double = 1,
+        Float = 2,
+        /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT64 if
+        /// negative values are likely.
+        Int64 = 3,
+        Uint64 = 4,
+        /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT32 if
+        /// negative values are likely.
+        Int32 = 5,
+        Fixed64 = 6,
+        Fixed32 = 7,
+        Bool = 8,
+        String = 9,
+        /// Tag-delimited aggregate.
+        Group = 10,
+        /// Length-delimited aggregate.
+        Message = 11,
+        /// New in version 2.
+        Bytes = 12,
+        Uint32 = 13,
+        Enum = 14,
+        Sfixed32 = 15,
+        Sfixed64 = 16,
+        /// Uses ZigZag encoding.
+        Sint32 = 17,
+        /// Uses ZigZag encoding.
+        Sint64 = 18,
+    }
+    #[allow(deprecated)]
+    impl TryFrom<i32> for Type {
+        type Error = DecodeError;
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                1 => Ok(Type::Double),
+                2 => Ok(Type::Float),
+                3 => Ok(Type::Int64),
+                4 => Ok(Type::Uint64),
+                5 => Ok(Type::Int32),
+                6 => Ok(Type::Fixed64),
+                7 => Ok(Type::Fixed32),
+                8 => Ok(Type::Bool),
+                9 => Ok(Type::String),
+                10 => Ok(Type::Group),
+                11 => Ok(Type::Message),
+                12 => Ok(Type::Bytes),
+                13 => Ok(Type::Uint32),
+                14 => Ok(Type::Enum),
+                15 => Ok(Type::Sfixed32),
+                16 => Ok(Type::Sfixed64),
+                17 => Ok(Type::Sint32),
+                18 => Ok(Type::Sint64),
+                _ => Err(DecodeError::UnknownVariant("Type", value)),
+            }
+        }
+    }
+    #[allow(deprecated)]
+    impl From<Type> for i32 {
+        fn from(value: Type) -> Self {
+            value as i32
+        }
+    }
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    #[repr(i32)]
+    pub enum Label {
+        /// 0 is reserved for errors
+        #[default]
+        Optional = 1,
+        Repeated = 3,
+        Required = 2,
+    }
+    #[allow(deprecated)]
+    impl TryFrom<i32> for Label {
+        type Error = DecodeError;
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                1 => Ok(Label::Optional),
+                3 => Ok(Label::Repeated),
+                2 => Ok(Label::Required),
+                _ => Err(DecodeError::UnknownVariant("Label", value)),
+            }
+        }
+    }
+    #[allow(deprecated)]
+    impl From<Label> for i32 {
+        fn from(value: Label) -> Self {
+            value as i32
+        }
+    }
+}
+/// Describes a oneof.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OneofDescriptorProto {
+    pub name: Option<String>,
+    pub options: Option<OneofOptions>,
+}
+#[allow(dead_code)]
+impl OneofDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+}
+impl Deserialize for OneofDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                18 => msg.options = Some(buf.read_msg()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for OneofDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        if let Some(v) = &self.options { buf.write_msg(18, v)? }
+        Ok(buf.pos)
+    }
+}
+/// Describes an enum type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnumDescriptorProto {
+    pub name: Option<String>,
+    pub value: Vec<EnumValueDescriptorProto>,
+    pub options: Option<EnumOptions>,
+    /// Range of reserved numeric values. Reserved numeric values may not be used
+    /// by enum values in the same enum declaration. Reserved ranges may not
+    /// overlap.
+    pub reserved_range: Vec<enum_descriptor_proto::EnumReservedRange>,
+    /// Reserved enum value names, which may not be reused. A given name may only
+    /// be reserved once.
+    pub reserved_name: Vec<String>,
+}
+#[allow(dead_code)]
+impl EnumDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+}
+impl Deserialize for EnumDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                18 => msg.value.push(buf.read_msg()?),
+                26 => msg.options = Some(buf.read_msg()?),
+                34 => msg.reserved_range.push(buf.read_msg()?),
+                42 => msg.reserved_name.push(buf.read_string()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for EnumDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.value.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + self.reserved_range.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.reserved_name.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        for v in &self.value { buf.write_msg(18, v)? }
+        if let Some(v) = &self.options { buf.write_msg(26, v)? }
+        for v in &self.reserved_range { buf.write_msg(34, v)? }
+        for v in &self.reserved_name { buf.write_string(42, v.as_str())? }
+        Ok(buf.pos)
+    }
+}
+pub mod enum_descriptor_proto {
+    #[allow(unused_imports)]
+    use super::*;
+    /// Range of reserved numeric values. Reserved values may not be used by
+    /// entries in the same enum. Reserved ranges may not overlap.
+    ///
+    /// Note that this is distinct from DescriptorProto.ReservedRange in that it
+    /// is inclusive such that it can appropriately represent the entire int32
+    /// domain.
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct EnumReservedRange {
+        /// Inclusive.
+        pub start: Option<i32>,
+        /// Inclusive.
+        pub end: Option<i32>,
+    }
+    #[allow(dead_code)]
+    impl EnumReservedRange {
+        pub fn start(&self) -> i32 { self.start.unwrap_or_default() }
+        pub fn has_start(&self) -> bool { self.start.is_some() }
+        pub fn set_start(&mut self, value: i32) { self.start = Some(value) }
+        pub fn clear_start(&mut self) { self.start = None }
+        pub fn end(&self) -> i32 { self.end.unwrap_or_default() }
+        pub fn has_end(&self) -> bool { self.end.is_some() }
+        pub fn set_end(&mut self, value: i32) { self.end = Some(value) }
+        pub fn clear_end(&mut self) { self.end = None }
+    }
+    impl Deserialize for EnumReservedRange {
+        fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+            let mut buf = Reader::new(src);
+            let mut msg: Self = Default::default();
+            while buf.pos < buf.src.len() {
+                let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+                match tag {
+                    8 => msg.start = Some(buf.read_int32()?),
+                    16 => msg.end = Some(buf.read_int32()?),
+                    _ => {
+                        buf.pos -= 1;
+                        let tag = buf.read_uint32()?;
+                        buf.read_unknown(tag)?;
+                    }
+                }
+            }
+            Ok(msg)
+        }
+    }
+    impl Serialize for EnumReservedRange {
+        fn encoded_len(&self) -> usize {
+            self.start.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+                + self.end.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+        }
+        fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+            let mut buf = Writer::new(buf);
+            if let Some(v) = &self.start { buf.write_int32(8, *v)? }
+            if let Some(v) = &self.end { buf.write_int32(16, *v)? }
+            Ok(buf.pos)
+        }
+    }
+}
+/// Describes a value within an enum.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnumValueDescriptorProto {
+    pub name: Option<String>,
+    pub number: Option<i32>,
+    pub options: Option<EnumValueOptions>,
+}
+#[allow(dead_code)]
+impl EnumValueDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+    pub fn number(&self) -> i32 { self.number.unwrap_or_default() }
+    pub fn has_number(&self) -> bool { self.number.is_some() }
+    pub fn set_number(&mut self, value: i32) { self.number = Some(value) }
+    pub fn clear_number(&mut self) { self.number = None }
+}
+impl Deserialize for EnumValueDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                16 => msg.number = Some(buf.read_int32()?),
+                26 => msg.options = Some(buf.read_msg()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for EnumValueDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.number.as_ref().map_or(0, |v| 1 + sizeof_int32(*v))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        if let Some(v) = &self.number { buf.write_int32(16, *v)? }
+        if let Some(v) = &self.options { buf.write_msg(26, v)? }
+        Ok(buf.pos)
+    }
+}
+/// Describes a service.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceDescriptorProto {
+    pub name: Option<String>,
+    pub method: Vec<MethodDescriptorProto>,
+    pub options: Option<ServiceOptions>,
+}
+#[allow(dead_code)]
+impl ServiceDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+}
+impl Deserialize for ServiceDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                18 => msg.method.push(buf.read_msg()?),
+                26 => msg.options = Some(buf.read_msg()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ServiceDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.method.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        for v in &self.method { buf.write_msg(18, v)? }
+        if let Some(v) = &self.options { buf.write_msg(26, v)? }
+        Ok(buf.pos)
+    }
+}
+/// Describes a method of a service.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MethodDescriptorProto {
+    pub name: Option<String>,
+    /// Input and output type names.  These are resolved in the same way as
+    /// FieldDescriptorProto.type_name, but must refer to a message type.
+    pub input_type: Option<String>,
+    pub output_type: Option<String>,
+    pub options: Option<MethodOptions>,
+    /// Identifies if client streams multiple client messages
+    pub client_streaming: Option<bool>,
+    /// Identifies if server streams multiple server messages
+    pub server_streaming: Option<bool>,
+}
+#[allow(dead_code)]
+impl MethodDescriptorProto {
+    pub fn name(&self) -> &str { self.name.as_deref().unwrap_or("") }
+    pub fn has_name(&self) -> bool { self.name.is_some() }
+    pub fn set_name(&mut self, value: String) { self.name = Some(value) }
+    pub fn clear_name(&mut self) { self.name = None }
+    pub fn input_type(&self) -> &str { self.input_type.as_deref().unwrap_or("") }
+    pub fn has_input_type(&self) -> bool { self.input_type.is_some() }
+    pub fn set_input_type(&mut self, value: String) { self.input_type = Some(value) }
+    pub fn clear_input_type(&mut self) { self.input_type = None }
+    pub fn output_type(&self) -> &str { self.output_type.as_deref().unwrap_or("") }
+    pub fn has_output_type(&self) -> bool { self.output_type.is_some() }
+    pub fn set_output_type(&mut self, value: String) { self.output_type = Some(value) }
+    pub fn clear_output_type(&mut self) { self.output_type = None }
+    pub fn client_streaming(&self) -> bool { self.client_streaming.unwrap_or(false) }
+    pub fn has_client_streaming(&self) -> bool { self.client_streaming.is_some() }
+    pub fn set_client_streaming(&mut self, value: bool) { self.client_streaming = Some(value) }
+    pub fn clear_client_streaming(&mut self) { self.client_streaming = None }
+    pub fn server_streaming(&self) -> bool { self.server_streaming.unwrap_or(false) }
+    pub fn has_server_streaming(&self) -> bool { self.server_streaming.is_some() }
+    pub fn set_server_streaming(&mut self, value: bool) { self.server_streaming = Some(value) }
+    pub fn clear_server_streaming(&mut self) { self.server_streaming = None }
+}
+impl Deserialize for MethodDescriptorProto {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.name = Some(buf.read_string()?),
+                18 => msg.input_type = Some(buf.read_string()?),
+                26 => msg.output_type = Some(buf.read_string()?),
+                34 => msg.options = Some(buf.read_msg()?),
+                40 => msg.client_streaming = Some(buf.read_bool()?),
+                48 => msg.server_streaming = Some(buf.read_bool()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for MethodDescriptorProto {
+    fn encoded_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.input_type.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.output_type.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.options.as_ref().map_or(0, |v| 1 + sizeof_len(v.encoded_len()))
+            + self.client_streaming.as_ref().map_or(0, |_| 1 + 1)
+            + self.server_streaming.as_ref().map_or(0, |_| 1 + 1)
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.name { buf.write_string(10, v.as_str())? }
+        if let Some(v) = &self.input_type { buf.write_string(18, v.as_str())? }
+        if let Some(v) = &self.output_type { buf.write_string(26, v.as_str())? }
+        if let Some(v) = &self.options { buf.write_msg(34, v)? }
+        if let Some(v) = &self.client_streaming { buf.write_bool(40, *v)? }
+        if let Some(v) = &self.server_streaming { buf.write_bool(48, *v)? }
+        Ok(buf.pos)
+    }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileOptions {
+    pub java_package: Option<String>,
+    pub java_outer_classname: Option<String>,
+    pub java_multiple_files: Option<bool>,
+    #[deprecated]
+    pub java_generate_equals_and_hash: Option<bool>,
+    pub java_string_check_utf8: Option<bool>,
+    pub optimize_for: Option<file_options::OptimizeMode>,
+    pub go_package: Option<String>,
+    pub cc_generic_services: Option<bool>,
+    pub java_generic_services: Option<bool>,
+    pub py_generic_services: Option<bool>,
+    /// Is this file deprecated?
+    pub deprecated: Option<bool>,
+    /// Enables the use of arenas for the proto messages in this file. This applies
+    /// only to generated classes for C++.
+    pub cc_enable_arenas: Option<bool>,
+    pub objc_class_prefix: Option<String>,
+    pub csharp_namespace: Option<String>,
+    pub swift_prefix: Option<String>,
+    pub php_class_prefix: Option<String>,
+    pub php_namespace: Option<String>,
+    pub php_metadata_namespace: Option<String>,
+    pub ruby_package: Option<String>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+#[allow(deprecated)]
+impl FileOptions {
+    pub fn java_package(&self) -> &str { self.java_package.as_deref().unwrap_or("") }
+    pub fn has_java_package(&self) -> bool { self.java_package.is_some() }
+    pub fn set_java_package(&mut self, value: String) { self.java_package = Some(value) }
+    pub fn clear_java_package(&mut self) { self.java_package = None }
+    pub fn java_outer_classname(&self) -> &str { self.java_outer_classname.as_deref().unwrap_or("") }
+    pub fn has_java_outer_classname(&self) -> bool { self.java_outer_classname.is_some() }
+    pub fn set_java_outer_classname(&mut self, value: String) { self.java_outer_classname = Some(value) }
+    pub fn clear_java_outer_classname(&mut self) { self.java_outer_classname = None }
+    pub fn java_multiple_files(&self) -> bool { self.java_multiple_files.unwrap_or(false) }
+    pub fn has_java_multiple_files(&self) -> bool { self.java_multiple_files.is_some() }
+    pub fn set_java_multiple_files(&mut self, value: bool) { self.java_multiple_files = Some(value) }
+    pub fn clear_java_multiple_files(&mut self) { self.java_multiple_files = None }
+    #[deprecated]
+    pub fn java_generate_equals_and_hash(&self) -> bool { self.java_generate_equals_and_hash.unwrap_or_default() }
+    #[deprecated]
+    pub fn has_java_generate_equals_and_hash(&self) -> bool { self.java_generate_equals_and_hash.is_some() }
+    #[deprecated]
+    pub fn set_java_generate_equals_and_hash(&mut self, value: bool) { self.java_generate_equals_and_hash = Some(value) }
+    #[deprecated]
+    pub fn clear_java_generate_equals_and_hash(&mut self) { self.java_generate_equals_and_hash = None }
+    pub fn java_string_check_utf8(&self) -> bool { self.java_string_check_utf8.unwrap_or(false) }
+    pub fn has_java_string_check_utf8(&self) -> bool { self.java_string_check_utf8.is_some() }
+    pub fn set_java_string_check_utf8(&mut self, value: bool) { self.java_string_check_utf8 = Some(value) }
+    pub fn clear_java_string_check_utf8(&mut self) { self.java_string_check_utf8 = None }
+    pub fn optimize_for(&self) -> file_options::OptimizeMode { self.optimize_for.unwrap_or(file_options::OptimizeMode::Speed) }
+    pub fn has_optimize_for(&self) -> bool { self.optimize_for.is_some() }
+    pub fn set_optimize_for(&mut self, value: file_options::OptimizeMode) { self.optimize_for = Some(value) }
+    pub fn clear_optimize_for(&mut self) { self.optimize_for = None }
+    pub fn go_package(&self) -> &str { self.go_package.as_deref().unwrap_or("") }
+    pub fn has_go_package(&self) -> bool { self.go_package.is_some() }
+    pub fn set_go_package(&mut self, value: String) { self.go_package = Some(value) }
+    pub fn clear_go_package(&mut self) { self.go_package = None }
+    pub fn cc_generic_services(&self) -> bool { self.cc_generic_services.unwrap_or(false) }
+    pub fn has_cc_generic_services(&self) -> bool { self.cc_generic_services.is_some() }
+    pub fn set_cc_generic_services(&mut self, value: bool) { self.cc_generic_services = Some(value) }
+    pub fn clear_cc_generic_services(&mut self) { self.cc_generic_services = None }
+    pub fn java_generic_services(&self) -> bool { self.java_generic_services.unwrap_or(false) }
+    pub fn has_java_generic_services(&self) -> bool { self.java_generic_services.is_some() }
+    pub fn set_java_generic_services(&mut self, value: bool) { self.java_generic_services = Some(value) }
+    pub fn clear_java_generic_services(&mut self) { self.java_generic_services = None }
+    pub fn py_generic_services(&self) -> bool { self.py_generic_services.unwrap_or(false) }
+    pub fn has_py_generic_services(&self) -> bool { self.py_generic_services.is_some() }
+    pub fn set_py_generic_services(&mut self, value: bool) { self.py_generic_services = Some(value) }
+    pub fn clear_py_generic_services(&mut self) { self.py_generic_services = None }
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+    pub fn cc_enable_arenas(&self) -> bool { self.cc_enable_arenas.unwrap_or(true) }
+    pub fn has_cc_enable_arenas(&self) -> bool { self.cc_enable_arenas.is_some() }
+    pub fn set_cc_enable_arenas(&mut self, value: bool) { self.cc_enable_arenas = Some(value) }
+    pub fn clear_cc_enable_arenas(&mut self) { self.cc_enable_arenas = None }
+    pub fn objc_class_prefix(&self) -> &str { self.objc_class_prefix.as_deref().unwrap_or("") }
+    pub fn has_objc_class_prefix(&self) -> bool { self.objc_class_prefix.is_some() }
+    pub fn set_objc_class_prefix(&mut self, value: String) { self.objc_class_prefix = Some(value) }
+    pub fn clear_objc_class_prefix(&mut self) { self.objc_class_prefix = None }
+    pub fn csharp_namespace(&self) -> &str { self.csharp_namespace.as_deref().unwrap_or("") }
+    pub fn has_csharp_namespace(&self) -> bool { self.csharp_namespace.is_some() }
+    pub fn set_csharp_namespace(&mut self, value: String) { self.csharp_namespace = Some(value) }
+    pub fn clear_csharp_namespace(&mut self) { self.csharp_namespace = None }
+    pub fn swift_prefix(&self) -> &str { self.swift_prefix.as_deref().unwrap_or("") }
+    pub fn has_swift_prefix(&self) -> bool { self.swift_prefix.is_some() }
+    pub fn set_swift_prefix(&mut self, value: String) { self.swift_prefix = Some(value) }
+    pub fn clear_swift_prefix(&mut self) { self.swift_prefix = None }
+    pub fn php_class_prefix(&self) -> &str { self.php_class_prefix.as_deref().unwrap_or("") }
+    pub fn has_php_class_prefix(&self) -> bool { self.php_class_prefix.is_some() }
+    pub fn set_php_class_prefix(&mut self, value: String) { self.php_class_prefix = Some(value) }
+    pub fn clear_php_class_prefix(&mut self) { self.php_class_prefix = None }
+    pub fn php_namespace(&self) -> &str { self.php_namespace.as_deref().unwrap_or("") }
+    pub fn has_php_namespace(&self) -> bool { self.php_namespace.is_some() }
+    pub fn set_php_namespace(&mut self, value: String) { self.php_namespace = Some(value) }
+    pub fn clear_php_namespace(&mut self) { self.php_namespace = None }
+    pub fn php_metadata_namespace(&self) -> &str { self.php_metadata_namespace.as_deref().unwrap_or("") }
+    pub fn has_php_metadata_namespace(&self) -> bool { self.php_metadata_namespace.is_some() }
+    pub fn set_php_metadata_namespace(&mut self, value: String) { self.php_metadata_namespace = Some(value) }
+    pub fn clear_php_metadata_namespace(&mut self) { self.php_metadata_namespace = None }
+    pub fn ruby_package(&self) -> &str { self.ruby_package.as_deref().unwrap_or("") }
+    pub fn has_ruby_package(&self) -> bool { self.ruby_package.is_some() }
+    pub fn set_ruby_package(&mut self, value: String) { self.ruby_package = Some(value) }
+    pub fn clear_ruby_package(&mut self) { self.ruby_package = None }
+}
+#[allow(deprecated)]
+impl Deserialize for FileOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                10 => msg.java_package = Some(buf.read_string()?),
+                66 => msg.java_outer_classname = Some(buf.read_string()?),
+                80 => msg.java_multiple_files = Some(buf.read_bool()?),
+                160 => msg.java_generate_equals_and_hash = Some(buf.read_bool()?),
+                216 => msg.java_string_check_utf8 = Some(buf.read_bool()?),
+                72 => msg.optimize_for = Some(buf.read_enum()?),
+                90 => msg.go_package = Some(buf.read_string()?),
+                128 => msg.cc_generic_services = Some(buf.read_bool()?),
+                136 => msg.java_generic_services = Some(buf.read_bool()?),
+                144 => msg.py_generic_services = Some(buf.read_bool()?),
+                184 => msg.deprecated = Some(buf.read_bool()?),
+                248 => msg.cc_enable_arenas = Some(buf.read_bool()?),
+                290 => msg.objc_class_prefix = Some(buf.read_string()?),
+                298 => msg.csharp_namespace = Some(buf.read_string()?),
+                314 => msg.swift_prefix = Some(buf.read_string()?),
+                322 => msg.php_class_prefix = Some(buf.read_string()?),
+                330 => msg.php_namespace = Some(buf.read_string()?),
+                354 => msg.php_metadata_namespace = Some(buf.read_string()?),
+                362 => msg.ruby_package = Some(buf.read_string()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+#[allow(deprecated)]
+impl Serialize for FileOptions {
+    fn encoded_len(&self) -> usize {
+        self.java_package.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.java_outer_classname.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.java_multiple_files.as_ref().map_or(0, |_| 1 + 1)
+            + self.java_generate_equals_and_hash.as_ref().map_or(0, |_| 2 + 1)
+            + self.java_string_check_utf8.as_ref().map_or(0, |_| 2 + 1)
+            + self.optimize_for.as_ref().map_or(0, |_| 1 + 1)
+            + self.go_package.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.cc_generic_services.as_ref().map_or(0, |_| 2 + 1)
+            + self.java_generic_services.as_ref().map_or(0, |_| 2 + 1)
+            + self.py_generic_services.as_ref().map_or(0, |_| 2 + 1)
+            + self.deprecated.as_ref().map_or(0, |_| 2 + 1)
+            + self.cc_enable_arenas.as_ref().map_or(0, |_| 2 + 1)
+            + self.objc_class_prefix.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.csharp_namespace.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.swift_prefix.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.php_class_prefix.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.php_namespace.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.php_metadata_namespace.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.ruby_package.as_ref().map_or(0, |v| 2 + sizeof_len(v.len()))
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.java_package { buf.write_string(10, v.as_str())? }
+        if let Some(v) = &self.java_outer_classname { buf.write_string(66, v.as_str())? }
+        if let Some(v) = &self.java_multiple_files { buf.write_bool(80, *v)? }
+        if let Some(v) = &self.java_generate_equals_and_hash { buf.write_bool(160, *v)? }
+        if let Some(v) = &self.java_string_check_utf8 { buf.write_bool(216, *v)? }
+        if let Some(v) = &self.optimize_for { buf.write_int32(72, i32::from(*v))? }
+        if let Some(v) = &self.go_package { buf.write_string(90, v.as_str())? }
+        if let Some(v) = &self.cc_generic_services { buf.write_bool(128, *v)? }
+        if let Some(v) = &self.java_generic_services { buf.write_bool(136, *v)? }
+        if let Some(v) = &self.py_generic_services { buf.write_bool(144, *v)? }
+        if let Some(v) = &self.deprecated { buf.write_bool(184, *v)? }
+        if let Some(v) = &self.cc_enable_arenas { buf.write_bool(248, *v)? }
+        if let Some(v) = &self.objc_class_prefix { buf.write_string(290, v.as_str())? }
+        if let Some(v) = &self.csharp_namespace { buf.write_string(298, v.as_str())? }
+        if let Some(v) = &self.swift_prefix { buf.write_string(314, v.as_str())? }
+        if let Some(v) = &self.php_class_prefix { buf.write_string(322, v.as_str())? }
+        if let Some(v) = &self.php_namespace { buf.write_string(330, v.as_str())? }
+        if let Some(v) = &self.php_metadata_namespace { buf.write_string(354, v.as_str())? }
+        if let Some(v) = &self.ruby_package { buf.write_string(362, v.as_str())? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for FileOptions {
+    const NAME: &'static str = "google.protobuf.FileOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+pub mod file_options {
+    #[allow(unused_imports)]
+    use super::*;
+    /// Generated classes can be optimized for speed or code size.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    #[repr(i32)]
+    pub enum OptimizeMode {
+        /// Generate complete code for parsing, serialization,
+        /// etc.
+        #[default]
+        Speed = 1,
+        /// Use ReflectionOps to implement these methods.
+        CodeSize = 2,
+        /// Generate code using MessageLite and the lite runtime.
+        LiteRuntime = 3,
+    }
+    #[allow(deprecated)]
+    impl TryFrom<i32> for OptimizeMode {
+        type Error = DecodeError;
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                1 => Ok(OptimizeMode::Speed),
+                2 => Ok(OptimizeMode::CodeSize),
+                3 => Ok(OptimizeMode::LiteRuntime),
+                _ => Err(DecodeError::UnknownVariant("OptimizeMode", value)),
+            }
+        }
+    }
+    #[allow(deprecated)]
+    impl From<OptimizeMode> for i32 {
+        fn from(value: OptimizeMode) -> Self {
+            value as i32
+        }
+    }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageOptions {
+    pub message_set_wire_format: Option<bool>,
+    pub no_standard_descriptor_accessor: Option<bool>,
+    /// Is this message deprecated?
+    pub deprecated: Option<bool>,
+    /// Whether the message is an automatically generated map entry type for the
+    /// maps field.
+    pub map_entry: Option<bool>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+impl MessageOptions {
+    pub fn message_set_wire_format(&self) -> bool { self.message_set_wire_format.unwrap_or(false) }
+    pub fn has_message_set_wire_format(&self) -> bool { self.message_set_wire_format.is_some() }
+    pub fn set_message_set_wire_format(&mut self, value: bool) { self.message_set_wire_format = Some(value) }
+    pub fn clear_message_set_wire_format(&mut self) { self.message_set_wire_format = None }
+    pub fn no_standard_descriptor_accessor(&self) -> bool { self.no_standard_descriptor_accessor.unwrap_or(false) }
+    pub fn has_no_standard_descriptor_accessor(&self) -> bool { self.no_standard_descriptor_accessor.is_some() }
+    pub fn set_no_standard_descriptor_accessor(&mut self, value: bool) { self.no_standard_descriptor_accessor = Some(value) }
+    pub fn clear_no_standard_descriptor_accessor(&mut self) { self.no_standard_descriptor_accessor = None }
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+    pub fn map_entry(&self) -> bool { self.map_entry.unwrap_or_default() }
+    pub fn has_map_entry(&self) -> bool { self.map_entry.is_some() }
+    pub fn set_map_entry(&mut self, value: bool) { self.map_entry = Some(value) }
+    pub fn clear_map_entry(&mut self) { self.map_entry = None }
+}
+impl Deserialize for MessageOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                8 => msg.message_set_wire_format = Some(buf.read_bool()?),
+                16 => msg.no_standard_descriptor_accessor = Some(buf.read_bool()?),
+                24 => msg.deprecated = Some(buf.read_bool()?),
+                56 => msg.map_entry = Some(buf.read_bool()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for MessageOptions {
+    fn encoded_len(&self) -> usize {
+        self.message_set_wire_format.as_ref().map_or(0, |_| 1 + 1)
+            + self.no_standard_descriptor_accessor.as_ref().map_or(0, |_| 1 + 1)
+            + self.deprecated.as_ref().map_or(0, |_| 1 + 1)
+            + self.map_entry.as_ref().map_or(0, |_| 1 + 1)
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.message_set_wire_format { buf.write_bool(8, *v)? }
+        if let Some(v) = &self.no_standard_descriptor_accessor { buf.write_bool(16, *v)? }
+        if let Some(v) = &self.deprecated { buf.write_bool(24, *v)? }
+        if let Some(v) = &self.map_entry { buf.write_bool(56, *v)? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for MessageOptions {
+    const NAME: &'static str = "google.protobuf.MessageOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldOptions {
+    pub ctype: Option<field_options::CType>,
+    /// The packed option can be enabled for repeated primitive fields to enable
+    /// a more efficient representation on the wire.
+    pub packed: Option<bool>,
+    pub jstype: Option<field_options::JSType>,
+    pub lazy: Option<bool>,
+    pub unverified_lazy: Option<bool>,
+    /// Is this field deprecated?
+    pub deprecated: Option<bool>,
+    /// For Google-internal migration only. Do not use.
+    pub weak: Option<bool>,
+    /// Indicate that the field value should not be printed out when using debug
+    /// formats, e.g. when the field contains sensitive credentials.
+    pub debug_redact: Option<bool>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+impl FieldOptions {
+    pub fn ctype(&self) -> field_options::CType { self.ctype.unwrap_or(field_options::CType::String) }
+    pub fn has_ctype(&self) -> bool { self.ctype.is_some() }
+    pub fn set_ctype(&mut self, value: field_options::CType) { self.ctype = Some(value) }
+    pub fn clear_ctype(&mut self) { self.ctype = None }
+    pub fn packed(&self) -> bool { self.packed.unwrap_or_default() }
+    pub fn has_packed(&self) -> bool { self.packed.is_some() }
+    pub fn set_packed(&mut self, value: bool) { self.packed = Some(value) }
+    pub fn clear_packed(&mut self) { self.packed = None }
+    pub fn jstype(&self) -> field_options::JSType { self.jstype.unwrap_or(field_options::JSType::JsNormal) }
+    pub fn has_jstype(&self) -> bool { self.jstype.is_some() }
+    pub fn set_jstype(&mut self, value: field_options::JSType) { self.jstype = Some(value) }
+    pub fn clear_jstype(&mut self) { self.jstype = None }
+    pub fn lazy(&self) -> bool { self.lazy.unwrap_or(false) }
+    pub fn has_lazy(&self) -> bool { self.lazy.is_some() }
+    pub fn set_lazy(&mut self, value: bool) { self.lazy = Some(value) }
+    pub fn clear_lazy(&mut self) { self.lazy = None }
+    pub fn unverified_lazy(&self) -> bool { self.unverified_lazy.unwrap_or(false) }
+    pub fn has_unverified_lazy(&self) -> bool { self.unverified_lazy.is_some() }
+    pub fn set_unverified_lazy(&mut self, value: bool) { self.unverified_lazy = Some(value) }
+    pub fn clear_unverified_lazy(&mut self) { self.unverified_lazy = None }
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+    pub fn weak(&self) -> bool { self.weak.unwrap_or(false) }
+    pub fn has_weak(&self) -> bool { self.weak.is_some() }
+    pub fn set_weak(&mut self, value: bool) { self.weak = Some(value) }
+    pub fn clear_weak(&mut self) { self.weak = None }
+    pub fn debug_redact(&self) -> bool { self.debug_redact.unwrap_or(false) }
+    pub fn has_debug_redact(&self) -> bool { self.debug_redact.is_some() }
+    pub fn set_debug_redact(&mut self, value: bool) { self.debug_redact = Some(value) }
+    pub fn clear_debug_redact(&mut self) { self.debug_redact = None }
+}
+impl Deserialize for FieldOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                8 => msg.ctype = Some(buf.read_enum()?),
+                16 => msg.packed = Some(buf.read_bool()?),
+                48 => msg.jstype = Some(buf.read_enum()?),
+                40 => msg.lazy = Some(buf.read_bool()?),
+                120 => msg.unverified_lazy = Some(buf.read_bool()?),
+                24 => msg.deprecated = Some(buf.read_bool()?),
+                80 => msg.weak = Some(buf.read_bool()?),
+                128 => msg.debug_redact = Some(buf.read_bool()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for FieldOptions {
+    fn encoded_len(&self) -> usize {
+        self.ctype.as_ref().map_or(0, |_| 1 + 1)
+            + self.packed.as_ref().map_or(0, |_| 1 + 1)
+            + self.jstype.as_ref().map_or(0, |_| 1 + 1)
+            + self.lazy.as_ref().map_or(0, |_| 1 + 1)
+            + self.unverified_lazy.as_ref().map_or(0, |_| 1 + 1)
+            + self.deprecated.as_ref().map_or(0, |_| 1 + 1)
+            + self.weak.as_ref().map_or(0, |_| 1 + 1)
+            + self.debug_redact.as_ref().map_or(0, |_| 2 + 1)
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.ctype { buf.write_int32(8, i32::from(*v))? }
+        if let Some(v) = &self.packed { buf.write_bool(16, *v)? }
+        if let Some(v) = &self.jstype { buf.write_int32(48, i32::from(*v))? }
+        if let Some(v) = &self.lazy { buf.write_bool(40, *v)? }
+        if let Some(v) = &self.unverified_lazy { buf.write_bool(120, *v)? }
+        if let Some(v) = &self.deprecated { buf.write_bool(24, *v)? }
+        if let Some(v) = &self.weak { buf.write_bool(80, *v)? }
+        if let Some(v) = &self.debug_redact { buf.write_bool(128, *v)? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for FieldOptions {
+    const NAME: &'static str = "google.protobuf.FieldOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+pub mod field_options {
+    #[allow(unused_imports)]
+    use super::*;
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    #[repr(i32)]
+    pub enum CType {
+        /// Default mode.
+        #[default]
+        String = 0,
+        Cord = 1,
+        StringPiece = 2,
+    }
+    #[allow(deprecated)]
+    impl TryFrom<i32> for CType {
+        type Error = DecodeError;
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                0 => Ok(CType::String),
+                1 => Ok(CType::Cord),
+                2 => Ok(CType::StringPiece),
+                _ => Err(DecodeError::UnknownVariant("CType", value)),
+            }
+        }
+    }
+    #[allow(deprecated)]
+    impl From<CType> for i32 {
+        fn from(value: CType) -> Self {
+            value as i32
+        }
+    }
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    #[repr(i32)]
+    pub enum JSType {
+        /// Use the default type.
+        #[default]
+        JsNormal = 0,
+        /// Use JavaScript strings.
+        JsString = 1,
+        /// Use JavaScript numbers.
+        JsNumber = 2,
+    }
+    #[allow(deprecated)]
+    impl TryFrom<i32> for JSType {
+        type Error = DecodeError;
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                0 => Ok(JSType::JsNormal),
+                1 => Ok(JSType::JsString),
+                2 => Ok(JSType::JsNumber),
+                _ => Err(DecodeError::UnknownVariant("JSType", value)),
+            }
+        }
+    }
+    #[allow(deprecated)]
+    impl From<JSType> for i32 {
+        fn from(value: JSType) -> Self {
+            value as i32
+        }
+    }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OneofOptions {
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+impl Deserialize for OneofOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for OneofOptions {
+    fn encoded_len(&self) -> usize {
+        self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for OneofOptions {
+    const NAME: &'static str = "google.protobuf.OneofOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnumOptions {
+    /// Set this option to true to allow mapping different tag names to the same
+    /// value.
+    pub allow_alias: Option<bool>,
+    /// Is this enum deprecated?
+    pub deprecated: Option<bool>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+impl EnumOptions {
+    pub fn allow_alias(&self) -> bool { self.allow_alias.unwrap_or_default() }
+    pub fn has_allow_alias(&self) -> bool { self.allow_alias.is_some() }
+    pub fn set_allow_alias(&mut self, value: bool) { self.allow_alias = Some(value) }
+    pub fn clear_allow_alias(&mut self) { self.allow_alias = None }
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+}
+impl Deserialize for EnumOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                16 => msg.allow_alias = Some(buf.read_bool()?),
+                24 => msg.deprecated = Some(buf.read_bool()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for EnumOptions {
+    fn encoded_len(&self) -> usize {
+        self.allow_alias.as_ref().map_or(0, |_| 1 + 1)
+            + self.deprecated.as_ref().map_or(0, |_| 1 + 1)
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.allow_alias { buf.write_bool(16, *v)? }
+        if let Some(v) = &self.deprecated { buf.write_bool(24, *v)? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for EnumOptions {
+    const NAME: &'static str = "google.protobuf.EnumOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnumValueOptions {
+    /// Is this enum value deprecated?
+    pub deprecated: Option<bool>,
+    /// Indicate that fields annotated with this enum value should not be printed
+    /// out when using debug formats.
+    pub debug_redact: Option<bool>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+impl EnumValueOptions {
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+    pub fn debug_redact(&self) -> bool { self.debug_redact.unwrap_or(false) }
+    pub fn has_debug_redact(&self) -> bool { self.debug_redact.is_some() }
+    pub fn set_debug_redact(&mut self, value: bool) { self.debug_redact = Some(value) }
+    pub fn clear_debug_redact(&mut self) { self.debug_redact = None }
+}
+impl Deserialize for EnumValueOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                8 => msg.deprecated = Some(buf.read_bool()?),
+                24 => msg.debug_redact = Some(buf.read_bool()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for EnumValueOptions {
+    fn encoded_len(&self) -> usize {
+        self.deprecated.as_ref().map_or(0, |_| 1 + 1)
+            + self.debug_redact.as_ref().map_or(0, |_| 1 + 1)
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.deprecated { buf.write_bool(8, *v)? }
+        if let Some(v) = &self.debug_redact { buf.write_bool(24, *v)? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for EnumValueOptions {
+    const NAME: &'static str = "google.protobuf.EnumValueOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServiceOptions {
+    /// Is this service deprecated?
+    pub deprecated: Option<bool>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+impl ServiceOptions {
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+}
+impl Deserialize for ServiceOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                264 => msg.deprecated = Some(buf.read_bool()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for ServiceOptions {
+    fn encoded_len(&self) -> usize {
+        self.deprecated.as_ref().map_or(0, |_| 2 + 1)
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.deprecated { buf.write_bool(264, *v)? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for ServiceOptions {
+    const NAME: &'static str = "google.protobuf.ServiceOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MethodOptions {
+    /// Is this method deprecated?
+    pub deprecated: Option<bool>,
+    pub idempotency_level: Option<method_options::IdempotencyLevel>,
+    /// The parser stores options it doesn't recognize here.
+    pub uninterpreted_option: Vec<UninterpretedOption>,
+    pub extensions: ExtensionSet,
+}
+#[allow(dead_code)]
+impl MethodOptions {
+    pub fn deprecated(&self) -> bool { self.deprecated.unwrap_or(false) }
+    pub fn has_deprecated(&self) -> bool { self.deprecated.is_some() }
+    pub fn set_deprecated(&mut self, value: bool) { self.deprecated = Some(value) }
+    pub fn clear_deprecated(&mut self) { self.deprecated = None }
+    pub fn idempotency_level(&self) -> method_options::IdempotencyLevel { self.idempotency_level.unwrap_or(method_options::IdempotencyLevel::IdempotencyUnknown) }
+    pub fn has_idempotency_level(&self) -> bool { self.idempotency_level.is_some() }
+    pub fn set_idempotency_level(&mut self, value: method_options::IdempotencyLevel) { self.idempotency_level = Some(value) }
+    pub fn clear_idempotency_level(&mut self) { self.idempotency_level = None }
+}
+impl Deserialize for MethodOptions {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            match buf.read_uint32()? {
+                264 => msg.deprecated = Some(buf.read_bool()?),
+                272 => msg.idempotency_level = Some(buf.read_enum()?),
+                7994 => msg.uninterpreted_option.push(buf.read_msg()?),
+                tag @ (8000..=4294967295) => buf.read_extension(tag, &mut msg.extensions)?,
+                tag => buf.read_unknown(tag)?,
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for MethodOptions {
+    fn encoded_len(&self) -> usize {
+        self.deprecated.as_ref().map_or(0, |_| 2 + 1)
+            + self.idempotency_level.as_ref().map_or(0, |_| 2 + 1)
+            + self.uninterpreted_option.iter().fold(0, |acc, v| acc + 2 + sizeof_len(v.encoded_len()))
+            + self.extensions.encoded_len()
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        if let Some(v) = &self.deprecated { buf.write_bool(264, *v)? }
+        if let Some(v) = &self.idempotency_level { buf.write_int32(272, i32::from(*v))? }
+        for v in &self.uninterpreted_option { buf.write_msg(7994, v)? }
+        buf.write_extensions(&self.extensions)?;
+        Ok(buf.pos)
+    }
+}
+impl Extendable for MethodOptions {
+    const NAME: &'static str = "google.protobuf.MethodOptions";
+    fn extensions(&self) -> &ExtensionSet { &self.extensions }
+    fn extensions_mut(&mut self) -> &mut ExtensionSet { &mut self.extensions }
+}
+pub mod method_options {
+    #[allow(unused_imports)]
+    use super::*;
+    /// Is this method side-effect-free (or safe in HTTP parlance), or idempotent,
+    /// or neither? HTTP based RPC implementation may choose GET verb for safe
+    /// methods, and PUT verb for idempotent methods instead of the default POST.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    #[repr(i32)]
+    pub enum IdempotencyLevel {
+        #[default]
+        IdempotencyUnknown = 0,
+        /// implies idempotent
+        NoSideEffects = 1,
+        /// idempotent, but may have side effects
+        Idempotent = 2,
+    }
+    #[allow(deprecated)]
+    impl TryFrom<i32> for IdempotencyLevel {
+        type Error = DecodeError;
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                0 => Ok(IdempotencyLevel::IdempotencyUnknown),
+                1 => Ok(IdempotencyLevel::NoSideEffects),
+                2 => Ok(IdempotencyLevel::Idempotent),
+                _ => Err(DecodeError::UnknownVariant("IdempotencyLevel", value)),
+            }
+        }
+    }
+    #[allow(deprecated)]
+    impl From<IdempotencyLevel> for i32 {
+        fn from(value: IdempotencyLevel) -> Self {
+            value as i32
+        }
+    }
+}
+/// A message representing a option the parser does not recognize. This only
+/// appears in options protos created by the compiler::Parser class.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UninterpretedOption {
+    pub name: Vec<uninterpreted_option::NamePart>,
+    /// The value of the uninterpreted option, in whatever type the tokenizer
+    /// identified it as during parsing. Exactly one of these should be set.
+    pub identifier_value: Option<String>,
+    pub positive_int_value: Option<u64>,
+    pub negative_int_value: Option<i64>,
+    pub double_value: Option<f64>,
+    pub string_value: Option<Vec<u8>>,
+    pub aggregate_value: Option<String>,
+}
+#[allow(dead_code)]
+impl UninterpretedOption {
+    pub fn identifier_value(&self) -> &str { self.identifier_value.as_deref().unwrap_or("") }
+    pub fn has_identifier_value(&self) -> bool { self.identifier_value.is_some() }
+    pub fn set_identifier_value(&mut self, value: String) { self.identifier_value = Some(value) }
+    pub fn clear_identifier_value(&mut self) { self.identifier_value = None }
+    pub fn positive_int_value(&self) -> u64 { self.positive_int_value.unwrap_or_default() }
+    pub fn has_positive_int_value(&self) -> bool { self.positive_int_value.is_some() }
+    pub fn set_positive_int_value(&mut self, value: u64) { self.positive_int_value = Some(value) }
+    pub fn clear_positive_int_value(&mut self) { self.positive_int_value = None }
+    pub fn negative_int_value(&self) -> i64 { self.negative_int_value.unwrap_or_default() }
+    pub fn has_negative_int_value(&self) -> bool { self.negative_int_value.is_some() }
+    pub fn set_negative_int_value(&mut self, value: i64) { self.negative_int_value = Some(value) }
+    pub fn clear_negative_int_value(&mut self) { self.negative_int_value = None }
+    pub fn double_value(&self) -> f64 { self.double_value.unwrap_or_default() }
+    pub fn has_double_value(&self) -> bool { self.double_value.is_some() }
+    pub fn set_double_value(&mut self, value: f64) { self.double_value = Some(value) }
+    pub fn clear_double_value(&mut self) { self.double_value = None }
+    pub fn string_value(&self) -> &[u8] { self.string_value.as_deref().unwrap_or(b"") }
+    pub fn has_string_value(&self) -> bool { self.string_value.is_some() }
+    pub fn set_string_value(&mut self, value: Vec<u8>) { self.string_value = Some(value) }
+    pub fn clear_string_value(&mut self) { self.string_value = None }
+    pub fn aggregate_value(&self) -> &str { self.aggregate_value.as_deref().unwrap_or("") }
+    pub fn has_aggregate_value(&self) -> bool { self.aggregate_value.is_some() }
+    pub fn set_aggregate_value(&mut self, value: String) { self.aggregate_value = Some(value) }
+    pub fn clear_aggregate_value(&mut self) { self.aggregate_value = None }
+}
+impl Deserialize for UninterpretedOption {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                18 => msg.name.push(buf.read_msg()?),
+                26 => msg.identifier_value = Some(buf.read_string()?),
+                32 => msg.positive_int_value = Some(buf.read_uint64()?),
+                40 => msg.negative_int_value = Some(buf.read_int64()?),
+                49 => msg.double_value = Some(buf.read_double()?),
+                58 => msg.string_value = Some(buf.read_bytes()?),
+                66 => msg.aggregate_value = Some(buf.read_string()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for UninterpretedOption {
+    fn encoded_len(&self) -> usize {
+        self.name.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+            + self.identifier_value.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.positive_int_value.as_ref().map_or(0, |v| 1 + sizeof_varint(*v))
+            + self.negative_int_value.as_ref().map_or(0, |v| 1 + sizeof_varint(*v as u64))
+            + self.double_value.as_ref().map_or(0, |_| 1 + 8)
+            + self.string_value.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+            + self.aggregate_value.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.name { buf.write_msg(18, v)? }
+        if let Some(v) = &self.identifier_value { buf.write_string(26, v.as_str())? }
+        if let Some(v) = &self.positive_int_value { buf.write_uint64(32, *v)? }
+        if let Some(v) = &self.negative_int_value { buf.write_int64(40, *v)? }
+        if let Some(v) = &self.double_value { buf.write_double(49, *v)? }
+        if let Some(v) = &self.string_value { buf.write_bytes(58, v.as_slice())? }
+        if let Some(v) = &self.aggregate_value { buf.write_string(66, v.as_str())? }
+        Ok(buf.pos)
+    }
+}
+pub mod uninterpreted_option {
+    #[allow(unused_imports)]
+    use super::*;
+    /// The name of the uninterpreted option.  Each string represents a segment in
+    /// a dot-separated name.  is_extension is true iff a segment represents an
+    /// extension (denoted with parentheses in options specs in .proto files).
+    /// E.g.,{ \["foo", false\], \["bar.baz", true\], \["moo", false\] } represents
+    /// "foo.(bar.baz).moo".
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct NamePart {
+        pub name_part: String,
+        pub is_extension: bool,
+    }
+    impl Deserialize for NamePart {
+        fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+            let mut buf = Reader::new(src);
+            let mut msg: Self = Default::default();
+            while buf.pos < buf.src.len() {
+                let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+                match tag {
+                    10 => msg.name_part = buf.read_string()?,
+                    16 => msg.is_extension = buf.read_bool()?,
+                    _ => {
+                        buf.pos -= 1;
+                        let tag = buf.read_uint32()?;
+                        buf.read_unknown(tag)?;
+                    }
+                }
+            }
+            Ok(msg)
+        }
+    }
+    impl Serialize for NamePart {
+        fn encoded_len(&self) -> usize {
+            1 + sizeof_len(self.name_part.len())
+                + 1 + 1
+        }
+        fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+            let mut buf = Writer::new(buf);
+            buf.write_string(10, self.name_part.as_str())?;
+            buf.write_bool(16, self.is_extension)?;
+            Ok(buf.pos)
+        }
+    }
+}
+/// Encapsulates information about the original source file from which a
+/// FileDescriptorProto was generated.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SourceCodeInfo {
+    /// A Location identifies a piece of source code in a .proto file which
+    /// corresponds to a particular definition.
+    pub location: Vec<source_code_info::Location>,
+}
+impl Deserialize for SourceCodeInfo {
+    fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = Reader::new(src);
+        let mut msg: Self = Default::default();
+        while buf.pos < buf.src.len() {
+            let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+            match tag {
+                10 => msg.location.push(buf.read_msg()?),
+                _ => {
+                    buf.pos -= 1;
+                    let tag = buf.read_uint32()?;
+                    buf.read_unknown(tag)?;
+                }
+            }
+        }
+        Ok(msg)
+    }
+}
+impl Serialize for SourceCodeInfo {
+    fn encoded_len(&self) -> usize {
+        self.location.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.encoded_len()))
+    }
+    fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Writer::new(buf);
+        for v in &self.location { buf.write_msg(10, v)? }
+        Ok(buf.pos)
+    }
+}
+pub mod source_code_info {
+    #[allow(unused_imports)]
+    use super::*;
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Location {
+        /// Identifies which part of the FileDescriptorProto was defined at this
+        /// location.
+        pub path: Vec<i32>,
+        /// Always has exactly three or four elements: start line, start column,
+        /// end line (optional, otherwise assumed same as start line), end column.
+        /// These are packed into a single field for efficiency.  Note that line
+        /// and column numbers are zero-based.
+        pub span: Vec<i32>,
+        /// If this SourceCodeInfo represents a complete declaration, these are any
+        /// comments appearing before and after the declaration which appear to be
+        /// attached to the declaration.
+        pub leading_comments: Option<String>,
+        pub trailing_comments: Option<String>,
+        pub leading_detached_comments: Vec<String>,
+    }
+    #[allow(dead_code)]
+    impl Location {
+        pub fn leading_comments(&self) -> &str { self.leading_comments.as_deref().unwrap_or("") }
+        pub fn has_leading_comments(&self) -> bool { self.leading_comments.is_some() }
+        pub fn set_leading_comments(&mut self, value: String) { self.leading_comments = Some(value) }
+        pub fn clear_leading_comments(&mut self) { self.leading_comments = None }
+        pub fn trailing_comments(&self) -> &str { self.trailing_comments.as_deref().unwrap_or("") }
+        pub fn has_trailing_comments(&self) -> bool { self.trailing_comments.is_some() }
+        pub fn set_trailing_comments(&mut self, value: String) { self.trailing_comments = Some(value) }
+        pub fn clear_trailing_comments(&mut self) { self.trailing_comments = None }
+    }
+    impl Deserialize for Location {
+        fn decode(src: &[u8]) -> Result<Self, DecodeError> {
+            let mut buf = Reader::new(src);
+            let mut msg: Self = Default::default();
+            while buf.pos < buf.src.len() {
+                let tag = buf.src[buf.pos] as u32; buf.pos += 1;
+                match tag {
+                    10 => msg.path = buf.read_packed(Reader::read_int32)?,
+                    18 => msg.span = buf.read_packed(Reader::read_int32)?,
+                    26 => msg.leading_comments = Some(buf.read_string()?),
+                    34 => msg.trailing_comments = Some(buf.read_string()?),
+                    50 => msg.leading_detached_comments.push(buf.read_string()?),
+                    _ => {
+                        buf.pos -= 1;
+                        let tag = buf.read_uint32()?;
+                        buf.read_unknown(tag)?;
+                    }
+                }
+            }
+            Ok(msg)
+        }
+    }
+    impl Serialize for Location {
+        fn encoded_len(&self) -> usize {
+            (if self.path.is_empty() { 0 } else { 1 + sizeof_len(self.path.iter().fold(0, |acc, v| acc + sizeof_int32(*v))) })
+                + if self.span.is_empty() { 0 } else { 1 + sizeof_len(self.span.iter().fold(0, |acc, v| acc + sizeof_int32(*v))) }
+                + self.leading_comments.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+                + self.trailing_comments.as_ref().map_or(0, |v| 1 + sizeof_len(v.len()))
+                + self.leading_detached_comments.iter().fold(0, |acc, v| acc + 1 + sizeof_len(v.len()))
+        }
+        fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+            let mut buf = Writer::new(buf);
+            buf.write_packed_int32(10, &self.path)?;
+            buf.write_packed_int32(18, &self.span)?;
+            if let Some(v) = &self.leading_comments { buf.write_string(26, v.as_str())? }
+            if let Some(v) = &self.trailing_comments { buf.write_string(34, v.as_str())? }
+            for v in &self.leading_detached_comments { buf.write_string(50, v.as_str())? }
+            Ok(buf.pos)
+        }
+    }
+}
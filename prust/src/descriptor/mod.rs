@@ -0,0 +1,12 @@
+//! Types of `google/protobuf/descriptor.proto`, they describe proto files
+//! themselves and are used to exchange schemas with other tools, e.g. gRPC
+//! reflection.
+//!
+//! `generated.rs` is generated by `prust-build` from `descriptor.proto` in
+//! this directory, `cargo test -p prust-build` fails once it's out of date.
+
+#[rustfmt::skip]
+#[allow(clippy::all)]
+mod generated;
+
+pub use generated::*;
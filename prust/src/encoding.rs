@@ -13,6 +13,8 @@ pub enum DecodeError {
     UnknownVariant(&'static str, i32),
     // utf8 validate error
     Utf8,
+    // A value can't be converted to the custom rust type of the field
+    Conversion(String),
 }
 
 impl std::fmt::Display for DecodeError {
@@ -27,6 +29,7 @@ impl std::fmt::Display for DecodeError {
                 write!(f, "unknown enum value {typ}: {value}")
             }
             DecodeError::Utf8 => f.write_str("invalid UTF-8"),
+            DecodeError::Conversion(reason) => write!(f, "invalid value: {reason}"),
         }
     }
 }
@@ -257,7 +260,7 @@ impl<'a> Writer<'a> {
         self.write_varint32(v)
     }
 
-    fn write(&mut self, v: &[u8]) -> Result<(), EncodeError> {
+    pub(crate) fn write(&mut self, v: &[u8]) -> Result<(), EncodeError> {
         if self.buf.len() - self.pos < v.len() {
             return Err(EncodeError::Eof);
         }
@@ -432,7 +435,7 @@ impl<'a> Writer<'a> {
         self.write_tag(tag)?;
 
         // write length delimiter
-        let len = array.len() * size_of::<T>();
+        let len = size_of_val(array);
         self.write_varint32(len as u32)?;
 
         if self.buf.len() - self.pos < len {
@@ -903,7 +906,7 @@ impl<'a> Reader<'a> {
     //   so, we might receive some wire bytes like [0, 1, 2, 4].
     pub fn read_packed_fixed<T>(&mut self) -> Result<Vec<T>, DecodeError> {
         let len = self.read_varint()? as usize;
-        if len % size_of::<T>() != 0 {
+        if !len.is_multiple_of(size_of::<T>()) {
             return Err(DecodeError::Malformed);
         }
 
@@ -0,0 +1,679 @@
+//! Proto2 extensions.
+//!
+//! Extension fields are kept as raw wire records in the [`ExtensionSet`] of
+//! the extendable message, and they are decoded only when they are accessed
+//! by the typed [`Extension`] identifiers generated from `extend` blocks.
+
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+use crate::encoding::{
+    DecodeError, Deserialize, EncodeError, Reader, Serialize, Writer, sizeof_int32, sizeof_len,
+    sizeof_sint32, sizeof_sint64, sizeof_varint,
+};
+
+/// Encoding of a single extension value, e.g. `sint32` and `sfixed32` are
+/// both `i32` but encoded differently.
+pub trait Codec {
+    type Value;
+
+    const WIRE_TYPE: u32;
+
+    /// Read the value, the tag is consumed already. `None` is returned if
+    /// the value should be ignored, e.g. unknown values of closed enums.
+    fn read(reader: &mut Reader) -> Result<Option<Self::Value>, DecodeError>;
+
+    /// Size of the value without tag
+    fn size(value: &Self::Value) -> usize;
+
+    fn write(writer: &mut Writer, tag: u32, value: &Self::Value) -> Result<(), EncodeError>;
+}
+
+/// Scalar values which can be written in packed encoding
+pub trait Packable: Codec {
+    fn write_packed(
+        writer: &mut Writer,
+        tag: u32,
+        values: &[Self::Value],
+    ) -> Result<(), EncodeError>;
+}
+
+/// Type of extension fields, single values are [`Codec`]s, and repeated
+/// values are [`Repeated`] or [`Packed`].
+pub trait ExtensionType {
+    type Value;
+
+    /// Decode all records of the extension field
+    fn decode(records: &[u8]) -> Result<Option<Self::Value>, DecodeError>;
+
+    fn encoded_len(number: u32, value: &Self::Value) -> usize;
+
+    fn encode(writer: &mut Writer, number: u32, value: &Self::Value) -> Result<(), EncodeError>;
+}
+
+impl<C: Codec> ExtensionType for C {
+    type Value = C::Value;
+
+    fn decode(records: &[u8]) -> Result<Option<Self::Value>, DecodeError> {
+        let mut reader = Reader::new(records);
+        let mut value = None;
+
+        while reader.pos < reader.src.len() {
+            let tag = reader.read_uint32()?;
+            if tag & 0x7 != C::WIRE_TYPE {
+                reader.read_unknown(tag)?;
+                continue;
+            }
+
+            // the last one wins, messages are not merged
+            if let Some(v) = C::read(&mut reader)? {
+                value = Some(v);
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn encoded_len(number: u32, value: &Self::Value) -> usize {
+        sizeof_varint((number << 3) as u64) + C::size(value)
+    }
+
+    fn encode(writer: &mut Writer, number: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        C::write(writer, number << 3 | C::WIRE_TYPE, value)
+    }
+}
+
+/// Repeated extension field, every element is written with its own tag.
+pub struct Repeated<C>(PhantomData<C>);
+
+/// Repeated extension field, which is written in packed encoding.
+pub struct Packed<C>(PhantomData<C>);
+
+fn decode_repeated<C: Codec>(records: &[u8]) -> Result<Option<Vec<C::Value>>, DecodeError> {
+    let mut reader = Reader::new(records);
+    let mut array = Vec::new();
+
+    while reader.pos < reader.src.len() {
+        let tag = reader.read_uint32()?;
+        let wire_type = tag & 0x7;
+
+        if wire_type == C::WIRE_TYPE {
+            if let Some(v) = C::read(&mut reader)? {
+                array.push(v);
+            }
+        } else if wire_type == 2 {
+            // parsers must accept both packed and unpacked encoding
+            let len = reader.read_varint()? as usize;
+            if reader.src.len() - reader.pos < len {
+                return Err(DecodeError::Eof);
+            }
+
+            let end = reader.pos + len;
+            while reader.pos < end {
+                if let Some(v) = C::read(&mut reader)? {
+                    array.push(v);
+                }
+            }
+
+            if reader.pos != end {
+                return Err(DecodeError::Malformed);
+            }
+        } else {
+            reader.read_unknown(tag)?;
+        }
+    }
+
+    if array.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(array))
+    }
+}
+
+impl<C: Codec> ExtensionType for Repeated<C> {
+    type Value = Vec<C::Value>;
+
+    fn decode(records: &[u8]) -> Result<Option<Self::Value>, DecodeError> {
+        decode_repeated::<C>(records)
+    }
+
+    fn encoded_len(number: u32, value: &Self::Value) -> usize {
+        let tag_size = sizeof_varint((number << 3) as u64);
+        value.iter().fold(0, |acc, v| acc + tag_size + C::size(v))
+    }
+
+    fn encode(writer: &mut Writer, number: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        for v in value {
+            C::write(writer, number << 3 | C::WIRE_TYPE, v)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: Packable> ExtensionType for Packed<C> {
+    type Value = Vec<C::Value>;
+
+    fn decode(records: &[u8]) -> Result<Option<Self::Value>, DecodeError> {
+        decode_repeated::<C>(records)
+    }
+
+    fn encoded_len(number: u32, value: &Self::Value) -> usize {
+        if value.is_empty() {
+            return 0;
+        }
+
+        let len = value.iter().fold(0, |acc, v| acc + C::size(v));
+        sizeof_varint((number << 3) as u64) + sizeof_len(len)
+    }
+
+    fn encode(writer: &mut Writer, number: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        C::write_packed(writer, number << 3 | 2, value)
+    }
+}
+
+macro_rules! scalar {
+    (
+        $name:ident,
+        $typ:ty,
+        $wire_type:expr,
+        $read:ident,
+        $write:ident,
+        $write_packed:ident,
+        |$v:pat_param| $size:expr
+    ) => {
+        pub struct $name;
+
+        impl Codec for $name {
+            type Value = $typ;
+
+            const WIRE_TYPE: u32 = $wire_type;
+
+            #[inline]
+            fn read(reader: &mut Reader) -> Result<Option<Self::Value>, DecodeError> {
+                reader.$read().map(Some)
+            }
+
+            #[inline]
+            fn size(value: &Self::Value) -> usize {
+                let $v = *value;
+                $size
+            }
+
+            #[inline]
+            fn write(
+                writer: &mut Writer,
+                tag: u32,
+                value: &Self::Value,
+            ) -> Result<(), EncodeError> {
+                writer.$write(tag, *value)
+            }
+        }
+
+        impl Packable for $name {
+            #[inline]
+            fn write_packed(
+                writer: &mut Writer,
+                tag: u32,
+                values: &[Self::Value],
+            ) -> Result<(), EncodeError> {
+                writer.$write_packed(tag, values)
+            }
+        }
+    };
+}
+
+#[rustfmt::skip]
+mod scalars {
+    use super::*;
+
+    scalar!(Double, f64, 1, read_double, write_double, write_packed, |_| 8);
+    scalar!(Float, f32, 5, read_float, write_float, write_packed, |_| 4);
+    scalar!(Int32, i32, 0, read_int32, write_int32, write_packed_int32, |v| sizeof_int32(v));
+    scalar!(Int64, i64, 0, read_int64, write_int64, write_packed_int64, |v| sizeof_varint(v as u64));
+    scalar!(Uint32, u32, 0, read_uint32, write_uint32, write_packed_uint32, |v| sizeof_varint(v as u64));
+    scalar!(Uint64, u64, 0, read_uint64, write_uint64, write_packed_uint64, |v| sizeof_varint(v));
+    scalar!(Sint32, i32, 0, read_sint32, write_sint32, write_packed_sint32, |v| sizeof_sint32(v));
+    scalar!(Sint64, i64, 0, read_sint64, write_sint64, write_packed_sint64, |v| sizeof_sint64(v));
+    scalar!(Fixed32, u32, 5, read_fixed32, write_fixed32, write_packed, |_| 4);
+    scalar!(Fixed64, u64, 1, read_fixed64, write_fixed64, write_packed, |_| 8);
+    scalar!(Sfixed32, i32, 5, read_sfixed32, write_sfixed32, write_packed, |_| 4);
+    scalar!(Sfixed64, i64, 1, read_sfixed64, write_sfixed64, write_packed, |_| 8);
+    scalar!(Bool, bool, 0, read_bool, write_bool, write_packed, |_| 1);
+}
+
+pub use scalars::*;
+
+pub struct String;
+
+impl Codec for String {
+    type Value = std::string::String;
+
+    const WIRE_TYPE: u32 = 2;
+
+    #[inline]
+    fn read(reader: &mut Reader) -> Result<Option<Self::Value>, DecodeError> {
+        reader.read_string().map(Some)
+    }
+
+    #[inline]
+    fn size(value: &Self::Value) -> usize {
+        sizeof_len(value.len())
+    }
+
+    #[inline]
+    fn write(writer: &mut Writer, tag: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        writer.write_string(tag, value)
+    }
+}
+
+pub struct Bytes;
+
+impl Codec for Bytes {
+    type Value = Vec<u8>;
+
+    const WIRE_TYPE: u32 = 2;
+
+    #[inline]
+    fn read(reader: &mut Reader) -> Result<Option<Self::Value>, DecodeError> {
+        reader.read_bytes().map(Some)
+    }
+
+    #[inline]
+    fn size(value: &Self::Value) -> usize {
+        sizeof_len(value.len())
+    }
+
+    #[inline]
+    fn write(writer: &mut Writer, tag: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        writer.write_bytes(tag, value)
+    }
+}
+
+pub struct Message<T>(PhantomData<T>);
+
+impl<T: Serialize + Deserialize> Codec for Message<T> {
+    type Value = T;
+
+    const WIRE_TYPE: u32 = 2;
+
+    #[inline]
+    fn read(reader: &mut Reader) -> Result<Option<Self::Value>, DecodeError> {
+        reader.read_msg().map(Some)
+    }
+
+    #[inline]
+    fn size(value: &Self::Value) -> usize {
+        sizeof_len(value.encoded_len())
+    }
+
+    #[inline]
+    fn write(writer: &mut Writer, tag: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        writer.write_msg(tag, value)
+    }
+}
+
+/// Enum values, unknown values are ignored just like closed enum fields.
+pub struct Enum<E>(PhantomData<E>);
+
+impl<E> Codec for Enum<E>
+where
+    E: TryFrom<i32, Error = DecodeError> + Into<i32> + Copy,
+{
+    type Value = E;
+
+    const WIRE_TYPE: u32 = 0;
+
+    #[inline]
+    fn read(reader: &mut Reader) -> Result<Option<Self::Value>, DecodeError> {
+        // the record is kept in the extension set anyway
+        match E::try_from(reader.read_int32()?) {
+            Ok(value) => Ok(Some(value)),
+            Err(DecodeError::UnknownVariant(_, _)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    #[inline]
+    fn size(value: &Self::Value) -> usize {
+        sizeof_int32((*value).into())
+    }
+
+    #[inline]
+    fn write(writer: &mut Writer, tag: u32, value: &Self::Value) -> Result<(), EncodeError> {
+        writer.write_int32(tag, (*value).into())
+    }
+}
+
+impl<E> Packable for Enum<E>
+where
+    E: TryFrom<i32, Error = DecodeError> + Into<i32> + Copy,
+{
+    #[inline]
+    fn write_packed(
+        writer: &mut Writer,
+        tag: u32,
+        values: &[Self::Value],
+    ) -> Result<(), EncodeError> {
+        writer.write_packed_enum(tag, values)
+    }
+}
+
+/// Typed identifier of an extension field, which extends message `M`
+/// with a value of type `T`.
+pub struct Extension<M, T> {
+    number: u32,
+    name: &'static str,
+
+    _marker: PhantomData<fn() -> (M, T)>,
+}
+
+impl<M, T> Extension<M, T> {
+    pub const fn new(number: u32, name: &'static str) -> Self {
+        Self {
+            number,
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Full name of the extension field, e.g. `foo.bar`
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Extension fields of an extendable message, which are stored as raw wire
+/// records and keyed by field number.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExtensionSet {
+    fields: BTreeMap<u32, Vec<u8>>,
+}
+
+impl ExtensionSet {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    #[inline]
+    pub fn contains(&self, number: u32) -> bool {
+        self.fields.contains_key(&number)
+    }
+
+    /// Field numbers of all extension fields present
+    pub fn numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.fields.keys().copied()
+    }
+
+    /// Raw wire records of the field, tags included
+    #[inline]
+    pub fn raw(&self, number: u32) -> Option<&[u8]> {
+        self.fields.get(&number).map(|v| v.as_slice())
+    }
+
+    pub fn get<T: ExtensionType>(&self, number: u32) -> Result<Option<T::Value>, DecodeError> {
+        match self.fields.get(&number) {
+            Some(records) => T::decode(records),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set<T: ExtensionType>(&mut self, number: u32, value: &T::Value) {
+        let mut records = vec![0; T::encoded_len(number, value)];
+        if records.is_empty() {
+            self.fields.remove(&number);
+            return;
+        }
+
+        let mut writer = Writer::new(&mut records);
+        T::encode(&mut writer, number, value).expect("buffer is sized by encoded_len");
+
+        self.fields.insert(number, records);
+    }
+
+    #[inline]
+    pub fn remove(&mut self, number: u32) {
+        self.fields.remove(&number);
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        self.fields.values().map(|records| records.len()).sum()
+    }
+}
+
+/// Messages with extension ranges, `extensions 100 to 199;`
+pub trait Extendable: Sized {
+    /// Full name of the message, e.g. `foo.Bar`
+    const NAME: &'static str;
+
+    fn extensions(&self) -> &ExtensionSet;
+
+    fn extensions_mut(&mut self) -> &mut ExtensionSet;
+
+    #[inline]
+    fn has_extension<T>(&self, ext: &Extension<Self, T>) -> bool {
+        self.extensions().contains(ext.number)
+    }
+
+    /// Decode the extension field, `None` is returned if it is not present
+    #[inline]
+    fn get_extension<T: ExtensionType>(
+        &self,
+        ext: &Extension<Self, T>,
+    ) -> Result<Option<T::Value>, DecodeError> {
+        self.extensions().get::<T>(ext.number)
+    }
+
+    #[inline]
+    fn set_extension<T: ExtensionType>(&mut self, ext: &Extension<Self, T>, value: T::Value) {
+        self.extensions_mut().set::<T>(ext.number, &value)
+    }
+
+    #[inline]
+    fn clear_extension<T>(&mut self, ext: &Extension<Self, T>) {
+        self.extensions_mut().remove(ext.number)
+    }
+
+    /// Decode the message, registered extension fields are decoded eagerly,
+    /// so malformed ones are reported here, and the others are dropped like
+    /// unknown fields.
+    ///
+    /// Only extension fields of this message are checked, messages nested
+    /// in it are decoded as usual.
+    fn decode_with_registry(src: &[u8], registry: &ExtensionRegistry) -> Result<Self, DecodeError>
+    where
+        Self: Deserialize,
+    {
+        let mut msg = Self::decode(src)?;
+
+        let set = msg.extensions_mut();
+        let mut result = Ok(());
+        set.fields
+            .retain(|number, records| match registry.find(Self::NAME, *number) {
+                Some(info) => {
+                    if result.is_ok() {
+                        result = (info.validate)(records);
+                    }
+                    true
+                }
+                None => false,
+            });
+        result?;
+
+        Ok(msg)
+    }
+}
+
+/// Information of a registered extension field
+#[derive(Clone, Copy, Debug)]
+pub struct ExtensionInfo {
+    /// Full name of the extended message
+    pub extendee: &'static str,
+    pub number: u32,
+    /// Full name of the extension field
+    pub name: &'static str,
+
+    validate: fn(&[u8]) -> Result<(), DecodeError>,
+}
+
+/// Known extension fields, generated files provide a `register_extensions`
+/// function to register all extensions declared in them.
+#[derive(Debug, Default)]
+pub struct ExtensionRegistry {
+    // extendee -> field number -> info
+    extensions: HashMap<&'static str, HashMap<u32, ExtensionInfo>>,
+}
+
+impl ExtensionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<M: Extendable, T: ExtensionType>(&mut self, ext: &Extension<M, T>) {
+        fn validate<T: ExtensionType>(records: &[u8]) -> Result<(), DecodeError> {
+            T::decode(records).map(|_| ())
+        }
+
+        self.extensions.entry(M::NAME).or_default().insert(
+            ext.number,
+            ExtensionInfo {
+                extendee: M::NAME,
+                number: ext.number,
+                name: ext.name,
+                validate: validate::<T>,
+            },
+        );
+    }
+
+    #[inline]
+    pub fn find(&self, extendee: &str, number: u32) -> Option<&ExtensionInfo> {
+        self.extensions.get(extendee)?.get(&number)
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&ExtensionInfo> {
+        self.extensions
+            .values()
+            .flat_map(|fields| fields.values())
+            .find(|info| info.name == name)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.extensions.values().map(|fields| fields.len()).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty()
+    }
+}
+
+impl Reader<'_> {
+    /// Keep the field in the extension set, the tag is consumed already.
+    pub fn read_extension(&mut self, tag: u32, set: &mut ExtensionSet) -> Result<(), DecodeError> {
+        let start = self.pos;
+        self.read_unknown(tag)?;
+
+        let records = set.fields.entry(tag >> 3).or_default();
+
+        let mut buf = [0u8; 5];
+        let mut writer = Writer::new(&mut buf);
+        // 5 bytes is enough for any u32
+        let _ = writer.write_varint(tag as u64);
+        let len = writer.pos;
+
+        records.extend_from_slice(&buf[..len]);
+        records.extend_from_slice(&self.src[start..self.pos]);
+
+        Ok(())
+    }
+}
+
+impl Writer<'_> {
+    pub fn write_extensions(&mut self, set: &ExtensionSet) -> Result<(), EncodeError> {
+        for records in set.fields.values() {
+            self.write(records)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Foo {
+        extensions: ExtensionSet,
+    }
+
+    impl Extendable for Foo {
+        const NAME: &'static str = "test.Foo";
+
+        fn extensions(&self) -> &ExtensionSet {
+            &self.extensions
+        }
+
+        fn extensions_mut(&mut self) -> &mut ExtensionSet {
+            &mut self.extensions
+        }
+    }
+
+    const SINGLE: Extension<Foo, Sint32> = Extension::new(100, "test.single");
+    const PACKED: Extension<Foo, Packed<Uint32>> = Extension::new(101, "test.packed");
+    const NAMES: Extension<Foo, Repeated<String>> = Extension::new(102, "test.names");
+
+    #[test]
+    fn set_and_get() {
+        let mut foo = Foo {
+            extensions: ExtensionSet::default(),
+        };
+
+        assert!(!foo.has_extension(&SINGLE));
+        assert_eq!(foo.get_extension(&SINGLE), Ok(None));
+
+        foo.set_extension(&SINGLE, -3);
+        foo.set_extension(&PACKED, vec![1, 300]);
+        foo.set_extension(&NAMES, vec!["a".to_string(), "b".to_string()]);
+
+        assert_eq!(foo.get_extension(&SINGLE), Ok(Some(-3)));
+        assert_eq!(foo.get_extension(&PACKED), Ok(Some(vec![1, 300])));
+        assert_eq!(
+            foo.get_extension(&NAMES),
+            Ok(Some(vec!["a".to_string(), "b".to_string()]))
+        );
+        // tag 101 << 3 | 2, length 3, 1, 300
+        assert_eq!(
+            foo.extensions.raw(101),
+            Some(&[0xaa, 0x06, 3, 1, 0xac, 0x02][..])
+        );
+
+        foo.clear_extension(&SINGLE);
+        assert!(!foo.has_extension(&SINGLE));
+    }
+
+    #[test]
+    fn read_extension() {
+        // field 100 twice, and packed field 101 in unpacked encoding
+        let data = [0xa0, 0x06, 0x01, 0xa0, 0x06, 0x04, 0xa8, 0x06, 0x07];
+
+        let mut set = ExtensionSet::default();
+        let mut reader = Reader::new(&data);
+        while reader.pos < data.len() {
+            let tag = reader.read_uint32().unwrap();
+            reader.read_extension(tag, &mut set).unwrap();
+        }
+
+        // the last one wins
+        assert_eq!(set.get::<Sint32>(100), Ok(Some(2)));
+        assert_eq!(set.get::<Packed<Uint32>>(101), Ok(Some(vec![7])));
+        assert_eq!(set.encoded_len(), data.len());
+    }
+}
@@ -1,9 +1,21 @@
 #[cfg(target_endian = "big")]
 compile_error!("Prust does not big-endian yet");
 
+// generated code refers to the runtime as `prust`
+extern crate self as prust;
+
+mod container;
+mod convert;
+pub mod descriptor;
 mod encoding;
+pub mod extension;
+mod unknown;
 
+pub use container::{ProtoBytes, ProtoRepeated, ProtoString};
+pub use convert::{ProtoMessage, ProtoScalar};
 pub use encoding::*;
+pub use extension::{Extendable, Extension, ExtensionRegistry, ExtensionSet};
+pub use unknown::UnknownFields;
 
 #[cfg(feature = "tonic")]
 pub use tonic;
@@ -0,0 +1,187 @@
+//! Unknown values of closed enums.
+//!
+//! With `prust_build::Config::open_enums`, a value of a proto2 enum that is
+//! not a known variant doesn't fail the decoding. The wire record of it is
+//! kept in the [`UnknownFields`] of the message instead, and written back
+//! after the known fields, like protobuf does with its unknown field set.
+
+use crate::encoding::{DecodeError, EncodeError, Reader, Writer};
+
+/// Raw wire records of unknown enum values, tags included
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UnknownFields {
+    records: Vec<u8>,
+}
+
+impl UnknownFields {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Records in the order they were read
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.records
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.records.clear()
+    }
+
+    #[inline]
+    pub fn encoded_len(&self) -> usize {
+        self.records.len()
+    }
+
+    // keep a record, `value` is encoded already
+    fn push(&mut self, tag: u32, value: &[u8]) {
+        let mut buf = [0u8; 5];
+        let mut writer = Writer::new(&mut buf);
+        // 5 bytes is enough for any u32
+        let _ = writer.write_varint(tag as u64);
+        let len = writer.pos;
+
+        self.records.extend_from_slice(&buf[..len]);
+        self.records.extend_from_slice(value);
+    }
+}
+
+impl Reader<'_> {
+    /// Read a value of a closed enum, `None` is returned if the value is not
+    /// a known variant, the record is kept in `unknown` with `tag` then.
+    pub fn read_closed_enum<E: TryFrom<i32, Error = DecodeError>>(
+        &mut self,
+        tag: u32,
+        unknown: &mut UnknownFields,
+    ) -> Result<Option<E>, DecodeError> {
+        let start = self.pos;
+        match E::try_from(self.read_int32()?) {
+            Ok(value) => Ok(Some(value)),
+            Err(DecodeError::UnknownVariant(_, _)) => {
+                unknown.push(tag, &self.src[start..self.pos]);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Read packed values of a closed enum, unknown ones are kept in
+    /// `unknown` as unpacked records with `tag`, like protobuf does.
+    pub fn read_packed_closed_enum<E: TryFrom<i32, Error = DecodeError>>(
+        &mut self,
+        tag: u32,
+        unknown: &mut UnknownFields,
+    ) -> Result<Vec<E>, DecodeError> {
+        let len = self.read_varint()? as usize;
+        if self.src.len() - self.pos < len {
+            return Err(DecodeError::Eof);
+        }
+
+        let mut array = Vec::with_capacity(len);
+
+        let end = self.pos + len;
+        while self.pos < end {
+            if let Some(value) = self.read_closed_enum(tag, unknown)? {
+                array.push(value);
+            }
+        }
+
+        if self.pos != end {
+            Err(DecodeError::Malformed)
+        } else {
+            Ok(array)
+        }
+    }
+
+    /// Read a map entry with a closed enum value, `None` is returned if the
+    /// value is unknown, the whole entry is kept in `unknown` with `tag` then.
+    pub fn read_closed_enum_entry<K, E, KF>(
+        &mut self,
+        tag: u32,
+        read_key: KF,
+        unknown: &mut UnknownFields,
+    ) -> Result<Option<(K, E)>, DecodeError>
+    where
+        K: Default,
+        E: TryFrom<i32, Error = DecodeError>,
+        KF: FnMut(&mut Self) -> Result<K, DecodeError>,
+    {
+        let start = self.pos;
+        let (key, value) = self.read_key_value(read_key, Reader::read_int32)?;
+        match E::try_from(value) {
+            Ok(value) => Ok(Some((key, value))),
+            Err(DecodeError::UnknownVariant(_, _)) => {
+                unknown.push(tag, &self.src[start..self.pos]);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl Writer<'_> {
+    #[inline]
+    pub fn write_unknown_fields(&mut self, unknown: &UnknownFields) -> Result<(), EncodeError> {
+        self.write(&unknown.records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum State {
+        On,
+    }
+
+    impl TryFrom<i32> for State {
+        type Error = DecodeError;
+
+        fn try_from(value: i32) -> Result<Self, DecodeError> {
+            match value {
+                1 => Ok(State::On),
+                _ => Err(DecodeError::UnknownVariant("State", value)),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_values_are_kept() {
+        // field 1 = 7, packed field 2 = [1, 7], map field 3 = { 1: 7 }
+        let data = [
+            0x08, 0x07, 0x12, 0x02, 0x01, 0x07, 0x1a, 0x04, 0x08, 0x01, 0x10, 0x07,
+        ];
+
+        let mut unknown = UnknownFields::default();
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.read_uint32().unwrap(), 0x08);
+        assert_eq!(
+            reader.read_closed_enum::<State>(0x08, &mut unknown),
+            Ok(None)
+        );
+        assert_eq!(reader.read_uint32().unwrap(), 0x12);
+        assert_eq!(
+            reader.read_packed_closed_enum::<State>(0x10, &mut unknown),
+            Ok(vec![State::On])
+        );
+        assert_eq!(reader.read_uint32().unwrap(), 0x1a);
+        assert_eq!(
+            reader.read_closed_enum_entry::<u32, State, _>(0x1a, Reader::read_uint32, &mut unknown),
+            Ok(None)
+        );
+
+        // the packed value is unpacked
+        assert_eq!(
+            unknown.as_bytes(),
+            [0x08, 0x07, 0x10, 0x07, 0x1a, 0x04, 0x08, 0x01, 0x10, 0x07]
+        );
+
+        let mut buf = vec![0; unknown.encoded_len()];
+        let mut writer = Writer::new(&mut buf);
+        writer.write_unknown_fields(&unknown).unwrap();
+        assert_eq!(buf, unknown.as_bytes());
+    }
+}